@@ -0,0 +1,333 @@
+//! The `asset` module contains types and proofs for confidential
+//! assets, where each asset has its own value generator and the asset
+//! of a commitment is hidden as well as its value.
+//!
+//! An [`AssetTag`] \\(H\_a\\) is derived from the identifier of the
+//! asset by hashing it to the group.  Outputs use a
+//! [`BlindedAssetTag`] \\(H = H\_a + \tilde{a} B\_{blinding}\\), and
+//! commit to their value \\(v\\) as
+//! \\[
+//!     V = v H + \tilde{v} B\_{blinding}.
+//! \\]
+//! A [`SurjectionProof`] shows that the blinded tag of an output
+//! blinds the same asset as one of the tags of the inputs, without
+//! revealing which one.  The range of \\(v\\) is proven with a
+//! [`RangeProof`](::RangeProof) over the generators returned by
+//! [`BlindedAssetTag::pedersen_gens`], so that each asset has its own
+//! range proofs.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use sha3::Sha3_512;
+
+use errors::ProofError;
+use generators::PedersenGens;
+use scalar;
+use sigma;
+use transcript::TranscriptProtocol;
+
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// The value generator \\(H\_a\\) of an asset.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AssetTag(pub RistrettoPoint);
+
+impl AssetTag {
+    /// Derives the generator of the asset with the identifier
+    /// `asset_id`, with SHA3-512 hash-to-group.
+    pub fn from_id(asset_id: &[u8]) -> AssetTag {
+        let mut input = b"bulletproofs asset tag".to_vec();
+        input.extend_from_slice(asset_id);
+        AssetTag(RistrettoPoint::hash_from_bytes::<Sha3_512>(&input))
+    }
+
+    /// Blinds the tag with the blinding factor `blinding`, giving
+    /// \\(H\_a + \tilde{a} B\_{blinding}\\).
+    pub fn blind(&self, pc_gens: &PedersenGens, blinding: &Scalar) -> BlindedAssetTag {
+        BlindedAssetTag(self.0 + blinding * pc_gens.B_blinding)
+    }
+}
+
+/// A blinded asset tag \\(H = H\_a + \tilde{a} B\_{blinding}\\), which
+/// hides the asset \\(a\\).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BlindedAssetTag(pub RistrettoPoint);
+
+impl BlindedAssetTag {
+    /// Decompresses a blinded tag from its wire format.
+    ///
+    /// Returns [`ProofError::FormatError`] if the point is not valid.
+    pub fn from_compressed(H: &CompressedRistretto) -> Result<BlindedAssetTag, ProofError> {
+        H.decompress()
+            .map(BlindedAssetTag)
+            .ok_or(ProofError::FormatError)
+    }
+
+    /// Compresses the blinded tag.
+    pub fn compress(&self) -> CompressedRistretto {
+        self.0.compress()
+    }
+
+    /// Returns the Pedersen generators for values of this asset, which
+    /// use the blinded tag as the value generator \\(B\\) and the
+    /// other generators of `pc_gens`.
+    ///
+    /// Range proofs created and verified with these generators prove
+    /// the range of values committed with this tag.
+    pub fn pedersen_gens(&self, pc_gens: &PedersenGens) -> PedersenGens {
+        PedersenGens {
+            B: self.0,
+            B_blinding: pc_gens.B_blinding,
+            B_meta: pc_gens.B_meta,
+        }
+    }
+}
+
+/// A proof that an output tag \\(H\\) blinds the same asset as one of
+/// the input tags \\(H\_0, \ldots, H\_{n-1}\\).
+///
+/// The output tag blinds the same asset as \\(H\_i\\) exactly when
+/// \\(D\_i = H - H\_i\\) is a multiple of \\(B\_{blinding}\\).  The
+/// proof is a one-out-of-\\(n\\) Schnorr proof of knowledge of the
+/// discrete logarithm of one of the \\(D\_i\\): the prover simulates
+/// the proofs for the other tags, and the challenges \\(c\_i\\) must
+/// add up to the challenge \\(c\\) derived from the transcript, with
+/// \\[
+///     R\_i = s\_i B\_{blinding} - c\_i D\_i.
+/// \\]
+/// The proof has \\(64n\\) bytes.  The same construction proves set
+/// membership in the [`membership`](::membership) module.
+#[derive(Clone, Debug)]
+pub struct SurjectionProof {
+    /// Challenges \\(c\_i\\), one for each input tag
+    c: Vec<Scalar>,
+    /// Responses \\(s\_i\\), one for each input tag
+    s: Vec<Scalar>,
+}
+
+impl SurjectionProof {
+    /// Creates a proof that `output_tag`, blinded with
+    /// `output_blinding`, blinds the same asset as the input tag at
+    /// `index`, blinded with `input_blinding`.
+    ///
+    /// Returns [`ProofError::CommitmentMismatch`] if `index` is out of
+    /// range, or if the tags do not blind the same asset with these
+    /// blinding factors.
+    pub fn prove(
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        input_tags: &[BlindedAssetTag],
+        output_tag: &BlindedAssetTag,
+        index: usize,
+        input_blinding: &Scalar,
+        output_blinding: &Scalar,
+    ) -> Result<SurjectionProof, ProofError> {
+        let x = output_blinding - input_blinding;
+        let D: Vec<RistrettoPoint> = input_tags.iter().map(|H_i| output_tag.0 - H_i.0).collect();
+
+        commit_statement(transcript, input_tags, output_tag);
+
+        let (c, s) = sigma::prove_one_of_many(pc_gens, transcript, &D, index, &x)?;
+        Ok(SurjectionProof { c, s })
+    }
+
+    /// Verifies that `output_tag` blinds the same asset as one of the
+    /// `input_tags`.
+    pub fn verify(
+        &self,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        input_tags: &[BlindedAssetTag],
+        output_tag: &BlindedAssetTag,
+    ) -> Result<(), ProofError> {
+        if self.c.len() != input_tags.len() {
+            return Err(ProofError::ProofSizeMismatch);
+        }
+
+        commit_statement(transcript, input_tags, output_tag);
+
+        let D: Vec<RistrettoPoint> = input_tags.iter().map(|H_i| output_tag.0 - H_i.0).collect();
+        sigma::verify_one_of_many(pc_gens, transcript, &D, &self.c, &self.s)
+    }
+
+    /// Serializes the proof into a byte array of \\(64n\\) bytes,
+    /// with the challenges followed by the responses.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = scalar::encode_vec(&self.c);
+        buf.extend_from_slice(&scalar::encode_vec(&self.s));
+        buf
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a
+    /// `SurjectionProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<SurjectionProof, ProofError> {
+        if slice.is_empty() || slice.len() % 64 != 0 {
+            return Err(ProofError::FormatError);
+        }
+        let (c, s) = slice.split_at(slice.len() / 2);
+        Ok(SurjectionProof {
+            c: scalar::decode_vec(c)?,
+            s: scalar::decode_vec(s)?,
+        })
+    }
+}
+
+/// Commits the input and output tags of a surjection proof.
+fn commit_statement(
+    transcript: &mut Transcript,
+    input_tags: &[BlindedAssetTag],
+    output_tag: &BlindedAssetTag,
+) {
+    transcript.surjection_domain_sep(input_tags.len() as u64);
+    for H_i in input_tags {
+        transcript.commit_point(b"H_i", &H_i.compress());
+    }
+    transcript.commit_point(b"H", &output_tag.compress());
+}
+
+impl Serialize for SurjectionProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for SurjectionProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct SurjectionProofVisitor;
+
+        impl<'de> Visitor<'de> for SurjectionProofVisitor {
+            type Value = SurjectionProof;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("a valid SurjectionProof")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<SurjectionProof, E>
+            where
+                E: serde::de::Error,
+            {
+                SurjectionProof::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(SurjectionProofVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand;
+
+    use generators::BulletproofGens;
+    use range_proof::RangeProof;
+
+    #[test]
+    fn surjection_proof_hides_the_input() {
+        let pc_gens = PedersenGens::default();
+        let mut rng = rand::thread_rng();
+        let assets: Vec<_> = [b"gold", b"silv", b"bron"]
+            .iter()
+            .map(|id| AssetTag::from_id(&id[..]))
+            .collect();
+        let input_blindings = scalar::random_vec(&mut rng, 3);
+        let input_tags: Vec<_> = assets
+            .iter()
+            .zip(&input_blindings)
+            .map(|(asset, blinding)| asset.blind(&pc_gens, blinding))
+            .collect();
+
+        let output_blinding = Scalar::random(&mut rng);
+        let output_tag = assets[1].blind(&pc_gens, &output_blinding);
+
+        let mut transcript = Transcript::new(b"SurjectionTest");
+        let proof = SurjectionProof::prove(
+            &pc_gens,
+            &mut transcript,
+            &input_tags,
+            &output_tag,
+            1,
+            &input_blindings[1],
+            &output_blinding,
+        ).unwrap();
+        let proof = SurjectionProof::from_bytes(&proof.to_bytes()).unwrap();
+
+        let mut transcript = Transcript::new(b"SurjectionTest");
+        assert!(
+            proof
+                .verify(&pc_gens, &mut transcript, &input_tags, &output_tag)
+                .is_ok()
+        );
+
+        // The proof does not hold for a set without the asset
+        let other_tags = [input_tags[0], input_tags[2], input_tags[0]];
+        let mut transcript = Transcript::new(b"SurjectionTest");
+        assert_eq!(
+            proof.verify(&pc_gens, &mut transcript, &other_tags, &output_tag),
+            Err(ProofError::VerificationError)
+        );
+
+        // The prover cannot claim a different asset
+        let mut transcript = Transcript::new(b"SurjectionTest");
+        let result = SurjectionProof::prove(
+            &pc_gens,
+            &mut transcript,
+            &input_tags,
+            &output_tag,
+            0,
+            &input_blindings[0],
+            &output_blinding,
+        );
+        assert_eq!(result.unwrap_err(), ProofError::CommitmentMismatch);
+    }
+
+    #[test]
+    fn range_proof_over_asset_generators() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+
+        let asset = AssetTag::from_id(b"gold");
+        let tag = asset.blind(&pc_gens, &Scalar::random(&mut rng));
+        let asset_gens = tag.pedersen_gens(&pc_gens);
+        let v_blinding = Scalar::random(&mut rng);
+
+        let mut transcript = Transcript::new(b"AssetRangeTest");
+        let (proof, V) =
+            RangeProof::prove_single(&bp_gens, &asset_gens, &mut transcript, 9, &v_blinding, 32)
+                .unwrap();
+        let expected = tag.0 * Scalar::from(9u64) + v_blinding * pc_gens.B_blinding;
+        assert_eq!(V, expected.compress());
+
+        let mut transcript = Transcript::new(b"AssetRangeTest");
+        assert!(
+            proof
+                .verify_single(&bp_gens, &asset_gens, &mut transcript, &V, 32)
+                .is_ok()
+        );
+
+        // The proof does not verify for another asset
+        let other_gens = AssetTag::from_id(b"silv")
+            .blind(&pc_gens, &Scalar::zero())
+            .pedersen_gens(&pc_gens);
+        let mut transcript = Transcript::new(b"AssetRangeTest");
+        assert!(
+            proof
+                .verify_single(&bp_gens, &other_gens, &mut transcript, &V, 32)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! Command-line tool for creating, verifying and inspecting range proofs.
+//!
+//! This binary is only built with the `cli` feature.  Points, scalars
+//! and proofs are read and written as lowercase hex strings.
+
+#![allow(non_snake_case)]
+
+extern crate bulletproofs;
+extern crate curve25519_dalek;
+extern crate merlin;
+extern crate rand;
+
+use std::env;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::process;
+
+use bulletproofs::{BatchVerifier, BulletproofGens, PedersenGens, RangeProof};
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+
+const USAGE: &str = "\
+Usage:
+    bulletproofs commit <value> [<blinding>]
+    bulletproofs prove <value> <blinding> <bits> [<label>]
+    bulletproofs verify <proof> <commitment>[,<commitment>...] <bits> [<label>]
+    bulletproofs batch-verify <file>
+    bulletproofs inspect <proof>
+
+Values are decimal integers; blindings, commitments and proofs are hex.
+The default transcript label is \"bulletproofs-cli\".
+
+Each line of a batch-verify file has the form
+    <proof> <commitment>[,<commitment>...] <bits> [<label>]
+and empty lines and lines starting with '#' are ignored.";
+
+const DEFAULT_LABEL: &str = "bulletproofs-cli";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    let result = match args.split_first() {
+        Some((&"commit", rest)) => commit(rest),
+        Some((&"prove", rest)) => prove(rest),
+        Some((&"verify", rest)) => verify(rest),
+        Some((&"batch-verify", rest)) => batch_verify(rest),
+        Some((&"inspect", rest)) => inspect(rest),
+        Some((&"help", _)) | Some((&"--help", _)) | Some((&"-h", _)) => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(format!("missing or unknown command\n\n{}", USAGE)),
+    };
+
+    if let Err(message) = result {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+}
+
+fn commit(args: &[&str]) -> Result<(), String> {
+    let (value, blinding) = match args {
+        [value] => (parse_value(value)?, Scalar::random(&mut rand::thread_rng())),
+        [value, blinding] => (parse_value(value)?, parse_scalar(blinding)?),
+        _ => return Err(format!("wrong number of arguments\n\n{}", USAGE)),
+    };
+
+    let V = PedersenGens::default().commit(Scalar::from(value), blinding);
+    println!("commitment: {}", to_hex(V.compress().as_bytes()));
+    println!("blinding:   {}", to_hex(blinding.as_bytes()));
+    Ok(())
+}
+
+fn prove(args: &[&str]) -> Result<(), String> {
+    if args.len() < 3 || args.len() > 4 {
+        return Err(format!("wrong number of arguments\n\n{}", USAGE));
+    }
+    let value = parse_value(args[0])?;
+    let blinding = parse_scalar(args[1])?;
+    let n = parse_bits(args[2])?;
+    let label = args.get(3).cloned().unwrap_or(DEFAULT_LABEL);
+
+    let pc_gens = PedersenGens::default();
+    // The bitsize is padded to a power of two for proving.
+    let bp_gens = BulletproofGens::new(n.next_power_of_two(), 1);
+    let mut transcript = Transcript::new(static_label(label));
+
+    let (proof, V) =
+        RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, value, &blinding, n)
+            .map_err(|e| e.to_string())?;
+    println!("proof:      {}", to_hex(&proof.to_bytes()));
+    println!("commitment: {}", to_hex(V.as_bytes()));
+    Ok(())
+}
+
+fn verify(args: &[&str]) -> Result<(), String> {
+    if args.len() < 3 || args.len() > 4 {
+        return Err(format!("wrong number of arguments\n\n{}", USAGE));
+    }
+    let (proof, value_commitments, n, label) = parse_statement(args)?;
+
+    let pc_gens = PedersenGens::default();
+    // The bitsize and the number of values are padded to a power of two
+    // for verification.
+    let bp_gens = BulletproofGens::new(
+        n.next_power_of_two(),
+        value_commitments.len().next_power_of_two(),
+    );
+    let mut transcript = Transcript::new(label);
+
+    match proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n) {
+        Ok(()) => {
+            println!("valid");
+            Ok(())
+        }
+        Err(e) => Err(format!("invalid: {}", e)),
+    }
+}
+
+fn batch_verify(args: &[&str]) -> Result<(), String> {
+    let path = match args {
+        [path] => path,
+        _ => return Err(format!("wrong number of arguments\n\n{}", USAGE)),
+    };
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mut statements = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("{}: {}", path, e))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 || fields.len() > 4 {
+            return Err(format!("{}:{}: wrong number of fields", path, i + 1));
+        }
+        let statement =
+            parse_statement(&fields).map_err(|e| format!("{}:{}: {}", path, i + 1, e))?;
+        statements.push(statement);
+    }
+
+    let max_n = statements.iter().map(|s| s.2).max().unwrap_or(8);
+    let max_m = statements.iter().map(|s| s.1.len()).max().unwrap_or(1);
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(max_n.next_power_of_two(), max_m.next_power_of_two());
+
+    let count = statements.len();
+    let mut batch = BatchVerifier::new(&bp_gens, &pc_gens);
+    for (proof, value_commitments, n, label) in statements {
+        batch.add(proof, Transcript::new(label), value_commitments, n);
+    }
+
+    match batch.verify_all() {
+        Ok(()) => {
+            println!("all {} proofs valid", count);
+            Ok(())
+        }
+        Err(e) => Err(format!("batch invalid: {}", e)),
+    }
+}
+
+fn inspect(args: &[&str]) -> Result<(), String> {
+    let bytes = match args {
+        [proof] => from_hex(proof)?,
+        _ => return Err(format!("wrong number of arguments\n\n{}", USAGE)),
+    };
+    let proof = RangeProof::from_bytes(&bytes).map_err(|e| e.to_string())?;
+    let lg_nm = proof.implied_log_size();
+
+    println!("size:         {} bytes", bytes.len());
+    println!("n * m:        {}", 1u64 << lg_nm);
+
+    // See `RangeProof::to_bytes` for the layout.
+    let names = ["A", "S", "T_1", "T_2", "t_x", "t_x_blinding", "e_blinding"];
+    let mut chunks = bytes.chunks(32);
+    for name in names.iter() {
+        let chunk = chunks.next().expect("checked by from_bytes");
+        println!("{:<13} {}", format!("{}:", name), to_hex(chunk));
+    }
+    for j in 0..lg_nm {
+        let L = chunks.next().expect("checked by from_bytes");
+        let R = chunks.next().expect("checked by from_bytes");
+        println!("{:<13} {}", format!("L_{}:", j), to_hex(L));
+        println!("{:<13} {}", format!("R_{}:", j), to_hex(R));
+    }
+    for name in ["a", "b"].iter() {
+        let chunk = chunks.next().expect("checked by from_bytes");
+        println!("{:<13} {}", format!("{}:", name), to_hex(chunk));
+    }
+    Ok(())
+}
+
+/// Parses `<proof> <commitment>[,<commitment>...] <bits> [<label>]`.
+fn parse_statement(
+    args: &[&str],
+) -> Result<(RangeProof, Vec<CompressedRistretto>, usize, &'static [u8]), String> {
+    let proof = RangeProof::from_bytes(&from_hex(args[0])?).map_err(|e| e.to_string())?;
+    let value_commitments = args[1]
+        .split(',')
+        .map(parse_point)
+        .collect::<Result<Vec<_>, _>>()?;
+    let n = parse_bits(args[2])?;
+    let label = static_label(args.get(3).cloned().unwrap_or(DEFAULT_LABEL));
+    Ok((proof, value_commitments, n, label))
+}
+
+fn parse_value(s: &str) -> Result<u64, String> {
+    s.parse()
+        .map_err(|_| format!("invalid value '{}', expected an unsigned integer", s))
+}
+
+fn parse_bits(s: &str) -> Result<usize, String> {
+    match s.parse() {
+        Ok(n) if n > 0 && n <= 64 => Ok(n),
+        _ => Err(format!("invalid bitsize '{}', expected 1 to 64", s)),
+    }
+}
+
+fn parse_scalar(s: &str) -> Result<Scalar, String> {
+    read32(&from_hex(s)?)
+        .and_then(Scalar::from_canonical_bytes)
+        .ok_or_else(|| format!("invalid scalar '{}'", s))
+}
+
+fn parse_point(s: &str) -> Result<CompressedRistretto, String> {
+    read32(&from_hex(s)?)
+        .map(CompressedRistretto)
+        .ok_or_else(|| format!("invalid point '{}'", s))
+}
+
+fn read32(bytes: &[u8]) -> Option<[u8; 32]> {
+    if bytes.len() != 32 {
+        return None;
+    }
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(bytes);
+    Some(buf)
+}
+
+/// Transcript labels must be `'static`; the process only creates a
+/// handful of them, so leaking is fine.
+fn static_label(label: &str) -> &'static [u8] {
+    Box::leak(label.as_bytes().to_vec().into_boxed_slice())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Result<Vec<u8>, String> {
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return Err(format!("invalid hex string '{}'", s));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| format!("invalid hex string '{}'", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn commands_accept_non_power_of_two_bitsize() {
+        let blinding = Scalar::random(&mut rand::thread_rng());
+        let blinding_hex = to_hex(blinding.as_bytes());
+        assert_eq!(prove(&["1000", &blinding_hex, "48"]), Ok(()));
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut transcript = Transcript::new(DEFAULT_LABEL.as_bytes());
+        let (proof, V) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 1000, &blinding, 48)
+                .unwrap();
+        let proof_hex = to_hex(&proof.to_bytes());
+        let V_hex = to_hex(V.as_bytes());
+        assert_eq!(verify(&[&proof_hex, &V_hex, "48"]), Ok(()));
+
+        let path = env::temp_dir().join("bulletproofs-cli-batch-test.txt");
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "{} {} 48", proof_hex, V_hex).unwrap();
+        writeln!(file, "{} {} 48", proof_hex, V_hex).unwrap();
+        drop(file);
+        let result = batch_verify(&[path.to_str().unwrap()]);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn commands_accept_non_power_of_two_aggregation() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 4);
+        let values = [1u64, 2, 3];
+        let blindings: Vec<Scalar> = (0..3)
+            .map(|_| Scalar::random(&mut rand::thread_rng()))
+            .collect();
+        let mut transcript = Transcript::new(DEFAULT_LABEL.as_bytes());
+        let (proof, value_commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            32,
+        ).unwrap();
+        let proof_hex = to_hex(&proof.to_bytes());
+        let commitments_hex = value_commitments
+            .iter()
+            .map(|V| to_hex(V.as_bytes()))
+            .collect::<Vec<_>>()
+            .join(",");
+        assert_eq!(verify(&[&proof_hex, &commitments_hex, "32"]), Ok(()));
+
+        let path = env::temp_dir().join("bulletproofs-cli-aggregation-test.txt");
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "{} {} 32", proof_hex, commitments_hex).unwrap();
+        drop(file);
+        let result = batch_verify(&[path.to_str().unwrap()]);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(result, Ok(()));
+    }
+}
@@ -0,0 +1,312 @@
+//! The `circuit_proof` module contains an API for proving that a
+//! committed assignment satisfies an arithmetic circuit, as described
+//! in Section 5 of the Bulletproofs paper.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+
+use errors::R1CSError;
+use generators::{BulletproofGens, PedersenGens};
+use r1cs::{ConstraintSystem, LinearCombination, Prover, R1CSProof, Variable, Verifier};
+use transcript::TranscriptProtocol;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// An arithmetic circuit with \\(n\\) multiplication gates, \\(m\\)
+/// committed values and \\(Q\\) linear constraints.
+///
+/// An assignment \\(\mathbf{a}\_L, \mathbf{a}\_R, \mathbf{a}\_O \in
+/// {\mathbb Z\_p}^n\\) to the gates and \\(\mathbf{v} \in {\mathbb
+/// Z\_p}^m\\) to the committed values satisfies the circuit when
+/// \\[
+/// \begin{aligned}
+///   \mathbf{a}\_L \circ \mathbf{a}\_R &= \mathbf{a}\_O, \\\\
+///   W\_L \mathbf{a}\_L + W\_R \mathbf{a}\_R + W\_O \mathbf{a}\_O
+///     &= W\_V \mathbf{v} + \mathbf{c}.
+/// \end{aligned}
+/// \\]
+/// Each matrix is stored as a vector of its \\(Q\\) rows.
+#[derive(Clone, Debug)]
+pub struct ArithmeticCircuit {
+    /// The number \\(n\\) of multiplication gates.
+    pub n: usize,
+    /// The number \\(m\\) of committed values.
+    pub m: usize,
+    /// The \\(Q \times n\\) weights of the left gate inputs.
+    pub W_L: Vec<Vec<Scalar>>,
+    /// The \\(Q \times n\\) weights of the right gate inputs.
+    pub W_R: Vec<Vec<Scalar>>,
+    /// The \\(Q \times n\\) weights of the gate outputs.
+    pub W_O: Vec<Vec<Scalar>>,
+    /// The \\(Q \times m\\) weights of the committed values.
+    pub W_V: Vec<Vec<Scalar>>,
+    /// The \\(Q\\) constant terms.
+    pub c: Vec<Scalar>,
+}
+
+impl ArithmeticCircuit {
+    /// Returns the number \\(Q\\) of linear constraints.
+    pub fn num_constraints(&self) -> usize {
+        self.c.len()
+    }
+
+    /// Checks that every matrix has \\(Q\\) rows of the right width.
+    fn check_dimensions(&self) -> Result<(), R1CSError> {
+        let q = self.num_constraints();
+        let rows_ok = |W: &Vec<Vec<Scalar>>, width: usize| {
+            W.len() == q && W.iter().all(|row| row.len() == width)
+        };
+        if rows_ok(&self.W_L, self.n)
+            && rows_ok(&self.W_R, self.n)
+            && rows_ok(&self.W_O, self.n)
+            && rows_ok(&self.W_V, self.m)
+        {
+            Ok(())
+        } else {
+            Err(R1CSError::InvalidCircuit)
+        }
+    }
+
+    /// Adds the gates and linear constraints of the circuit to `cs`,
+    /// given the variables for the committed values.
+    fn synthesize<CS: ConstraintSystem>(
+        &self,
+        cs: &mut CS,
+        V: &[Variable],
+        assignment: Option<(&[Scalar], &[Scalar])>,
+    ) -> Result<(), R1CSError> {
+        let mut gates = Vec::with_capacity(self.n);
+        for i in 0..self.n {
+            gates.push(cs.allocate_multiplier(assignment.map(|(a_L, a_R)| (a_L[i], a_R[i])))?);
+        }
+
+        for q in 0..self.num_constraints() {
+            let lc: LinearCombination = gates
+                .iter()
+                .enumerate()
+                .flat_map(|(i, &(l, r, o))| {
+                    vec![
+                        (l, self.W_L[q][i]),
+                        (r, self.W_R[q][i]),
+                        (o, self.W_O[q][i]),
+                    ]
+                }).chain(V.iter().zip(self.W_V[q].iter()).map(|(&V_j, w)| (V_j, -w)))
+                .chain(Some((Variable::One, -self.c[q])))
+                .collect();
+            cs.constrain(lc);
+        }
+
+        Ok(())
+    }
+}
+
+/// A proof that a set of committed values, together with a secret
+/// assignment to the multiplication gates, satisfies an
+/// [`ArithmeticCircuit`].
+///
+/// The proof is an [`R1CSProof`] for the constraint system built from
+/// the circuit, so it has the same size: \\(2 \lg n + 13\\) 32-byte
+/// elements, where \\(n\\) is rounded up to a power of two.
+#[derive(Clone, Debug)]
+pub struct CircuitProof(R1CSProof);
+
+impl CircuitProof {
+    /// Creates a proof that the committed values `v` and the gate
+    /// inputs `a_L`, `a_R` satisfy `circuit`.
+    ///
+    /// The gate outputs are computed as \\(\mathbf{a}\_O = \mathbf{a}\_L
+    /// \circ \mathbf{a}\_R\\).  Returns the proof together with the
+    /// commitments to `v` under the blinding factors `v_blinding`.
+    pub fn prove(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        circuit: &ArithmeticCircuit,
+        a_L: &[Scalar],
+        a_R: &[Scalar],
+        v: &[Scalar],
+        v_blinding: &[Scalar],
+    ) -> Result<(CircuitProof, Vec<CompressedRistretto>), R1CSError> {
+        circuit.check_dimensions()?;
+        if a_L.len() != circuit.n
+            || a_R.len() != circuit.n
+            || v.len() != circuit.m
+            || v_blinding.len() != circuit.m
+        {
+            return Err(R1CSError::InvalidCircuit);
+        }
+
+        transcript.circuit_domain_sep(
+            circuit.n as u64,
+            circuit.m as u64,
+            circuit.num_constraints() as u64,
+        );
+
+        let mut prover = Prover::new(bp_gens, pc_gens, transcript);
+
+        let (commitments, vars): (Vec<_>, Vec<_>) = v
+            .iter()
+            .zip(v_blinding.iter())
+            .map(|(v_j, v_blinding_j)| prover.commit(*v_j, *v_blinding_j))
+            .unzip();
+
+        circuit.synthesize(&mut prover, &vars, Some((a_L, a_R)))?;
+
+        let proof = prover.prove()?;
+
+        Ok((CircuitProof(proof), commitments))
+    }
+
+    /// Verifies that the values committed in `commitments` satisfy
+    /// `circuit`.
+    pub fn verify(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        circuit: &ArithmeticCircuit,
+        commitments: &[CompressedRistretto],
+    ) -> Result<(), R1CSError> {
+        circuit.check_dimensions()?;
+        if commitments.len() != circuit.m {
+            return Err(R1CSError::InvalidCircuit);
+        }
+
+        transcript.circuit_domain_sep(
+            circuit.n as u64,
+            circuit.m as u64,
+            circuit.num_constraints() as u64,
+        );
+
+        let mut verifier = Verifier::new(bp_gens, pc_gens, transcript);
+
+        let vars: Vec<_> = commitments.iter().map(|V| verifier.commit(*V)).collect();
+
+        circuit.synthesize(&mut verifier, &vars, None)?;
+
+        verifier.verify(&self.0)
+    }
+
+    /// Serializes the proof into a byte array, using the encoding of
+    /// [`R1CSProof::to_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `CircuitProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<CircuitProof, R1CSError> {
+        R1CSProof::from_bytes(slice).map(CircuitProof)
+    }
+}
+
+impl Serialize for CircuitProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CircuitProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        R1CSProof::deserialize(deserializer).map(CircuitProof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand;
+
+    /// A circuit for committed \\(v\_0, v\_1, v\_2\\) with
+    /// \\(v\_0 v\_1 = v\_2\\) and \\(v\_0 + v\_1 = 7\\).
+    fn example_circuit() -> ArithmeticCircuit {
+        let one = Scalar::one();
+        let zero = Scalar::zero();
+        ArithmeticCircuit {
+            n: 1,
+            m: 3,
+            W_L: vec![vec![one], vec![zero], vec![zero], vec![one]],
+            W_R: vec![vec![zero], vec![one], vec![zero], vec![one]],
+            W_O: vec![vec![zero], vec![zero], vec![one], vec![zero]],
+            W_V: vec![
+                vec![one, zero, zero],
+                vec![zero, one, zero],
+                vec![zero, zero, one],
+                vec![zero, zero, zero],
+            ],
+            c: vec![zero, zero, zero, Scalar::from(7u64)],
+        }
+    }
+
+    fn circuit_proof(a: u64, b: u64, c: u64) -> Result<(), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let circuit = example_circuit();
+
+        let mut rng = rand::thread_rng();
+        let v: Vec<Scalar> = vec![a.into(), b.into(), c.into()];
+        let v_blinding: Vec<Scalar> = (0..3).map(|_| Scalar::random(&mut rng)).collect();
+
+        let (proof, commitments) = CircuitProof::prove(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"CircuitTest"),
+            &circuit,
+            &v[0..1],
+            &v[1..2],
+            &v,
+            &v_blinding,
+        )?;
+
+        let proof = CircuitProof::from_bytes(&proof.to_bytes())?;
+
+        proof.verify(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"CircuitTest"),
+            &circuit,
+            &commitments,
+        )
+    }
+
+    #[test]
+    fn prove_and_verify_circuit() {
+        assert!(circuit_proof(3, 4, 12).is_ok());
+        assert!(circuit_proof(3, 4, 13).is_err());
+        assert!(circuit_proof(3, 5, 15).is_err());
+    }
+
+    #[test]
+    fn rejects_inconsistent_dimensions() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let mut circuit = example_circuit();
+        circuit.W_O.pop();
+
+        let v = vec![Scalar::one(); 3];
+        assert_eq!(
+            CircuitProof::prove(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"CircuitTest"),
+                &circuit,
+                &v[0..1],
+                &v[1..2],
+                &v,
+                &v,
+            ).map(|_| ()),
+            Err(R1CSError::InvalidCircuit)
+        );
+    }
+}
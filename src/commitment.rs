@@ -0,0 +1,224 @@
+//! The `commitment` module contains typed Pedersen commitments and
+//! their openings, with the homomorphic arithmetic they support.
+
+#![allow(non_snake_case)]
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+use clear_on_drop::clear::Clear;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+use errors::ProofError;
+
+/// A Pedersen commitment \\(V = vB + \tilde{v}B\_{blinding}\\).
+///
+/// Commitments can be added, subtracted and multiplied by scalars,
+/// and the result commits to the same combination of the values and
+/// blinding factors, so that it is opened by the same combination of
+/// the [`Opening`]s.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Commitment(pub RistrettoPoint);
+
+impl Commitment {
+    /// Decompresses a commitment from its wire format.
+    ///
+    /// Returns [`ProofError::FormatError`] if the point is not valid.
+    pub fn from_compressed(V: &CompressedRistretto) -> Result<Commitment, ProofError> {
+        V.decompress()
+            .map(Commitment)
+            .ok_or(ProofError::FormatError)
+    }
+
+    /// Compresses the commitment, in the format taken by the
+    /// verifiers.
+    pub fn compress(&self) -> CompressedRistretto {
+        self.0.compress()
+    }
+}
+
+impl From<RistrettoPoint> for Commitment {
+    fn from(point: RistrettoPoint) -> Commitment {
+        Commitment(point)
+    }
+}
+
+impl Add for Commitment {
+    type Output = Commitment;
+
+    fn add(self, rhs: Commitment) -> Commitment {
+        Commitment(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Commitment {
+    type Output = Commitment;
+
+    fn sub(self, rhs: Commitment) -> Commitment {
+        Commitment(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Commitment {
+    type Output = Commitment;
+
+    fn neg(self) -> Commitment {
+        Commitment(-self.0)
+    }
+}
+
+impl Mul<Scalar> for Commitment {
+    type Output = Commitment;
+
+    fn mul(self, rhs: Scalar) -> Commitment {
+        Commitment(self.0 * rhs)
+    }
+}
+
+/// The opening \\((v, \tilde{v})\\) of a [`Commitment`].
+///
+/// The value is kept as a `Scalar`, so that the arithmetic on openings
+/// matches the arithmetic on commitments exactly, including
+/// wrap-around modulo the group order.  The opening is cleared when
+/// dropped.
+#[derive(Clone, Debug)]
+pub struct Opening {
+    /// The committed value \\(v\\)
+    pub value: Scalar,
+    /// The blinding factor \\(\tilde{v}\\)
+    pub blinding: Scalar,
+}
+
+impl Opening {
+    /// Creates the opening of a commitment to the integer `value`.
+    pub fn new(value: u64, blinding: Scalar) -> Opening {
+        Opening {
+            value: Scalar::from(value),
+            blinding,
+        }
+    }
+}
+
+impl Drop for Opening {
+    fn drop(&mut self) {
+        self.value.clear();
+        self.blinding.clear();
+    }
+}
+
+impl<'a, 'b> Add<&'b Opening> for &'a Opening {
+    type Output = Opening;
+
+    fn add(self, rhs: &'b Opening) -> Opening {
+        Opening {
+            value: self.value + rhs.value,
+            blinding: self.blinding + rhs.blinding,
+        }
+    }
+}
+
+impl Add for Opening {
+    type Output = Opening;
+
+    fn add(self, rhs: Opening) -> Opening {
+        &self + &rhs
+    }
+}
+
+impl<'a, 'b> Sub<&'b Opening> for &'a Opening {
+    type Output = Opening;
+
+    fn sub(self, rhs: &'b Opening) -> Opening {
+        Opening {
+            value: self.value - rhs.value,
+            blinding: self.blinding - rhs.blinding,
+        }
+    }
+}
+
+impl Sub for Opening {
+    type Output = Opening;
+
+    fn sub(self, rhs: Opening) -> Opening {
+        &self - &rhs
+    }
+}
+
+impl<'a> Neg for &'a Opening {
+    type Output = Opening;
+
+    fn neg(self) -> Opening {
+        Opening {
+            value: -self.value,
+            blinding: -self.blinding,
+        }
+    }
+}
+
+impl Neg for Opening {
+    type Output = Opening;
+
+    fn neg(self) -> Opening {
+        -&self
+    }
+}
+
+impl<'a> Mul<Scalar> for &'a Opening {
+    type Output = Opening;
+
+    fn mul(self, rhs: Scalar) -> Opening {
+        Opening {
+            value: self.value * rhs,
+            blinding: self.blinding * rhs,
+        }
+    }
+}
+
+impl Mul<Scalar> for Opening {
+    type Output = Opening;
+
+    fn mul(self, rhs: Scalar) -> Opening {
+        &self * rhs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use generators::PedersenGens;
+    use rand;
+
+    #[test]
+    fn arithmetic_is_homomorphic() {
+        let pc_gens = PedersenGens::default();
+        let mut rng = rand::thread_rng();
+        let a = Opening::new(100, Scalar::random(&mut rng));
+        let b = Opening::new(42, Scalar::random(&mut rng));
+        let w = Scalar::from(3u64);
+
+        let (A, B) = (pc_gens.commit_opening(&a), pc_gens.commit_opening(&b));
+
+        assert!(pc_gens.opens_to(&(A + B), &(&a + &b)));
+        assert!(pc_gens.opens_to(&(A - B), &(&a - &b)));
+        assert!(pc_gens.opens_to(&(-A), &(-&a)));
+        assert!(pc_gens.opens_to(&(A * w + B), &(&a * w + b.clone())));
+        assert!(!pc_gens.opens_to(&(A + B), &(&a - &b)));
+
+        let difference = &a - &b;
+        assert_eq!(difference.value, Scalar::from(58u64));
+    }
+
+    #[test]
+    fn compressed_round_trip() {
+        let pc_gens = PedersenGens::default();
+        let A = pc_gens.commit_opening(&Opening::new(7, Scalar::from(9u64)));
+        assert_eq!(Commitment::from_compressed(&A.compress()), Ok(A));
+
+        let invalid = CompressedRistretto([0xff; 32]);
+        assert_eq!(
+            Commitment::from_compressed(&invalid),
+            Err(ProofError::FormatError)
+        );
+    }
+}
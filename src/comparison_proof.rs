@@ -0,0 +1,198 @@
+//! The `comparison_proof` module contains an API for proving that one
+//! committed value is at most another.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::RangeProof;
+use transcript::TranscriptProtocol;
+
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A proof that the values \\(a\\) and \\(b\\) committed in
+/// \\(C\_a\\) and \\(C\_b\\) satisfy \\(a \leq b\\).
+///
+/// This is a 64-bit range proof for the difference
+/// \\(C\_b - C\_a\\), which is a commitment to \\(b - a\\) with
+/// blinding factor \\(\tilde{b} - \tilde{a}\\).  It shows that
+/// \\(b - a\\) lies in \\([0, 2^{64})\\), which implies
+/// \\(a \leq b\\) when both values are known to be 64-bit, for
+/// instance because they carry range proofs of their own.
+#[derive(Clone, Debug)]
+pub struct ComparisonProof {
+    range_proof: RangeProof,
+}
+
+impl ComparisonProof {
+    /// Creates a proof that `a` is at most `b`, given the openings of
+    /// their commitments, and returns the proof together with the
+    /// commitments \\(C\_a\\) and \\(C\_b\\).
+    ///
+    /// Returns [`ProofError::InvalidRange`] if `a` is greater than
+    /// `b`.
+    pub fn prove(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        a: u64,
+        a_blinding: &Scalar,
+        b: u64,
+        b_blinding: &Scalar,
+    ) -> Result<(ComparisonProof, CompressedRistretto, CompressedRistretto), ProofError> {
+        if a > b {
+            return Err(ProofError::InvalidRange);
+        }
+        let C_a = pc_gens.commit(Scalar::from(a), *a_blinding).compress();
+        let C_b = pc_gens.commit(Scalar::from(b), *b_blinding).compress();
+
+        transcript.comparison_domain_sep();
+        transcript.commit_point(b"C_a", &C_a);
+        transcript.commit_point(b"C_b", &C_b);
+
+        let (range_proof, _) = RangeProof::prove_single(
+            bp_gens,
+            pc_gens,
+            transcript,
+            b - a,
+            &(b_blinding - a_blinding),
+            64,
+        )?;
+        Ok((ComparisonProof { range_proof }, C_a, C_b))
+    }
+
+    /// Verifies that the value committed in `C_a` is at most the value
+    /// committed in `C_b`.
+    pub fn verify(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        C_a: &CompressedRistretto,
+        C_b: &CompressedRistretto,
+    ) -> Result<(), ProofError> {
+        transcript.comparison_domain_sep();
+        transcript.commit_point(b"C_a", C_a);
+        transcript.commit_point(b"C_b", C_b);
+
+        let A = C_a.decompress().ok_or(ProofError::FormatError)?;
+        let B = C_b.decompress().ok_or(ProofError::FormatError)?;
+        let difference = (B - A).compress();
+
+        self.range_proof
+            .verify_single(bp_gens, pc_gens, transcript, &difference, 64)
+    }
+
+    /// Serializes the proof into a byte array, in the format of
+    /// [`RangeProof::to_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.range_proof.to_bytes()
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a
+    /// `ComparisonProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<ComparisonProof, ProofError> {
+        let range_proof = RangeProof::from_bytes(slice)?;
+        Ok(ComparisonProof { range_proof })
+    }
+}
+
+impl Serialize for ComparisonProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for ComparisonProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ComparisonProofVisitor;
+
+        impl<'de> Visitor<'de> for ComparisonProofVisitor {
+            type Value = ComparisonProof;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("a valid ComparisonProof")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<ComparisonProof, E>
+            where
+                E: serde::de::Error,
+            {
+                ComparisonProof::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(ComparisonProofVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand;
+
+    fn prove(
+        a: u64,
+        b: u64,
+    ) -> Result<(ComparisonProof, CompressedRistretto, CompressedRistretto), ProofError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut rng = rand::thread_rng();
+        let (a_blinding, b_blinding) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+
+        let mut transcript = Transcript::new(b"ComparisonTest");
+        ComparisonProof::prove(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            a,
+            &a_blinding,
+            b,
+            &b_blinding,
+        )
+    }
+
+    #[test]
+    fn prove_and_verify() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+
+        for &(a, b) in &[(3, 5), (7, 7), (0, u64::max_value())] {
+            let (proof, C_a, C_b) = prove(a, b).unwrap();
+            let proof = ComparisonProof::from_bytes(&proof.to_bytes()).unwrap();
+
+            let mut transcript = Transcript::new(b"ComparisonTest");
+            assert!(
+                proof
+                    .verify(&bp_gens, &pc_gens, &mut transcript, &C_a, &C_b)
+                    .is_ok()
+            );
+
+            let mut transcript = Transcript::new(b"ComparisonTest");
+            assert!(
+                proof
+                    .verify(&bp_gens, &pc_gens, &mut transcript, &C_b, &C_a)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_greater_value() {
+        assert_eq!(prove(6, 5).unwrap_err(), ProofError::InvalidRange);
+    }
+}
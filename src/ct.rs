@@ -0,0 +1,342 @@
+//! The `ct` module contains a builder and a batch verifier for
+//! confidential transactions, whose amounts are hidden in Pedersen
+//! commitments.
+//!
+//! A transaction spends input commitments and creates output
+//! commitments.  It carries a [`BalanceProof`] that the inputs pay for
+//! the outputs and a public fee, and an aggregated [`RangeProof`] that
+//! each output amount is in \\([0, 2^n)\\), so that no output can
+//! wrap around the group order to create money.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand;
+
+use commitment::Opening;
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::{BatchTerms, RangeProof};
+use sigma::BalanceProof;
+
+/// A confidential transaction, as created by [`TxBuilder`].
+#[derive(Clone, Debug)]
+pub struct Transaction {
+    /// Commitments to the amounts spent by the transaction
+    pub inputs: Vec<CompressedRistretto>,
+    /// Commitments to the amounts created by the transaction
+    pub outputs: Vec<CompressedRistretto>,
+    /// The public fee
+    pub fee: u64,
+    /// The excess commitment
+    /// \\(E = \sum\_i I\_i - \sum\_j O\_j - f B\\), a commitment to zero
+    pub excess: CompressedRistretto,
+    /// Proof that the excess commits to zero
+    pub balance_proof: BalanceProof,
+    /// Aggregated range proof for the outputs
+    pub range_proof: RangeProof,
+}
+
+/// Creates a [`Transaction`] from the openings of its inputs, the
+/// amounts of its outputs and its fee.
+///
+/// The builder chooses random blinding factors for the outputs, and
+/// returns their openings together with the transaction, so that the
+/// recipients can spend them.  The balance proof and then the range
+/// proof are created on the transcript passed to
+/// [`TxBuilder::build`].
+///
+/// # Example
+/// ```
+/// extern crate rand;
+/// use rand::thread_rng;
+///
+/// extern crate curve25519_dalek;
+/// use curve25519_dalek::scalar::Scalar;
+///
+/// extern crate merlin;
+/// use merlin::Transcript;
+///
+/// extern crate bulletproofs;
+/// use bulletproofs::ct::{TxBuilder, TxVerifier};
+/// use bulletproofs::{BulletproofGens, Opening, PedersenGens};
+///
+/// # fn main() {
+/// let pc_gens = PedersenGens::default();
+/// let bp_gens = BulletproofGens::new(64, 2);
+///
+/// let input = Opening::new(100, Scalar::random(&mut thread_rng()));
+/// let input_commitment = pc_gens.commit_opening(&input).compress();
+///
+/// let (tx, _output_openings) = TxBuilder::new(&bp_gens, &pc_gens)
+///     .input(input_commitment, input)
+///     .output(70)
+///     .output(25)
+///     .fee(5)
+///     .build(&mut Transcript::new(b"doctest example"))
+///     .expect("A real program could handle errors");
+///
+/// let mut verifier = TxVerifier::new(&bp_gens, &pc_gens);
+/// verifier.add(tx, Transcript::new(b"doctest example"), 64);
+/// assert!(verifier.verify_all().is_ok());
+/// # }
+/// ```
+pub struct TxBuilder<'g> {
+    bp_gens: &'g BulletproofGens,
+    pc_gens: &'g PedersenGens,
+    n: usize,
+    input_commitments: Vec<CompressedRistretto>,
+    input_openings: Vec<Opening>,
+    output_values: Vec<u64>,
+    fee: u64,
+}
+
+impl<'g> TxBuilder<'g> {
+    /// Creates a builder for a transaction with 64-bit outputs, no
+    /// inputs or outputs and no fee.
+    pub fn new(bp_gens: &'g BulletproofGens, pc_gens: &'g PedersenGens) -> Self {
+        TxBuilder {
+            bp_gens,
+            pc_gens,
+            n: 64,
+            input_commitments: Vec::new(),
+            input_openings: Vec::new(),
+            output_values: Vec::new(),
+            fee: 0,
+        }
+    }
+
+    /// Sets the bitsize `n` of the range proof for the outputs.
+    pub fn bitsize(mut self, n: usize) -> Self {
+        self.n = n;
+        self
+    }
+
+    /// Adds an input `commitment` with its `opening`.
+    pub fn input(mut self, commitment: CompressedRistretto, opening: Opening) -> Self {
+        self.input_commitments.push(commitment);
+        self.input_openings.push(opening);
+        self
+    }
+
+    /// Adds an output with amount `value`.
+    pub fn output(mut self, value: u64) -> Self {
+        self.output_values.push(value);
+        self
+    }
+
+    /// Sets the public fee.
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Creates the transaction, returning it together with the
+    /// openings of the outputs, in the order the outputs were added.
+    ///
+    /// Returns [`ProofError::CommitmentMismatch`] if an input
+    /// commitment does not match its opening, and
+    /// [`ProofError::Unbalanced`] if the inputs do not equal the
+    /// outputs plus the fee.
+    pub fn build(
+        self,
+        transcript: &mut Transcript,
+    ) -> Result<(Transaction, Vec<Opening>), ProofError> {
+        let pc_gens = self.pc_gens;
+        for (I, opening) in self.input_commitments.iter().zip(&self.input_openings) {
+            if pc_gens.commit_opening(opening).compress() != *I {
+                return Err(ProofError::CommitmentMismatch);
+            }
+        }
+
+        let mut rng = rand::thread_rng();
+        let output_openings: Vec<Opening> = self
+            .output_values
+            .iter()
+            .map(|&v| Opening::new(v, Scalar::random(&mut rng)))
+            .collect();
+
+        let (balance_proof, excess) = BalanceProof::prove(
+            pc_gens,
+            transcript,
+            &self.input_openings,
+            &output_openings,
+            self.fee,
+        )?;
+        let (range_proof, outputs) = RangeProof::prove_openings(
+            self.bp_gens,
+            pc_gens,
+            transcript,
+            &output_openings,
+            self.n,
+        )?;
+
+        let tx = Transaction {
+            inputs: self.input_commitments,
+            outputs,
+            fee: self.fee,
+            excess: excess.compress(),
+            balance_proof,
+            range_proof,
+        };
+        Ok((tx, output_openings))
+    }
+}
+
+/// Accumulates transactions and verifies all of them at once.
+///
+/// The balance and range proofs of all transactions, and the check
+/// that each excess commitment matches the inputs, outputs and fee of
+/// its transaction, are combined with random weights into a single
+/// multiscalar multiplication, like in the
+/// [`BatchVerifier`](::BatchVerifier) for range proofs.  The
+/// `bp_gens` must be large enough for the largest bitsize and number
+/// of outputs in the batch.
+pub struct TxVerifier<'g> {
+    bp_gens: &'g BulletproofGens,
+    pc_gens: &'g PedersenGens,
+    entries: Vec<(Transaction, Transcript, usize)>,
+}
+
+impl<'g> TxVerifier<'g> {
+    /// Creates an empty verifier using the given generators.
+    pub fn new(bp_gens: &'g BulletproofGens, pc_gens: &'g PedersenGens) -> Self {
+        TxVerifier {
+            bp_gens,
+            pc_gens,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Queues a transaction `tx` whose outputs are proven to be in
+    /// the range \\([0, 2^n)\\).
+    ///
+    /// The `transcript` must have the same initial state as the one
+    /// passed to [`TxBuilder::build`].
+    pub fn add(&mut self, tx: Transaction, transcript: Transcript, n: usize) {
+        self.entries.push((tx, transcript, n));
+    }
+
+    /// Returns the number of queued transactions.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no transactions are queued.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Verifies all queued transactions with one combined check.
+    ///
+    /// Returns `Ok(())` if all transactions are valid.  An empty batch
+    /// is trivially valid.
+    pub fn verify_all(self) -> Result<(), ProofError> {
+        let mut rng = rand::thread_rng();
+        let mut batch = BatchTerms::new();
+
+        for (tx, mut transcript, n) in self.entries {
+            let c = tx.balance_proof.challenge(
+                &mut transcript,
+                &tx.inputs,
+                &tx.outputs,
+                tx.fee,
+                &tx.excess,
+            );
+            let terms =
+                tx.range_proof
+                    .verification_terms(self.bp_gens, &mut transcript, &tx.outputs, n)?;
+            batch.add_terms(&terms, Scalar::random(&mut rng));
+
+            // w_1 (s B_blinding - R - c E) = 0
+            let w_1 = Scalar::random(&mut rng);
+            batch.B_blinding_scalar += w_1 * tx.balance_proof.s;
+            batch.add_point(-w_1, tx.balance_proof.R);
+
+            // w_2 (sum(I) - sum(O) - fee B - E) = 0
+            let w_2 = Scalar::random(&mut rng);
+            batch.B_scalar -= w_2 * Scalar::from(tx.fee);
+            for I in tx.inputs {
+                batch.add_point(w_2, I);
+            }
+            for O in tx.outputs {
+                batch.add_point(-w_2, O);
+            }
+            batch.add_point(-(w_1 * c + w_2), tx.excess);
+        }
+
+        batch.verify(self.bp_gens, self.pc_gens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        inputs: &[u64],
+        outputs: &[u64],
+        fee: u64,
+    ) -> Result<(Transaction, Vec<Opening>), ProofError> {
+        let mut rng = rand::thread_rng();
+        let mut builder = TxBuilder::new(bp_gens, pc_gens).bitsize(32).fee(fee);
+        for &v in inputs {
+            let opening = Opening::new(v, Scalar::random(&mut rng));
+            builder = builder.input(pc_gens.commit_opening(&opening).compress(), opening);
+        }
+        for &v in outputs {
+            builder = builder.output(v);
+        }
+        builder.build(&mut Transcript::new(b"TxTest"))
+    }
+
+    #[test]
+    fn build_and_verify_batch() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+
+        let (tx_1, openings) = build(&bp_gens, &pc_gens, &[100], &[60, 39], 1).unwrap();
+        let (tx_2, _) = build(&bp_gens, &pc_gens, &[7, 8], &[15], 0).unwrap();
+        let O = pc_gens.commit_opening(&openings[0]);
+        assert_eq!(tx_1.outputs[0], O.compress());
+
+        let mut verifier = TxVerifier::new(&bp_gens, &pc_gens);
+        verifier.add(tx_1.clone(), Transcript::new(b"TxTest"), 32);
+        verifier.add(tx_2.clone(), Transcript::new(b"TxTest"), 32);
+        assert_eq!(verifier.len(), 2);
+        assert!(verifier.verify_all().is_ok());
+
+        // A changed fee or excess invalidates the whole batch
+        let mut bad_fee = tx_1.clone();
+        bad_fee.fee = 2;
+        let mut bad_excess = tx_1;
+        bad_excess.excess = tx_2.excess;
+        for bad_tx in vec![bad_fee, bad_excess] {
+            let mut verifier = TxVerifier::new(&bp_gens, &pc_gens);
+            verifier.add(tx_2.clone(), Transcript::new(b"TxTest"), 32);
+            verifier.add(bad_tx, Transcript::new(b"TxTest"), 32);
+            assert_eq!(verifier.verify_all(), Err(ProofError::VerificationError));
+        }
+    }
+
+    #[test]
+    fn build_rejects_invalid_transactions() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+
+        let result = build(&bp_gens, &pc_gens, &[100], &[60, 39], 2);
+        assert_eq!(result.unwrap_err(), ProofError::Unbalanced);
+
+        let opening = Opening::new(5, Scalar::zero());
+        let commitment = pc_gens.commit_opening(&Opening::new(6, Scalar::zero()));
+        let result = TxBuilder::new(&bp_gens, &pc_gens)
+            .input(commitment.compress(), opening)
+            .output(5)
+            .build(&mut Transcript::new(b"TxTest"));
+        assert_eq!(result.unwrap_err(), ProofError::CommitmentMismatch);
+    }
+}
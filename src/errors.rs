@@ -1,37 +1,156 @@
 //! Errors related to proving and verifying proofs.
 
+use std::error::Error;
+use std::fmt;
+
+use range_proof::blame::MisbehaviorEvidence;
+
 /// Represents an error in proof creation, verification, or parsing.
-#[derive(Fail, Clone, Debug, Eq, PartialEq)]
+///
+/// More variants may be added in later versions, so matches on this
+/// type should include a wildcard arm.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ProofError {
     /// This error occurs when a proof failed to verify.
-    #[fail(display = "Proof verification failed.")]
     VerificationError,
-    /// This error occurs when the proof encoding is malformed.
-    #[fail(display = "Proof data could not be parsed.")]
+    /// This error occurs when the proof encoding is malformed in a
+    /// way which is not covered by a more specific error.
     FormatError,
+    /// This error occurs when the encoding has a length which no
+    /// well-formed encoding has.
+    InvalidLength {
+        /// The length of the encoding, in bytes.
+        length: usize,
+    },
+    /// This error occurs when an element of the encoding is not a
+    /// valid compressed Ristretto point.
+    InvalidPoint {
+        /// The index of the 32-byte element in the encoding.
+        index: usize,
+    },
+    /// This error occurs when an element of the encoding is not the
+    /// canonical encoding of a scalar.
+    InvalidScalar {
+        /// The index of the 32-byte element in the encoding.
+        index: usize,
+    },
+    /// This error occurs when a buffer is too small to hold the
+    /// encoding written into it.
+    BufferTooSmall {
+        /// The size of the encoding, in bytes.
+        size: usize,
+    },
     /// This error occurs during proving if the number of blinding
     /// factors does not match the number of values.
-    #[fail(display = "Wrong number of blinding factors supplied.")]
     WrongNumBlindingFactors,
+    /// This error occurs if the number of metadata scalars does not
+    /// match the number of values or value commitments.
+    WrongNumMetadata,
+    /// This error occurs when the bounds of a range proof are
+    /// inverted, or the value does not lie between them.
+    InvalidRange,
+    /// This error occurs when a value commitment given to the prover
+    /// does not open to the given value and blinding factor.
+    CommitmentMismatch,
+    /// This error occurs when the values of the inputs of a balance
+    /// proof do not equal the values of the outputs plus the fee.
+    Unbalanced,
     /// This error occurs when attempting to create a proof with
-    /// bitsize other than \\(8\\), \\(16\\), \\(32\\), or \\(64\\).
-    #[fail(display = "Invalid bitsize, must have n = 8,16,32,64.")]
+    /// bitsize \\(0\\) or greater than \\(128\\).
     InvalidBitsize,
-    /// This error occurs when attempting to create an aggregated
-    /// proof with non-power-of-two aggregation size.
-    #[fail(display = "Invalid aggregation size, m must be a power of 2.")]
+    /// This error occurs when attempting to create or verify a proof
+    /// of no values, or to create a
+    /// [`RangeProofHeader`](::RangeProofHeader) whose aggregation size
+    /// is not a power of two.
     InvalidAggregation,
     /// This error occurs when the generators are of the wrong length.
-    #[fail(display = "Invalid generators length, must be equal to n.")]
     InvalidGeneratorsLength,
+    /// This error occurs when the size of the proof does not match
+    /// the bitsize and number of commitments given to the verifier.
+    ProofSizeMismatch,
+    /// This error occurs when one or more proofs in a batch failed
+    /// to verify.
+    BatchVerificationError {
+        /// A vector with the indexes of the proofs that failed to verify.
+        bad_proofs: Vec<usize>,
+    },
+    /// This error occurs when the thread pool for parallel batch
+    /// verification could not be created.
+    ThreadPoolError,
     /// This error results from an internal error during proving.
     ///
     /// The single-party prover is implemented by performing
     /// multiparty computation with ourselves.  However, because the
     /// MPC protocol is not exposed by the single-party API, we
     /// consider its errors to be internal errors.
-    #[fail(display = "Internal error during proof creation: {}", _0)]
     ProvingError(MPCError),
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProofError::VerificationError => write!(f, "Proof verification failed."),
+            ProofError::FormatError => write!(f, "Proof data could not be parsed."),
+            ProofError::InvalidLength { length } => {
+                write!(f, "Proof data has an invalid length of {} bytes.", length)
+            }
+            ProofError::InvalidPoint { index } => {
+                write!(
+                    f,
+                    "Element {} of the proof data is not a valid point.",
+                    index
+                )
+            }
+            ProofError::InvalidScalar { index } => write!(
+                f,
+                "Element {} of the proof data is not a canonical scalar.",
+                index
+            ),
+            ProofError::BufferTooSmall { size } => {
+                write!(f, "Buffer is too small for an encoding of {} bytes.", size)
+            }
+            ProofError::WrongNumBlindingFactors => {
+                write!(f, "Wrong number of blinding factors supplied.")
+            }
+            ProofError::WrongNumMetadata => write!(f, "Wrong number of metadata scalars supplied."),
+            ProofError::InvalidRange => write!(f, "Value does not lie in the range [min, max]."),
+            ProofError::CommitmentMismatch => {
+                write!(f, "Value commitment does not match its opening.")
+            }
+            ProofError::Unbalanced => write!(f, "Inputs do not equal outputs plus fee."),
+            ProofError::InvalidBitsize => write!(f, "Invalid bitsize, must have 0 < n <= 128."),
+            ProofError::InvalidAggregation => {
+                write!(
+                    f,
+                    "Invalid aggregation size, must have m > 0, and a power of 2 in a header."
+                )
+            }
+            ProofError::InvalidGeneratorsLength => {
+                write!(f, "Invalid generators length, must be equal to n.")
+            }
+            ProofError::ProofSizeMismatch => write!(
+                f,
+                "Proof size does not match the bitsize and number of commitments."
+            ),
+            ProofError::BatchVerificationError { bad_proofs } => {
+                write!(f, "Batch verification failed for proofs {:?}", bad_proofs)
+            }
+            ProofError::ThreadPoolError => write!(f, "Could not create the thread pool."),
+            ProofError::ProvingError(e) => write!(f, "Internal error during proof creation: {}", e),
+            ProofError::__Nonexhaustive => write!(f, "Unknown proof error."),
+        }
+    }
+}
+
+impl Error for ProofError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProofError::ProvingError(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 impl From<MPCError> for ProofError {
@@ -40,6 +159,7 @@ impl From<MPCError> for ProofError {
             MPCError::InvalidBitsize => ProofError::InvalidBitsize,
             MPCError::InvalidAggregation => ProofError::InvalidAggregation,
             MPCError::InvalidGeneratorsLength => ProofError::InvalidGeneratorsLength,
+            MPCError::CommitmentMismatch => ProofError::CommitmentMismatch,
             _ => ProofError::ProvingError(e),
         }
     }
@@ -52,43 +172,153 @@ impl From<MPCError> for ProofError {
 /// API: although the MPC protocol is used internally for single-party
 /// proving, its API should not expose the complexity of the MPC
 /// protocol.
-#[derive(Fail, Clone, Debug, Eq, PartialEq)]
+///
+/// More variants may be added in later versions, so matches on this
+/// type should include a wildcard arm.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum MPCError {
     /// This error occurs when the dealer gives a zero challenge,
-    /// which would annihilate the blinding factors.
-    #[fail(display = "Dealer gave a malicious challenge value.")]
+    /// which would annihilate the blinding factors, or a challenge
+    /// which was not derived from the commitments of the parties.
     MaliciousDealer,
     /// This error occurs when attempting to create a proof with
-    /// bitsize other than \\(8\\), \\(16\\), \\(32\\), or \\(64\\).
-    #[fail(display = "Invalid bitsize, must have n = 8,16,32,64")]
+    /// bitsize \\(0\\) or greater than \\(128\\).
     InvalidBitsize,
-    /// This error occurs when attempting to create an aggregated
-    /// proof with non-power-of-two aggregation size.
-    #[fail(display = "Invalid aggregation size, m must be a power of 2")]
+    /// This error occurs when attempting to create a dealer without
+    /// any party, or a party without any value.
     InvalidAggregation,
     /// This error occurs when the generators are of the wrong length.
-    #[fail(display = "Invalid generators length, must be equal to n.")]
     InvalidGeneratorsLength,
+    /// This error occurs when a party is given a value commitment
+    /// which does not open to its value and blinding factor.
+    CommitmentMismatch,
     /// This error occurs when the dealer is given the wrong number of
     /// value commitments.
-    #[fail(display = "Wrong number of value commitments")]
     WrongNumBitCommitments,
     /// This error occurs when the dealer is given the wrong number of
     /// polynomial commitments.
-    #[fail(display = "Wrong number of value commitments")]
     WrongNumPolyCommitments,
     /// This error occurs when the dealer is given the wrong number of
     /// proof shares.
-    #[fail(display = "Wrong number of proof shares")]
     WrongNumProofShares,
     /// This error occurs when one or more parties submit malformed
     /// proof shares.
-    #[fail(
-        display = "Malformed proof shares from parties {:?}",
-        bad_shares
-    )]
     MalformedProofShares {
         /// A vector with the indexes of the parties whose shares were malformed.
         bad_shares: Vec<usize>,
+        /// The evidence against each of these parties, in the same
+        /// order.
+        evidence: Vec<MisbehaviorEvidence>,
     },
+    /// This error occurs when a checkpoint of a dealer or party state
+    /// is malformed, is of another kind of state, or does not verify
+    /// under the checkpoint key.
+    InvalidCheckpoint,
+    /// This error occurs when the evidence of a malformed proof share
+    /// does not show that the share fails the claimed audit check.
+    InvalidEvidence,
+    /// This error occurs when the partial messages of the
+    /// shareholders of a blinding factor cannot be combined with the
+    /// message of the lead party, because a value commitment is not a
+    /// valid point.
+    InvalidPartialMessage,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl fmt::Display for MPCError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MPCError::MaliciousDealer => write!(f, "Dealer gave a malicious challenge value."),
+            MPCError::InvalidBitsize => write!(f, "Invalid bitsize, must have 0 < n <= 128"),
+            MPCError::InvalidAggregation => {
+                write!(f, "Invalid aggregation size, must have at least one value")
+            }
+            MPCError::InvalidGeneratorsLength => {
+                write!(f, "Invalid generators length, must be equal to n.")
+            }
+            MPCError::CommitmentMismatch => {
+                write!(f, "Value commitment does not match its opening")
+            }
+            MPCError::WrongNumBitCommitments => write!(f, "Wrong number of value commitments"),
+            MPCError::WrongNumPolyCommitments => {
+                write!(f, "Wrong number of polynomial commitments")
+            }
+            MPCError::WrongNumProofShares => write!(f, "Wrong number of proof shares"),
+            MPCError::MalformedProofShares { bad_shares, .. } => {
+                write!(f, "Malformed proof shares from parties {:?}", bad_shares)
+            }
+            MPCError::InvalidCheckpoint => write!(f, "Checkpoint could not be restored."),
+            MPCError::InvalidEvidence => write!(f, "Misbehavior evidence does not verify."),
+            MPCError::InvalidPartialMessage => write!(f, "Partial messages could not be combined."),
+            MPCError::__Nonexhaustive => write!(f, "Unknown MPC error."),
+        }
+    }
+}
+
+impl Error for MPCError {}
+
+/// Represents an error during the proving or verifying of a
+/// constraint system proof.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum R1CSError {
+    /// This error occurs when the generators are of the wrong length.
+    InvalidGeneratorsLength,
+    /// This error occurs when the proof encoding is malformed.
+    FormatError,
+    /// This error occurs when a proof failed to verify.
+    VerificationError,
+    /// This error occurs when the prover allocates a multiplier
+    /// without supplying its assignment.
+    MissingAssignment,
+    /// This error occurs when the matrices of an arithmetic circuit,
+    /// or the assignments given to it, have inconsistent dimensions.
+    InvalidCircuit,
+    /// This error occurs when a gadget is given inputs it cannot
+    /// constrain.
+    GadgetError {
+        /// The description of the reasons for the error.
+        description: String,
+    },
+    /// This error occurs when the prover checks its constraints in
+    /// debug mode and some of them are not satisfied by the witness.
+    UnsatisfiedConstraints {
+        /// The indices of the unsatisfied constraints, in the order in
+        /// which they were added.
+        indices: Vec<usize>,
+    },
+}
+
+impl fmt::Display for R1CSError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            R1CSError::InvalidGeneratorsLength => write!(
+                f,
+                "Invalid generators length, must be at least the number of multipliers."
+            ),
+            R1CSError::FormatError => write!(f, "Proof data could not be parsed."),
+            R1CSError::VerificationError => write!(f, "Proof verification failed."),
+            R1CSError::MissingAssignment => write!(f, "Variable does not have a value assignment."),
+            R1CSError::InvalidCircuit => write!(f, "Circuit dimensions are inconsistent."),
+            R1CSError::GadgetError { description } => write!(f, "Gadget error: {:?}", description),
+            R1CSError::UnsatisfiedConstraints { indices } => {
+                write!(f, "Constraints {:?} are not satisfied.", indices)
+            }
+        }
+    }
+}
+
+impl Error for R1CSError {}
+
+impl From<ProofError> for R1CSError {
+    fn from(e: ProofError) -> R1CSError {
+        match e {
+            ProofError::InvalidGeneratorsLength => R1CSError::InvalidGeneratorsLength,
+            ProofError::FormatError
+            | ProofError::InvalidLength { .. }
+            | ProofError::InvalidPoint { .. }
+            | ProofError::InvalidScalar { .. } => R1CSError::FormatError,
+            _ => R1CSError::VerificationError,
+        }
+    }
 }
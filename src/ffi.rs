@@ -0,0 +1,418 @@
+//! The `ffi` module contains `extern "C"` functions for creating and
+//! verifying single and aggregated range proofs over byte buffers, so
+//! that the crate can be used from C, C++ and mobile applications.
+//!
+//! Scalars and points are encoded as 32 bytes each, and proofs with
+//! [`RangeProof::to_bytes`].  The proofs use the default
+//! [`PedersenGens`] and the [`BulletproofGens::shared`] generators,
+//! and the transcript is bound to the caller's `label`, which must be
+//! the same for the prover and the verifier.
+//!
+//! Every function returns one of the `BULLETPROOFS_*` codes, whose
+//! values are stable across releases.  Panics are caught and reported
+//! as [`BULLETPROOFS_ERROR_INTERNAL`] rather than unwinding into the
+//! caller.
+//!
+//! To link the functions into a C program, build the crate as a static
+//! or dynamic library:
+//!
+//! ```text
+//! cargo rustc --release --lib --features "ffi" -- --crate-type staticlib
+//! ```
+//!
+//! This module is only available with the `ffi` feature.
+
+use std::os::raw::c_int;
+use std::panic;
+use std::slice;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use merlin::Transcript;
+
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::RangeProof;
+use scalar;
+use util::read32;
+
+/// The operation succeeded.
+pub const BULLETPROOFS_OK: c_int = 0;
+/// The proof failed to verify.
+pub const BULLETPROOFS_ERROR_VERIFICATION: c_int = 1;
+/// A proof, scalar or point could not be parsed.
+pub const BULLETPROOFS_ERROR_FORMAT: c_int = 2;
+/// A value does not lie in the range.
+pub const BULLETPROOFS_ERROR_INVALID_RANGE: c_int = 3;
+/// The bitsize is zero or greater than 128.
+pub const BULLETPROOFS_ERROR_INVALID_BITSIZE: c_int = 4;
+/// The number of values is zero.
+pub const BULLETPROOFS_ERROR_INVALID_AGGREGATION: c_int = 5;
+/// The proof does not match the bitsize and number of commitments.
+pub const BULLETPROOFS_ERROR_PROOF_SIZE: c_int = 6;
+/// An output buffer is too small.
+pub const BULLETPROOFS_ERROR_BUFFER_TOO_SMALL: c_int = 7;
+/// A required pointer is null.
+pub const BULLETPROOFS_ERROR_NULL_POINTER: c_int = 8;
+/// Any other error, including a panic inside the library.
+pub const BULLETPROOFS_ERROR_INTERNAL: c_int = 255;
+
+/// Returns the size in bytes of a proof for `m` values of `n` bits,
+/// or `0` if `n` or `m` is invalid.
+#[no_mangle]
+pub extern "C" fn bulletproofs_proof_size(n: usize, m: usize) -> usize {
+    if check_parameters(n, m).is_err() {
+        return 0;
+    }
+    let rounds = (n.next_power_of_two() * m.next_power_of_two()).trailing_zeros() as usize;
+    (9 + 2 * rounds) * 32
+}
+
+/// Creates a proof that each of the `m` values in `values` is in
+/// \\([0, 2^n)\\).
+///
+/// `blindings` holds `32 * m` bytes of blinding factors.  The proof is
+/// written to `proof_out`, which must hold at least
+/// [`bulletproofs_proof_size`] bytes, and the `32 * m` bytes of value
+/// commitments to `commitments_out`.
+///
+/// # Safety
+///
+/// Each pointer must be valid for the length given above.
+#[no_mangle]
+pub unsafe extern "C" fn bulletproofs_prove(
+    label: *const u8,
+    label_len: usize,
+    values: *const u64,
+    blindings: *const u8,
+    m: usize,
+    n: usize,
+    proof_out: *mut u8,
+    proof_out_len: usize,
+    commitments_out: *mut u8,
+) -> c_int {
+    if label.is_null()
+        || values.is_null()
+        || blindings.is_null()
+        || proof_out.is_null()
+        || commitments_out.is_null()
+    {
+        return BULLETPROOFS_ERROR_NULL_POINTER;
+    }
+    if let Err(code) = check_parameters(n, m) {
+        return code;
+    }
+    let label = slice::from_raw_parts(label, label_len);
+    let values = slice::from_raw_parts(values, m);
+    let blindings = slice::from_raw_parts(blindings, 32 * m);
+    let proof_out = slice::from_raw_parts_mut(proof_out, proof_out_len);
+    let commitments_out = slice::from_raw_parts_mut(commitments_out, 32 * m);
+
+    catch_errors(|| {
+        let blindings = scalar::decode_vec(blindings)?;
+        let bp_gens = BulletproofGens::shared(n.next_power_of_two(), m.next_power_of_two());
+        let (proof, value_commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &PedersenGens::default(),
+            &mut transcript(label),
+            values,
+            &blindings,
+            n,
+        )?;
+
+        let proof = proof.to_bytes();
+        if proof.len() > proof_out.len() {
+            return Ok(BULLETPROOFS_ERROR_BUFFER_TOO_SMALL);
+        }
+        proof_out[..proof.len()].copy_from_slice(&proof);
+        for (out, commitment) in commitments_out.chunks_mut(32).zip(value_commitments.iter()) {
+            out.copy_from_slice(commitment.as_bytes());
+        }
+        Ok(BULLETPROOFS_OK)
+    })
+}
+
+/// Creates a proof that `value` is in \\([0, 2^n)\\).
+///
+/// This is [`bulletproofs_prove`] for a single value, with a 32-byte
+/// `blinding` and a 32-byte `commitment_out`.
+///
+/// # Safety
+///
+/// Each pointer must be valid for the length given above.
+#[no_mangle]
+pub unsafe extern "C" fn bulletproofs_prove_single(
+    label: *const u8,
+    label_len: usize,
+    value: u64,
+    blinding: *const u8,
+    n: usize,
+    proof_out: *mut u8,
+    proof_out_len: usize,
+    commitment_out: *mut u8,
+) -> c_int {
+    bulletproofs_prove(
+        label,
+        label_len,
+        &value,
+        blinding,
+        1,
+        n,
+        proof_out,
+        proof_out_len,
+        commitment_out,
+    )
+}
+
+/// Verifies a proof created by [`bulletproofs_prove`] against the
+/// `32 * m` bytes of `commitments`.
+///
+/// # Safety
+///
+/// Each pointer must be valid for the length given above.
+#[no_mangle]
+pub unsafe extern "C" fn bulletproofs_verify(
+    label: *const u8,
+    label_len: usize,
+    proof: *const u8,
+    proof_len: usize,
+    commitments: *const u8,
+    m: usize,
+    n: usize,
+) -> c_int {
+    if label.is_null() || proof.is_null() || commitments.is_null() {
+        return BULLETPROOFS_ERROR_NULL_POINTER;
+    }
+    if let Err(code) = check_parameters(n, m) {
+        return code;
+    }
+    let label = slice::from_raw_parts(label, label_len);
+    let proof = slice::from_raw_parts(proof, proof_len);
+    let commitments = slice::from_raw_parts(commitments, 32 * m);
+
+    catch_errors(|| {
+        let proof = RangeProof::from_bytes(proof)?;
+        let value_commitments: Vec<_> = commitments
+            .chunks(32)
+            .map(|chunk| CompressedRistretto(read32(chunk)))
+            .collect();
+
+        let bp_gens = BulletproofGens::shared(n.next_power_of_two(), m.next_power_of_two());
+        proof.verify_multiple(
+            &bp_gens,
+            &PedersenGens::default(),
+            &mut transcript(label),
+            &value_commitments,
+            n,
+        )?;
+        Ok(BULLETPROOFS_OK)
+    })
+}
+
+/// Verifies a proof created by [`bulletproofs_prove_single`] against
+/// the 32-byte `commitment`.
+///
+/// # Safety
+///
+/// Each pointer must be valid for the length given above.
+#[no_mangle]
+pub unsafe extern "C" fn bulletproofs_verify_single(
+    label: *const u8,
+    label_len: usize,
+    proof: *const u8,
+    proof_len: usize,
+    commitment: *const u8,
+    n: usize,
+) -> c_int {
+    bulletproofs_verify(label, label_len, proof, proof_len, commitment, 1, n)
+}
+
+/// Checks the bitsize `n` and the number of values `m`, so that no
+/// generators or buffers are sized from invalid ones.
+fn check_parameters(n: usize, m: usize) -> Result<(), c_int> {
+    if n == 0 || n > 128 {
+        return Err(BULLETPROOFS_ERROR_INVALID_BITSIZE);
+    }
+    if m == 0 || m > 1 << 31 {
+        return Err(BULLETPROOFS_ERROR_INVALID_AGGREGATION);
+    }
+    Ok(())
+}
+
+/// Returns the transcript for the caller's `label`.
+fn transcript(label: &[u8]) -> Transcript {
+    let mut transcript = Transcript::new(b"bulletproofs ffi");
+    transcript.commit_bytes(b"label", label);
+    transcript
+}
+
+/// Runs `f`, converting its errors and panics to error codes.
+fn catch_errors<F>(f: F) -> c_int
+where
+    F: FnOnce() -> Result<c_int, ProofError>,
+{
+    match panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+        Ok(Ok(code)) => code,
+        Ok(Err(e)) => error_code(&e),
+        Err(_) => BULLETPROOFS_ERROR_INTERNAL,
+    }
+}
+
+/// Maps a `ProofError` to its stable error code.
+fn error_code(e: &ProofError) -> c_int {
+    match e {
+        ProofError::VerificationError => BULLETPROOFS_ERROR_VERIFICATION,
+        ProofError::FormatError
+        | ProofError::InvalidLength { .. }
+        | ProofError::InvalidPoint { .. }
+        | ProofError::InvalidScalar { .. } => BULLETPROOFS_ERROR_FORMAT,
+        ProofError::InvalidRange => BULLETPROOFS_ERROR_INVALID_RANGE,
+        ProofError::InvalidBitsize => BULLETPROOFS_ERROR_INVALID_BITSIZE,
+        ProofError::InvalidAggregation => BULLETPROOFS_ERROR_INVALID_AGGREGATION,
+        ProofError::ProofSizeMismatch => BULLETPROOFS_ERROR_PROOF_SIZE,
+        _ => BULLETPROOFS_ERROR_INTERNAL,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+    use rand::thread_rng;
+
+    const LABEL: &[u8] = b"ffi test";
+
+    #[test]
+    fn proof_size_of_padded_aggregation() {
+        assert_eq!(
+            bulletproofs_proof_size(32, 3),
+            bulletproofs_proof_size(32, 4)
+        );
+        assert_eq!(
+            bulletproofs_proof_size(48, 1),
+            bulletproofs_proof_size(64, 1)
+        );
+        assert_eq!(bulletproofs_proof_size(32, 0), 0);
+    }
+
+    #[test]
+    fn prove_and_verify_aggregated() {
+        let mut rng = thread_rng();
+        let (n, m) = (32, 4);
+        let values = [1u64, 2, 3, 4];
+        let blindings: Vec<u8> = (0..m)
+            .flat_map(|_| Scalar::random(&mut rng).to_bytes().to_vec())
+            .collect();
+        let mut proof = vec![0u8; bulletproofs_proof_size(n, m)];
+        let mut commitments = vec![0u8; 32 * m];
+
+        unsafe {
+            let code = bulletproofs_prove(
+                LABEL.as_ptr(),
+                LABEL.len(),
+                values.as_ptr(),
+                blindings.as_ptr(),
+                m,
+                n,
+                proof.as_mut_ptr(),
+                proof.len(),
+                commitments.as_mut_ptr(),
+            );
+            assert_eq!(code, BULLETPROOFS_OK);
+
+            let (label, len) = (LABEL.as_ptr(), proof.len());
+            let (proof, commitments) = (proof.as_ptr(), commitments.as_ptr());
+            let code = bulletproofs_verify(label, LABEL.len(), proof, len, commitments, m, n);
+            assert_eq!(code, BULLETPROOFS_OK);
+            let code = bulletproofs_verify(label, LABEL.len(), proof, len, commitments, m, 16);
+            assert_eq!(code, BULLETPROOFS_ERROR_PROOF_SIZE);
+            let code = bulletproofs_verify(label, 3, proof, len, commitments, m, n);
+            assert_eq!(code, BULLETPROOFS_ERROR_VERIFICATION);
+        }
+    }
+
+    #[test]
+    fn prove_and_verify_single() {
+        let blinding = Scalar::random(&mut thread_rng()).to_bytes();
+        let mut proof = vec![0u8; bulletproofs_proof_size(64, 1)];
+        let mut short_proof = vec![0u8; 32];
+        let mut commitment = [0u8; 32];
+
+        unsafe {
+            let prove = |proof: &mut [u8], commitment: &mut [u8; 32]| {
+                bulletproofs_prove_single(
+                    LABEL.as_ptr(),
+                    LABEL.len(),
+                    1037578891,
+                    blinding.as_ptr(),
+                    64,
+                    proof.as_mut_ptr(),
+                    proof.len(),
+                    commitment.as_mut_ptr(),
+                )
+            };
+            assert_eq!(prove(&mut proof, &mut commitment), BULLETPROOFS_OK);
+            assert_eq!(
+                prove(&mut short_proof, &mut commitment),
+                BULLETPROOFS_ERROR_BUFFER_TOO_SMALL
+            );
+
+            let (label, len) = (LABEL.as_ptr(), proof.len());
+            let (proof, commitment) = (proof.as_ptr(), commitment.as_ptr());
+            let code = bulletproofs_verify_single(label, LABEL.len(), proof, len, commitment, 64);
+            assert_eq!(code, BULLETPROOFS_OK);
+            let code = bulletproofs_verify_single(label, LABEL.len(), proof, 64, commitment, 64);
+            assert_eq!(code, BULLETPROOFS_ERROR_FORMAT);
+        }
+    }
+
+    #[test]
+    fn null_pointers_are_rejected() {
+        let null = 0 as *const u8;
+        let code =
+            unsafe { bulletproofs_verify(LABEL.as_ptr(), LABEL.len(), null, 0, null, 1, 64) };
+        assert_eq!(code, BULLETPROOFS_ERROR_NULL_POINTER);
+    }
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        let values = [0u64];
+        let blinding = [0u8; 32];
+        let mut proof = [0u8; 32];
+        let mut commitment = [0u8; 32];
+
+        {
+            let mut prove = |m, n| unsafe {
+                bulletproofs_prove(
+                    LABEL.as_ptr(),
+                    LABEL.len(),
+                    values.as_ptr(),
+                    blinding.as_ptr(),
+                    m,
+                    n,
+                    proof.as_mut_ptr(),
+                    proof.len(),
+                    commitment.as_mut_ptr(),
+                )
+            };
+            assert_eq!(prove(1, 0), BULLETPROOFS_ERROR_INVALID_BITSIZE);
+            assert_eq!(prove(1, 129), BULLETPROOFS_ERROR_INVALID_BITSIZE);
+            assert_eq!(prove(0, 64), BULLETPROOFS_ERROR_INVALID_AGGREGATION);
+        }
+
+        let verify = |m, n| unsafe {
+            let (label, len) = (LABEL.as_ptr(), LABEL.len());
+            bulletproofs_verify(label, len, proof.as_ptr(), 32, commitment.as_ptr(), m, n)
+        };
+        assert_eq!(verify(1, 0), BULLETPROOFS_ERROR_INVALID_BITSIZE);
+        assert_eq!(
+            verify(1, usize::max_value()),
+            BULLETPROOFS_ERROR_INVALID_BITSIZE
+        );
+        assert_eq!(verify(0, 64), BULLETPROOFS_ERROR_INVALID_AGGREGATION);
+        assert_eq!(
+            verify((1 << 31) + 1, 64),
+            BULLETPROOFS_ERROR_INVALID_AGGREGATION
+        );
+    }
+}
@@ -6,14 +6,24 @@
 
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_COMPRESSED;
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
-use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoBasepointTable, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::MultiscalarMul;
+use curve25519_dalek::traits::{IsIdentity, MultiscalarMul, VartimeMultiscalarMul};
+use rand;
+use std::cmp;
+use std::iter;
+use std::sync::{Arc, Mutex};
 
-use digest::{ExtendableOutput, Input, XofReader};
+use commitment::{Commitment, Opening};
+use errors::ProofError;
+
+use digest::{Digest, ExtendableOutput, Input, XofReader};
 use sha3::{Sha3XofReader, Sha3_512, Shake256};
 
-/// Represents a pair of base points for Pedersen commitments.
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Represents the base points for Pedersen commitments.
 ///
 /// The Bulletproofs implementation and API is designed to support
 /// pluggable bases for Pedersen commitments, so that the choice of
@@ -23,13 +33,21 @@ use sha3::{Sha3XofReader, Sha3_512, Shake256};
 ///
 /// * `B`: the `ristretto255` basepoint;
 /// * `B_blinding`: the result of `ristretto255` SHA3-512
-/// hash-to-group on input `B_bytes`.
+/// hash-to-group on input `B_bytes`;
+/// * `B_meta`: the result of `ristretto255` SHA3-512
+/// hash-to-group on input `B_blinding_bytes`.
+///
+/// `B_meta` is only used by three-term commitments
+/// \\(vB + mB\_{meta} + \tilde{v}B\_{blinding}\\), which bind a
+/// metadata scalar \\(m\\), such as an asset code, to the value.
 #[derive(Copy, Clone)]
 pub struct PedersenGens {
     /// Base for the committed value
     pub B: RistrettoPoint,
     /// Base for the blinding factor
     pub B_blinding: RistrettoPoint,
+    /// Base for the metadata of three-term commitments
+    pub B_meta: RistrettoPoint,
 }
 
 impl PedersenGens {
@@ -37,19 +55,130 @@ impl PedersenGens {
     pub fn commit(&self, value: Scalar, blinding: Scalar) -> RistrettoPoint {
         RistrettoPoint::multiscalar_mul(&[value, blinding], &[self.B, self.B_blinding])
     }
+
+    /// Creates the typed commitment for an `opening`.
+    pub fn commit_opening(&self, opening: &Opening) -> Commitment {
+        Commitment(self.commit(opening.value, opening.blinding))
+    }
+
+    /// Checks whether `commitment` opens to `opening`.
+    pub fn opens_to(&self, commitment: &Commitment, opening: &Opening) -> bool {
+        self.commit_opening(opening) == *commitment
+    }
+
+    /// Creates a three-term commitment using the value scalar, a
+    /// metadata scalar and a blinding factor.
+    pub fn commit_with_metadata(
+        &self,
+        value: Scalar,
+        metadata: Scalar,
+        blinding: Scalar,
+    ) -> RistrettoPoint {
+        RistrettoPoint::multiscalar_mul(
+            &[value, metadata, blinding],
+            &[self.B, self.B_meta, self.B_blinding],
+        )
+    }
+
+    /// Precomputes tables of multiples of \\(B\\) and
+    /// \\(B\_{blinding}\\), for creating many commitments with these
+    /// generators.
+    pub fn precompute(&self) -> PedersenGensTable {
+        PedersenGensTable {
+            gens: *self,
+            B_table: RistrettoBasepointTable::create(&self.B),
+            B_blinding_table: RistrettoBasepointTable::create(&self.B_blinding),
+        }
+    }
+
+    /// Checks that each commitment in `openings` opens to the given
+    /// value and blinding factor.
+    ///
+    /// All the openings are checked at once, using a random linear
+    /// combination of the opening equations and a single multiscalar
+    /// multiplication.  If any opening is wrong, the check fails
+    /// except with negligible probability.
+    ///
+    /// Returns `ProofError::FormatError` if a commitment is not a
+    /// valid point, and `ProofError::VerificationError` if an opening
+    /// is wrong.
+    pub fn verify_openings(
+        &self,
+        openings: &[(CompressedRistretto, Scalar, Scalar)],
+    ) -> Result<(), ProofError> {
+        let mut rng = rand::thread_rng();
+        let weights: Vec<Scalar> = openings.iter().map(|_| Scalar::random(&mut rng)).collect();
+
+        let value_scalar: Scalar = weights
+            .iter()
+            .zip(openings.iter())
+            .map(|(w, &(_, value, _))| w * value)
+            .sum();
+        let blinding_scalar: Scalar = weights
+            .iter()
+            .zip(openings.iter())
+            .map(|(w, &(_, _, blinding))| w * blinding)
+            .sum();
+
+        let mega_check = RistrettoPoint::optional_multiscalar_mul(
+            iter::once(value_scalar)
+                .chain(iter::once(blinding_scalar))
+                .chain(weights.iter().map(|w| -w)),
+            iter::once(Some(self.B))
+                .chain(iter::once(Some(self.B_blinding)))
+                .chain(openings.iter().map(|(C, _, _)| C.decompress())),
+        ).ok_or(ProofError::FormatError)?;
+
+        if mega_check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
 }
 
 impl Default for PedersenGens {
     fn default() -> Self {
+        let B_blinding =
+            RistrettoPoint::hash_from_bytes::<Sha3_512>(RISTRETTO_BASEPOINT_COMPRESSED.as_bytes());
         PedersenGens {
             B: RISTRETTO_BASEPOINT_POINT,
-            B_blinding: RistrettoPoint::hash_from_bytes::<Sha3_512>(
-                RISTRETTO_BASEPOINT_COMPRESSED.as_bytes(),
-            ),
+            B_blinding,
+            B_meta: RistrettoPoint::hash_from_bytes::<Sha3_512>(B_blinding.compress().as_bytes()),
         }
     }
 }
 
+/// Precomputed tables for the bases of a [`PedersenGens`], created
+/// with [`PedersenGens::precompute`].
+///
+/// Committing with the tables uses fixed-base scalar multiplications,
+/// which are several times faster than the variable-base
+/// multiplications of [`PedersenGens::commit`].  Creating the tables
+/// costs about as much as a few dozen commitments, and each table
+/// takes about 30KB, so they are kept out of the `PedersenGens`
+/// struct, which is cheap to copy.
+pub struct PedersenGensTable {
+    gens: PedersenGens,
+    B_table: RistrettoBasepointTable,
+    B_blinding_table: RistrettoBasepointTable,
+}
+
+impl PedersenGensTable {
+    /// Returns the generators the tables were computed for.
+    pub fn gens(&self) -> &PedersenGens {
+        &self.gens
+    }
+
+    /// Creates a Pedersen commitment using the value scalar and a
+    /// blinding factor.
+    ///
+    /// The result is the same as with [`PedersenGens::commit`].
+    pub fn commit(&self, value: Scalar, blinding: Scalar) -> RistrettoPoint {
+        &value * &self.B_table + &blinding * &self.B_blinding_table
+    }
+}
+
 /// The `GeneratorsChain` creates an arbitrary-long sequence of
 /// orthogonal generators.  The sequence can be deterministically
 /// produced starting with an arbitrary point.
@@ -68,6 +197,42 @@ impl GeneratorsChain {
             reader: shake.xof_result(),
         }
     }
+
+    /// Creates the chain of generators with prefix `G` or `H` for the
+    /// party with index `party_index`.
+    fn for_party(prefix: u8, party_index: usize) -> Self {
+        Self::new(&party_label(prefix, party_index))
+    }
+
+    /// Creates the chain of offsets applied by `salt` to the
+    /// generators with prefix `G` or `H` of the party with index
+    /// `party_index`.
+    fn for_salt(prefix: u8, party_index: usize, salt: &[u8]) -> Self {
+        let mut label = party_label(prefix, party_index);
+        label.extend_from_slice(b"salt");
+        label.extend_from_slice(salt);
+        Self::new(&label)
+    }
+
+    /// Advances the chain by `n` generators, without mapping the
+    /// skipped XOF output to the group.
+    fn fast_forward(mut self, n: usize) -> Self {
+        let mut discard = [0u8; 64];
+        for _ in 0..n {
+            self.reader.read(&mut discard);
+        }
+        self
+    }
+}
+
+/// Returns the label of the chain of generators with prefix `G` or
+/// `H` for the party with index `party_index`.
+fn party_label(prefix: u8, party_index: usize) -> Vec<u8> {
+    use byteorder::{ByteOrder, LittleEndian};
+
+    let mut label = vec![prefix, 0, 0, 0, 0];
+    LittleEndian::write_u32(&mut label[1..5], party_index as u32);
+    label
 }
 
 impl Default for GeneratorsChain {
@@ -116,6 +281,23 @@ impl Iterator for GeneratorsChain {
 /// chain, and even forward-compatible to multiparty aggregation of
 /// constraint system proofs, since the generators are namespaced by
 /// their party index.
+///
+/// # Salted Generators
+///
+/// A service can derive a fresh generator set for each session or
+/// epoch from a base set and a public salt with
+/// [`BulletproofGens::salted`].  The salts are committed to the
+/// transcript by the prover and the verifier, so a proof only
+/// verifies against the generators it was created with.  To verify an
+/// older proof, the verifier re-derives its generators from the base
+/// set and the salt recorded with the proof.
+///
+/// # Sharing Generators
+///
+/// The generators are stored behind an [`Arc`], so cloning a
+/// `BulletproofGens` is cheap and does not copy the points.  A clone
+/// can be moved into another thread or task, for instance by a
+/// multi-threaded proving service, instead of borrowing the original.
 #[derive(Clone)]
 pub struct BulletproofGens {
     /// The maximum number of usable generators for each party.
@@ -123,9 +305,11 @@ pub struct BulletproofGens {
     /// Number of values or parties
     pub party_capacity: usize,
     /// Precomputed \\(\mathbf G\\) generators for each party.
-    G_vec: Vec<Vec<RistrettoPoint>>,
+    G_vec: Arc<Vec<Vec<RistrettoPoint>>>,
     /// Precomputed \\(\mathbf H\\) generators for each party.
-    H_vec: Vec<Vec<RistrettoPoint>>,
+    H_vec: Arc<Vec<Vec<RistrettoPoint>>>,
+    /// The salts applied to the generators, in order.
+    salts: Vec<Vec<u8>>,
 }
 
 impl BulletproofGens {
@@ -135,39 +319,250 @@ impl BulletproofGens {
     ///
     /// * `gens_capacity` is the number of generators to precompute
     ///    for each party.  For rangeproofs, it is sufficient to pass
-    ///    `64`, the maximum bitsize of the rangeproofs.  For circuit
+    ///    `128`, the maximum bitsize of the rangeproofs.  For circuit
     ///    proofs, the capacity must be greater than the number of
     ///    multipliers, rounded up to the next power of two.
     ///
     /// * `party_capacity` is the maximum number of parties that can
     ///    produce an aggregated proof.
     pub fn new(gens_capacity: usize, party_capacity: usize) -> Self {
-        use byteorder::{ByteOrder, LittleEndian};
+        let mut gens = BulletproofGens {
+            gens_capacity: 0,
+            party_capacity: 0,
+            G_vec: Arc::new(Vec::new()),
+            H_vec: Arc::new(Vec::new()),
+            salts: Vec::new(),
+        };
+        gens.increase_capacity(gens_capacity, party_capacity);
+        gens
+    }
+
+    /// Returns generators with at least the given capacities, which
+    /// are shared by the whole process.
+    ///
+    /// The shared generators are derived on first use, and grown with
+    /// [`BulletproofGens::increase_capacity`] when a larger capacity
+    /// is requested, so they may have a larger capacity than asked
+    /// for.  The result is a clone, which does not copy the points.
+    pub fn shared(gens_capacity: usize, party_capacity: usize) -> BulletproofGens {
+        lazy_static! {
+            static ref SHARED: Mutex<BulletproofGens> = Mutex::new(BulletproofGens::new(0, 0));
+        }
+
+        // The generators are valid even if another thread panicked
+        // while holding the lock.
+        let mut gens = SHARED.lock().unwrap_or_else(|e| e.into_inner());
+        gens.increase_capacity(gens_capacity, party_capacity);
+        gens.clone()
+    }
+
+    /// Returns the shared generators for single 64-bit range proofs.
+    ///
+    /// This is [`BulletproofGens::shared`] with capacities 64 and 1.
+    pub fn default_64() -> BulletproofGens {
+        BulletproofGens::shared(64, 1)
+    }
+
+    /// Increases the generators capacity to at least `gens_capacity`
+    /// generators for each of at least `party_capacity` parties.
+    ///
+    /// The existing generators are kept, and the new ones continue
+    /// the same chains, so the result is the same as creating the
+    /// generators with the larger capacity, and proofs created with
+    /// the smaller set still verify.  The prefix of each chain is
+    /// skipped over without recomputing its points.  The salts of
+    /// `self` are applied to the new generators as well.
+    ///
+    /// Clones of `self` made before the call keep their capacity.
+    pub fn increase_capacity(&mut self, gens_capacity: usize, party_capacity: usize) {
+        let gens_capacity = cmp::max(gens_capacity, self.gens_capacity);
+        let party_capacity = cmp::max(party_capacity, self.party_capacity);
+        if gens_capacity == self.gens_capacity && party_capacity == self.party_capacity {
+            return;
+        }
+
+        let salts = &self.salts;
+        let extend = |prefix: u8, vecs: &mut Vec<Vec<RistrettoPoint>>| {
+            for j in 0..party_capacity {
+                if j == vecs.len() {
+                    vecs.push(Vec::new());
+                }
+                let start = vecs[j].len();
+                let mut new_gens: Vec<RistrettoPoint> = GeneratorsChain::for_party(prefix, j)
+                    .fast_forward(start)
+                    .take(gens_capacity - start)
+                    .collect();
+                for salt in salts.iter() {
+                    let offsets = GeneratorsChain::for_salt(prefix, j, salt).fast_forward(start);
+                    for (P, offset) in new_gens.iter_mut().zip(offsets) {
+                        *P += offset;
+                    }
+                }
+                vecs[j].extend(new_gens);
+            }
+        };
+        extend(b'G', Arc::make_mut(&mut self.G_vec));
+        extend(b'H', Arc::make_mut(&mut self.H_vec));
+
+        self.gens_capacity = gens_capacity;
+        self.party_capacity = party_capacity;
+    }
+
+    /// Derives a new set of generators from `self` and a public `salt`.
+    ///
+    /// Each generator is offset by a point obtained by hashing its
+    /// label together with the salt, so that the discrete logarithm
+    /// relations between the salted generators are as unknown as those
+    /// between the original ones.  Salting an already salted set
+    /// applies both salts.
+    pub fn salted(&self, salt: &[u8]) -> BulletproofGens {
+        let apply = |prefix: u8, vecs: &Vec<Vec<RistrettoPoint>>| {
+            Arc::new(
+                vecs.iter()
+                    .enumerate()
+                    .map(|(j, gens)| {
+                        gens.iter()
+                            .zip(GeneratorsChain::for_salt(prefix, j, salt))
+                            .map(|(P, offset)| P + offset)
+                            .collect::<Vec<_>>()
+                    }).collect(),
+            )
+        };
+
+        let mut salts = self.salts.clone();
+        salts.push(salt.to_vec());
 
         BulletproofGens {
+            gens_capacity: self.gens_capacity,
+            party_capacity: self.party_capacity,
+            G_vec: apply(b'G', &self.G_vec),
+            H_vec: apply(b'H', &self.H_vec),
+            salts,
+        }
+    }
+
+    /// Returns the salts applied to these generators, in the order in
+    /// which they were applied.
+    pub fn salts(&self) -> &[Vec<u8>] {
+        &self.salts
+    }
+
+    /// Serializes the generators into a byte array, so that they can
+    /// be cached instead of derived again.
+    ///
+    /// The layout is:
+    /// * the capacities `gens_capacity` and `party_capacity`, and the
+    ///   number of salts, as 32-bit little-endian integers,
+    /// * each salt, prefixed with its length as a 32-bit little-endian
+    ///   integer,
+    /// * the compressed \\(\mathbf G\\) generators of each party,
+    ///   followed by the compressed \\(\mathbf H\\) generators of each
+    ///   party,
+    /// * a 32-byte checksum, the first half of the SHA3-512 hash of
+    ///   the preceding bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        use byteorder::{ByteOrder, LittleEndian};
+
+        let salts_len: usize = self.salts.iter().map(|salt| 4 + salt.len()).sum();
+        let num_points = 2 * self.gens_capacity * self.party_capacity;
+        let mut buf = Vec::with_capacity(12 + salts_len + 32 * num_points + 32);
+
+        let mut header = [0u8; 12];
+        LittleEndian::write_u32(&mut header[0..4], self.gens_capacity as u32);
+        LittleEndian::write_u32(&mut header[4..8], self.party_capacity as u32);
+        LittleEndian::write_u32(&mut header[8..12], self.salts.len() as u32);
+        buf.extend_from_slice(&header);
+        for salt in self.salts.iter() {
+            let mut len = [0u8; 4];
+            LittleEndian::write_u32(&mut len, salt.len() as u32);
+            buf.extend_from_slice(&len);
+            buf.extend_from_slice(salt);
+        }
+        for gens in self.G_vec.iter().chain(self.H_vec.iter()) {
+            for P in gens.iter() {
+                buf.extend_from_slice(P.compress().as_bytes());
+            }
+        }
+
+        let checksum = Sha3_512::digest(&buf);
+        buf.extend_from_slice(&checksum[..32]);
+        buf
+    }
+
+    /// Deserializes generators serialized with
+    /// [`BulletproofGens::to_bytes`].
+    ///
+    /// Returns [`ProofError::FormatError`] if the length of the slice
+    /// does not match its header, if the checksum does not match, or
+    /// if any of the generators is not a valid point.
+    ///
+    /// The checksum only detects corrupted data: the generators are
+    /// not derived again, so they must be loaded from a trusted
+    /// source.
+    pub fn from_bytes(slice: &[u8]) -> Result<BulletproofGens, ProofError> {
+        use byteorder::{ByteOrder, LittleEndian};
+        use util::read32;
+
+        if slice.len() < 12 + 32 {
+            return Err(ProofError::FormatError);
+        }
+        let (body, checksum) = slice.split_at(slice.len() - 32);
+        if Sha3_512::digest(body)[..32] != checksum[..] {
+            return Err(ProofError::FormatError);
+        }
+
+        let gens_capacity = LittleEndian::read_u32(&body[0..4]) as usize;
+        let party_capacity = LittleEndian::read_u32(&body[4..8]) as usize;
+        let num_salts = LittleEndian::read_u32(&body[8..12]) as usize;
+
+        let mut pos = 12;
+        let mut salts = Vec::new();
+        for _ in 0..num_salts {
+            if body.len() < pos + 4 {
+                return Err(ProofError::FormatError);
+            }
+            let len = LittleEndian::read_u32(&body[pos..pos + 4]) as usize;
+            pos += 4;
+            if body.len() - pos < len {
+                return Err(ProofError::FormatError);
+            }
+            salts.push(body[pos..pos + len].to_vec());
+            pos += len;
+        }
+
+        // Parties without generators would let a short input allocate
+        // an arbitrary number of empty vectors.
+        if gens_capacity == 0 && party_capacity != 0 {
+            return Err(ProofError::FormatError);
+        }
+
+        let points = &body[pos..];
+        let num_points = gens_capacity
+            .checked_mul(party_capacity)
+            .and_then(|n| n.checked_mul(2));
+        if num_points.and_then(|n| n.checked_mul(32)) != Some(points.len()) {
+            return Err(ProofError::FormatError);
+        }
+
+        let mut vecs = points
+            .chunks(32 * cmp::max(gens_capacity, 1))
+            .map(|chunk| {
+                chunk
+                    .chunks(32)
+                    .map(|bytes| CompressedRistretto(read32(bytes)).decompress())
+                    .collect::<Option<Vec<_>>>()
+            }).collect::<Option<Vec<_>>>()
+            .ok_or(ProofError::FormatError)?;
+        let H_vec = vecs.split_off(party_capacity);
+        let G_vec = vecs;
+
+        Ok(BulletproofGens {
             gens_capacity,
             party_capacity,
-            G_vec: (0..party_capacity)
-                .map(|i| {
-                    let party_index = i as u32;
-                    let mut label = [b'G', 0, 0, 0, 0];
-                    LittleEndian::write_u32(&mut label[1..5], party_index);
-
-                    GeneratorsChain::new(&label)
-                        .take(gens_capacity)
-                        .collect::<Vec<_>>()
-                }).collect(),
-            H_vec: (0..party_capacity)
-                .map(|i| {
-                    let party_index = i as u32;
-                    let mut label = [b'H', 0, 0, 0, 0];
-                    LittleEndian::write_u32(&mut label[1..5], party_index);
-
-                    GeneratorsChain::new(&label)
-                        .take(gens_capacity)
-                        .collect::<Vec<_>>()
-                }).collect(),
-        }
+            G_vec: Arc::new(G_vec),
+            H_vec: Arc::new(H_vec),
+            salts,
+        })
     }
 
     /// Returns j-th share of generators, with an appropriate
@@ -202,6 +597,41 @@ impl BulletproofGens {
     }
 }
 
+impl Serialize for BulletproofGens {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for BulletproofGens {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BulletproofGensVisitor;
+
+        impl<'de> Visitor<'de> for BulletproofGensVisitor {
+            type Value = BulletproofGens;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("valid BulletproofGens")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<BulletproofGens, E>
+            where
+                E: serde::de::Error,
+            {
+                BulletproofGens::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(BulletproofGensVisitor)
+    }
+}
+
 struct AggregatedGensIter<'a> {
     array: &'a Vec<Vec<RistrettoPoint>>,
     n: usize,
@@ -267,6 +697,17 @@ mod tests {
     extern crate hex;
     use super::*;
 
+    #[test]
+    fn clones_share_generators() {
+        let gens = BulletproofGens::new(64, 2);
+        let clone = gens.clone();
+        assert!(Arc::ptr_eq(&gens.G_vec, &clone.G_vec));
+        assert!(Arc::ptr_eq(&gens.H_vec, &clone.H_vec));
+
+        let handle = ::std::thread::spawn(move || clone.share(1).G(64).count());
+        assert_eq!(handle.join().unwrap(), 64);
+    }
+
     #[test]
     fn aggregated_gens_iter_matches_flat_map() {
         let gens = BulletproofGens::new(64, 8);
@@ -307,4 +748,124 @@ mod tests {
         helper(16, 2);
         helper(16, 1);
     }
+
+    #[test]
+    fn batch_verify_openings() {
+        let pc_gens = PedersenGens::default();
+        let mut rng = rand::thread_rng();
+
+        let mut openings: Vec<_> = (0..10u64)
+            .map(|v| {
+                let value = Scalar::from(v);
+                let blinding = Scalar::random(&mut rng);
+                (pc_gens.commit(value, blinding).compress(), value, blinding)
+            }).collect();
+        assert!(pc_gens.verify_openings(&openings).is_ok());
+        assert!(pc_gens.verify_openings(&[]).is_ok());
+
+        openings[3].1 += Scalar::one();
+        assert_eq!(
+            pc_gens.verify_openings(&openings),
+            Err(ProofError::VerificationError)
+        );
+
+        openings[3].1 -= Scalar::one();
+        openings[5].0 = CompressedRistretto([0xff; 32]);
+        assert_eq!(
+            pc_gens.verify_openings(&openings),
+            Err(ProofError::FormatError)
+        );
+    }
+
+    #[test]
+    fn precomputed_commitments_match() {
+        let pc_gens = PedersenGens::default();
+        let table = pc_gens.precompute();
+        let mut rng = rand::thread_rng();
+
+        for v in 0..4u64 {
+            let blinding = Scalar::random(&mut rng);
+            assert_eq!(
+                table.commit(v.into(), blinding),
+                pc_gens.commit(v.into(), blinding)
+            );
+        }
+    }
+
+    #[test]
+    fn salted_gens_are_deterministic_and_distinct() {
+        let gens = BulletproofGens::new(16, 2);
+        let salted = gens.salted(b"epoch 1");
+
+        assert_eq!(salted.salts(), &[b"epoch 1".to_vec()][..]);
+        assert_eq!(salted.G_vec, gens.salted(b"epoch 1").G_vec);
+        assert!(salted.G_vec != gens.G_vec);
+        assert!(salted.H_vec != gens.salted(b"epoch 2").H_vec);
+    }
+
+    #[test]
+    fn gens_bytes_roundtrip() {
+        use bincode;
+
+        let gens = BulletproofGens::new(16, 2).salted(b"epoch 1");
+        let bytes = gens.to_bytes();
+        assert_eq!(bytes.len(), 12 + 4 + 7 + 2 * 16 * 2 * 32 + 32);
+
+        let decoded = BulletproofGens::from_bytes(&bytes).unwrap();
+        assert_eq!((decoded.gens_capacity, decoded.party_capacity), (16, 2));
+        assert_eq!(decoded.G_vec, gens.G_vec);
+        assert_eq!(decoded.H_vec, gens.H_vec);
+        assert_eq!(decoded.salts(), gens.salts());
+
+        let decoded: BulletproofGens =
+            bincode::deserialize(&bincode::serialize(&gens).unwrap()).unwrap();
+        assert_eq!(decoded.G_vec, gens.G_vec);
+
+        let mut corrupted = bytes.clone();
+        corrupted[100] ^= 1;
+        assert!(BulletproofGens::from_bytes(&corrupted).is_err());
+        assert!(BulletproofGens::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        let empty = BulletproofGens::new(0, 0);
+        assert!(BulletproofGens::from_bytes(&empty.to_bytes()).is_ok());
+    }
+
+    #[test]
+    fn shared_gens_are_reused() {
+        let expected = BulletproofGens::new(64, 4);
+
+        let gens = BulletproofGens::default_64();
+        let again = BulletproofGens::default_64();
+        assert!(gens.gens_capacity >= 64 && gens.party_capacity >= 1);
+        assert_eq!(gens.G_vec[0][..64], expected.G_vec[0][..]);
+        assert!(Arc::ptr_eq(&gens.G_vec, &again.G_vec));
+
+        let larger = BulletproofGens::shared(64, 4);
+        assert!(larger.party_capacity >= 4);
+        assert_eq!(larger.H_vec[3][..64], expected.H_vec[3][..]);
+    }
+
+    #[test]
+    fn increase_capacity_extends_chains() {
+        let mut gens = BulletproofGens::new(16, 2);
+        let clone = gens.clone();
+        gens.increase_capacity(64, 4);
+
+        let expected = BulletproofGens::new(64, 4);
+        assert_eq!((gens.gens_capacity, gens.party_capacity), (64, 4));
+        assert_eq!(gens.G_vec, expected.G_vec);
+        assert_eq!(gens.H_vec, expected.H_vec);
+        assert_eq!(clone.G_vec[0].len(), 16);
+
+        // The capacity never shrinks
+        gens.increase_capacity(8, 1);
+        assert_eq!((gens.gens_capacity, gens.party_capacity), (64, 4));
+
+        // New generators are salted like the existing ones
+        let mut salted = BulletproofGens::new(16, 1).salted(b"epoch 1");
+        salted.increase_capacity(32, 2);
+        let expected = BulletproofGens::new(32, 2).salted(b"epoch 1");
+        assert_eq!(salted.G_vec, expected.G_vec);
+        assert_eq!(salted.H_vec, expected.H_vec);
+    }
 }
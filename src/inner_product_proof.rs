@@ -1,37 +1,71 @@
 #![allow(non_snake_case)]
-#![doc(include = "../docs/inner-product-protocol.md")]
+#![cfg_attr(feature = "nightly", doc(include = "../docs/inner-product-protocol.md"))]
 
 use std::borrow::Borrow;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
 use std::iter;
 
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::VartimeMultiscalarMul;
+use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
 use merlin::Transcript;
+use rand;
+use subtle::ConstantTimeEq;
 
 use errors::ProofError;
+use math::inner_product;
+use multiscalar;
+use scalar;
 use transcript::TranscriptProtocol;
-
+use util::read32;
+
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A logarithmic-size proof of knowledge of two vectors with a given
+/// inner product, as used in range proofs and constraint system
+/// proofs.
+///
+/// For vectors of generators \\(G\\) and \\(H\\) of length \\(n\\), a
+/// power of two, a point \\(Q\\), and factors \\(y\_i\\), the proof
+/// shows knowledge of vectors \\(a\\) and \\(b\\) such that
+/// \\[
+///     P = \langle a, G \rangle + \langle b, H' \rangle + \langle a, b \rangle Q,
+/// \\]
+/// where \\(H'\_i = y\_i H\_i\\).  The factors let the verifier fold
+/// a change of basis of \\(H\\) into its own multiscalar
+/// multiplication; protocols without one pass factors equal to one.
+///
+/// The proof consists of \\(\lg n\\) pairs of points \\((L\_j, R\_j)\\)
+/// and the two final scalars \\(a\\) and \\(b\\).  The challenges are
+/// drawn from the transcript, so the statement \\(P\\) must be bound
+/// to it by the parent protocol before the proof is created or
+/// verified.
 #[derive(Clone, Debug)]
 pub struct InnerProductProof {
-    pub(crate) L_vec: Vec<CompressedRistretto>,
-    pub(crate) R_vec: Vec<CompressedRistretto>,
-    pub(crate) a: Scalar,
-    pub(crate) b: Scalar,
+    L_vec: Vec<CompressedRistretto>,
+    R_vec: Vec<CompressedRistretto>,
+    a: Scalar,
+    b: Scalar,
 }
 
 impl InnerProductProof {
     /// Create an inner-product proof.
     ///
     /// The proof is created with respect to the bases \\(G\\), \\(H'\\),
-    /// where \\(H'\_i = H\_i \cdot \texttt{Hprime\\_factors}\_i\\).
+    /// where \\(H'\_i = H\_i \cdot \texttt{Hprime\\_factors}\_i\\), for
+    /// the vectors `a_vec` and `b_vec`, so that it verifies against
+    /// \\(P = \langle a, G \rangle + \langle b, H' \rangle + \langle a, b \rangle Q\\).
     ///
-    /// The `verifier` is passed in as a parameter so that the
+    /// The `transcript` is passed in as a parameter so that the
     /// challenges depend on the *entire* transcript (including parent
     /// protocols).
     ///
-    /// The lengths of the vectors must all be the same, and must all be
-    /// either 0 or a power of 2.
+    /// # Panics
+    ///
+    /// Panics if the vectors do not all have the same length, a power
+    /// of 2, or if there are fewer `Hprime_factors`.
     pub fn create(
         transcript: &mut Transcript,
         Q: &RistrettoPoint,
@@ -175,71 +209,104 @@ impl InnerProductProof {
         }
     }
 
+    /// Returns the number of folding rounds in the proof, which is
+    /// \\(\lg n\\) for vectors of length \\(n\\).
+    pub fn rounds(&self) -> usize {
+        self.L_vec.len()
+    }
+
+    /// Returns the final scalars \\((a, b)\\) sent by the prover
+    /// after the last folding round.
+    pub fn final_scalars(&self) -> (Scalar, Scalar) {
+        (self.a, self.b)
+    }
+
+    /// Returns the points \\(L_j\\) committed by the prover, in
+    /// creation order.
+    pub fn L_vec(&self) -> &[CompressedRistretto] {
+        &self.L_vec
+    }
+
+    /// Returns the points \\(R_j\\) committed by the prover, in
+    /// creation order.
+    pub fn R_vec(&self) -> &[CompressedRistretto] {
+        &self.R_vec
+    }
+
+    /// Returns an iterator over the pairs of points \\((L_j, R_j)\\)
+    /// committed by the prover, in creation order.
+    pub fn lr_points(
+        &self,
+    ) -> impl Iterator<Item = (&CompressedRistretto, &CompressedRistretto)> {
+        self.L_vec.iter().zip(self.R_vec.iter())
+    }
+
     /// Computes three vectors of verification scalars \\([u\_{i}^{2}]\\), \\([u\_{i}^{-2}]\\) and \\([s\_{i}]\\) for combined multiscalar multiplication
-    /// in a parent protocol. See [inner product protocol notes](index.html#verification-equation) for details.
+    /// in a parent protocol. See the inner product protocol notes for details.
     /// The verifier must provide the input length \\(n\\) explicitly to avoid unbounded allocation within the inner product proof.
-    pub(crate) fn verification_scalars(
+    ///
+    /// The proof holds if
+    /// \\[
+    ///     P = a b Q + \sum\_i a s\_i G\_i + \sum\_i b s\_{n-1-i} H'\_i
+    ///         - \sum\_j u\_j^2 L\_j - \sum\_j u\_j^{-2} R\_j,
+    /// \\]
+    /// as [`verify`](InnerProductProof::verify) checks on its own.
+    pub fn verification_scalars(
         &self,
         n: usize,
         transcript: &mut Transcript,
     ) -> Result<(Vec<Scalar>, Vec<Scalar>, Vec<Scalar>), ProofError> {
-        let lg_n = self.L_vec.len();
-        if lg_n >= 32 {
-            // 4 billion multiplications should be enough for anyone
-            // and this check prevents overflow in 1<<lg_n below.
-            return Err(ProofError::VerificationError);
-        }
-        if n != (1 << lg_n) {
-            return Err(ProofError::VerificationError);
-        }
-
-        transcript.innerproduct_domain_sep(n as u64);
-
-        // 1. Recompute x_k,...,x_1 based on the proof transcript
-
-        let mut challenges = Vec::with_capacity(lg_n);
-        for (L, R) in self.L_vec.iter().zip(self.R_vec.iter()) {
-            transcript.commit_point(b"L", L);
-            transcript.commit_point(b"R", R);
-            challenges.push(transcript.challenge_scalar(b"u"));
-        }
-
-        // 2. Compute 1/(u_k...u_1) and 1/u_k, ..., 1/u_1
-
-        let mut challenges_inv = challenges.clone();
-        let allinv = Scalar::batch_invert(&mut challenges_inv);
-
-        // 3. Compute u_i^2 and (1/u_i)^2
-
-        for i in 0..lg_n {
-            // XXX missing square fn upstream
-            challenges[i] = challenges[i] * challenges[i];
-            challenges_inv[i] = challenges_inv[i] * challenges_inv[i];
-        }
-        let challenges_sq = challenges;
-        let challenges_inv_sq = challenges_inv;
-
-        // 4. Compute s values inductively.
-
-        let mut s = Vec::with_capacity(n);
-        s.push(allinv);
-        for i in 1..n {
-            let lg_i = (32 - 1 - (i as u32).leading_zeros()) as usize;
-            let k = 1 << lg_i;
-            // The challenges are stored in "creation order" as [u_k,...,u_1],
-            // so u_{lg(i)+1} = is indexed by (lg_n-1) - lg_i
-            let u_lg_i_sq = challenges_sq[(lg_n - 1) - lg_i];
-            s.push(s[i - k] * u_lg_i_sq);
-        }
-
-        Ok((challenges_sq, challenges_inv_sq, s))
+        verification_scalars(self, n, transcript)
     }
 
-    /// This method is for testing that proof generation work,
-    /// but for efficiency the actual protocols would use `verification_scalars`
-    /// method to combine inner product verification with other checks
-    /// in a single multiscalar multiplication.
-    #[allow(dead_code)]
+    /// Verifies the proof for vectors of length `n` against the
+    /// statement \\(P = \langle a, G \rangle + \langle b, H' \rangle + \langle a, b \rangle Q\\),
+    /// where \\(H'\_i = H\_i \cdot \texttt{Hprime\\_factors}\_i\\).
+    ///
+    /// The `transcript` must be in the same state as the prover's was
+    /// when it created the proof.  For efficiency, protocols built on
+    /// the proof can use [`verification_scalars`](InnerProductProof::verification_scalars)
+    /// instead, to combine the inner product verification with their
+    /// other checks in a single multiscalar multiplication.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate curve25519_dalek;
+    /// extern crate merlin;
+    /// extern crate rand;
+    /// extern crate bulletproofs;
+    /// use curve25519_dalek::ristretto::RistrettoPoint;
+    /// use curve25519_dalek::scalar::Scalar;
+    /// use curve25519_dalek::traits::MultiscalarMul;
+    /// use merlin::Transcript;
+    /// use bulletproofs::{InnerProductProof, PedersenGens};
+    ///
+    /// # fn main() {
+    /// // The generators must have no known discrete logarithm relation
+    /// let mut rng = rand::thread_rng();
+    /// let n = 4;
+    /// let G: Vec<RistrettoPoint> = (0..n).map(|_| RistrettoPoint::random(&mut rng)).collect();
+    /// let H: Vec<RistrettoPoint> = (0..n).map(|_| RistrettoPoint::random(&mut rng)).collect();
+    /// let Q = PedersenGens::default().B;
+    /// let factors = vec![Scalar::one(); n];
+    ///
+    /// let a: Vec<Scalar> = (1u64..5).map(Scalar::from).collect();
+    /// let b: Vec<Scalar> = (5u64..9).map(Scalar::from).collect();
+    /// // <a, b> = 1*5 + 2*6 + 3*7 + 4*8 = 70
+    /// let P = RistrettoPoint::multiscalar_mul(
+    ///     a.iter().chain(b.iter()).chain(Some(&Scalar::from(70u64))),
+    ///     G.iter().chain(H.iter()).chain(Some(&Q)),
+    /// );
+    ///
+    /// let mut transcript = Transcript::new(b"IPPExample");
+    /// let proof = InnerProductProof::create(
+    ///     &mut transcript, &Q, &factors, G.clone(), H.clone(), a, b,
+    /// );
+    ///
+    /// let mut transcript = Transcript::new(b"IPPExample");
+    /// assert!(proof.verify(n, &mut transcript, &factors, &P, &Q, &G, &H).is_ok());
+    /// # }
+    /// ```
     pub fn verify<I>(
         &self,
         n: usize,
@@ -254,47 +321,90 @@ impl InnerProductProof {
         I: IntoIterator,
         I::Item: Borrow<Scalar>,
     {
-        let (u_sq, u_inv_sq, s) = self.verification_scalars(n, transcript)?;
-
-        let a_times_s = s.iter().map(|s_i| self.a * s_i);
-
-        // 1/s[i] is s[!i], and !i runs from n-1 to 0 as i runs from 0 to n-1
-        let inv_s = s.iter().rev();
-
-        let h_times_b_div_s = Hprime_factors
-            .into_iter()
-            .zip(inv_s)
-            .map(|(h_i, s_i_inv)| (self.b * s_i_inv) * h_i.borrow());
-
-        let neg_u_sq = u_sq.iter().map(|ui| -ui);
-        let neg_u_inv_sq = u_inv_sq.iter().map(|ui| -ui);
-
-        let Ls = self
-            .L_vec
-            .iter()
-            .map(|p| p.decompress().ok_or(ProofError::VerificationError))
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let Rs = self
-            .R_vec
-            .iter()
-            .map(|p| p.decompress().ok_or(ProofError::VerificationError))
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let expect_P = RistrettoPoint::vartime_multiscalar_mul(
-            iter::once(self.a * self.b)
-                .chain(a_times_s)
-                .chain(h_times_b_div_s)
-                .chain(neg_u_sq)
-                .chain(neg_u_inv_sq),
-            iter::once(Q)
-                .chain(G.iter())
-                .chain(H.iter())
-                .chain(Ls.iter())
-                .chain(Rs.iter()),
-        );
+        verify(self, n, transcript, Hprime_factors, P, Q, G, H)
+    }
+
+    /// Verifies many proofs with the same \\(Q\\), \\(G\\) and \\(H\\)
+    /// in a single multiscalar multiplication.
+    ///
+    /// Each statement is a tuple `(proof, transcript, Hprime_factors, P)`,
+    /// checked as by [`verify`](InnerProductProof::verify) for vectors of
+    /// length `Hprime_factors.len()`.  Each verification equation is
+    /// multiplied by a random weight and the equations are summed, so
+    /// the generators appear only once in the multiplication.  The
+    /// statements may have different lengths: each proof uses the
+    /// prefixes of `G` and `H` of its own length.
+    ///
+    /// Returns [`ProofError::InvalidGeneratorsLength`] if `G` or `H`
+    /// is shorter than one of the statements, and
+    /// [`ProofError::VerificationError`] if at least one of the proofs
+    /// is invalid.  An empty batch is trivially valid.
+    pub fn verify_batch<'a, I>(
+        statements: I,
+        Q: &RistrettoPoint,
+        G: &[RistrettoPoint],
+        H: &[RistrettoPoint],
+    ) -> Result<(), ProofError>
+    where
+        I: IntoIterator<
+            Item = (
+                &'a InnerProductProof,
+                &'a mut Transcript,
+                &'a [Scalar],
+                &'a RistrettoPoint,
+            ),
+        >,
+    {
+        let mut rng = rand::thread_rng();
+
+        let mut Q_scalar = Scalar::zero();
+        let mut g_scalars: Vec<Scalar> = Vec::new();
+        let mut h_scalars: Vec<Scalar> = Vec::new();
+        let mut dynamic_scalars: Vec<Scalar> = Vec::new();
+        let mut dynamic_points: Vec<Option<RistrettoPoint>> = Vec::new();
+
+        for (proof, transcript, Hprime_factors, P) in statements {
+            let n = Hprime_factors.len();
+            if G.len() < n || H.len() < n {
+                return Err(ProofError::InvalidGeneratorsLength);
+            }
+            let (u_sq, u_inv_sq, s) = proof.verification_scalars(n, transcript)?;
+
+            // Random weight for this proof's verification equation
+            let c = Scalar::random(&mut rng);
+
+            Q_scalar += c * proof.a * proof.b;
+            if g_scalars.len() < n {
+                g_scalars.resize(n, Scalar::zero());
+                h_scalars.resize(n, Scalar::zero());
+            }
+            let (c_a, c_b) = (c * proof.a, c * proof.b);
+            for i in 0..n {
+                g_scalars[i] += c_a * s[i];
+                // 1/s[i] is s[!i], as in `verify`
+                h_scalars[i] += c_b * s[n - 1 - i] * Hprime_factors[i];
+            }
 
-        if expect_P == *P {
+            dynamic_scalars.extend(u_sq.iter().chain(u_inv_sq.iter()).map(|u| -c * u));
+            dynamic_points.extend(proof.L_vec.iter().map(|L| L.decompress()));
+            dynamic_points.extend(proof.R_vec.iter().map(|R| R.decompress()));
+            dynamic_scalars.push(-c);
+            dynamic_points.push(Some(*P));
+        }
+
+        let n = g_scalars.len();
+        let check = multiscalar::optional_multiscalar_mul(
+            iter::once(Q_scalar)
+                .chain(g_scalars)
+                .chain(h_scalars)
+                .chain(dynamic_scalars),
+            iter::once(Some(*Q))
+                .chain(G[..n].iter().map(|&G_i| Some(G_i)))
+                .chain(H[..n].iter().map(|&H_i| Some(H_i)))
+                .chain(dynamic_points),
+        ).ok_or(ProofError::VerificationError)?;
+
+        if check.is_identity() {
             Ok(())
         } else {
             Err(ProofError::VerificationError)
@@ -307,7 +417,7 @@ impl InnerProductProof {
     /// For vectors of length `n` the proof size is
     /// \\(32 \cdot (2\lg n+2)\\) bytes.
     pub fn serialized_size(&self) -> usize {
-        (self.L_vec.len() * 2 + 2) * 32
+        (self.rounds() * 2 + 2) * 32
     }
 
     /// Serializes the proof into a byte array of \\(2n+2\\) 32-byte elements.
@@ -325,63 +435,408 @@ impl InnerProductProof {
         buf
     }
 
+    /// Writes the proof into the start of `out` in the encoding of
+    /// [`to_bytes`](InnerProductProof::to_bytes), and returns the
+    /// number of bytes written, which is the
+    /// [`serialized_size`](InnerProductProof::serialized_size).
+    ///
+    /// Returns [`ProofError::BufferTooSmall`] if `out` is shorter than
+    /// the encoding.
+    pub fn write_bytes(&self, out: &mut [u8]) -> Result<usize, ProofError> {
+        let size = self.serialized_size();
+        if out.len() < size {
+            return Err(ProofError::BufferTooSmall { size });
+        }
+        let elements = self
+            .lr_points()
+            .flat_map(|(L, R)| iter::once(L.as_bytes()).chain(iter::once(R.as_bytes())))
+            .chain(iter::once(self.a.as_bytes()))
+            .chain(iter::once(self.b.as_bytes()));
+        for (chunk, element) in out[..size].chunks_mut(32).zip(elements) {
+            chunk.copy_from_slice(element);
+        }
+        Ok(size)
+    }
+
+    /// Writes the proof to `writer` in the encoding of
+    /// [`to_bytes`](InnerProductProof::to_bytes), without allocating an
+    /// intermediate buffer.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        for (l, r) in self.L_vec.iter().zip(self.R_vec.iter()) {
+            writer.write_all(l.as_bytes())?;
+            writer.write_all(r.as_bytes())?;
+        }
+        writer.write_all(self.a.as_bytes())?;
+        writer.write_all(self.b.as_bytes())
+    }
+
     /// Deserializes the proof from a byte slice.
     /// Returns an error in the following cases:
-    /// * the slice does not have \\(2n+2\\) 32-byte elements,
-    /// * \\(n\\) is larger or equal to 32 (proof is too big),
-    /// * any of \\(2n\\) points are not valid compressed Ristretto points,
-    /// * any of 2 scalars are not canonical scalars modulo Ristretto group order.
+    /// * [`ProofError::InvalidLength`] if the slice does not have
+    ///   \\(2n+2\\) 32-byte elements, or \\(n\\) is larger or equal
+    ///   to 32 (proof is too big),
+    /// * [`ProofError::InvalidScalar`] if any of the 2 scalars are not
+    ///   canonical scalars modulo Ristretto group order.
+    ///
+    /// The \\(2n\\) points are decompressed during verification, see
+    /// [`from_bytes_strict`](InnerProductProof::from_bytes_strict).
     pub fn from_bytes(slice: &[u8]) -> Result<InnerProductProof, ProofError> {
+        InnerProductProofRef::from_bytes(slice).map(|proof| proof.to_proof())
+    }
+
+    /// Deserializes the proof from a byte slice, like
+    /// [`from_bytes`](InnerProductProof::from_bytes), and also checks
+    /// that the \\(2n\\) points are valid compressed Ristretto points.
+    ///
+    /// Returns [`ProofError::InvalidPoint`] with the index of the first
+    /// invalid point in the encoding.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<InnerProductProof, ProofError> {
+        let proof = InnerProductProof::from_bytes(slice)?;
+        match proof.first_invalid_point() {
+            Some(index) => Err(ProofError::InvalidPoint { index }),
+            None => Ok(proof),
+        }
+    }
+
+    /// Returns the index in the encoding of the first point which does
+    /// not decompress, if any.
+    pub(crate) fn first_invalid_point(&self) -> Option<usize> {
+        self.lr_points()
+            .flat_map(|(L, R)| iter::once(L).chain(iter::once(R)))
+            .position(|P| P.decompress().is_none())
+    }
+}
+
+/// Compares the canonical encodings of the proofs in constant time.
+impl PartialEq for InnerProductProof {
+    fn eq(&self, other: &InnerProductProof) -> bool {
+        self.to_bytes()[..].ct_eq(&other.to_bytes()[..]).unwrap_u8() == 1
+    }
+}
+
+impl Eq for InnerProductProof {}
+
+/// Hashes the canonical encoding of the proof, so that equal proofs
+/// have equal hashes.
+impl Hash for InnerProductProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+impl Serialize for InnerProductProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for InnerProductProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct InnerProductProofVisitor;
+
+        impl<'de> Visitor<'de> for InnerProductProofVisitor {
+            type Value = InnerProductProof;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("a valid InnerProductProof")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<InnerProductProof, E>
+            where
+                E: serde::de::Error,
+            {
+                InnerProductProof::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(InnerProductProofVisitor)
+    }
+}
+
+/// A borrowed view of a serialized [`InnerProductProof`], which is
+/// parsed from a byte slice without copying the points.
+///
+/// High-throughput verifiers can check proofs straight from the
+/// buffers they receive: [`verify`](InnerProductProofRef::verify) and
+/// [`verification_scalars`](InnerProductProofRef::verification_scalars)
+/// read each point from the slice when they need it, and give the
+/// same results as the methods of the same names of
+/// [`InnerProductProof`].
+#[derive(Copy, Clone, Debug)]
+pub struct InnerProductProofRef<'a> {
+    /// The pairs of compressed points \\((L\_j, R\_j)\\)
+    bytes: &'a [u8],
+    lg_n: usize,
+    a: Scalar,
+    b: Scalar,
+}
+
+impl<'a> InnerProductProofRef<'a> {
+    /// Parses a proof in the encoding of
+    /// [`InnerProductProof::to_bytes`], borrowing the points from the
+    /// slice.
+    ///
+    /// Returns the same errors as [`InnerProductProof::from_bytes`].
+    pub fn from_bytes(slice: &'a [u8]) -> Result<InnerProductProofRef<'a>, ProofError> {
         let b = slice.len();
+        let invalid_length = ProofError::InvalidLength { length: b };
         if b % 32 != 0 {
-            return Err(ProofError::FormatError);
+            return Err(invalid_length);
         }
         let num_elements = b / 32;
         if num_elements < 2 {
-            return Err(ProofError::FormatError);
+            return Err(invalid_length);
         }
         if (num_elements - 2) % 2 != 0 {
-            return Err(ProofError::FormatError);
+            return Err(invalid_length);
         }
         let lg_n = (num_elements - 2) / 2;
         if lg_n >= 32 {
-            return Err(ProofError::FormatError);
+            return Err(invalid_length);
         }
 
-        use util::read32;
+        let pos = 2 * lg_n * 32;
+        let a = scalar::decode_element(slice, 2 * lg_n)?;
+        let b = scalar::decode_element(slice, 2 * lg_n + 1)?;
+
+        Ok(InnerProductProofRef {
+            bytes: &slice[..pos],
+            lg_n,
+            a,
+            b,
+        })
+    }
+
+    /// Returns the number of folding rounds in the proof, which is
+    /// \\(\lg n\\) for vectors of length \\(n\\).
+    pub fn rounds(&self) -> usize {
+        self.lg_n
+    }
+
+    /// Returns the final scalars \\((a, b)\\) sent by the prover
+    /// after the last folding round.
+    pub fn final_scalars(&self) -> (Scalar, Scalar) {
+        (self.a, self.b)
+    }
+
+    /// Returns an iterator over the pairs of points \\((L_j, R_j)\\)
+    /// committed by the prover, in creation order.
+    pub fn lr_points(
+        &self,
+    ) -> impl Iterator<Item = (CompressedRistretto, CompressedRistretto)> + 'a {
+        let proof = *self;
+        (0..self.lg_n).map(move |j| proof.lr(j))
+    }
+
+    /// Computes the verification scalars of the proof, as
+    /// [`InnerProductProof::verification_scalars`] does.
+    pub fn verification_scalars(
+        &self,
+        n: usize,
+        transcript: &mut Transcript,
+    ) -> Result<(Vec<Scalar>, Vec<Scalar>, Vec<Scalar>), ProofError> {
+        verification_scalars(self, n, transcript)
+    }
 
-        let mut L_vec: Vec<CompressedRistretto> = Vec::with_capacity(lg_n);
-        let mut R_vec: Vec<CompressedRistretto> = Vec::with_capacity(lg_n);
-        for i in 0..lg_n {
-            let pos = 2 * i * 32;
-            L_vec.push(CompressedRistretto(read32(&slice[pos..])));
-            R_vec.push(CompressedRistretto(read32(&slice[pos + 32..])));
+    /// Verifies the proof, as [`InnerProductProof::verify`] does.
+    pub fn verify<I>(
+        &self,
+        n: usize,
+        transcript: &mut Transcript,
+        Hprime_factors: I,
+        P: &RistrettoPoint,
+        Q: &RistrettoPoint,
+        G: &[RistrettoPoint],
+        H: &[RistrettoPoint],
+    ) -> Result<(), ProofError>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Scalar>,
+    {
+        verify(self, n, transcript, Hprime_factors, P, Q, G, H)
+    }
+
+    /// Copies the proof into an owned [`InnerProductProof`].
+    pub fn to_proof(&self) -> InnerProductProof {
+        let (L_vec, R_vec) = self.lr_points().unzip();
+        InnerProductProof {
+            L_vec,
+            R_vec,
+            a: self.a,
+            b: self.b,
         }
+    }
+}
 
-        let pos = 2 * lg_n * 32;
-        let a =
-            Scalar::from_canonical_bytes(read32(&slice[pos..])).ok_or(ProofError::FormatError)?;
-        let b = Scalar::from_canonical_bytes(read32(&slice[pos + 32..]))
-            .ok_or(ProofError::FormatError)?;
+/// The elements of an inner-product proof, which is either owned or
+/// borrowed from its serialization.
+trait ProofElements {
+    /// Returns the number of folding rounds.
+    fn rounds(&self) -> usize;
+    /// Returns the pair of points \\((L\_j, R\_j)\\) of round \\(j\\).
+    fn lr(&self, j: usize) -> (CompressedRistretto, CompressedRistretto);
+    /// Returns the final scalars \\((a, b)\\).
+    fn final_scalars(&self) -> (Scalar, Scalar);
+}
+
+impl ProofElements for InnerProductProof {
+    fn rounds(&self) -> usize {
+        self.L_vec.len()
+    }
 
-        Ok(InnerProductProof { L_vec, R_vec, a, b })
+    fn lr(&self, j: usize) -> (CompressedRistretto, CompressedRistretto) {
+        (self.L_vec[j], self.R_vec[j])
+    }
+
+    fn final_scalars(&self) -> (Scalar, Scalar) {
+        (self.a, self.b)
     }
 }
 
-/// Computes an inner product of two vectors
-/// \\[
-///    {\langle {\mathbf{a}}, {\mathbf{b}} \rangle} = \sum\_{i=0}^{n-1} a\_i \cdot b\_i.
-/// \\]
-/// Panics if the lengths of \\(\mathbf{a}\\) and \\(\mathbf{b}\\) are not equal.
-pub fn inner_product(a: &[Scalar], b: &[Scalar]) -> Scalar {
-    let mut out = Scalar::zero();
-    if a.len() != b.len() {
-        panic!("inner_product(a,b): lengths of vectors do not match");
+impl<'a> ProofElements for InnerProductProofRef<'a> {
+    fn rounds(&self) -> usize {
+        self.lg_n
     }
-    for i in 0..a.len() {
-        out += a[i] * b[i];
+
+    fn lr(&self, j: usize) -> (CompressedRistretto, CompressedRistretto) {
+        let pos = 2 * j * 32;
+        (
+            CompressedRistretto(read32(&self.bytes[pos..])),
+            CompressedRistretto(read32(&self.bytes[pos + 32..])),
+        )
+    }
+
+    fn final_scalars(&self) -> (Scalar, Scalar) {
+        (self.a, self.b)
+    }
+}
+
+/// Implements [`InnerProductProof::verification_scalars`] for both
+/// representations of the proof.
+fn verification_scalars<T: ProofElements>(
+    proof: &T,
+    n: usize,
+    transcript: &mut Transcript,
+) -> Result<(Vec<Scalar>, Vec<Scalar>, Vec<Scalar>), ProofError> {
+    let lg_n = proof.rounds();
+    if lg_n >= 32 {
+        // 4 billion multiplications should be enough for anyone
+        // and this check prevents overflow in 1<<lg_n below.
+        return Err(ProofError::VerificationError);
+    }
+    if n != (1 << lg_n) {
+        return Err(ProofError::VerificationError);
+    }
+
+    transcript.innerproduct_domain_sep(n as u64);
+
+    // 1. Recompute x_k,...,x_1 based on the proof transcript
+
+    let mut challenges = Vec::with_capacity(lg_n);
+    for j in 0..lg_n {
+        let (L, R) = proof.lr(j);
+        transcript.commit_point(b"L", &L);
+        transcript.commit_point(b"R", &R);
+        challenges.push(transcript.challenge_scalar(b"u"));
+    }
+
+    // 2. Compute 1/(u_k...u_1) and 1/u_k, ..., 1/u_1
+
+    let mut challenges_inv = challenges.clone();
+    let allinv = Scalar::batch_invert(&mut challenges_inv);
+
+    // 3. Compute u_i^2 and (1/u_i)^2
+
+    for i in 0..lg_n {
+        // XXX missing square fn upstream
+        challenges[i] = challenges[i] * challenges[i];
+        challenges_inv[i] = challenges_inv[i] * challenges_inv[i];
+    }
+    let challenges_sq = challenges;
+    let challenges_inv_sq = challenges_inv;
+
+    // 4. Compute s values inductively.
+
+    let mut s = Vec::with_capacity(n);
+    s.push(allinv);
+    for i in 1..n {
+        let lg_i = (32 - 1 - (i as u32).leading_zeros()) as usize;
+        let k = 1 << lg_i;
+        // The challenges are stored in "creation order" as [u_k,...,u_1],
+        // so u_{lg(i)+1} = is indexed by (lg_n-1) - lg_i
+        let u_lg_i_sq = challenges_sq[(lg_n - 1) - lg_i];
+        s.push(s[i - k] * u_lg_i_sq);
+    }
+
+    Ok((challenges_sq, challenges_inv_sq, s))
+}
+
+/// Implements [`InnerProductProof::verify`] for both representations
+/// of the proof.
+fn verify<T: ProofElements, I>(
+    proof: &T,
+    n: usize,
+    transcript: &mut Transcript,
+    Hprime_factors: I,
+    P: &RistrettoPoint,
+    Q: &RistrettoPoint,
+    G: &[RistrettoPoint],
+    H: &[RistrettoPoint],
+) -> Result<(), ProofError>
+where
+    I: IntoIterator,
+    I::Item: Borrow<Scalar>,
+{
+    let (u_sq, u_inv_sq, s) = verification_scalars(proof, n, transcript)?;
+    let (a, b) = proof.final_scalars();
+
+    let a_times_s = s.iter().map(|s_i| a * s_i);
+
+    // 1/s[i] is s[!i], and !i runs from n-1 to 0 as i runs from 0 to n-1
+    let inv_s = s.iter().rev();
+
+    let h_times_b_div_s = Hprime_factors
+        .into_iter()
+        .zip(inv_s)
+        .map(|(h_i, s_i_inv)| (b * s_i_inv) * h_i.borrow());
+
+    let neg_u_sq = u_sq.iter().map(|ui| -ui);
+    let neg_u_inv_sq = u_inv_sq.iter().map(|ui| -ui);
+
+    let Ls = (0..proof.rounds())
+        .map(|j| proof.lr(j).0.decompress())
+        .collect::<Option<Vec<_>>>()
+        .ok_or(ProofError::VerificationError)?;
+
+    let Rs = (0..proof.rounds())
+        .map(|j| proof.lr(j).1.decompress())
+        .collect::<Option<Vec<_>>>()
+        .ok_or(ProofError::VerificationError)?;
+
+    let expect_P = RistrettoPoint::vartime_multiscalar_mul(
+        iter::once(a * b)
+            .chain(a_times_s)
+            .chain(h_times_b_div_s)
+            .chain(neg_u_sq)
+            .chain(neg_u_inv_sq),
+        iter::once(Q)
+            .chain(G.iter())
+            .chain(H.iter())
+            .chain(Ls.iter())
+            .chain(Rs.iter()),
+    );
+
+    if expect_P == *P {
+        Ok(())
+    } else {
+        Err(ProofError::VerificationError)
     }
-    out
 }
 
 #[cfg(test)]
@@ -390,7 +845,7 @@ mod tests {
 
     use rand::OsRng;
     use sha3::Sha3_512;
-    use util;
+    use math;
 
     fn test_helper_create(n: usize) {
         let mut rng = OsRng::new().unwrap();
@@ -410,14 +865,14 @@ mod tests {
 
         // y_inv is (the inverse of) a random challenge
         let y_inv = Scalar::random(&mut rng);
-        let Hprime_factors: Vec<Scalar> = util::exp_iter(y_inv).take(n).collect();
+        let Hprime_factors: Vec<Scalar> = math::powers(y_inv, n).collect();
 
         // P would be determined upstream, but we need a correct P to check the proof.
         //
         // To generate P = <a,G> + <b,H'> + <a,b> Q, compute
         //             P = <a,G> + <b',H> + <a,b> Q,
         // where b' = b \circ y^(-n)
-        let b_prime = b.iter().zip(util::exp_iter(y_inv)).map(|(bi, yi)| bi * yi);
+        let b_prime = b.iter().zip(math::exp_iter(y_inv)).map(|(bi, yi)| bi * yi);
         // a.iter() has Item=&Scalar, need Item=Scalar to chain with b_prime
         let a_prime = a.iter().cloned();
 
@@ -440,7 +895,7 @@ mod tests {
         let mut verifier = Transcript::new(b"innerproducttest");
         assert!(
             proof
-                .verify(n, &mut verifier, util::exp_iter(y_inv), &P, &Q, &G, &H)
+                .verify(n, &mut verifier, math::exp_iter(y_inv), &P, &Q, &G, &H)
                 .is_ok()
         );
 
@@ -448,7 +903,19 @@ mod tests {
         let mut verifier = Transcript::new(b"innerproducttest");
         assert!(
             proof
-                .verify(n, &mut verifier, util::exp_iter(y_inv), &P, &Q, &G, &H)
+                .verify(n, &mut verifier, math::exp_iter(y_inv), &P, &Q, &G, &H)
+                .is_ok()
+        );
+
+        let mut bytes = Vec::new();
+        proof.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes, proof.to_bytes());
+        let proof_ref = InnerProductProofRef::from_bytes(&bytes).unwrap();
+        assert_eq!(proof_ref.to_proof().to_bytes(), bytes);
+        let mut verifier = Transcript::new(b"innerproducttest");
+        assert!(
+            proof_ref
+                .verify(n, &mut verifier, math::exp_iter(y_inv), &P, &Q, &G, &H)
                 .is_ok()
         );
     }
@@ -478,6 +945,64 @@ mod tests {
         test_helper_create(64);
     }
 
+    #[test]
+    fn verify_batch_of_mixed_sizes() {
+        let mut rng = OsRng::new().unwrap();
+        let Q = RistrettoPoint::hash_from_bytes::<Sha3_512>(b"test point");
+        let G: Vec<RistrettoPoint> = (0..8).map(|_| RistrettoPoint::random(&mut rng)).collect();
+        let H: Vec<RistrettoPoint> = (0..8).map(|_| RistrettoPoint::random(&mut rng)).collect();
+
+        let mut proofs = Vec::new();
+        let mut factors = Vec::new();
+        let mut Ps = Vec::new();
+        for &n in &[1, 4, 8, 2] {
+            let a: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+            let b: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+            let y_inv = Scalar::random(&mut rng);
+            let Hprime_factors: Vec<Scalar> = math::powers(y_inv, n).collect();
+
+            let b_prime = b.iter().zip(&Hprime_factors).map(|(b_i, y_i)| b_i * y_i);
+            Ps.push(RistrettoPoint::vartime_multiscalar_mul(
+                a.iter()
+                    .cloned()
+                    .chain(b_prime)
+                    .chain(iter::once(inner_product(&a, &b))),
+                G[..n].iter().chain(H[..n].iter()).chain(iter::once(&Q)),
+            ));
+            proofs.push(InnerProductProof::create(
+                &mut Transcript::new(b"innerproductbatchtest"),
+                &Q,
+                &Hprime_factors,
+                G[..n].to_vec(),
+                H[..n].to_vec(),
+                a,
+                b,
+            ));
+            factors.push(Hprime_factors);
+        }
+
+        let batch = |Ps: &[RistrettoPoint]| {
+            let mut transcripts = vec![Transcript::new(b"innerproductbatchtest"); Ps.len()];
+            let statements = proofs
+                .iter()
+                .zip(transcripts.iter_mut())
+                .zip(factors.iter())
+                .zip(Ps.iter())
+                .map(|(((proof, transcript), factors), P)| (proof, transcript, &factors[..], P));
+            InnerProductProof::verify_batch(statements, &Q, &G, &H)
+        };
+
+        assert!(batch(&Ps).is_ok());
+        Ps[2] = Ps[2] + Q;
+        assert_eq!(batch(&Ps), Err(ProofError::VerificationError));
+        let mut transcript = Transcript::new(b"innerproductbatchtest");
+        let statement = (&proofs[2], &mut transcript, &factors[2][..], &Ps[2]);
+        assert_eq!(
+            InnerProductProof::verify_batch(iter::once(statement), &Q, &G[..4], &H[..4]),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+    }
+
     #[test]
     fn test_inner_product() {
         let a = vec![
@@ -1,7 +1,11 @@
-#![feature(nll)]
-#![feature(external_doc)]
+#![cfg_attr(feature = "nightly", feature(nll))]
+#![cfg_attr(feature = "nightly", feature(external_doc))]
 #![deny(missing_docs)]
-#![doc(include = "../README.md")]
+#![cfg_attr(feature = "nightly", doc(include = "../README.md"))]
+#![cfg_attr(
+    not(feature = "nightly"),
+    doc = "A pure-Rust implementation of Bulletproofs using Ristretto."
+)]
 #![doc(html_logo_url = "https://doc.dalek.rs/assets/dalek-logo-clear.png")]
 
 extern crate byteorder;
@@ -12,36 +16,103 @@ extern crate sha3;
 
 extern crate clear_on_drop;
 extern crate curve25519_dalek;
+#[macro_use]
+extern crate lazy_static;
 extern crate merlin;
 extern crate subtle;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde;
 
-#[macro_use]
-extern crate failure;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
+#[cfg(feature = "futures")]
+extern crate futures;
 
 #[cfg(test)]
 extern crate bincode;
 
 mod util;
 
-#[doc(include = "../docs/notes.md")]
+#[cfg_attr(feature = "nightly", doc(include = "../docs/notes.md"))]
 mod notes {}
+pub mod asset;
+mod circuit_proof;
+mod commitment;
+mod comparison_proof;
+pub mod ct;
 mod errors;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 mod generators;
 mod inner_product_proof;
+pub mod math;
+pub mod membership;
+pub mod multiscalar;
+pub mod r1cs;
 mod range_proof;
+mod range_proof_plus;
+pub mod scalar;
+pub mod sigma;
+pub mod solvency;
 mod transcript;
+mod value_proof;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod weighted_inner_product_proof;
 
+pub use circuit_proof::{ArithmeticCircuit, CircuitProof};
+pub use commitment::{Commitment, Opening};
+pub use comparison_proof::ComparisonProof;
 pub use errors::ProofError;
-pub use generators::{BulletproofGens, BulletproofGensShare, PedersenGens};
-pub use range_proof::RangeProof;
+pub use generators::{BulletproofGens, BulletproofGensShare, PedersenGens, PedersenGensTable};
+pub use inner_product_proof::{InnerProductProof, InnerProductProofRef};
+pub use range_proof::{
+    BatchVerifier, RangeProof, RangeProofBuilder, RangeProofHeader, RangeProver, RangeVerifier,
+    VerificationReport, VerifierNonce, MEMO_SIZE,
+};
+pub use range_proof_plus::RangeProofPlus;
+pub use value_proof::ValueOpeningProof;
+pub use weighted_inner_product_proof::WeightedInnerProductProof;
+
+#[cfg(feature = "rayon")]
+pub use range_proof::{BatchOptions, FailureMode};
 
-#[doc(include = "../docs/aggregation-api.md")]
+pub use range_proof::bitsize;
+pub use range_proof::delegation;
+#[cfg(feature = "service")]
+pub use range_proof::service;
+
+#[cfg_attr(feature = "nightly", doc(include = "../docs/aggregation-api.md"))]
+#[cfg_attr(
+    not(feature = "nightly"),
+    doc = "The `aggregation` module contains the API for the aggregated MPC protocol."
+)]
 pub mod aggregation {
     pub use errors::MPCError;
+    pub use range_proof::blame;
+    #[cfg(feature = "futures")]
+    pub use range_proof::async_session;
     pub use range_proof::dealer;
     pub use range_proof::messages;
     pub use range_proof::party;
+    pub use range_proof::secrets;
+    pub use range_proof::session_manager;
+    pub use range_proof::threshold;
+    pub use range_proof::transport;
+}
+
+/// The `prelude` re-exports the types most commonly needed to create
+/// and verify range proofs, so that they can be imported with a single
+/// `use bulletproofs::prelude::*;`.
+pub mod prelude {
+    pub use errors::{MPCError, ProofError};
+    pub use generators::{BulletproofGens, PedersenGens};
+    pub use merlin::Transcript;
+    pub use range_proof::bitsize::ProvableValue;
+    pub use range_proof::{
+        BatchVerifier, RangeProof, RangeProofBuilder, RangeProver, RangeVerifier,
+    };
+    pub use transcript::TranscriptProtocol;
 }
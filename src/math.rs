@@ -0,0 +1,249 @@
+//! The `math` module contains the vector and power helpers used by the
+//! provers and verifiers.
+//!
+//! They are public so that external verifiers can recompute the same
+//! scalars, for instance to fold the verification equation of a
+//! [`RangeProof`](::RangeProof) into a larger multiscalar
+//! multiplication.
+
+use curve25519_dalek::scalar::Scalar;
+
+use scalar;
+use util;
+
+/// Provides an iterator over the powers of a `Scalar`.
+///
+/// This struct is created by the [`exp_iter`] function.  The iterator
+/// is infinite; use [`powers`] for an iterator of known length.
+#[derive(Clone, Debug)]
+pub struct ScalarExp {
+    x: Scalar,
+    next_exp_x: Scalar,
+}
+
+impl Iterator for ScalarExp {
+    type Item = Scalar;
+
+    fn next(&mut self) -> Option<Scalar> {
+        let exp_x = self.next_exp_x;
+        self.next_exp_x *= self.x;
+        Some(exp_x)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::max_value(), None)
+    }
+}
+
+/// Return an iterator of the powers of `x`, \\(1, x, x^2, \ldots\\).
+pub fn exp_iter(x: Scalar) -> ScalarExp {
+    let next_exp_x = Scalar::one();
+    ScalarExp { x, next_exp_x }
+}
+
+/// Provides an iterator over the first `n` powers of a `Scalar`.
+///
+/// This struct is created by the [`powers`] function.
+#[derive(Clone, Debug)]
+pub struct Powers {
+    exp: ScalarExp,
+    remaining: usize,
+}
+
+impl Iterator for Powers {
+    type Item = Scalar;
+
+    fn next(&mut self) -> Option<Scalar> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        self.exp.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Powers {}
+
+/// Return an iterator of the `n` powers \\(1, x, \ldots, x^{n-1}\\).
+pub fn powers(x: Scalar, n: usize) -> Powers {
+    Powers {
+        exp: exp_iter(x),
+        remaining: n,
+    }
+}
+
+/// Computes an inner product of two vectors
+/// \\[
+///    {\langle {\mathbf{a}}, {\mathbf{b}} \rangle} = \sum\_{i=0}^{n-1} a\_i \cdot b\_i.
+/// \\]
+/// Panics if the lengths of \\(\mathbf{a}\\) and \\(\mathbf{b}\\) are not equal.
+pub fn inner_product(a: &[Scalar], b: &[Scalar]) -> Scalar {
+    let mut out = Scalar::zero();
+    if a.len() != b.len() {
+        panic!("inner_product(a,b): lengths of vectors do not match");
+    }
+    for i in 0..a.len() {
+        out += a[i] * b[i];
+    }
+    out
+}
+
+/// Computes a weighted inner product of two vectors
+/// \\[
+///    {\langle {\mathbf{a}}, {\mathbf{b}} \rangle}\_y = \sum\_{i=0}^{n-1} a\_i \cdot b\_i \cdot y^{i+1}.
+/// \\]
+/// Panics if the lengths of \\(\mathbf{a}\\) and \\(\mathbf{b}\\) are not equal.
+pub fn weighted_inner_product(a: &[Scalar], b: &[Scalar], y: &Scalar) -> Scalar {
+    let mut out = Scalar::zero();
+    if a.len() != b.len() {
+        panic!("weighted_inner_product(a,b): lengths of vectors do not match");
+    }
+    let mut exp_y = *y;
+    for i in 0..a.len() {
+        out += a[i] * b[i] * exp_y;
+        exp_y *= y;
+    }
+    out
+}
+
+/// Computes the elementwise sum \\(\mathbf{a} + \mathbf{b}\\) of two vectors.
+///
+/// Panics if the lengths of \\(\mathbf{a}\\) and \\(\mathbf{b}\\) are not equal.
+pub fn add_vec(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
+    if a.len() != b.len() {
+        panic!("add_vec(a,b): lengths of vectors do not match");
+    }
+    a.iter().zip(b.iter()).map(|(a_i, b_i)| a_i + b_i).collect()
+}
+
+/// Takes the sum of all the powers of `x`, up to `n`, that is
+/// \\(1 + x + \cdots + x^{n-1}\\).
+///
+/// If `n` is a power of 2, it uses the efficient algorithm with `2*lg n` multiplications and additions.
+/// If `n` is not a power of 2, it uses the slow algorithm with `n` multiplications and additions.
+/// In the Bulletproofs case, all calls to `sum_of_powers` should have `n` as a power of 2.
+pub fn sum_of_powers(x: &Scalar, n: usize) -> Scalar {
+    if !n.is_power_of_two() {
+        return sum_of_powers_slow(x, n);
+    }
+    if n == 0 || n == 1 {
+        return Scalar::from(n as u64);
+    }
+    let mut m = n;
+    let mut result = Scalar::one() + x;
+    let mut factor = *x;
+    while m > 2 {
+        factor = factor * factor;
+        result = result + factor * result;
+        m = m / 2;
+    }
+    result
+}
+
+/// Takes the sum of the first `n` powers of 2, that is
+/// \\(\langle \mathbf{1}, \mathbf{2}^n \rangle = 2^n - 1\\).
+///
+/// This is a special case of [`sum_of_powers`] which needs at most
+/// `lg n` multiplications, and none if `n < 128`.
+pub fn sum_of_powers_of_2(n: usize) -> Scalar {
+    if n < 128 {
+        return scalar::from_u128((1u128 << n) - 1);
+    }
+    util::scalar_exp_vartime(&Scalar::from(2u64), n as u64) - Scalar::one()
+}
+
+// takes the sum of all of the powers of x, up to n
+fn sum_of_powers_slow(x: &Scalar, n: usize) -> Scalar {
+    powers(*x, n).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exp_2_is_powers_of_2() {
+        let exp_2: Vec<_> = exp_iter(Scalar::from(2u64)).take(4).collect();
+
+        assert_eq!(exp_2[0], Scalar::from(1u64));
+        assert_eq!(exp_2[1], Scalar::from(2u64));
+        assert_eq!(exp_2[2], Scalar::from(4u64));
+        assert_eq!(exp_2[3], Scalar::from(8u64));
+    }
+
+    #[test]
+    fn powers_has_exact_size() {
+        let mut iter = powers(Scalar::from(3u64), 3);
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next(), Some(Scalar::one()));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next(), Some(Scalar::from(3u64)));
+        assert_eq!(iter.next(), Some(Scalar::from(9u64)));
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_inner_product() {
+        let a = vec![
+            Scalar::from(1u64),
+            Scalar::from(2u64),
+            Scalar::from(3u64),
+            Scalar::from(4u64),
+        ];
+        let b = vec![
+            Scalar::from(2u64),
+            Scalar::from(3u64),
+            Scalar::from(4u64),
+            Scalar::from(5u64),
+        ];
+        assert_eq!(Scalar::from(40u64), inner_product(&a, &b));
+    }
+
+    #[test]
+    fn test_add_vec() {
+        let a = vec![Scalar::from(1u64), Scalar::from(2u64)];
+        let b = vec![Scalar::from(10u64), Scalar::from(20u64)];
+        assert_eq!(
+            add_vec(&a, &b),
+            vec![Scalar::from(11u64), Scalar::from(22u64)]
+        );
+    }
+
+    #[test]
+    fn test_sum_of_powers() {
+        let x = Scalar::from(10u64);
+        assert_eq!(sum_of_powers_slow(&x, 0), sum_of_powers(&x, 0));
+        assert_eq!(sum_of_powers_slow(&x, 1), sum_of_powers(&x, 1));
+        assert_eq!(sum_of_powers_slow(&x, 2), sum_of_powers(&x, 2));
+        assert_eq!(sum_of_powers_slow(&x, 4), sum_of_powers(&x, 4));
+        assert_eq!(sum_of_powers_slow(&x, 8), sum_of_powers(&x, 8));
+        assert_eq!(sum_of_powers_slow(&x, 16), sum_of_powers(&x, 16));
+        assert_eq!(sum_of_powers_slow(&x, 32), sum_of_powers(&x, 32));
+        assert_eq!(sum_of_powers_slow(&x, 64), sum_of_powers(&x, 64));
+    }
+
+    #[test]
+    fn test_sum_of_powers_of_2() {
+        let two = Scalar::from(2u64);
+        for &n in [0, 1, 3, 8, 64, 100, 127, 128, 256, 300].iter() {
+            assert_eq!(sum_of_powers_of_2(n), sum_of_powers_slow(&two, n));
+        }
+    }
+
+    #[test]
+    fn test_sum_of_powers_slow() {
+        let x = Scalar::from(10u64);
+        assert_eq!(sum_of_powers_slow(&x, 0), Scalar::zero());
+        assert_eq!(sum_of_powers_slow(&x, 1), Scalar::one());
+        assert_eq!(sum_of_powers_slow(&x, 2), Scalar::from(11u64));
+        assert_eq!(sum_of_powers_slow(&x, 3), Scalar::from(111u64));
+        assert_eq!(sum_of_powers_slow(&x, 4), Scalar::from(1111u64));
+        assert_eq!(sum_of_powers_slow(&x, 5), Scalar::from(11111u64));
+        assert_eq!(sum_of_powers_slow(&x, 6), Scalar::from(111111u64));
+    }
+}
@@ -0,0 +1,298 @@
+//! The `membership` module contains a proof that a commitment opens
+//! to one of a public set of values, or to the same value as one of a
+//! public set of commitments, without revealing which one.
+//!
+//! This can show, for instance, that a hidden amount is one of the
+//! valid denominations of a currency.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+
+use commitment::Commitment;
+use errors::ProofError;
+use generators::PedersenGens;
+use scalar;
+use sigma;
+use transcript::TranscriptProtocol;
+
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A proof that a commitment \\(V\\) opens to one of the values
+/// \\(v\_0, \ldots, v\_{n-1}\\), or to the same value as one of the
+/// commitments \\(C\_0, \ldots, C\_{n-1}\\).
+///
+/// The commitment opens to \\(v\_i\\) exactly when
+/// \\(D\_i = V - v\_i B\\) is a multiple of \\(B\_{blinding}\\), and
+/// to the value of \\(C\_i\\) exactly when \\(D\_i = V - C\_i\\) is.
+/// Like a [`SurjectionProof`](::asset::SurjectionProof), the proof is a
+/// one-out-of-\\(n\\) Schnorr proof of knowledge of the discrete
+/// logarithm of one of the \\(D\_i\\), and has \\(64n\\) bytes.
+#[derive(Clone, Debug)]
+pub struct MembershipProof {
+    /// Challenges \\(c\_i\\), one for each element of the set
+    c: Vec<Scalar>,
+    /// Responses \\(s\_i\\), one for each element of the set
+    s: Vec<Scalar>,
+}
+
+impl MembershipProof {
+    /// Creates a proof that the commitment to the value at `index` of
+    /// `values` with `blinding` opens to one of the `values`.
+    ///
+    /// Returns the proof and the commitment, or
+    /// [`ProofError::CommitmentMismatch`] if `index` is out of range.
+    pub fn prove_value(
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        index: usize,
+        blinding: &Scalar,
+    ) -> Result<(MembershipProof, CompressedRistretto), ProofError> {
+        let v = values.get(index).ok_or(ProofError::CommitmentMismatch)?;
+        let V = pc_gens.commit(Scalar::from(*v), *blinding);
+        let D = value_statements(pc_gens, values, &V);
+
+        commit_values(transcript, values, &V.compress());
+
+        let (c, s) = sigma::prove_one_of_many(pc_gens, transcript, &D, index, blinding)?;
+        Ok((MembershipProof { c, s }, V.compress()))
+    }
+
+    /// Verifies that `V` opens to one of the `values`.
+    pub fn verify_value(
+        &self,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        V: &CompressedRistretto,
+    ) -> Result<(), ProofError> {
+        if self.c.len() != values.len() {
+            return Err(ProofError::ProofSizeMismatch);
+        }
+        let V_point = V.decompress().ok_or(ProofError::FormatError)?;
+        let D = value_statements(pc_gens, values, &V_point);
+
+        commit_values(transcript, values, V);
+
+        sigma::verify_one_of_many(pc_gens, transcript, &D, &self.c, &self.s)
+    }
+
+    /// Creates a proof that `commitment`, with blinding factor
+    /// `blinding`, opens to the same value as the commitment at
+    /// `index` of `set`, with blinding factor `set_blinding`.
+    ///
+    /// Returns [`ProofError::CommitmentMismatch`] if `index` is out of
+    /// range, or if the commitments do not open to the same value with
+    /// these blinding factors.
+    pub fn prove_commitment(
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        set: &[Commitment],
+        commitment: &Commitment,
+        index: usize,
+        set_blinding: &Scalar,
+        blinding: &Scalar,
+    ) -> Result<MembershipProof, ProofError> {
+        let x = blinding - set_blinding;
+        let D: Vec<RistrettoPoint> = set.iter().map(|C_i| commitment.0 - C_i.0).collect();
+
+        commit_commitments(transcript, set, commitment);
+
+        let (c, s) = sigma::prove_one_of_many(pc_gens, transcript, &D, index, &x)?;
+        Ok(MembershipProof { c, s })
+    }
+
+    /// Verifies that `commitment` opens to the same value as one of the
+    /// commitments in `set`.
+    pub fn verify_commitment(
+        &self,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        set: &[Commitment],
+        commitment: &Commitment,
+    ) -> Result<(), ProofError> {
+        if self.c.len() != set.len() {
+            return Err(ProofError::ProofSizeMismatch);
+        }
+
+        commit_commitments(transcript, set, commitment);
+
+        let D: Vec<RistrettoPoint> = set.iter().map(|C_i| commitment.0 - C_i.0).collect();
+        sigma::verify_one_of_many(pc_gens, transcript, &D, &self.c, &self.s)
+    }
+
+    /// Serializes the proof into a byte array of \\(64n\\) bytes,
+    /// with the challenges followed by the responses.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = scalar::encode_vec(&self.c);
+        buf.extend_from_slice(&scalar::encode_vec(&self.s));
+        buf
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a
+    /// `MembershipProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<MembershipProof, ProofError> {
+        if slice.is_empty() || slice.len() % 64 != 0 {
+            return Err(ProofError::FormatError);
+        }
+        let (c, s) = slice.split_at(slice.len() / 2);
+        Ok(MembershipProof {
+            c: scalar::decode_vec(c)?,
+            s: scalar::decode_vec(s)?,
+        })
+    }
+}
+
+/// Computes the statements \\(D\_i = V - v\_i B\\) for a set of values.
+fn value_statements(
+    pc_gens: &PedersenGens,
+    values: &[u64],
+    V: &RistrettoPoint,
+) -> Vec<RistrettoPoint> {
+    values
+        .iter()
+        .map(|&v_i| V - Scalar::from(v_i) * pc_gens.B)
+        .collect()
+}
+
+/// Commits a set of values and the commitment \\(V\\).
+fn commit_values(transcript: &mut Transcript, values: &[u64], V: &CompressedRistretto) {
+    transcript.membership_domain_sep(values.len() as u64);
+    for &v_i in values {
+        transcript.commit_scalar(b"v_i", &Scalar::from(v_i));
+    }
+    transcript.commit_point(b"V", V);
+}
+
+/// Commits a set of commitments and the commitment \\(V\\).
+fn commit_commitments(transcript: &mut Transcript, set: &[Commitment], V: &Commitment) {
+    transcript.membership_domain_sep(set.len() as u64);
+    for C_i in set {
+        transcript.commit_point(b"C_i", &C_i.compress());
+    }
+    transcript.commit_point(b"V", &V.compress());
+}
+
+impl Serialize for MembershipProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for MembershipProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MembershipProofVisitor;
+
+        impl<'de> Visitor<'de> for MembershipProofVisitor {
+            type Value = MembershipProof;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("a valid MembershipProof")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<MembershipProof, E>
+            where
+                E: serde::de::Error,
+            {
+                MembershipProof::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(MembershipProofVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand;
+
+    #[test]
+    fn prove_and_verify_value_membership() {
+        let pc_gens = PedersenGens::default();
+        let denominations = [1u64, 5, 10, 20, 50, 100];
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let mut transcript = Transcript::new(b"MembershipTest");
+        let (proof, V) =
+            MembershipProof::prove_value(&pc_gens, &mut transcript, &denominations, 3, &blinding)
+                .unwrap();
+        let proof = MembershipProof::from_bytes(&proof.to_bytes()).unwrap();
+        let expected = pc_gens.commit(Scalar::from(20u64), blinding);
+        assert_eq!(V, expected.compress());
+
+        let mut transcript = Transcript::new(b"MembershipTest");
+        assert!(
+            proof
+                .verify_value(&pc_gens, &mut transcript, &denominations, &V)
+                .is_ok()
+        );
+
+        // The proof does not hold for a set without the value
+        let others = [1u64, 5, 10, 25, 50, 100];
+        let mut transcript = Transcript::new(b"MembershipTest");
+        assert_eq!(
+            proof.verify_value(&pc_gens, &mut transcript, &others, &V),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn prove_and_verify_commitment_membership() {
+        let pc_gens = PedersenGens::default();
+        let mut rng = rand::thread_rng();
+        let set_blindings = scalar::random_vec(&mut rng, 4);
+        let set: Vec<Commitment> = [3u64, 1, 4, 1]
+            .iter()
+            .zip(&set_blindings)
+            .map(|(&v, blinding)| pc_gens.commit(Scalar::from(v), *blinding).into())
+            .collect();
+
+        let blinding = Scalar::random(&mut rng);
+        let commitment: Commitment = pc_gens.commit(Scalar::from(4u64), blinding).into();
+
+        let mut transcript = Transcript::new(b"MembershipTest");
+        let proof = MembershipProof::prove_commitment(
+            &pc_gens,
+            &mut transcript,
+            &set,
+            &commitment,
+            2,
+            &set_blindings[2],
+            &blinding,
+        ).unwrap();
+
+        let mut transcript = Transcript::new(b"MembershipTest");
+        assert!(
+            proof
+                .verify_commitment(&pc_gens, &mut transcript, &set, &commitment)
+                .is_ok()
+        );
+
+        // The prover cannot claim a commitment to another value
+        let mut transcript = Transcript::new(b"MembershipTest");
+        let result = MembershipProof::prove_commitment(
+            &pc_gens,
+            &mut transcript,
+            &set,
+            &commitment,
+            0,
+            &set_blindings[0],
+            &blinding,
+        );
+        assert_eq!(result.unwrap_err(), ProofError::CommitmentMismatch);
+    }
+}
@@ -0,0 +1,309 @@
+//! The `multiscalar` module contains the variable-time multiscalar
+//! multiplication used by the verifiers.
+//!
+//! Straus's method, which `curve25519_dalek` uses for
+//! `optional_multiscalar_mul`, does work linear in the number of terms
+//! for every bit of the scalars.  Pippenger's bucket method amortises
+//! the additions across all terms, so it wins once the multiplication
+//! is large enough, as it is when verifying aggregated or batched
+//! proofs.  [`optional_multiscalar_mul`] switches between the two at
+//! [`PIPPENGER_THRESHOLD`] terms; [`pippenger`] can be called directly
+//! to force the choice.
+//!
+//! With the `multiscalar_backend` feature, a [`MultiscalarBackend`]
+//! can be installed with [`set_backend`] to offload large
+//! multiplications, for instance to a GPU through CUDA or OpenCL.
+
+#![allow(non_snake_case)]
+
+use std::borrow::Borrow;
+#[cfg(feature = "multiscalar_backend")]
+use std::sync::{Arc, RwLock};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, VartimeMultiscalarMul};
+
+/// The number of terms from which [`optional_multiscalar_mul`] uses
+/// Pippenger's method instead of Straus's.
+pub const PIPPENGER_THRESHOLD: usize = 256;
+
+/// Computes \\(\sum\_i s\_i P\_i\\) in variable time, returning `None`
+/// if any of the points is `None`.
+///
+/// Multiplications of at least [`PIPPENGER_THRESHOLD`] terms use
+/// [`pippenger`]; smaller ones use Straus's method.  With the
+/// `multiscalar_backend` feature, multiplications of at least
+/// [`MultiscalarBackend::min_terms`] terms are first offered to the
+/// installed backend.
+///
+/// # Panics
+///
+/// Panics if `scalars` and `points` have different lengths.
+pub fn optional_multiscalar_mul<I, J>(scalars: I, points: J) -> Option<RistrettoPoint>
+where
+    I: IntoIterator,
+    I::Item: Borrow<Scalar>,
+    J: IntoIterator<Item = Option<RistrettoPoint>>,
+{
+    let scalars: Vec<Scalar> = scalars.into_iter().map(|s| *s.borrow()).collect();
+    let points: Vec<RistrettoPoint> = points.into_iter().collect::<Option<_>>()?;
+    assert_eq!(scalars.len(), points.len());
+
+    #[cfg(feature = "multiscalar_backend")]
+    {
+        if let Some(backend) = backend() {
+            if scalars.len() >= backend.min_terms() {
+                if let Some(result) = backend.vartime_multiscalar_mul(&scalars, &points) {
+                    return Some(result);
+                }
+            }
+        }
+    }
+
+    if scalars.len() < PIPPENGER_THRESHOLD {
+        Some(RistrettoPoint::vartime_multiscalar_mul(scalars, points))
+    } else {
+        Some(pippenger(&scalars, &points))
+    }
+}
+
+/// Computes \\(\sum\_i s\_i P\_i\\) in variable time with Pippenger's
+/// bucket method.
+///
+/// Each scalar is written in signed radix \\(2\^w\\), and for every
+/// digit position the points are sorted into \\(2\^{w-1}\\) buckets
+/// by the absolute value of their digit, so that each window costs one
+/// addition per term plus two per bucket.
+///
+/// # Panics
+///
+/// Panics if `scalars` and `points` have different lengths.
+pub fn pippenger(scalars: &[Scalar], points: &[RistrettoPoint]) -> RistrettoPoint {
+    assert_eq!(scalars.len(), points.len());
+
+    let w = window_width(scalars.len());
+    let digits: Vec<Vec<i16>> = scalars.iter().map(|s| signed_digits(s, w)).collect();
+    let num_digits = digits_count(w);
+
+    let mut buckets = vec![RistrettoPoint::identity(); 1 << (w - 1)];
+    let mut result = RistrettoPoint::identity();
+    for k in (0..num_digits).rev() {
+        if k != num_digits - 1 {
+            for _ in 0..w {
+                result = &result + &result;
+            }
+        }
+
+        for bucket in buckets.iter_mut() {
+            *bucket = RistrettoPoint::identity();
+        }
+        for (digits_i, P_i) in digits.iter().zip(points.iter()) {
+            let d = digits_i[k];
+            if d > 0 {
+                buckets[(d - 1) as usize] += P_i;
+            } else if d < 0 {
+                buckets[(-d - 1) as usize] -= P_i;
+            }
+        }
+
+        // Bucket b holds the points with digit b + 1, so summing the
+        // running sums from the top counts each bucket b + 1 times.
+        let mut running_sum = RistrettoPoint::identity();
+        let mut window_sum = RistrettoPoint::identity();
+        for bucket in buckets.iter().rev() {
+            running_sum += bucket;
+            window_sum += &running_sum;
+        }
+        result += &window_sum;
+    }
+
+    result
+}
+
+/// A backend computing large variable-time multiscalar
+/// multiplications on behalf of the verifiers, for instance on a GPU.
+///
+/// This trait is only available with the `multiscalar_backend`
+/// feature.
+#[cfg(feature = "multiscalar_backend")]
+pub trait MultiscalarBackend: Send + Sync {
+    /// The smallest number of terms worth offloading to the backend.
+    fn min_terms(&self) -> usize;
+
+    /// Computes \\(\sum\_i s\_i P\_i\\), where `scalars` and `points`
+    /// have the same length.
+    ///
+    /// Returning `None`, for instance when the device is busy or
+    /// unavailable, makes the caller compute the result on the CPU.
+    fn vartime_multiscalar_mul(
+        &self,
+        scalars: &[Scalar],
+        points: &[RistrettoPoint],
+    ) -> Option<RistrettoPoint>;
+}
+
+/// Returns the process-wide backend slot.
+#[cfg(feature = "multiscalar_backend")]
+fn backend_slot() -> &'static RwLock<Option<Arc<dyn MultiscalarBackend>>> {
+    lazy_static! {
+        static ref SLOT: RwLock<Option<Arc<dyn MultiscalarBackend>>> = RwLock::new(None);
+    }
+
+    &SLOT
+}
+
+/// Returns the installed backend, if any.
+#[cfg(feature = "multiscalar_backend")]
+fn backend() -> Option<Arc<dyn MultiscalarBackend>> {
+    // The slot holds no invariant that a panic could break.
+    let slot = backend_slot().read().unwrap_or_else(|e| e.into_inner());
+    slot.clone()
+}
+
+/// Installs `backend` for the whole process, replacing any backend
+/// installed before.
+///
+/// This function is only available with the `multiscalar_backend`
+/// feature.
+#[cfg(feature = "multiscalar_backend")]
+pub fn set_backend<B: MultiscalarBackend + 'static>(backend: B) {
+    let mut slot = backend_slot().write().unwrap_or_else(|e| e.into_inner());
+    *slot = Some(Arc::new(backend));
+}
+
+/// Removes the installed backend, so that all multiplications are
+/// computed on the CPU again.
+///
+/// This function is only available with the `multiscalar_backend`
+/// feature.
+#[cfg(feature = "multiscalar_backend")]
+pub fn clear_backend() {
+    let mut slot = backend_slot().write().unwrap_or_else(|e| e.into_inner());
+    *slot = None;
+}
+
+/// Chooses the window width for `n` terms, balancing the per-term
+/// additions against the per-bucket ones.
+fn window_width(n: usize) -> usize {
+    if n < 500 {
+        6
+    } else if n < 800 {
+        7
+    } else {
+        8
+    }
+}
+
+/// The number of radix-\\(2\^w\\) digits needed for a 256-bit scalar.
+fn digits_count(w: usize) -> usize {
+    (256 + w - 1) / w
+}
+
+/// Writes `s` in radix \\(2\^w\\) with digits in
+/// \\([-2\^{w-1}, 2\^{w-1})\\), least significant first.
+///
+/// Reduced scalars are below \\(2\^{253}\\), so the top digit always
+/// has room for the final carry.
+fn signed_digits(s: &Scalar, w: usize) -> Vec<i16> {
+    let bytes = s.reduce().to_bytes();
+    let bit = |i: usize| -> i16 {
+        if i < 256 {
+            ((bytes[i / 8] >> (i % 8)) & 1) as i16
+        } else {
+            0
+        }
+    };
+
+    let radix = 1i16 << w;
+    let mut carry = 0i16;
+    (0..digits_count(w))
+        .map(|k| {
+            let window = (0..w).fold(0i16, |acc, j| acc | (bit(k * w + j) << j));
+            let digit = window + carry;
+            carry = (digit + radix / 2) >> w;
+            digit - (carry << w)
+        }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn random_terms(n: usize) -> (Vec<Scalar>, Vec<RistrettoPoint>) {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let scalars = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let points = (0..n).map(|_| RistrettoPoint::random(&mut rng)).collect();
+        (scalars, points)
+    }
+
+    #[test]
+    fn signed_digits_recompose() {
+        let (scalars, _) = random_terms(8);
+        for w in 6..9 {
+            for s in scalars.iter().chain(Some(-Scalar::one()).iter()) {
+                let radix = Scalar::from(1u64 << w);
+                let mut recomposed = Scalar::zero();
+                for &d in signed_digits(s, w).iter().rev() {
+                    let d_abs = Scalar::from(d.abs() as u64);
+                    recomposed = recomposed * radix + if d < 0 { -d_abs } else { d_abs };
+                }
+                assert_eq!(recomposed, *s);
+            }
+        }
+    }
+
+    #[test]
+    fn pippenger_matches_straus() {
+        for &n in &[1, 64, 300, 900] {
+            let (scalars, points) = random_terms(n);
+            let expected = RistrettoPoint::vartime_multiscalar_mul(&scalars, &points);
+            assert_eq!(pippenger(&scalars, &points), expected);
+        }
+    }
+
+    #[test]
+    fn optional_multiscalar_mul_rejects_missing_points() {
+        let (scalars, points) = random_terms(PIPPENGER_THRESHOLD);
+        let mut points: Vec<_> = points.into_iter().map(Some).collect();
+        points[7] = None;
+        assert!(optional_multiscalar_mul(&scalars, points).is_none());
+    }
+
+    #[cfg(feature = "multiscalar_backend")]
+    #[test]
+    fn large_multiplications_are_offloaded() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct CountingBackend(Arc<AtomicUsize>);
+
+        impl MultiscalarBackend for CountingBackend {
+            fn min_terms(&self) -> usize {
+                PIPPENGER_THRESHOLD
+            }
+
+            fn vartime_multiscalar_mul(
+                &self,
+                scalars: &[Scalar],
+                points: &[RistrettoPoint],
+            ) -> Option<RistrettoPoint> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Some(pippenger(scalars, points))
+            }
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        set_backend(CountingBackend(calls.clone()));
+
+        let (scalars, points) = random_terms(PIPPENGER_THRESHOLD);
+        let expected = RistrettoPoint::vartime_multiscalar_mul(&scalars, &points);
+        let result = optional_multiscalar_mul(&scalars, points.into_iter().map(Some));
+        clear_backend();
+
+        assert_eq!(result, Some(expected));
+        assert!(calls.load(Ordering::SeqCst) >= 1);
+    }
+}
@@ -0,0 +1,98 @@
+//! Definition of the constraint system trait.
+
+use super::{LinearCombination, Variable};
+use curve25519_dalek::scalar::Scalar;
+use errors::R1CSError;
+
+/// The interface for a constraint system, abstracting over the prover
+/// and verifier's roles.
+///
+/// Statements to be proved by an [`R1CSProof`](::r1cs::R1CSProof) are
+/// specified by programmatically constructing constraints.  These
+/// constraints need to be identical between the prover and verifier,
+/// since the prover and verifier need to construct the same statement.
+///
+/// To prevent code duplication or mismatches between the prover and
+/// verifier, gadgets for the constraint system should be written
+/// using the `ConstraintSystem` trait, so that the prover and
+/// verifier share the logic for specifying constraints.
+pub trait ConstraintSystem {
+    /// The constraint system of the second phase, in which the
+    /// randomized constraints are added.
+    type RandomizedCS: RandomizedConstraintSystem;
+
+    /// Allocate and constrain multiplication variables.
+    ///
+    /// Allocate variables `left`, `right`, and `out`
+    /// with the implicit constraint that
+    /// ```text
+    /// left * right = out
+    /// ```
+    /// and add the explicit constraints that
+    /// ```text
+    /// left = left_constraint
+    /// right = right_constraint
+    /// ```
+    ///
+    /// Returns `(left, right, out)` for use in further constraints.
+    fn multiply(
+        &mut self,
+        left: LinearCombination,
+        right: LinearCombination,
+    ) -> (Variable, Variable, Variable);
+
+    /// Allocate variables `left`, `right`, and `out` with the
+    /// implicit constraint that
+    /// ```text
+    /// left * right = out
+    /// ```
+    ///
+    /// The prover must pass the assignments for `left` and `right`,
+    /// and the verifier passes `None`.  Returns
+    /// `R1CSError::MissingAssignment` if the prover does not pass
+    /// the assignments.
+    fn allocate_multiplier(
+        &mut self,
+        input_assignments: Option<(Scalar, Scalar)>,
+    ) -> Result<(Variable, Variable, Variable), R1CSError>;
+
+    /// Enforce the explicit constraint that
+    /// ```text
+    /// lc = 0
+    /// ```
+    fn constrain(&mut self, lc: LinearCombination);
+
+    /// Obtain a challenge scalar bound to the commitments added so
+    /// far.
+    ///
+    /// The challenge only depends on the external commitments, not on
+    /// the multiplication variables, so it must only be used in
+    /// constraints whose soundness relies on the committed inputs
+    /// alone.  In a [`RandomizedConstraintSystem`], the challenge is
+    /// also bound to all the first-phase multipliers.
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar;
+
+    /// Specify additional constraints which depend on challenges
+    /// bound to all the variables of the first phase.
+    ///
+    /// The `callback` is called after all the first-phase variables
+    /// are committed, with a [`RandomizedConstraintSystem`] whose
+    /// challenges are bound to them.  Gadgets such as permutation
+    /// arguments, which need challenges bound to their multipliers
+    /// rather than only to the external commitments, add their
+    /// constraints in the callback.  The callbacks are called in the
+    /// order in which they were specified.
+    fn specify_randomized_constraints<F>(&mut self, callback: F) -> Result<(), R1CSError>
+    where
+        F: 'static + Fn(&mut Self::RandomizedCS) -> Result<(), R1CSError>;
+}
+
+/// The interface for the constraint system of the second phase, in
+/// which randomized constraints are added.
+///
+/// The multipliers allocated in the first phase are committed before
+/// the second phase starts, so the challenges returned by
+/// [`ConstraintSystem::challenge_scalar`] are bound to all the
+/// first-phase variables, and can be used in constraints on any of
+/// them.
+pub trait RandomizedConstraintSystem: ConstraintSystem {}
@@ -0,0 +1,104 @@
+//! A bit decomposition gadget, which constrains a value to fit in
+//! \\(n\\) bits and exposes the bits for further constraints.
+
+use curve25519_dalek::scalar::Scalar;
+
+use errors::R1CSError;
+use r1cs::{ConstraintSystem, LinearCombination, Variable};
+
+/// Constrains `v` to be an \\(n\\)-bit value, returning the variables
+/// of its bits, starting from the least significant one.
+///
+/// The prover passes the `value` of `v`, and the verifier passes
+/// `None`.  As in a [`RangeProof`](::RangeProof), each bit \\(b\_i\\)
+/// is allocated in a multiplier together with \\(b\_i - 1\\), whose
+/// product is constrained to zero, and the bits are constrained to sum
+/// to
+/// \\[
+///     v = \sum\_{i < n} 2^i b\_i.
+/// \\]
+/// This takes \\(n\\) multipliers, and the returned variables can be
+/// used to build further gadgets, such as parity checks, shifts or
+/// comparisons.
+///
+/// Returns [`R1CSError::MissingAssignment`] if the prover does not pass
+/// the value, and [`R1CSError::GadgetError`] if \\(n\\) exceeds
+/// \\(64\\).
+pub fn bit_decomposition<CS: ConstraintSystem>(
+    cs: &mut CS,
+    v: LinearCombination,
+    value: Option<u64>,
+    n: usize,
+) -> Result<Vec<Variable>, R1CSError> {
+    if n > 64 {
+        return Err(R1CSError::GadgetError {
+            description: "number of bits exceeds 64".to_string(),
+        });
+    }
+
+    let mut bits = Vec::with_capacity(n);
+    let mut sum = LinearCombination::default();
+    let mut exp_2 = Scalar::one();
+    for i in 0..n {
+        // b_i (b_i - 1) = 0
+        let (b_i, b_i_minus_one, o) = cs.allocate_multiplier(value.map(|value| {
+            let bit = Scalar::from((value >> i) & 1);
+            (bit, bit - Scalar::one())
+        }))?;
+        cs.constrain(b_i - b_i_minus_one - Scalar::one());
+        cs.constrain(o.into());
+
+        sum = sum + b_i * exp_2;
+        exp_2 = exp_2 + exp_2;
+        bits.push(b_i);
+    }
+
+    cs.constrain(v - sum);
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use merlin::Transcript;
+    use rand;
+
+    use generators::{BulletproofGens, PedersenGens};
+    use r1cs::{Prover, Verifier};
+
+    /// Proves that `value` is an even `n`-bit value.
+    fn even_proof(value: u64, n: usize) -> Result<(), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+
+        let (proof, V) = {
+            let mut transcript = Transcript::new(b"BitsTest");
+            let mut prover = Prover::new(&bp_gens, &pc_gens, &mut transcript);
+            let blinding = Scalar::random(&mut rand::thread_rng());
+            let (V, v) = prover.commit(Scalar::from(value), blinding);
+            let bits = bit_decomposition(&mut prover, v.into(), Some(value), n)?;
+            prover.constrain(bits[0].into());
+            (prover.prove()?, V)
+        };
+
+        let mut transcript = Transcript::new(b"BitsTest");
+        let mut verifier = Verifier::new(&bp_gens, &pc_gens, &mut transcript);
+        let v = verifier.commit(V);
+        let bits = bit_decomposition(&mut verifier, v.into(), None, n)?;
+        verifier.constrain(bits[0].into());
+        verifier.verify(&proof)
+    }
+
+    #[test]
+    fn bit_decomposition_of_even_values() {
+        assert!(even_proof(0, 8).is_ok());
+        assert!(even_proof(254, 8).is_ok());
+        assert!(even_proof(u64::max_value() - 1, 64).is_ok());
+
+        // Odd, and out of range
+        assert_eq!(even_proof(255, 8), Err(R1CSError::VerificationError));
+        assert_eq!(even_proof(256, 8), Err(R1CSError::VerificationError));
+        assert!(even_proof(0, 65).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+//! A Merkle membership gadget, which constrains a leaf to be included
+//! in a Merkle tree with a public root, without revealing its position.
+
+use curve25519_dalek::scalar::Scalar;
+
+use errors::R1CSError;
+use r1cs::gadgets::mimc::{mimc_gadget, mimc_hash};
+use r1cs::{ConstraintSystem, LinearCombination};
+
+/// A binary Merkle tree of scalars, hashed with
+/// [`mimc_hash`](::r1cs::gadgets::mimc_hash).
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    /// The levels of the tree, from the leaves up to the root
+    levels: Vec<Vec<Scalar>>,
+}
+
+/// The path from a leaf to the root of a [`MerkleTree`], which the
+/// prover passes to [`merkle_membership`].
+#[derive(Clone, Debug)]
+pub struct MerklePath {
+    /// The leaf
+    pub leaf: Scalar,
+    /// The position of the leaf, whose bits, starting from the least
+    /// significant one, are set where the path is the right child
+    pub index: usize,
+    /// The siblings of the nodes on the path, from the leaf upwards
+    pub siblings: Vec<Scalar>,
+}
+
+impl MerkleTree {
+    /// Builds the tree with the given `leaves`.
+    ///
+    /// Returns [`R1CSError::GadgetError`] if the number of leaves is
+    /// not a power of two.
+    pub fn new(leaves: Vec<Scalar>) -> Result<MerkleTree, R1CSError> {
+        if !leaves.len().is_power_of_two() {
+            return Err(R1CSError::GadgetError {
+                description: "number of leaves is not a power of two".to_string(),
+            });
+        }
+
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| mimc_hash(&pair[0], &pair[1]))
+                .collect();
+            levels.push(next);
+        }
+        Ok(MerkleTree { levels })
+    }
+
+    /// Returns the depth of the tree, that is, the length of its paths.
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> Scalar {
+        self.levels[self.depth()][0]
+    }
+
+    /// Returns the path of the leaf at `index`, or `None` if `index`
+    /// is out of range.
+    pub fn path(&self, index: usize) -> Option<MerklePath> {
+        let leaf = *self.levels[0].get(index)?;
+        let siblings = self.levels[..self.depth()]
+            .iter()
+            .enumerate()
+            .map(|(i, level)| level[(index >> i) ^ 1])
+            .collect();
+        Some(MerklePath {
+            leaf,
+            index,
+            siblings,
+        })
+    }
+}
+
+impl MerklePath {
+    /// Computes the root of the tree from the path.
+    pub fn root(&self) -> Scalar {
+        let mut node = self.leaf;
+        for (i, sibling) in self.siblings.iter().enumerate() {
+            node = if (self.index >> i) & 1 == 1 {
+                mimc_hash(sibling, &node)
+            } else {
+                mimc_hash(&node, sibling)
+            };
+        }
+        node
+    }
+}
+
+/// Constrains `leaf` to be included in the Merkle tree of depth
+/// `depth` with the public `root`.
+///
+/// The prover passes the `path` of the leaf, and the verifier passes
+/// `None`.  The siblings and the position are hidden in the
+/// multipliers, so only the root is revealed.  At each level, the
+/// gadget allocates the position bit \\(b\\) and the difference
+/// \\(d = s - n\\) between the sibling and the node, and orders the
+/// children as \\((n + bd, n + d - bd)\\).  This takes
+/// \\(2 + 644\\) multipliers per level.
+///
+/// Returns [`R1CSError::MissingAssignment`] if the prover does not pass
+/// the path, and [`R1CSError::GadgetError`] if the path does not have
+/// length `depth`.
+pub fn merkle_membership<CS: ConstraintSystem>(
+    cs: &mut CS,
+    leaf: LinearCombination,
+    path: Option<&MerklePath>,
+    depth: usize,
+    root: &Scalar,
+) -> Result<(), R1CSError> {
+    if path.map_or(false, |path| path.siblings.len() != depth) {
+        return Err(R1CSError::GadgetError {
+            description: "path length does not match the depth".to_string(),
+        });
+    }
+
+    let mut node = leaf;
+    let mut node_value = path.map(|path| path.leaf);
+    for i in 0..depth {
+        let assignment = path.and_then(|path| {
+            let n = node_value?;
+            let s = path.siblings[i];
+            let b = Scalar::from(((path.index >> i) & 1) as u64);
+            node_value = if b == Scalar::one() {
+                Some(mimc_hash(&s, &n))
+            } else {
+                Some(mimc_hash(&n, &s))
+            };
+            Some((b, s - n))
+        });
+
+        // b (s - n) = o, with b a bit
+        let (b, d, o) = cs.allocate_multiplier(assignment)?;
+        let (_, _, b_check) = cs.multiply(b.into(), Scalar::one() - b);
+        cs.constrain(b_check.into());
+
+        let left = node.clone() + o;
+        let right = node + d - o;
+        node = mimc_gadget(cs, left, right);
+    }
+
+    cs.constrain(node - *root);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use merlin::Transcript;
+    use rand;
+
+    use generators::{BulletproofGens, PedersenGens};
+    use r1cs::{Prover, R1CSProof, Verifier};
+
+    fn membership_proof(tree: &MerkleTree, index: usize, root: &Scalar) -> Result<(), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(2048, 1);
+        let path = tree.path(index).unwrap();
+
+        let (proof, V) = {
+            let mut transcript = Transcript::new(b"MerkleTest");
+            let mut prover = Prover::new(&bp_gens, &pc_gens, &mut transcript);
+            let blinding = Scalar::random(&mut rand::thread_rng());
+            let (V, leaf) = prover.commit(path.leaf, blinding);
+            merkle_membership(&mut prover, leaf.into(), Some(&path), tree.depth(), root)?;
+            (prover.prove()?, V)
+        };
+        let proof = R1CSProof::from_bytes(&proof.to_bytes())?;
+
+        let mut transcript = Transcript::new(b"MerkleTest");
+        let mut verifier = Verifier::new(&bp_gens, &pc_gens, &mut transcript);
+        let leaf = verifier.commit(V);
+        merkle_membership(&mut verifier, leaf.into(), None, tree.depth(), root)?;
+        verifier.verify(&proof)
+    }
+
+    #[test]
+    fn merkle_path_matches_root() {
+        let leaves: Vec<Scalar> = (0u64..8).map(Scalar::from).collect();
+        let tree = MerkleTree::new(leaves).unwrap();
+        assert_eq!(tree.depth(), 3);
+        for index in 0..8 {
+            assert_eq!(tree.path(index).unwrap().root(), tree.root());
+        }
+        assert!(tree.path(8).is_none());
+        assert!(MerkleTree::new(vec![Scalar::one(); 3]).is_err());
+    }
+
+    #[test]
+    fn prove_and_verify_merkle_membership() {
+        let leaves: Vec<Scalar> = (10u64..14).map(Scalar::from).collect();
+        let tree = MerkleTree::new(leaves).unwrap();
+
+        assert!(membership_proof(&tree, 2, &tree.root()).is_ok());
+        assert_eq!(
+            membership_proof(&tree, 1, &Scalar::from(7u64)),
+            Err(R1CSError::VerificationError)
+        );
+    }
+}
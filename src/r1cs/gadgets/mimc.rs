@@ -0,0 +1,64 @@
+//! The MiMC hash function, whose low multiplicative complexity makes
+//! it cheap to evaluate inside the constraint system.
+
+use byteorder::{ByteOrder, LittleEndian};
+use curve25519_dalek::scalar::Scalar;
+use sha3::Sha3_512;
+
+use r1cs::{ConstraintSystem, LinearCombination};
+
+/// The number of rounds of the MiMC Feistel network.
+pub const MIMC_ROUNDS: usize = 322;
+
+/// Hashes two scalars with MiMC in Feistel mode.
+///
+/// Each of the [`MIMC_ROUNDS`] rounds maps \\((x\_L, x\_R)\\) to
+/// \\((x\_R + (x\_L + c\_i)^3, x\_L)\\), with round constants
+/// \\(c\_i\\) derived by hashing to scalars, and the hash is the final
+/// \\(x\_L\\).  This matches [`mimc_gadget`] exactly.
+pub fn mimc_hash(left: &Scalar, right: &Scalar) -> Scalar {
+    let mut xl = *left;
+    let mut xr = *right;
+    for c in mimc_constants() {
+        let t = xl + c;
+        let next = xr + t * t * t;
+        xr = xl;
+        xl = next;
+    }
+    xl
+}
+
+/// Constrains the MiMC hash of `left` and `right`, returning it.
+///
+/// This takes two multipliers per round, \\(2 \cdot 322 = 644\\) in
+/// total, and matches [`mimc_hash`] exactly.
+pub fn mimc_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    left: LinearCombination,
+    right: LinearCombination,
+) -> LinearCombination {
+    let mut xl = left;
+    let mut xr = right;
+    for c in mimc_constants() {
+        let (t, _, t_squared) = cs.multiply(xl.clone() + c, xl + c);
+        let (_, _, t_cubed) = cs.multiply(t_squared.into(), t.into());
+        // Express x_L through the gate variable, so that the linear
+        // combinations do not grow from round to round
+        let next = xr + t_cubed;
+        xr = t - c;
+        xl = next;
+    }
+    xl
+}
+
+/// Derives the round constants of MiMC.
+fn mimc_constants() -> Vec<Scalar> {
+    let mut input = b"bulletproofs mimc ".to_vec();
+    input.extend_from_slice(&[0u8; 4]);
+    let offset = input.len() - 4;
+    (0..MIMC_ROUNDS)
+        .map(|i| {
+            LittleEndian::write_u32(&mut input[offset..], i as u32);
+            Scalar::hash_from_bytes::<Sha3_512>(&input)
+        }).collect()
+}
@@ -0,0 +1,20 @@
+//! Reusable gadgets for the constraint system.
+//!
+//! Each gadget is a function generic over
+//! [`ConstraintSystem`](::r1cs::ConstraintSystem), so that the prover
+//! and the verifier build the same constraints.  Some gadgets also
+//! come with functions which create or verify a complete
+//! [`R1CSProof`](::r1cs::R1CSProof) for a common statement, or which
+//! compute the same values outside of the constraint system.
+
+mod bits;
+mod merkle;
+mod mimc;
+mod pedersen_hash;
+mod shuffle;
+
+pub use self::bits::bit_decomposition;
+pub use self::merkle::{merkle_membership, MerklePath, MerkleTree};
+pub use self::mimc::{mimc_gadget, mimc_hash, MIMC_ROUNDS};
+pub use self::pedersen_hash::{pedersen_hash, pedersen_hash_gadget};
+pub use self::shuffle::{prove_shuffle, scalar_shuffle, tuple_shuffle, verify_shuffle};
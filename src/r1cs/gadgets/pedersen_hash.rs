@@ -0,0 +1,380 @@
+//! A windowed Pedersen hash, computed on an elliptic curve whose base
+//! field is the scalar field, so that its point arithmetic can be
+//! constrained by the constraint system.
+//!
+//! The embedded curve is
+//! \\[
+//!     E : y^2 = x^3 - 3x + b
+//! \\]
+//! over the field of scalars modulo
+//! \\(\ell = 2^{252} + 27742317777372353535851937790883648493\\).  It
+//! was found with the complex multiplication method for discriminant
+//! \\(-3355\\), so that its order is \\(4q\\) for the prime
+//! \\[
+//!     q = 2^{250} + 6935579444343088383962984447720912123.
+//! \\]
+//! The hash of a message of \\(2k\\) bits, split into windows
+//! \\(m\_j \in \\{0, 1, 2, 3\\}\\), is the \\(x\\)-coordinate of
+//! \\[
+//!     \sum\_{j < k} (m\_j + 1) G\_j,
+//! \\]
+//! where the generators \\(G\_j\\) of the subgroup of order \\(q\\) are
+//! derived by hashing to the curve.  It is collision resistant as long
+//! as no discrete logarithm relation between the generators is known.
+
+#![allow(non_snake_case)]
+
+use byteorder::{ByteOrder, LittleEndian};
+use curve25519_dalek::scalar::Scalar;
+use sha3::Sha3_512;
+
+use errors::R1CSError;
+use r1cs::{ConstraintSystem, LinearCombination, Variable};
+
+/// The coefficient \\(b\\) of the embedded curve.
+const CURVE_B: [u8; 32] = [
+    0x2f, 0x9b, 0xe5, 0x03, 0xc0, 0xae, 0xc2, 0xbc, 0xab, 0xc8, 0xb4, 0x37, 0x1d, 0x0e, 0x5d, 0x59,
+    0xa7, 0x03, 0x19, 0x5d, 0x28, 0x37, 0xa8, 0x6f, 0x9c, 0xcb, 0x58, 0x78, 0xad, 0x8e, 0x95, 0x08,
+];
+
+/// The exponent \\((\ell - 1) / 2\\) of the Legendre symbol.
+const LEGENDRE_EXPONENT: [u8; 32] = [
+    0xf6, 0xe9, 0x7a, 0x2e, 0x8d, 0x31, 0x09, 0x2c, 0x6b, 0xce, 0x7b, 0x51, 0xef, 0x7c, 0x6f, 0x0a,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08,
+];
+
+/// The exponent \\((\ell - 5) / 8\\) of Atkin's square root algorithm,
+/// which applies since \\(\ell \equiv 5 \pmod 8\\).
+const ATKIN_EXPONENT: [u8; 32] = [
+    0x7d, 0xba, 0x9e, 0x4b, 0x63, 0x4c, 0x02, 0xcb, 0x9a, 0xf3, 0x5e, 0xd4, 0x3b, 0xdf, 0x9b, 0x02,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,
+];
+
+/// Hashes a message of `bits`, whose number must be even and nonzero.
+///
+/// This matches [`pedersen_hash_gadget`] exactly.
+///
+/// Returns [`R1CSError::GadgetError`] if the number of bits is odd or
+/// zero.
+pub fn pedersen_hash(bits: &[bool]) -> Result<Scalar, R1CSError> {
+    check_length(bits.len())?;
+
+    let mut acc: Option<Point> = None;
+    for (j, window) in bits.chunks(2).enumerate() {
+        let Q = window_table(j)[window_index(window[0], window[1])];
+        acc = Some(match acc {
+            None => Q,
+            Some(P) => P.add(&Q),
+        });
+    }
+    Ok(acc.expect("the message is not empty").x)
+}
+
+/// Constrains the Pedersen hash of the message of `bits`, returning
+/// it.
+///
+/// The prover passes the `values` of the bits, and the verifier passes
+/// `None`.  The gadget constrains each bit to be \\(0\\) or \\(1\\),
+/// selects the multiple \\((m\_j + 1) G\_j\\) of each window with one
+/// multiplier, and adds the points with incomplete addition, which
+/// takes three multipliers: since the multiples of distinct generators
+/// are added, the exceptional cases of incomplete addition would
+/// reveal a discrete logarithm relation between the generators.  This
+/// takes \\(6k - 3\\) multipliers for \\(2k\\) bits, and matches
+/// [`pedersen_hash`] exactly.
+///
+/// Returns [`R1CSError::MissingAssignment`] if the prover does not
+/// pass the values, and [`R1CSError::GadgetError`] if the number of
+/// bits is odd or zero, or does not match the number of values.
+pub fn pedersen_hash_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    bits: &[LinearCombination],
+    values: Option<&[bool]>,
+) -> Result<LinearCombination, R1CSError> {
+    check_length(bits.len())?;
+    if values.map_or(false, |values| values.len() != bits.len()) {
+        return Err(R1CSError::GadgetError {
+            description: "number of values does not match the number of bits".to_string(),
+        });
+    }
+
+    for b in bits {
+        // b (1 - b) = 0
+        let (_, _, o) = cs.multiply(b.clone(), -b.clone() + Scalar::one());
+        cs.constrain(o.into());
+    }
+
+    let mut acc: Option<PointVar> = None;
+    for (j, window) in bits.chunks(2).enumerate() {
+        let table = window_table(j);
+        let (_, _, b0_b1) = cs.multiply(window[0].clone(), window[1].clone());
+        let Q = PointVar {
+            x: lookup(&table, &window[0], &window[1], b0_b1, |P| P.x),
+            y: lookup(&table, &window[0], &window[1], b0_b1, |P| P.y),
+            value: values.map(|v| table[window_index(v[2 * j], v[2 * j + 1])]),
+        };
+        acc = Some(match acc {
+            None => Q,
+            Some(P) => add_gadget(cs, P, Q)?,
+        });
+    }
+    Ok(acc.expect("the message is not empty").x)
+}
+
+/// An affine point on the embedded curve.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Point {
+    x: Scalar,
+    y: Scalar,
+}
+
+impl Point {
+    /// Adds two points with distinct \\(x\\)-coordinates.
+    fn add(&self, other: &Point) -> Point {
+        let lambda = (other.y - self.y) * (other.x - self.x).invert();
+        let x = lambda * lambda - self.x - other.x;
+        let y = lambda * (self.x - x) - self.y;
+        Point { x, y }
+    }
+
+    /// Doubles a point which is not of order two.
+    fn double(&self) -> Point {
+        let three = Scalar::from(3u64);
+        let lambda = (three * self.x * self.x - three) * (self.y + self.y).invert();
+        let x = lambda * lambda - self.x - self.x;
+        let y = lambda * (self.x - x) - self.y;
+        Point { x, y }
+    }
+}
+
+/// A point whose coordinates are linear combinations of variables,
+/// with its value if it is known to the prover.
+struct PointVar {
+    x: LinearCombination,
+    y: LinearCombination,
+    value: Option<Point>,
+}
+
+/// Constrains the sum of two points with distinct
+/// \\(x\\)-coordinates, returning it.
+fn add_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    P: PointVar,
+    Q: PointVar,
+) -> Result<PointVar, R1CSError> {
+    let (assignment, value) = match (P.value, Q.value) {
+        (Some(P), Some(Q)) => {
+            let lambda = (Q.y - P.y) * (Q.x - P.x).invert();
+            (Some((lambda, Q.x - P.x)), Some(P.add(&Q)))
+        }
+        _ => (None, None),
+    };
+
+    // lambda (x_Q - x_P) = y_Q - y_P
+    let (lambda, dx, dy) = cs.allocate_multiplier(assignment)?;
+    cs.constrain(Q.x.clone() - P.x - dx);
+    cs.constrain(Q.y.clone() - P.y - dy);
+    // Express P through the gate variables, so that the linear
+    // combinations do not grow from addition to addition
+    let x_P = Q.x.clone() - dx;
+    let y_P = Q.y - dy;
+
+    // x_R = lambda^2 - x_P - x_Q
+    let (_, _, lambda_squared) = cs.multiply(lambda.into(), lambda.into());
+    let x = lambda_squared - x_P.clone() - Q.x;
+    // y_R = lambda (x_P - x_R) - y_P
+    let (_, _, t) = cs.multiply(lambda.into(), x_P - x.clone());
+    let y = t - y_P;
+
+    Ok(PointVar { x, y, value })
+}
+
+/// Selects a coordinate of the entry of `table` at the window index
+/// given by the bits `b0` and `b1`, whose product is `b0_b1`.
+fn lookup<F: Fn(&Point) -> Scalar>(
+    table: &[Point; 4],
+    b0: &LinearCombination,
+    b1: &LinearCombination,
+    b0_b1: Variable,
+    coordinate: F,
+) -> LinearCombination {
+    let c: Vec<Scalar> = table.iter().map(coordinate).collect();
+    LinearCombination::from(c[0])
+        + b0.clone() * (c[1] - c[0])
+        + b1.clone() * (c[2] - c[0])
+        + b0_b1 * (c[3] - c[2] - c[1] + c[0])
+}
+
+/// Returns the window index of the bits `b0` and `b1`.
+fn window_index(b0: bool, b1: bool) -> usize {
+    (b0 as usize) + 2 * (b1 as usize)
+}
+
+/// Checks that a message of `n` bits consists of whole windows.
+fn check_length(n: usize) -> Result<(), R1CSError> {
+    if n == 0 || n % 2 != 0 {
+        return Err(R1CSError::GadgetError {
+            description: "number of bits is odd or zero".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Returns the multiples \\(G\_j, 2G\_j, 3G\_j, 4G\_j\\) of the
+/// generator of window `j`.
+fn window_table(j: usize) -> [Point; 4] {
+    let G = generator(j);
+    let G_2 = G.double();
+    [G, G_2, G_2.add(&G), G_2.double()]
+}
+
+/// Derives the generator of window `j` by hashing to the curve, and
+/// clearing the cofactor.
+fn generator(j: usize) -> Point {
+    let b = Scalar::from_bytes_mod_order(CURVE_B);
+    let mut input = b"bulletproofs pedersen hash ".to_vec();
+    input.extend_from_slice(&[0u8; 8]);
+    let offset = input.len() - 8;
+    LittleEndian::write_u32(&mut input[offset..offset + 4], j as u32);
+
+    let mut counter = 0u32;
+    loop {
+        LittleEndian::write_u32(&mut input[offset + 4..], counter);
+        let x = Scalar::hash_from_bytes::<Sha3_512>(&input);
+        if let Some(y) = sqrt(&(x * x * x - Scalar::from(3u64) * x + b)) {
+            return Point { x, y }.double().double();
+        }
+        counter += 1;
+    }
+}
+
+/// Returns the even square root of `a`, or `None` if `a` is not a
+/// nonzero square.
+fn sqrt(a: &Scalar) -> Option<Scalar> {
+    if pow(a, &LEGENDRE_EXPONENT) != Scalar::one() {
+        return None;
+    }
+    let two_a = a + a;
+    let t = pow(&two_a, &ATKIN_EXPONENT);
+    let root = a * t * (two_a * t * t - Scalar::one());
+    if root.as_bytes()[0] & 1 == 0 {
+        Some(root)
+    } else {
+        Some(-root)
+    }
+}
+
+/// Raises `x` to the little-endian `exponent`.
+fn pow(x: &Scalar, exponent: &[u8; 32]) -> Scalar {
+    let mut result = Scalar::one();
+    for byte in exponent.iter().rev() {
+        for i in (0..8).rev() {
+            result = result * result;
+            if (byte >> i) & 1 == 1 {
+                result *= x;
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use merlin::Transcript;
+    use rand::{self, Rng};
+
+    use generators::{BulletproofGens, PedersenGens};
+    use r1cs::{Prover, Verifier};
+
+    /// The order \\(q\\) of the generators.
+    const GROUP_ORDER: [u8; 32] = [
+        0xb3, 0x41, 0xcf, 0x0f, 0x8c, 0x87, 0x2b, 0x81, 0x5e, 0x5a, 0x46, 0x43, 0x12, 0x0d, 0xc4,
+        0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x04,
+    ];
+
+    /// Multiplies `P` by the little-endian `k`, returning `None` for
+    /// the identity.
+    fn mul(P: &Point, k: &[u8; 32]) -> Option<Point> {
+        let mut acc: Option<Point> = None;
+        for byte in k.iter().rev() {
+            for i in (0..8).rev() {
+                acc = acc.map(|A| A.double());
+                if (byte >> i) & 1 == 1 {
+                    acc = match acc {
+                        None => Some(*P),
+                        Some(A) if A.x != P.x => Some(A.add(P)),
+                        Some(A) if A.y == P.y => Some(A.double()),
+                        Some(_) => None,
+                    };
+                }
+            }
+        }
+        acc
+    }
+
+    #[test]
+    fn generators_have_prime_order() {
+        let b = Scalar::from_bytes_mod_order(CURVE_B);
+        for j in 0..4 {
+            let G = generator(j);
+            assert_eq!(G.y * G.y, G.x * G.x * G.x - Scalar::from(3u64) * G.x + b);
+
+            let mut q_minus_one = GROUP_ORDER;
+            q_minus_one[0] -= 1;
+            let minus_G = Point { x: G.x, y: -G.y };
+            assert_eq!(mul(&G, &q_minus_one), Some(minus_G));
+        }
+    }
+
+    fn hash_proof(bits: &[bool], expected: &Scalar) -> Result<(), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut rng = rand::thread_rng();
+
+        let (proof, commitments) = {
+            let mut transcript = Transcript::new(b"PedersenHashTest");
+            let mut prover = Prover::new(&bp_gens, &pc_gens, &mut transcript);
+            let (commitments, vars): (Vec<_>, Vec<LinearCombination>) = bits
+                .iter()
+                .map(|&b| {
+                    let (V, var) = prover.commit(Scalar::from(b as u64), Scalar::random(&mut rng));
+                    (V, var.into())
+                }).unzip();
+            let hash = pedersen_hash_gadget(&mut prover, &vars, Some(bits))?;
+            prover.constrain(hash - *expected);
+            (prover.prove()?, commitments)
+        };
+
+        let mut transcript = Transcript::new(b"PedersenHashTest");
+        let mut verifier = Verifier::new(&bp_gens, &pc_gens, &mut transcript);
+        let vars: Vec<LinearCombination> = commitments
+            .into_iter()
+            .map(|V| verifier.commit(V).into())
+            .collect();
+        let hash = pedersen_hash_gadget(&mut verifier, &vars, None)?;
+        verifier.constrain(hash - *expected);
+        verifier.verify(&proof)
+    }
+
+    #[test]
+    fn gadget_matches_hash() {
+        let mut rng = rand::thread_rng();
+        let bits: Vec<bool> = (0..16).map(|_| rng.gen()).collect();
+        let hash = pedersen_hash(&bits).unwrap();
+
+        assert!(hash_proof(&bits, &hash).is_ok());
+        assert_eq!(
+            hash_proof(&bits, &(hash + Scalar::one())),
+            Err(R1CSError::VerificationError)
+        );
+
+        // Appending a window changes the hash
+        let mut longer = bits.clone();
+        longer.extend_from_slice(&[false, false]);
+        assert!(pedersen_hash(&longer).unwrap() != hash);
+        assert!(pedersen_hash(&bits[..3]).is_err());
+    }
+}
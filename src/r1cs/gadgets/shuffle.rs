@@ -0,0 +1,262 @@
+//! A shuffle gadget, which constrains a list of values or tuples to be
+//! a permutation of another, as used for confidential asset transfers
+//! in Cloak.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+
+use commitment::Opening;
+use errors::R1CSError;
+use generators::{BulletproofGens, PedersenGens};
+use r1cs::{ConstraintSystem, LinearCombination, Prover, R1CSProof, Variable, Verifier};
+use transcript::TranscriptProtocol;
+
+/// The commitments to a list of tuples.
+type TupleCommitments = Vec<Vec<CompressedRistretto>>;
+
+/// Constrains the values `y` to be a permutation of the values `x`.
+///
+/// For a challenge \\(z\\), the gadget constrains
+/// \\[
+///     \prod\_i (x\_i - z) = \prod\_i (y\_i - z),
+/// \\]
+/// which holds for a random \\(z\\) only if both sides are the same
+/// polynomial in \\(z\\), that is, if the \\(y\_i\\) are a permutation
+/// of the \\(x\_i\\).  This takes \\(2(k - 1)\\) multipliers for
+/// \\(k\\) values.
+///
+/// The challenge is bound to the commitments, so the values must be
+/// linear combinations of committed variables.
+///
+/// Returns [`R1CSError::GadgetError`] if `x` and `y` have different
+/// lengths.
+pub fn scalar_shuffle<CS: ConstraintSystem>(
+    cs: &mut CS,
+    x: &[LinearCombination],
+    y: &[LinearCombination],
+) -> Result<(), R1CSError> {
+    if x.len() != y.len() {
+        return Err(R1CSError::GadgetError {
+            description: "x and y have different lengths".to_string(),
+        });
+    }
+
+    match x.len() {
+        0 => {}
+        1 => cs.constrain(x[0].clone() - y[0].clone()),
+        _ => {
+            let z = cs.challenge_scalar(b"shuffle challenge");
+            let x_product = product(cs, x, &z);
+            let y_product = product(cs, y, &z);
+            cs.constrain(x_product - y_product);
+        }
+    }
+    Ok(())
+}
+
+/// Constrains the tuples `y` to be a permutation of the tuples `x`.
+///
+/// For a challenge \\(w\\), each tuple \\((t\_0, \ldots, t\_{m-1})\\)
+/// is combined into the value \\(\sum\_j w^j t\_j\\), and the values
+/// are shuffled with [`scalar_shuffle`].  This takes no multipliers
+/// beyond those of the scalar shuffle.
+///
+/// The challenge is bound to the commitments, so the values must be
+/// linear combinations of committed variables.
+///
+/// Returns [`R1CSError::GadgetError`] if `x` and `y` have different
+/// lengths, or if the tuples have different sizes.
+pub fn tuple_shuffle<CS: ConstraintSystem>(
+    cs: &mut CS,
+    x: &[Vec<LinearCombination>],
+    y: &[Vec<LinearCombination>],
+) -> Result<(), R1CSError> {
+    let m = tuple_size(x, y)?;
+    if m <= 1 {
+        let x: Vec<_> = x.iter().flat_map(|t| t.iter().cloned()).collect();
+        let y: Vec<_> = y.iter().flat_map(|t| t.iter().cloned()).collect();
+        return scalar_shuffle(cs, &x, &y);
+    }
+
+    let w = cs.challenge_scalar(b"tuple shuffle challenge");
+    let x: Vec<_> = x.iter().map(|t| combine(t, &w)).collect();
+    let y: Vec<_> = y.iter().map(|t| combine(t, &w)).collect();
+    scalar_shuffle(cs, &x, &y)
+}
+
+/// Creates a proof that the committed `outputs` are a permutation of
+/// the committed `inputs`, where each input and output is a tuple of
+/// values, such as an amount and an asset type.
+///
+/// The `bp_gens` must have a `gens_capacity` of at least
+/// \\(2(k - 1)\\) for \\(k\\) tuples, rounded up to a power of two.
+///
+/// Returns the proof together with the commitments to the inputs and
+/// to the outputs, in the same shape as the openings.
+pub fn prove_shuffle(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    transcript: &mut Transcript,
+    inputs: &[Vec<Opening>],
+    outputs: &[Vec<Opening>],
+) -> Result<(R1CSProof, TupleCommitments, TupleCommitments), R1CSError> {
+    let m = tuple_size(inputs, outputs)?;
+    transcript.shuffle_domain_sep(inputs.len() as u64, m as u64);
+
+    let mut prover = Prover::new(bp_gens, pc_gens, transcript);
+    let (input_commitments, x) = commit_openings(&mut prover, inputs);
+    let (output_commitments, y) = commit_openings(&mut prover, outputs);
+
+    tuple_shuffle(&mut prover, &x, &y)?;
+    let proof = prover.prove()?;
+
+    Ok((proof, input_commitments, output_commitments))
+}
+
+/// Verifies a proof created by [`prove_shuffle`] that the committed
+/// `outputs` are a permutation of the committed `inputs`.
+pub fn verify_shuffle(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    transcript: &mut Transcript,
+    proof: &R1CSProof,
+    inputs: &[Vec<CompressedRistretto>],
+    outputs: &[Vec<CompressedRistretto>],
+) -> Result<(), R1CSError> {
+    let m = tuple_size(inputs, outputs)?;
+    transcript.shuffle_domain_sep(inputs.len() as u64, m as u64);
+
+    let mut verifier = Verifier::new(bp_gens, pc_gens, transcript);
+    let x = commit_points(&mut verifier, inputs);
+    let y = commit_points(&mut verifier, outputs);
+
+    tuple_shuffle(&mut verifier, &x, &y)?;
+    verifier.verify(proof)
+}
+
+/// Returns the common size of the tuples `x` and `y`, or an error if
+/// they differ in number or size.
+fn tuple_size<T>(x: &[Vec<T>], y: &[Vec<T>]) -> Result<usize, R1CSError> {
+    let m = x.first().map_or(0, |t| t.len());
+    if x.len() != y.len() || x.iter().chain(y).any(|t| t.len() != m) {
+        return Err(R1CSError::GadgetError {
+            description: "tuples differ in number or size".to_string(),
+        });
+    }
+    Ok(m)
+}
+
+/// Commits the openings of a list of tuples, returning the
+/// commitments and the committed variables.
+fn commit_openings(
+    prover: &mut Prover,
+    tuples: &[Vec<Opening>],
+) -> (TupleCommitments, Vec<Vec<LinearCombination>>) {
+    let mut commitments = Vec::with_capacity(tuples.len());
+    let mut vars = Vec::with_capacity(tuples.len());
+    for tuple in tuples {
+        let mut tuple_commitments = Vec::with_capacity(tuple.len());
+        let mut tuple_vars = Vec::with_capacity(tuple.len());
+        for opening in tuple {
+            let (V, var) = prover.commit(opening.value, opening.blinding);
+            tuple_commitments.push(V);
+            tuple_vars.push(var.into());
+        }
+        commitments.push(tuple_commitments);
+        vars.push(tuple_vars);
+    }
+    (commitments, vars)
+}
+
+/// Commits the commitments of a list of tuples, returning the
+/// committed variables.
+fn commit_points(
+    verifier: &mut Verifier,
+    tuples: &[Vec<CompressedRistretto>],
+) -> Vec<Vec<LinearCombination>> {
+    tuples
+        .iter()
+        .map(|tuple| tuple.iter().map(|V| verifier.commit(*V).into()).collect())
+        .collect()
+}
+
+/// Allocates multipliers for the product \\(\prod\_i (x\_i - z)\\) of
+/// at least two values, returning the variable of the product.
+fn product<CS: ConstraintSystem>(cs: &mut CS, x: &[LinearCombination], z: &Scalar) -> Variable {
+    let (_, _, mut acc) = cs.multiply(x[0].clone() - *z, x[1].clone() - *z);
+    for x_i in &x[2..] {
+        let (_, _, o) = cs.multiply(acc.into(), x_i.clone() - *z);
+        acc = o;
+    }
+    acc
+}
+
+/// Combines a tuple into the value \\(\sum\_j w^j t\_j\\).
+fn combine(tuple: &[LinearCombination], w: &Scalar) -> LinearCombination {
+    let mut exp_w = Scalar::one();
+    let mut sum = LinearCombination::default();
+    for t_j in tuple {
+        sum = sum + t_j.clone() * exp_w;
+        exp_w *= w;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand;
+
+    fn openings(tuples: &[[u64; 2]]) -> Vec<Vec<Opening>> {
+        let mut rng = rand::thread_rng();
+        tuples
+            .iter()
+            .map(|t| {
+                t.iter()
+                    .map(|&v| Opening::new(v, Scalar::random(&mut rng)))
+                    .collect()
+            }).collect()
+    }
+
+    fn shuffle_proof(inputs: &[[u64; 2]], outputs: &[[u64; 2]]) -> Result<(), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let inputs = openings(inputs);
+        let outputs = openings(outputs);
+
+        let mut transcript = Transcript::new(b"ShuffleTest");
+        let (proof, input_commitments, output_commitments) =
+            prove_shuffle(&bp_gens, &pc_gens, &mut transcript, &inputs, &outputs)?;
+        let proof = R1CSProof::from_bytes(&proof.to_bytes())?;
+
+        let mut transcript = Transcript::new(b"ShuffleTest");
+        verify_shuffle(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &proof,
+            &input_commitments,
+            &output_commitments,
+        )
+    }
+
+    #[test]
+    fn shuffle_of_tuples() {
+        // (amount, asset) pairs
+        let inputs = [[10, 1], [20, 2], [30, 1]];
+        assert!(shuffle_proof(&inputs, &[[30, 1], [10, 1], [20, 2]]).is_ok());
+        assert!(shuffle_proof(&inputs, &inputs).is_ok());
+        assert!(shuffle_proof(&inputs[..1], &inputs[..1]).is_ok());
+
+        // Swapping the assets of two tuples is not a shuffle
+        assert_eq!(
+            shuffle_proof(&inputs, &[[30, 1], [10, 2], [20, 1]]),
+            Err(R1CSError::VerificationError)
+        );
+        assert!(shuffle_proof(&inputs, &inputs[..2]).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! An importer for rank-1 constraint systems authored in other
+//! toolchains, in the style of zkinterface.
+//!
+//! An [`ExternalCircuit`] describes its constraints over a vector of
+//! variables
+//! \\[
+//!     z = (1, x\_1, \ldots, x\_k, w\_1, \ldots, w\_m),
+//! \\]
+//! where the instance variables \\(x\_i\\) are public and the witness
+//! variables \\(w\_j\\) are only known to the prover, as
+//! \\[
+//!     \langle A\_i, z \rangle \cdot \langle B\_i, z \rangle = \langle C\_i, z \rangle.
+//! \\]
+//! The circuit and the witness implement `Serialize` and
+//! `Deserialize`, so they can be read in any serde format, and the
+//! coefficients must be elements of the Ristretto scalar field.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+
+use errors::R1CSError;
+use generators::{BulletproofGens, PedersenGens};
+use r1cs::{ConstraintSystem, LinearCombination, Prover, R1CSProof, Variable, Verifier};
+use transcript::TranscriptProtocol;
+
+/// A term of a linear combination over the variables \\(z\\) of an
+/// [`ExternalCircuit`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Term {
+    /// The index of the variable in \\(z\\), where \\(0\\) is the
+    /// constant \\(1\\), followed by the instance and the witness
+    /// variables
+    pub variable: usize,
+    /// The coefficient of the variable
+    pub coefficient: Scalar,
+}
+
+/// A constraint \\(\langle A, z \rangle \cdot \langle B, z \rangle = \langle C, z \rangle\\)
+/// of an [`ExternalCircuit`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExternalConstraint {
+    /// The terms of \\(A\\)
+    pub a: Vec<Term>,
+    /// The terms of \\(B\\)
+    pub b: Vec<Term>,
+    /// The terms of \\(C\\)
+    pub c: Vec<Term>,
+}
+
+/// A rank-1 constraint system authored in another toolchain.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExternalCircuit {
+    /// The values of the public instance variables
+    pub instance: Vec<Scalar>,
+    /// The number of witness variables
+    pub num_witness: usize,
+    /// The constraints
+    pub constraints: Vec<ExternalConstraint>,
+}
+
+impl ExternalCircuit {
+    /// Returns the number of multipliers the circuit takes: one per
+    /// constraint, and one per two witness variables.
+    pub fn num_multipliers(&self) -> usize {
+        self.constraints.len() + (self.num_witness + 1) / 2
+    }
+
+    /// Checks that all the terms refer to variables of the circuit.
+    fn check(&self) -> Result<(), R1CSError> {
+        let num_variables = 1 + self.instance.len() + self.num_witness;
+        let valid = self.constraints.iter().all(|constraint| {
+            constraint
+                .a
+                .iter()
+                .chain(&constraint.b)
+                .chain(&constraint.c)
+                .all(|term| term.variable < num_variables)
+        });
+        if !valid {
+            return Err(R1CSError::GadgetError {
+                description: "term refers to a variable outside the circuit".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Constrains the `circuit` to be satisfied.
+///
+/// The prover passes the values of the `witness` variables, and the
+/// verifier passes `None`.  The witness variables are allocated two by
+/// two in multipliers whose outputs are unused, the instance variables
+/// become constants, and each constraint takes one multiplier.
+///
+/// Returns [`R1CSError::MissingAssignment`] if the prover does not pass
+/// the witness, and [`R1CSError::GadgetError`] if the witness does not
+/// have the size of the circuit, or if a term refers to a variable
+/// outside the circuit.
+pub fn external_circuit_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    circuit: &ExternalCircuit,
+    witness: Option<&[Scalar]>,
+) -> Result<(), R1CSError> {
+    circuit.check()?;
+    if witness.map_or(false, |witness| witness.len() != circuit.num_witness) {
+        return Err(R1CSError::GadgetError {
+            description: "witness does not match the circuit".to_string(),
+        });
+    }
+
+    let mut witness_vars = Vec::with_capacity(circuit.num_witness);
+    for j in (0..circuit.num_witness).step_by(2) {
+        let assignment =
+            witness.map(|w| (w[j], w.get(j + 1).cloned().unwrap_or_else(Scalar::zero)));
+        let (left, right, _) = cs.allocate_multiplier(assignment)?;
+        witness_vars.push(left);
+        witness_vars.push(right);
+    }
+
+    for constraint in &circuit.constraints {
+        let a = combination(&constraint.a, &circuit.instance, &witness_vars);
+        let b = combination(&constraint.b, &circuit.instance, &witness_vars);
+        let c = combination(&constraint.c, &circuit.instance, &witness_vars);
+        let (_, _, o) = cs.multiply(a, b);
+        cs.constrain(o - c);
+    }
+    Ok(())
+}
+
+/// Creates a proof that the `witness` satisfies the `circuit`.
+///
+/// The `bp_gens` must have a `gens_capacity` of at least
+/// [`ExternalCircuit::num_multipliers`], rounded up to a power of two.
+pub fn prove_external_circuit(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    transcript: &mut Transcript,
+    circuit: &ExternalCircuit,
+    witness: &[Scalar],
+) -> Result<R1CSProof, R1CSError> {
+    commit_circuit(transcript, circuit);
+
+    let mut prover = Prover::new(bp_gens, pc_gens, transcript);
+    external_circuit_gadget(&mut prover, circuit, Some(witness))?;
+    prover.prove()
+}
+
+/// Verifies a proof created by [`prove_external_circuit`] that the
+/// `circuit` is satisfied.
+pub fn verify_external_circuit(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    transcript: &mut Transcript,
+    circuit: &ExternalCircuit,
+    proof: &R1CSProof,
+) -> Result<(), R1CSError> {
+    commit_circuit(transcript, circuit);
+
+    let mut verifier = Verifier::new(bp_gens, pc_gens, transcript);
+    external_circuit_gadget(&mut verifier, circuit, None)?;
+    verifier.verify(proof)
+}
+
+/// Commits the dimensions and the instance of the circuit.
+fn commit_circuit(transcript: &mut Transcript, circuit: &ExternalCircuit) {
+    transcript.external_circuit_domain_sep(
+        circuit.instance.len() as u64,
+        circuit.num_witness as u64,
+        circuit.constraints.len() as u64,
+    );
+    for x_i in &circuit.instance {
+        transcript.commit_scalar(b"x_i", x_i);
+    }
+}
+
+/// Builds the linear combination of the `terms`, with the instance
+/// variables as constants.
+fn combination(
+    terms: &[Term],
+    instance: &[Scalar],
+    witness_vars: &[Variable],
+) -> LinearCombination {
+    let mut lc = LinearCombination::default();
+    for term in terms {
+        let k = instance.len();
+        lc = match term.variable {
+            0 => lc + term.coefficient,
+            i if i <= k => lc + term.coefficient * instance[i - 1],
+            i => lc + witness_vars[i - 1 - k] * term.coefficient,
+        };
+    }
+    lc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bincode;
+
+    fn term(variable: usize, coefficient: u64) -> Term {
+        Term {
+            variable,
+            coefficient: Scalar::from(coefficient),
+        }
+    }
+
+    /// The circuit of \\(w^3 + w + 5 = x\\), with \\(z = (1, x, w, w^2, w^3)\\).
+    fn cubic_circuit(x: u64) -> ExternalCircuit {
+        ExternalCircuit {
+            instance: vec![Scalar::from(x)],
+            num_witness: 3,
+            constraints: vec![
+                // w * w = w^2
+                ExternalConstraint {
+                    a: vec![term(2, 1)],
+                    b: vec![term(2, 1)],
+                    c: vec![term(3, 1)],
+                },
+                // w^2 * w = w^3
+                ExternalConstraint {
+                    a: vec![term(3, 1)],
+                    b: vec![term(2, 1)],
+                    c: vec![term(4, 1)],
+                },
+                // (w^3 + w + 5) * 1 = x
+                ExternalConstraint {
+                    a: vec![term(4, 1), term(2, 1), term(0, 5)],
+                    b: vec![term(0, 1)],
+                    c: vec![term(1, 1)],
+                },
+            ],
+        }
+    }
+
+    fn cubic_proof(x: u64, w: u64) -> Result<(), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+
+        // The circuit is transmitted in a serde format
+        let bytes = bincode::serialize(&cubic_circuit(x)).unwrap();
+        let circuit: ExternalCircuit = bincode::deserialize(&bytes).unwrap();
+        let witness: Vec<Scalar> = [w, w * w, w * w * w].iter().map(|&v| v.into()).collect();
+
+        let mut transcript = Transcript::new(b"ExternalCircuitTest");
+        let proof =
+            prove_external_circuit(&bp_gens, &pc_gens, &mut transcript, &circuit, &witness)?;
+
+        let mut transcript = Transcript::new(b"ExternalCircuitTest");
+        verify_external_circuit(&bp_gens, &pc_gens, &mut transcript, &circuit, &proof)
+    }
+
+    #[test]
+    fn prove_and_verify_external_circuit() {
+        assert!(cubic_proof(35, 3).is_ok());
+        assert_eq!(cubic_proof(36, 3), Err(R1CSError::VerificationError));
+
+        let mut circuit = cubic_circuit(35);
+        circuit.constraints[0].a[0].variable = 5;
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let witness = [Scalar::one(); 3];
+        let mut transcript = Transcript::new(b"ExternalCircuitTest");
+        let result =
+            prove_external_circuit(&bp_gens, &pc_gens, &mut transcript, &circuit, &witness);
+        assert!(result.is_err());
+    }
+}
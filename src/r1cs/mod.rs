@@ -0,0 +1,398 @@
+//! Proofs of satisfiability of rank-1 constraint systems.
+//!
+//! A constraint system is built by allocating multiplication gates
+//! with [`ConstraintSystem::multiply`] or
+//! [`ConstraintSystem::allocate_multiplier`], and by adding linear
+//! constraints over the gate wires and the committed high-level
+//! variables with [`ConstraintSystem::constrain`].
+//!
+//! The [`Prover`] and [`Verifier`] both implement
+//! [`ConstraintSystem`], so the same gadget code builds the
+//! statement on both sides.
+//!
+//! Constraints which depend on challenges bound to the multipliers,
+//! such as those of permutation arguments, are added in a second
+//! phase with [`ConstraintSystem::specify_randomized_constraints`]:
+//! the multipliers of the first phase are committed before the
+//! callbacks are called with a [`RandomizedConstraintSystem`].  The proof uses the inner-product
+//! argument from the range proof, together with the same
+//! [`BulletproofGens`](::BulletproofGens),
+//! [`PedersenGens`](::PedersenGens) and Merlin transcript plumbing.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::scalar::Scalar;
+
+pub mod gadgets;
+#[cfg(feature = "circuit_import")]
+pub mod import;
+
+mod constraint_system;
+mod linear_combination;
+mod proof;
+mod prover;
+mod verifier;
+
+pub use self::constraint_system::{ConstraintSystem, RandomizedConstraintSystem};
+pub use self::linear_combination::{LinearCombination, Variable};
+pub use self::proof::R1CSProof;
+pub use self::prover::{Prover, RandomizingProver};
+pub use self::verifier::{RandomizingVerifier, Verifier};
+
+pub use errors::R1CSError;
+
+/// Use a challenge, `z`, to flatten the constraints into the weight
+/// vectors used for proving and verification.
+///
+/// # Output
+///
+/// Returns a tuple of
+/// ```text
+/// (wL, wR, wO, wV, wc)
+/// ```
+/// where `w{L,R,O}` is \\( z \cdot z^Q \cdot W_{L,R,O} \\), `wV` is
+/// \\( -z \cdot z^Q \cdot W_V \\) and `wc` is
+/// \\( -z \cdot z^Q \cdot c \\).
+fn flatten(
+    constraints: &[LinearCombination],
+    z: &Scalar,
+    n: usize,
+    m: usize,
+) -> (Vec<Scalar>, Vec<Scalar>, Vec<Scalar>, Vec<Scalar>, Scalar) {
+    let mut wL = vec![Scalar::zero(); n];
+    let mut wR = vec![Scalar::zero(); n];
+    let mut wO = vec![Scalar::zero(); n];
+    let mut wV = vec![Scalar::zero(); m];
+    let mut wc = Scalar::zero();
+
+    let mut exp_z = *z;
+    for lc in constraints.iter() {
+        for (var, coeff) in &lc.terms {
+            match var {
+                Variable::MultiplierLeft(i) => {
+                    wL[*i] += exp_z * coeff;
+                }
+                Variable::MultiplierRight(i) => {
+                    wR[*i] += exp_z * coeff;
+                }
+                Variable::MultiplierOutput(i) => {
+                    wO[*i] += exp_z * coeff;
+                }
+                Variable::Committed(i) => {
+                    wV[*i] -= exp_z * coeff;
+                }
+                Variable::One => {
+                    wc -= exp_z * coeff;
+                }
+            }
+        }
+        exp_z *= z;
+    }
+
+    (wL, wR, wO, wV, wc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::ristretto::CompressedRistretto;
+    use merlin::Transcript;
+    use rand;
+
+    use generators::{BulletproofGens, PedersenGens};
+
+    /// Constrains `a * b = c`, where all three are committed.
+    fn mul_gadget<CS: ConstraintSystem>(cs: &mut CS, a: Variable, b: Variable, c: Variable) {
+        let (_, _, o) = cs.multiply(a.into(), b.into());
+        cs.constrain(o - c);
+    }
+
+    fn mul_proof(a: u64, b: u64, c: u64) -> Result<(), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let mut rng = rand::thread_rng();
+
+        let (proof, commitments) = {
+            let mut transcript = Transcript::new(b"R1CSMulTest");
+            let mut prover = Prover::new(&bp_gens, &pc_gens, &mut transcript);
+
+            let (com_a, var_a) = prover.commit(a.into(), Scalar::random(&mut rng));
+            let (com_b, var_b) = prover.commit(b.into(), Scalar::random(&mut rng));
+            let (com_c, var_c) = prover.commit(c.into(), Scalar::random(&mut rng));
+            mul_gadget(&mut prover, var_a, var_b, var_c);
+
+            (prover.prove()?, vec![com_a, com_b, com_c])
+        };
+
+        let proof = R1CSProof::from_bytes(&proof.to_bytes())?;
+
+        let mut transcript = Transcript::new(b"R1CSMulTest");
+        let mut verifier = Verifier::new(&bp_gens, &pc_gens, &mut transcript);
+
+        let vars: Vec<Variable> = commitments
+            .into_iter()
+            .map(|V| verifier.commit(V))
+            .collect();
+        mul_gadget(&mut verifier, vars[0], vars[1], vars[2]);
+
+        verifier.verify(&proof)
+    }
+
+    #[test]
+    fn mul_gadget_test() {
+        assert!(mul_proof(3, 4, 12).is_ok());
+        assert_eq!(mul_proof(3, 4, 13), Err(R1CSError::VerificationError));
+    }
+
+    /// Constrains `{x, y}` to be a permutation of `{a, b}`, using a
+    /// challenge bound to the commitments.
+    fn shuffle_gadget<CS: ConstraintSystem>(
+        cs: &mut CS,
+        x: Variable,
+        y: Variable,
+        a: Variable,
+        b: Variable,
+    ) {
+        let k = cs.challenge_scalar(b"shuffle challenge");
+        let (_, _, lhs) = cs.multiply(x - k, y - k);
+        let (_, _, rhs) = cs.multiply(a - k, b - k);
+        cs.constrain(lhs - rhs);
+    }
+
+    fn shuffle_proof(input: [u64; 2], output: [u64; 2]) -> Result<(), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let mut rng = rand::thread_rng();
+
+        let (proof, commitments) = {
+            let mut transcript = Transcript::new(b"R1CSShuffleTest");
+            let mut prover = Prover::new(&bp_gens, &pc_gens, &mut transcript);
+
+            let (commitments, vars): (Vec<CompressedRistretto>, Vec<Variable>) = input
+                .iter()
+                .chain(output.iter())
+                .map(|v| prover.commit((*v).into(), Scalar::random(&mut rng)))
+                .unzip();
+            shuffle_gadget(&mut prover, vars[0], vars[1], vars[2], vars[3]);
+
+            (prover.prove()?, commitments)
+        };
+
+        let mut transcript = Transcript::new(b"R1CSShuffleTest");
+        let mut verifier = Verifier::new(&bp_gens, &pc_gens, &mut transcript);
+
+        let vars: Vec<Variable> = commitments
+            .into_iter()
+            .map(|V| verifier.commit(V))
+            .collect();
+        shuffle_gadget(&mut verifier, vars[0], vars[1], vars[2], vars[3]);
+
+        verifier.verify(&proof)
+    }
+
+    #[test]
+    fn shuffle_gadget_test() {
+        assert!(shuffle_proof([3, 7], [7, 3]).is_ok());
+        assert!(shuffle_proof([3, 7], [3, 7]).is_ok());
+        assert!(shuffle_proof([3, 7], [3, 8]).is_err());
+    }
+
+    /// Constrains `{x^2, y^2}` to be a permutation of `{a, b}`, using a
+    /// challenge bound to the squares, which are only multipliers.
+    fn squares_shuffle_gadget<CS: ConstraintSystem>(
+        cs: &mut CS,
+        x: Variable,
+        y: Variable,
+        a: Variable,
+        b: Variable,
+    ) -> Result<(), R1CSError> {
+        let (_, _, x_sq) = cs.multiply(x.into(), x.into());
+        let (_, _, y_sq) = cs.multiply(y.into(), y.into());
+        cs.specify_randomized_constraints(move |cs| {
+            let k = cs.challenge_scalar(b"shuffle challenge");
+            let (_, _, lhs) = cs.multiply(x_sq - k, y_sq - k);
+            let (_, _, rhs) = cs.multiply(a - k, b - k);
+            cs.constrain(lhs - rhs);
+            Ok(())
+        })
+    }
+
+    fn squares_shuffle_proof(input: [u64; 2], output: [u64; 2]) -> Result<(), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let mut rng = rand::thread_rng();
+
+        let (proof, commitments) = {
+            let mut transcript = Transcript::new(b"R1CSRandomizedTest");
+            let mut prover = Prover::new(&bp_gens, &pc_gens, &mut transcript);
+
+            let (commitments, vars): (Vec<CompressedRistretto>, Vec<Variable>) = input
+                .iter()
+                .chain(output.iter())
+                .map(|v| prover.commit((*v).into(), Scalar::random(&mut rng)))
+                .unzip();
+            squares_shuffle_gadget(&mut prover, vars[0], vars[1], vars[2], vars[3])?;
+
+            (prover.prove()?, commitments)
+        };
+
+        let proof = R1CSProof::from_bytes(&proof.to_bytes())?;
+
+        let mut transcript = Transcript::new(b"R1CSRandomizedTest");
+        let mut verifier = Verifier::new(&bp_gens, &pc_gens, &mut transcript);
+
+        let vars: Vec<Variable> = commitments
+            .into_iter()
+            .map(|V| verifier.commit(V))
+            .collect();
+        squares_shuffle_gadget(&mut verifier, vars[0], vars[1], vars[2], vars[3])?;
+
+        verifier.verify(&proof)
+    }
+
+    #[test]
+    fn randomized_constraints_test() {
+        assert!(squares_shuffle_proof([3, 7], [49, 9]).is_ok());
+        assert!(squares_shuffle_proof([3, 7], [9, 49]).is_ok());
+        assert_eq!(
+            squares_shuffle_proof([3, 7], [9, 48]),
+            Err(R1CSError::VerificationError)
+        );
+    }
+
+    /// Proves that `x` is an 8-bit value with `x + y = 300`, sharing
+    /// the commitment to `x` between the range proof and the
+    /// constraint system.
+    fn linked_range_proof(x: u64, y: u64) -> Result<(), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let mut rng = rand::thread_rng();
+
+        let (range_proof, proof, commitments) = {
+            let mut transcript = Transcript::new(b"R1CSLinkedRangeTest");
+            let mut prover = Prover::new(&bp_gens, &pc_gens, &mut transcript);
+
+            let (com_x, var_x) = prover.commit(x.into(), Scalar::random(&mut rng));
+            let (com_y, var_y) = prover.commit(y.into(), Scalar::random(&mut rng));
+            let range_proof = prover.prove_range(var_x, 8)?;
+            prover.constrain(var_x + var_y - Scalar::from(300u64));
+
+            (range_proof, prover.prove()?, vec![com_x, com_y])
+        };
+
+        let mut transcript = Transcript::new(b"R1CSLinkedRangeTest");
+        let mut verifier = Verifier::new(&bp_gens, &pc_gens, &mut transcript);
+
+        let var_x = verifier.commit(commitments[0]);
+        let var_y = verifier.commit(commitments[1]);
+        verifier.verify_range(&range_proof, var_x, 8)?;
+        verifier.constrain(var_x + var_y - Scalar::from(300u64));
+
+        verifier.verify(&proof)
+    }
+
+    #[test]
+    fn linked_range_proof_test() {
+        assert!(linked_range_proof(200, 100).is_ok());
+        assert!(linked_range_proof(300, 0).is_err());
+        assert_eq!(
+            linked_range_proof(200, 101),
+            Err(R1CSError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn proof_serialization() {
+        use bincode;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let mut rng = rand::thread_rng();
+
+        let (proof, commitments) = {
+            let mut transcript = Transcript::new(b"R1CSSerializationTest");
+            let mut prover = Prover::new(&bp_gens, &pc_gens, &mut transcript);
+
+            let (com_a, var_a) = prover.commit(3u64.into(), Scalar::random(&mut rng));
+            let (com_b, var_b) = prover.commit(4u64.into(), Scalar::random(&mut rng));
+            let (com_c, var_c) = prover.commit(12u64.into(), Scalar::random(&mut rng));
+            mul_gadget(&mut prover, var_a, var_b, var_c);
+
+            (prover.prove().unwrap(), vec![com_a, com_b, com_c])
+        };
+
+        // A single multiplier gives an inner product proof without
+        // folding rounds
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 16 * 32);
+
+        let proof: R1CSProof = bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        assert_eq!(proof.to_bytes(), bytes);
+
+        let mut transcript = Transcript::new(b"R1CSSerializationTest");
+        let mut verifier = Verifier::new(&bp_gens, &pc_gens, &mut transcript);
+        let vars: Vec<Variable> = commitments
+            .into_iter()
+            .map(|V| verifier.commit(V))
+            .collect();
+        mul_gadget(&mut verifier, vars[0], vars[1], vars[2]);
+        assert!(verifier.verify(&proof).is_ok());
+
+        // Non-canonical scalars and truncated proofs are rejected
+        let mut non_canonical = bytes.clone();
+        for byte in &mut non_canonical[11 * 32..12 * 32] {
+            *byte = 0xff;
+        }
+        assert_eq!(
+            R1CSProof::from_bytes(&non_canonical).unwrap_err(),
+            R1CSError::FormatError
+        );
+        assert_eq!(
+            R1CSProof::from_bytes(&bytes[..13 * 32]).unwrap_err(),
+            R1CSError::FormatError
+        );
+        assert_eq!(
+            R1CSProof::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+            R1CSError::FormatError
+        );
+    }
+
+    #[test]
+    fn debug_mode_reports_unsatisfied_constraints() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let mut rng = rand::thread_rng();
+
+        let mut transcript = Transcript::new(b"R1CSDebugTest");
+        let mut prover = Prover::new(&bp_gens, &pc_gens, &mut transcript);
+        prover.set_debug(true);
+
+        let (_, a) = prover.commit(3u64.into(), Scalar::random(&mut rng));
+        let (_, b) = prover.commit(4u64.into(), Scalar::random(&mut rng));
+        let (_, c) = prover.commit(13u64.into(), Scalar::random(&mut rng));
+        // Constraints 0 and 1 bind the inputs of the multiplier
+        let (_, _, o) = prover.multiply(a.into(), b.into());
+        prover.constrain(o - c);
+        prover.constrain(a + b - Scalar::from(7u64));
+        assert_eq!(prover.unsatisfied_constraints(), vec![2]);
+
+        assert_eq!(
+            prover.prove().unwrap_err(),
+            R1CSError::UnsatisfiedConstraints { indices: vec![2] }
+        );
+    }
+
+    #[test]
+    fn prover_requires_assignments() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let mut transcript = Transcript::new(b"R1CSAssignmentTest");
+        let mut prover = Prover::new(&bp_gens, &pc_gens, &mut transcript);
+
+        assert_eq!(
+            prover.allocate_multiplier(None),
+            Err(R1CSError::MissingAssignment)
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Definition of the proof struct.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+
+use errors::R1CSError;
+use inner_product_proof::InnerProductProof;
+use scalar;
+
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A proof of some statement specified by a
+/// [`ConstraintSystem`](::r1cs::ConstraintSystem).
+///
+/// Statements are specified by writing gadget functions which add
+/// constraints to a [`ConstraintSystem`](::r1cs::ConstraintSystem)
+/// implementation.  To construct an [`R1CSProof`], a prover constructs
+/// a [`Prover`](::r1cs::Prover), then passes it to gadget functions
+/// to build the constraint system, then consumes the constraint system
+/// using [`Prover::prove`](::r1cs::Prover::prove) to produce an
+/// [`R1CSProof`].  To verify an [`R1CSProof`], a verifier constructs a
+/// [`Verifier`](::r1cs::Verifier), then passes it to the same gadget
+/// functions to (re)build the constraint system, then consumes the
+/// constraint system using
+/// [`Verifier::verify`](::r1cs::Verifier::verify) to verify the
+/// proof.
+#[derive(Clone, Debug)]
+pub struct R1CSProof {
+    /// Commitment to the values of input wires in the first phase
+    pub(super) A_I1: CompressedRistretto,
+    /// Commitment to the values of output wires in the first phase
+    pub(super) A_O1: CompressedRistretto,
+    /// Commitment to the blinding factors in the first phase
+    pub(super) S1: CompressedRistretto,
+    /// Commitment to the values of input wires in the second phase
+    pub(super) A_I2: CompressedRistretto,
+    /// Commitment to the values of output wires in the second phase
+    pub(super) A_O2: CompressedRistretto,
+    /// Commitment to the blinding factors in the second phase
+    pub(super) S2: CompressedRistretto,
+    /// Commitment to the \\(t_1\\) coefficient of \\( t(x) \\)
+    pub(super) T_1: CompressedRistretto,
+    /// Commitment to the \\(t_3\\) coefficient of \\( t(x) \\)
+    pub(super) T_3: CompressedRistretto,
+    /// Commitment to the \\(t_4\\) coefficient of \\( t(x) \\)
+    pub(super) T_4: CompressedRistretto,
+    /// Commitment to the \\(t_5\\) coefficient of \\( t(x) \\)
+    pub(super) T_5: CompressedRistretto,
+    /// Commitment to the \\(t_6\\) coefficient of \\( t(x) \\)
+    pub(super) T_6: CompressedRistretto,
+    /// Evaluation of the polynomial \\(t(x)\\) at the challenge point \\(x\\)
+    pub(super) t_x: Scalar,
+    /// Blinding factor for the synthetic commitment to \\( t(x) \\)
+    pub(super) t_x_blinding: Scalar,
+    /// Blinding factor for the synthetic commitment to the
+    /// inner-product arguments
+    pub(super) e_blinding: Scalar,
+    /// Proof data for the inner-product argument.
+    pub(super) ipp_proof: InnerProductProof,
+}
+
+impl R1CSProof {
+    /// Serializes the proof into a byte array of \\(2 \lg n + 16\\)
+    /// 32-byte elements, where \\(n\\) is the number of
+    /// multiplication gates rounded up to a power of two.
+    ///
+    /// The layout of the proof is:
+    /// * six compressed Ristretto points \\(A\_{I1}, A\_{O1}, S\_1, A\_{I2}, A\_{O2}, S\_2\\),
+    /// * five compressed Ristretto points \\(T\_1, T\_3, T\_4, T\_5, T\_6\\),
+    /// * three scalars \\(t\_x, \tilde{t}\_x, \tilde{e}\\),
+    /// * the inner product proof.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(14 * 32 + self.ipp_proof.serialized_size());
+        buf.extend_from_slice(self.A_I1.as_bytes());
+        buf.extend_from_slice(self.A_O1.as_bytes());
+        buf.extend_from_slice(self.S1.as_bytes());
+        buf.extend_from_slice(self.A_I2.as_bytes());
+        buf.extend_from_slice(self.A_O2.as_bytes());
+        buf.extend_from_slice(self.S2.as_bytes());
+        buf.extend_from_slice(self.T_1.as_bytes());
+        buf.extend_from_slice(self.T_3.as_bytes());
+        buf.extend_from_slice(self.T_4.as_bytes());
+        buf.extend_from_slice(self.T_5.as_bytes());
+        buf.extend_from_slice(self.T_6.as_bytes());
+        buf.extend_from_slice(self.t_x.as_bytes());
+        buf.extend_from_slice(self.t_x_blinding.as_bytes());
+        buf.extend_from_slice(self.e_blinding.as_bytes());
+        buf.extend_from_slice(self.ipp_proof.to_bytes().as_slice());
+        buf
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `R1CSProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<R1CSProof, R1CSError> {
+        if slice.len() % 32 != 0 {
+            return Err(R1CSError::FormatError);
+        }
+        if slice.len() < 14 * 32 {
+            return Err(R1CSError::FormatError);
+        }
+
+        use util::read32;
+
+        let A_I1 = CompressedRistretto(read32(&slice[0 * 32..]));
+        let A_O1 = CompressedRistretto(read32(&slice[1 * 32..]));
+        let S1 = CompressedRistretto(read32(&slice[2 * 32..]));
+        let A_I2 = CompressedRistretto(read32(&slice[3 * 32..]));
+        let A_O2 = CompressedRistretto(read32(&slice[4 * 32..]));
+        let S2 = CompressedRistretto(read32(&slice[5 * 32..]));
+        let T_1 = CompressedRistretto(read32(&slice[6 * 32..]));
+        let T_3 = CompressedRistretto(read32(&slice[7 * 32..]));
+        let T_4 = CompressedRistretto(read32(&slice[8 * 32..]));
+        let T_5 = CompressedRistretto(read32(&slice[9 * 32..]));
+        let T_6 = CompressedRistretto(read32(&slice[10 * 32..]));
+
+        let t_x = scalar::decode(&slice[11 * 32..12 * 32])?;
+        let t_x_blinding = scalar::decode(&slice[12 * 32..13 * 32])?;
+        let e_blinding = scalar::decode(&slice[13 * 32..14 * 32])?;
+
+        let ipp_proof = InnerProductProof::from_bytes(&slice[14 * 32..])?;
+
+        Ok(R1CSProof {
+            A_I1,
+            A_O1,
+            S1,
+            A_I2,
+            A_O2,
+            S2,
+            T_1,
+            T_3,
+            T_4,
+            T_5,
+            T_6,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            ipp_proof,
+        })
+    }
+}
+
+impl Serialize for R1CSProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for R1CSProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct R1CSProofVisitor;
+
+        impl<'de> Visitor<'de> for R1CSProofVisitor {
+            type Value = R1CSProof;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("a valid R1CSProof")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<R1CSProof, E>
+            where
+                E: serde::de::Error,
+            {
+                R1CSProof::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(R1CSProofVisitor)
+    }
+}
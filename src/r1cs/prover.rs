@@ -0,0 +1,638 @@
+#![allow(non_snake_case)]
+
+use clear_on_drop::clear::Clear;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::MultiscalarMul;
+use merlin::Transcript;
+use rand;
+use std::iter;
+use std::mem;
+
+use super::{ConstraintSystem, LinearCombination, R1CSProof, RandomizedConstraintSystem, Variable};
+
+use commitment::Opening;
+use errors::R1CSError;
+use generators::{BulletproofGens, PedersenGens};
+use inner_product_proof::InnerProductProof;
+use math;
+use range_proof::RangeProof;
+use scalar::{self, ScalarVec};
+use transcript::TranscriptProtocol;
+use util;
+
+/// A [`ConstraintSystem`] implementation for use by the prover.
+///
+/// The prover commits high-level variables and their blinding factors
+/// `(v, v_blinding)`, allocates low-level variables and creates
+/// constraints in terms of these high-level variables and low-level
+/// variables.
+///
+/// When all constraints are added, the prover calls
+/// [`prove`](Prover::prove) to create a proof that all of them are
+/// satisfied.
+pub struct Prover<'t, 'g> {
+    transcript: &'t mut Transcript,
+    bp_gens: &'g BulletproofGens,
+    pc_gens: &'g PedersenGens,
+    /// The constraints accumulated so far.
+    constraints: Vec<LinearCombination>,
+    /// Stores assignments to the "left" of multiplication gates
+    a_L: Vec<Scalar>,
+    /// Stores assignments to the "right" of multiplication gates
+    a_R: Vec<Scalar>,
+    /// Stores assignments to the "output" of multiplication gates
+    a_O: Vec<Scalar>,
+    /// High-level witness data (value openings to V commitments)
+    v: Vec<Scalar>,
+    /// High-level witness data (blinding openings to V commitments)
+    v_blinding: Vec<Scalar>,
+    /// The callbacks which add the randomized constraints.
+    deferred_constraints: Vec<DeferredConstraint<'t, 'g>>,
+    /// Whether to check the constraints before proving.
+    debug: bool,
+}
+
+/// A callback which adds randomized constraints to the prover.
+type DeferredConstraint<'t, 'g> =
+    Box<dyn Fn(&mut RandomizingProver<'t, 'g>) -> Result<(), R1CSError>>;
+
+/// A [`ConstraintSystem`] implementation for the prover's second
+/// phase, whose challenges are bound to all the first-phase variables.
+///
+/// It is passed to the callbacks given to
+/// [`ConstraintSystem::specify_randomized_constraints`].
+pub struct RandomizingProver<'t, 'g> {
+    prover: Prover<'t, 'g>,
+}
+
+/// Overwrite secrets with null bytes when they go out of scope.
+impl<'t, 'g> Drop for Prover<'t, 'g> {
+    fn drop(&mut self) {
+        // See the comment in `PartyAwaitingBitChallenge` for why the
+        // vectors are cleared element by element.
+        for e in self.v.iter_mut() {
+            e.clear();
+        }
+        for e in self.v_blinding.iter_mut() {
+            e.clear();
+        }
+        for e in self.a_L.iter_mut() {
+            e.clear();
+        }
+        for e in self.a_R.iter_mut() {
+            e.clear();
+        }
+        for e in self.a_O.iter_mut() {
+            e.clear();
+        }
+    }
+}
+
+impl<'t, 'g> ConstraintSystem for Prover<'t, 'g> {
+    type RandomizedCS = RandomizingProver<'t, 'g>;
+
+    fn multiply(
+        &mut self,
+        mut left: LinearCombination,
+        mut right: LinearCombination,
+    ) -> (Variable, Variable, Variable) {
+        // Synthesize the assignments for l,r,o
+        let l = self.eval(&left);
+        let r = self.eval(&right);
+        let o = l * r;
+
+        // Create variables for l,r,o ...
+        let l_var = Variable::MultiplierLeft(self.a_L.len());
+        let r_var = Variable::MultiplierRight(self.a_R.len());
+        let o_var = Variable::MultiplierOutput(self.a_O.len());
+        // ... and assign them
+        self.a_L.push(l);
+        self.a_R.push(r);
+        self.a_O.push(o);
+
+        // Constrain l,r,o:
+        left.terms.push((l_var, -Scalar::one()));
+        right.terms.push((r_var, -Scalar::one()));
+        self.constrain(left);
+        self.constrain(right);
+
+        (l_var, r_var, o_var)
+    }
+
+    fn allocate_multiplier(
+        &mut self,
+        input_assignments: Option<(Scalar, Scalar)>,
+    ) -> Result<(Variable, Variable, Variable), R1CSError> {
+        let (l, r) = input_assignments.ok_or(R1CSError::MissingAssignment)?;
+        let o = l * r;
+
+        // Create variables for l,r,o ...
+        let l_var = Variable::MultiplierLeft(self.a_L.len());
+        let r_var = Variable::MultiplierRight(self.a_R.len());
+        let o_var = Variable::MultiplierOutput(self.a_O.len());
+        // ... and assign them
+        self.a_L.push(l);
+        self.a_R.push(r);
+        self.a_O.push(o);
+
+        Ok((l_var, r_var, o_var))
+    }
+
+    fn constrain(&mut self, lc: LinearCombination) {
+        // TODO: check that the linear combinations are valid
+        // (e.g. that variables are valid, that the linear combination
+        // evals to 0 for prover, etc).
+        self.constraints.push(lc);
+    }
+
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        self.transcript.challenge_scalar(label)
+    }
+
+    fn specify_randomized_constraints<F>(&mut self, callback: F) -> Result<(), R1CSError>
+    where
+        F: 'static + Fn(&mut Self::RandomizedCS) -> Result<(), R1CSError>,
+    {
+        self.deferred_constraints.push(Box::new(callback));
+        Ok(())
+    }
+}
+
+impl<'t, 'g> ConstraintSystem for RandomizingProver<'t, 'g> {
+    type RandomizedCS = Self;
+
+    fn multiply(
+        &mut self,
+        left: LinearCombination,
+        right: LinearCombination,
+    ) -> (Variable, Variable, Variable) {
+        self.prover.multiply(left, right)
+    }
+
+    fn allocate_multiplier(
+        &mut self,
+        input_assignments: Option<(Scalar, Scalar)>,
+    ) -> Result<(Variable, Variable, Variable), R1CSError> {
+        self.prover.allocate_multiplier(input_assignments)
+    }
+
+    fn constrain(&mut self, lc: LinearCombination) {
+        self.prover.constrain(lc)
+    }
+
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        self.prover.transcript.challenge_scalar(label)
+    }
+
+    fn specify_randomized_constraints<F>(&mut self, callback: F) -> Result<(), R1CSError>
+    where
+        F: 'static + Fn(&mut Self::RandomizedCS) -> Result<(), R1CSError>,
+    {
+        callback(self)
+    }
+}
+
+impl<'t, 'g> RandomizedConstraintSystem for RandomizingProver<'t, 'g> {}
+
+impl<'t, 'g> Prover<'t, 'g> {
+    /// Construct an empty constraint system.
+    ///
+    /// # Inputs
+    ///
+    /// The `bp_gens` and `pc_gens` are generators for Bulletproofs
+    /// and for the Pedersen commitments, respectively.  The
+    /// [`BulletproofGens`] should have `gens_capacity` greater than
+    /// the number of multiplication constraints that will eventually
+    /// be added into the constraint system, rounded up to a power of
+    /// two.
+    ///
+    /// The `transcript` parameter is a Merlin proof transcript.  The
+    /// `Prover` holds onto the `&mut Transcript` until it consumes
+    /// itself during [`Prover::prove`], releasing its borrow of the
+    /// transcript.  This ensures that the transcript cannot be
+    /// altered except by the `Prover` before proving is complete.
+    pub fn new(
+        bp_gens: &'g BulletproofGens,
+        pc_gens: &'g PedersenGens,
+        transcript: &'t mut Transcript,
+    ) -> Self {
+        transcript.r1cs_domain_sep();
+
+        Prover {
+            transcript,
+            bp_gens,
+            pc_gens,
+            constraints: Vec::new(),
+            a_L: Vec::new(),
+            a_R: Vec::new(),
+            a_O: Vec::new(),
+            v: Vec::new(),
+            v_blinding: Vec::new(),
+            deferred_constraints: Vec::new(),
+            debug: false,
+        }
+    }
+
+    /// Enables or disables the debug mode, in which
+    /// [`prove`](Prover::prove) evaluates every constraint against the
+    /// witness before creating the proof.
+    ///
+    /// In debug mode, `prove` returns
+    /// [`R1CSError::UnsatisfiedConstraints`] with the indices of the
+    /// unsatisfied constraints instead of a proof which would fail to
+    /// verify.  The constraints are indexed in the order in which they
+    /// were added, including the two constraints which each call to
+    /// [`ConstraintSystem::multiply`] adds for its inputs, and the
+    /// randomized constraints last.
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+    }
+
+    /// Returns the indices of the constraints added so far which are
+    /// not satisfied by the witness.
+    ///
+    /// Randomized constraints are only added by
+    /// [`prove`](Prover::prove), so they are only checked there, in
+    /// debug mode.
+    pub fn unsatisfied_constraints(&self) -> Vec<usize> {
+        self.constraints
+            .iter()
+            .enumerate()
+            .filter(|(_, lc)| self.eval(lc) != Scalar::zero())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Creates commitment to a high-level variable and adds it to the
+    /// transcript.
+    ///
+    /// # Inputs
+    ///
+    /// The `v` and `v_blinding` parameters are openings to the
+    /// commitment to the external variable for the constraint
+    /// system.  Passing the opening (the value together with the
+    /// blinding factor) makes it possible to reference pre-existing
+    /// commitments in the constraint system.  All external variables
+    /// must be passed up-front, so that challenges produced by
+    /// [`ConstraintSystem::challenge_scalar`] are bound to the
+    /// external variables.
+    ///
+    /// # Returns
+    ///
+    /// Returns a pair of a Pedersen commitment (as a compressed
+    /// Ristretto point), and a [`Variable`] corresponding to it, which
+    /// can be used to form constraints.
+    pub fn commit(&mut self, v: Scalar, v_blinding: Scalar) -> (CompressedRistretto, Variable) {
+        let i = self.v.len();
+        self.v.push(v);
+        self.v_blinding.push(v_blinding);
+
+        // Add the commitment to the transcript.
+        let V = self.pc_gens.commit(v, v_blinding).compress();
+        self.transcript.commit_point(b"V", &V);
+
+        (V, Variable::Committed(i))
+    }
+
+    /// Creates a range proof that the committed variable `var` is an
+    /// `n`-bit value, on the transcript of the constraint system.
+    ///
+    /// This proves that a value is in range and satisfies the
+    /// constraints of the system without committing it twice: the
+    /// [`Verifier`](::r1cs::Verifier) checks the range proof with
+    /// [`Verifier::verify_range`](::r1cs::Verifier::verify_range) at
+    /// the same point of the transcript, against the commitment of the
+    /// same variable.  The `bp_gens` must also have a `gens_capacity`
+    /// of at least `n`.
+    ///
+    /// Returns [`R1CSError::GadgetError`] if `var` is not a committed
+    /// variable.
+    pub fn prove_range(&mut self, var: Variable, n: usize) -> Result<RangeProof, R1CSError> {
+        let i = match var {
+            Variable::Committed(i) => i,
+            _ => {
+                return Err(R1CSError::GadgetError {
+                    description: "range proofs require a committed variable".to_string(),
+                })
+            }
+        };
+
+        let opening = Opening {
+            value: self.v[i],
+            blinding: self.v_blinding[i],
+        };
+        let (proof, _) =
+            RangeProof::prove_openings(self.bp_gens, self.pc_gens, self.transcript, &[opening], n)?;
+        Ok(proof)
+    }
+
+    fn eval(&self, lc: &LinearCombination) -> Scalar {
+        lc.terms
+            .iter()
+            .map(|(var, coeff)| {
+                coeff * match var {
+                    Variable::MultiplierLeft(i) => self.a_L[*i],
+                    Variable::MultiplierRight(i) => self.a_R[*i],
+                    Variable::MultiplierOutput(i) => self.a_O[*i],
+                    Variable::Committed(i) => self.v[*i],
+                    Variable::One => Scalar::one(),
+                }
+            }).sum()
+    }
+
+    /// Calls the callbacks which add the randomized constraints, after
+    /// the first-phase variables are committed.
+    fn create_randomized_constraints(mut self) -> Result<Self, R1CSError> {
+        if self.deferred_constraints.is_empty() {
+            self.transcript.r1cs_1phase_domain_sep();
+            return Ok(self);
+        }
+
+        self.transcript.r1cs_2phase_domain_sep();
+        // The callbacks take the wrapper by mutable reference, so the
+        // prover is moved into it and back out afterwards.
+        let callbacks = mem::replace(&mut self.deferred_constraints, Vec::new());
+        let mut wrapped_self = RandomizingProver { prover: self };
+        for callback in callbacks.iter() {
+            callback(&mut wrapped_self)?;
+        }
+        Ok(wrapped_self.prover)
+    }
+
+    /// Consume this `ConstraintSystem` to produce a proof.
+    pub fn prove(mut self) -> Result<R1CSProof, R1CSError> {
+        // Commit a length _suffix_ for the number of high-level variables.
+        // We cannot do this in advance because user can commit variables one-by-one,
+        // but this suffix provides safe disambiguation because each variable
+        // is prefixed with a separate label.
+        self.transcript.r1cs_num_commitments(self.v.len() as u64);
+
+        let bp_gens = self.bp_gens;
+        let pc_gens = self.pc_gens;
+        let n1 = self.a_L.len();
+        if bp_gens.gens_capacity < n1 || bp_gens.party_capacity < 1 {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+        // We are performing a single-party circuit proof, so party index is 0.
+        let gens = bp_gens.share(0);
+
+        // Create a `TranscriptRng` from the high-level witness data
+        let mut rng = {
+            let mut builder = self.transcript.build_rng();
+
+            // Commit the blinding factors for the input wires
+            for v_b in &self.v_blinding {
+                builder = builder.commit_witness_bytes(b"v_blinding", v_b.as_bytes());
+            }
+
+            builder.finalize(&mut rand::thread_rng())
+        };
+
+        // Commit the multipliers of the first phase
+        let mut i_blinding1 = Scalar::random(&mut rng);
+        let mut o_blinding1 = Scalar::random(&mut rng);
+        let mut s_blinding1 = Scalar::random(&mut rng);
+
+        let s_L1: ScalarVec = scalar::random_vec(&mut rng, n1).into();
+        let s_R1: ScalarVec = scalar::random_vec(&mut rng, n1).into();
+
+        // A_I = <a_L, G> + <a_R, H> + i_blinding * B_blinding
+        let A_I1 = RistrettoPoint::multiscalar_mul(
+            iter::once(&i_blinding1)
+                .chain(self.a_L.iter())
+                .chain(self.a_R.iter()),
+            iter::once(&pc_gens.B_blinding)
+                .chain(gens.G(n1))
+                .chain(gens.H(n1)),
+        ).compress();
+
+        // A_O = <a_O, G> + o_blinding * B_blinding
+        let A_O1 = RistrettoPoint::multiscalar_mul(
+            iter::once(&o_blinding1).chain(self.a_O.iter()),
+            iter::once(&pc_gens.B_blinding).chain(gens.G(n1)),
+        ).compress();
+
+        // S = <s_L, G> + <s_R, H> + s_blinding * B_blinding
+        let S1 = RistrettoPoint::multiscalar_mul(
+            iter::once(&s_blinding1)
+                .chain(s_L1.iter())
+                .chain(s_R1.iter()),
+            iter::once(&pc_gens.B_blinding)
+                .chain(gens.G(n1))
+                .chain(gens.H(n1)),
+        ).compress();
+
+        self.transcript.commit_point(b"A_I1", &A_I1);
+        self.transcript.commit_point(b"A_O1", &A_O1);
+        self.transcript.commit_point(b"S1", &S1);
+
+        // Add the randomized constraints, which may allocate more
+        // multipliers
+        self = self.create_randomized_constraints()?;
+
+        if self.debug {
+            let indices = self.unsatisfied_constraints();
+            if !indices.is_empty() {
+                return Err(R1CSError::UnsatisfiedConstraints { indices });
+            }
+        }
+
+        let n = self.a_L.len();
+        let n2 = n - n1;
+        let padded_n = n.next_power_of_two();
+        if bp_gens.gens_capacity < padded_n {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        // Commit the multipliers of the second phase, which are empty
+        // if there are no randomized constraints
+        let mut i_blinding2 = Scalar::random(&mut rng);
+        let mut o_blinding2 = Scalar::random(&mut rng);
+        let mut s_blinding2 = Scalar::random(&mut rng);
+
+        let s_L2: ScalarVec = scalar::random_vec(&mut rng, n2).into();
+        let s_R2: ScalarVec = scalar::random_vec(&mut rng, n2).into();
+
+        let A_I2 = RistrettoPoint::multiscalar_mul(
+            iter::once(&i_blinding2)
+                .chain(self.a_L[n1..].iter())
+                .chain(self.a_R[n1..].iter()),
+            iter::once(&pc_gens.B_blinding)
+                .chain(gens.G(n).skip(n1))
+                .chain(gens.H(n).skip(n1)),
+        ).compress();
+
+        let A_O2 = RistrettoPoint::multiscalar_mul(
+            iter::once(&o_blinding2).chain(self.a_O[n1..].iter()),
+            iter::once(&pc_gens.B_blinding).chain(gens.G(n).skip(n1)),
+        ).compress();
+
+        let S2 = RistrettoPoint::multiscalar_mul(
+            iter::once(&s_blinding2)
+                .chain(s_L2.iter())
+                .chain(s_R2.iter()),
+            iter::once(&pc_gens.B_blinding)
+                .chain(gens.G(n).skip(n1))
+                .chain(gens.H(n).skip(n1)),
+        ).compress();
+
+        self.transcript.commit_point(b"A_I2", &A_I2);
+        self.transcript.commit_point(b"A_O2", &A_O2);
+        self.transcript.commit_point(b"S2", &S2);
+
+        let y = self.transcript.challenge_scalar(b"y");
+        let z = self.transcript.challenge_scalar(b"z");
+
+        let (wL, wR, wO, wV, _) = super::flatten(&self.constraints, &z, n, self.v.len());
+
+        let s_L: Vec<Scalar> = s_L1.iter().chain(s_L2.iter()).cloned().collect();
+        let s_R: Vec<Scalar> = s_R1.iter().chain(s_R2.iter()).cloned().collect();
+
+        let mut l_poly = util::VecPoly3::zero(n);
+        let mut r_poly = util::VecPoly3::zero(n);
+
+        let mut exp_y = Scalar::one(); // y^n starting at n=0
+        let y_inv = y.invert();
+        let exp_y_inv: Vec<Scalar> = math::powers(y_inv, padded_n).collect();
+
+        for i in 0..n {
+            // l_poly.0 = 0
+            // l_poly.1 = a_L + y^-n * (z * z^Q * W_R)
+            l_poly.1[i] = self.a_L[i] + exp_y_inv[i] * wR[i];
+            // l_poly.2 = a_O
+            l_poly.2[i] = self.a_O[i];
+            // l_poly.3 = s_L
+            l_poly.3[i] = s_L[i];
+            // r_poly.0 = (z * z^Q * W_O) - y^n
+            r_poly.0[i] = wO[i] - exp_y;
+            // r_poly.1 = y^n * a_R + (z * z^Q * W_L)
+            r_poly.1[i] = exp_y * self.a_R[i] + wL[i];
+            // r_poly.2 = 0
+            // r_poly.3 = y^n * s_R
+            r_poly.3[i] = exp_y * s_R[i];
+
+            exp_y = exp_y * y; // y^i -> y^(i+1)
+        }
+
+        let t_poly = l_poly.inner_product(&r_poly);
+
+        let t_1_blinding = Scalar::random(&mut rng);
+        let t_3_blinding = Scalar::random(&mut rng);
+        let t_4_blinding = Scalar::random(&mut rng);
+        let t_5_blinding = Scalar::random(&mut rng);
+        let t_6_blinding = Scalar::random(&mut rng);
+
+        let T_1 = pc_gens.commit(t_poly.t1, t_1_blinding).compress();
+        let T_3 = pc_gens.commit(t_poly.t3, t_3_blinding).compress();
+        let T_4 = pc_gens.commit(t_poly.t4, t_4_blinding).compress();
+        let T_5 = pc_gens.commit(t_poly.t5, t_5_blinding).compress();
+        let T_6 = pc_gens.commit(t_poly.t6, t_6_blinding).compress();
+
+        self.transcript.commit_point(b"T_1", &T_1);
+        self.transcript.commit_point(b"T_3", &T_3);
+        self.transcript.commit_point(b"T_4", &T_4);
+        self.transcript.commit_point(b"T_5", &T_5);
+        self.transcript.commit_point(b"T_6", &T_6);
+
+        let x = self.transcript.challenge_scalar(b"x");
+        // Challenge value for separating the commitments of the two
+        // phases
+        let u = self.transcript.challenge_scalar(b"u");
+
+        // t_2_blinding = <z*z^Q, W_V * v_blinding>
+        let t_2_blinding = wV
+            .iter()
+            .zip(self.v_blinding.iter())
+            .map(|(c, v_blinding)| c * v_blinding)
+            .sum();
+
+        let t_blinding_poly = util::Poly6 {
+            t0: Scalar::zero(),
+            t1: t_1_blinding,
+            t2: t_2_blinding,
+            t3: t_3_blinding,
+            t4: t_4_blinding,
+            t5: t_5_blinding,
+            t6: t_6_blinding,
+        };
+
+        let t_x = t_poly.eval(x);
+        let t_x_blinding = t_blinding_poly.eval(x);
+        let mut l_vec = l_poly.eval(x);
+        l_vec.append(&mut vec![Scalar::zero(); padded_n - n]);
+
+        let mut r_vec = r_poly.eval(x);
+        r_vec.append(&mut vec![Scalar::zero(); padded_n - n]);
+
+        // The padded multipliers have a_L = a_R = a_O = 0, so their
+        // entries of r(x) are just -y^i.
+        for i in n..padded_n {
+            r_vec[i] = -exp_y;
+            exp_y = exp_y * y; // y^i -> y^(i+1)
+        }
+
+        let i_blinding = i_blinding1 + u * i_blinding2;
+        let o_blinding = o_blinding1 + u * o_blinding2;
+        let s_blinding = s_blinding1 + u * s_blinding2;
+        let e_blinding = x * (i_blinding + x * (o_blinding + x * s_blinding));
+
+        // The commitment blindings are not needed anymore.
+        i_blinding1.clear();
+        o_blinding1.clear();
+        s_blinding1.clear();
+        i_blinding2.clear();
+        o_blinding2.clear();
+        s_blinding2.clear();
+
+        self.transcript.commit_scalar(b"t_x", &t_x);
+        self.transcript
+            .commit_scalar(b"t_x_blinding", &t_x_blinding);
+        self.transcript.commit_scalar(b"e_blinding", &e_blinding);
+
+        // Get a challenge value to combine statements for the IPP
+        let w = self.transcript.challenge_scalar(b"w");
+        let Q = w * pc_gens.B;
+
+        // The second-phase multipliers, and the padded ones, are
+        // committed with the generators scaled by u.
+        let G: Vec<RistrettoPoint> = gens
+            .G(padded_n)
+            .enumerate()
+            .map(|(i, G_i)| if i < n1 { *G_i } else { u * G_i })
+            .collect();
+        let H_factors: Vec<Scalar> = exp_y_inv
+            .iter()
+            .enumerate()
+            .map(|(i, y_inv_i)| if i < n1 { *y_inv_i } else { u * y_inv_i })
+            .collect();
+
+        let ipp_proof = InnerProductProof::create(
+            self.transcript,
+            &Q,
+            &H_factors,
+            G,
+            gens.H(padded_n).cloned().collect(),
+            l_vec,
+            r_vec,
+        );
+
+        Ok(R1CSProof {
+            A_I1,
+            A_O1,
+            S1,
+            A_I2,
+            A_O2,
+            S2,
+            T_1,
+            T_3,
+            T_4,
+            T_5,
+            T_6,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            ipp_proof,
+        })
+    }
+}
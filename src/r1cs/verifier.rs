@@ -0,0 +1,402 @@
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use merlin::Transcript;
+use std::iter;
+use std::mem;
+
+use super::{ConstraintSystem, LinearCombination, R1CSProof, RandomizedConstraintSystem, Variable};
+
+use errors::R1CSError;
+use generators::{BulletproofGens, PedersenGens};
+use math;
+use multiscalar;
+use range_proof::RangeProof;
+use transcript::TranscriptProtocol;
+
+/// A [`ConstraintSystem`] implementation for use by the verifier.
+///
+/// The verifier adds high-level variable commitments to the transcript,
+/// allocates low-level variables and creates constraints in terms of
+/// these high-level variables and low-level variables.
+///
+/// When all constraints are added, the verifier calls
+/// [`verify`](Verifier::verify) to check that the given proof
+/// satisfies them.
+pub struct Verifier<'t, 'g> {
+    transcript: &'t mut Transcript,
+    bp_gens: &'g BulletproofGens,
+    pc_gens: &'g PedersenGens,
+    /// The constraints accumulated so far.
+    constraints: Vec<LinearCombination>,
+    /// Records the number of low-level variables allocated so far.
+    num_vars: usize,
+    /// Commitments to the high-level variables.
+    V: Vec<CompressedRistretto>,
+    /// The callbacks which add the randomized constraints.
+    deferred_constraints: Vec<DeferredConstraint<'t, 'g>>,
+}
+
+/// A callback which adds randomized constraints to the verifier.
+type DeferredConstraint<'t, 'g> =
+    Box<dyn Fn(&mut RandomizingVerifier<'t, 'g>) -> Result<(), R1CSError>>;
+
+/// A [`ConstraintSystem`] implementation for the verifier's second
+/// phase, whose challenges are bound to all the first-phase variables.
+///
+/// It is passed to the callbacks given to
+/// [`ConstraintSystem::specify_randomized_constraints`].
+pub struct RandomizingVerifier<'t, 'g> {
+    verifier: Verifier<'t, 'g>,
+}
+
+impl<'t, 'g> ConstraintSystem for Verifier<'t, 'g> {
+    type RandomizedCS = RandomizingVerifier<'t, 'g>;
+
+    fn multiply(
+        &mut self,
+        mut left: LinearCombination,
+        mut right: LinearCombination,
+    ) -> (Variable, Variable, Variable) {
+        let var = self.num_vars;
+        self.num_vars += 1;
+
+        // Create variables for l,r,o
+        let l_var = Variable::MultiplierLeft(var);
+        let r_var = Variable::MultiplierRight(var);
+        let o_var = Variable::MultiplierOutput(var);
+
+        // Constrain l,r,o:
+        left.terms.push((l_var, -Scalar::one()));
+        right.terms.push((r_var, -Scalar::one()));
+        self.constrain(left);
+        self.constrain(right);
+
+        (l_var, r_var, o_var)
+    }
+
+    fn allocate_multiplier(
+        &mut self,
+        _: Option<(Scalar, Scalar)>,
+    ) -> Result<(Variable, Variable, Variable), R1CSError> {
+        let var = self.num_vars;
+        self.num_vars += 1;
+
+        // Create variables for l,r,o
+        let l_var = Variable::MultiplierLeft(var);
+        let r_var = Variable::MultiplierRight(var);
+        let o_var = Variable::MultiplierOutput(var);
+
+        Ok((l_var, r_var, o_var))
+    }
+
+    fn constrain(&mut self, lc: LinearCombination) {
+        // TODO: check that the linear combinations are valid
+        // (e.g. that variables are valid, that the linear combination
+        // evals to 0 for prover, etc).
+        self.constraints.push(lc);
+    }
+
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        self.transcript.challenge_scalar(label)
+    }
+
+    fn specify_randomized_constraints<F>(&mut self, callback: F) -> Result<(), R1CSError>
+    where
+        F: 'static + Fn(&mut Self::RandomizedCS) -> Result<(), R1CSError>,
+    {
+        self.deferred_constraints.push(Box::new(callback));
+        Ok(())
+    }
+}
+
+impl<'t, 'g> ConstraintSystem for RandomizingVerifier<'t, 'g> {
+    type RandomizedCS = Self;
+
+    fn multiply(
+        &mut self,
+        left: LinearCombination,
+        right: LinearCombination,
+    ) -> (Variable, Variable, Variable) {
+        self.verifier.multiply(left, right)
+    }
+
+    fn allocate_multiplier(
+        &mut self,
+        input_assignments: Option<(Scalar, Scalar)>,
+    ) -> Result<(Variable, Variable, Variable), R1CSError> {
+        self.verifier.allocate_multiplier(input_assignments)
+    }
+
+    fn constrain(&mut self, lc: LinearCombination) {
+        self.verifier.constrain(lc)
+    }
+
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        self.verifier.transcript.challenge_scalar(label)
+    }
+
+    fn specify_randomized_constraints<F>(&mut self, callback: F) -> Result<(), R1CSError>
+    where
+        F: 'static + Fn(&mut Self::RandomizedCS) -> Result<(), R1CSError>,
+    {
+        callback(self)
+    }
+}
+
+impl<'t, 'g> RandomizedConstraintSystem for RandomizingVerifier<'t, 'g> {}
+
+impl<'t, 'g> Verifier<'t, 'g> {
+    /// Construct an empty constraint system.
+    ///
+    /// # Inputs
+    ///
+    /// The `bp_gens` and `pc_gens` are generators for Bulletproofs
+    /// and for the Pedersen commitments, respectively.  The
+    /// [`BulletproofGens`] should have `gens_capacity` greater than
+    /// the number of multiplication constraints that will eventually
+    /// be added into the constraint system, rounded up to a power of
+    /// two.
+    ///
+    /// The `transcript` parameter is a Merlin proof transcript.  The
+    /// `Verifier` holds onto the `&mut Transcript` until it consumes
+    /// itself during [`Verifier::verify`], releasing its borrow of the
+    /// transcript.  This ensures that the transcript cannot be
+    /// altered except by the `Verifier` before verification is
+    /// complete.
+    pub fn new(
+        bp_gens: &'g BulletproofGens,
+        pc_gens: &'g PedersenGens,
+        transcript: &'t mut Transcript,
+    ) -> Self {
+        transcript.r1cs_domain_sep();
+
+        Verifier {
+            transcript,
+            bp_gens,
+            pc_gens,
+            constraints: Vec::new(),
+            num_vars: 0,
+            V: Vec::new(),
+            deferred_constraints: Vec::new(),
+        }
+    }
+
+    /// Creates a variable for a commitment to a high-level variable
+    /// and adds the commitment to the transcript.
+    ///
+    /// The commitments must be added in the same order as the prover
+    /// created them.
+    ///
+    /// # Returns
+    ///
+    /// Returns a [`Variable`] corresponding to the commitment, which
+    /// can be used to form constraints.
+    pub fn commit(&mut self, commitment: CompressedRistretto) -> Variable {
+        let i = self.V.len();
+        self.V.push(commitment);
+
+        // Add the commitment to the transcript.
+        self.transcript.commit_point(b"V", &commitment);
+
+        Variable::Committed(i)
+    }
+
+    /// Verifies a range proof created by
+    /// [`Prover::prove_range`](::r1cs::Prover::prove_range) that the
+    /// committed variable `var` is an `n`-bit value.
+    ///
+    /// The range proof must be verified at the same point of the
+    /// transcript as the prover created it.
+    ///
+    /// Returns [`R1CSError::GadgetError`] if `var` is not a committed
+    /// variable.
+    pub fn verify_range(
+        &mut self,
+        proof: &RangeProof,
+        var: Variable,
+        n: usize,
+    ) -> Result<(), R1CSError> {
+        let V = match var {
+            Variable::Committed(i) => self.V[i],
+            _ => {
+                return Err(R1CSError::GadgetError {
+                    description: "range proofs require a committed variable".to_string(),
+                })
+            }
+        };
+        proof.verify_single(self.bp_gens, self.pc_gens, self.transcript, &V, n)?;
+        Ok(())
+    }
+
+    /// Calls the callbacks which add the randomized constraints, after
+    /// the first-phase variables are committed.
+    fn create_randomized_constraints(mut self) -> Result<Self, R1CSError> {
+        if self.deferred_constraints.is_empty() {
+            self.transcript.r1cs_1phase_domain_sep();
+            return Ok(self);
+        }
+
+        self.transcript.r1cs_2phase_domain_sep();
+        // The callbacks take the wrapper by mutable reference, so the
+        // verifier is moved into it and back out afterwards.
+        let callbacks = mem::replace(&mut self.deferred_constraints, Vec::new());
+        let mut wrapped_self = RandomizingVerifier { verifier: self };
+        for callback in callbacks.iter() {
+            callback(&mut wrapped_self)?;
+        }
+        Ok(wrapped_self.verifier)
+    }
+
+    /// Consume this `ConstraintSystem` and check the proof against
+    /// its constraints.
+    pub fn verify(mut self, proof: &R1CSProof) -> Result<(), R1CSError> {
+        // Commit a length _suffix_ for the number of high-level variables.
+        // We cannot do this in advance because user can commit variables one-by-one,
+        // but this suffix provides safe disambiguation because each variable
+        // is prefixed with a separate label.
+        self.transcript.r1cs_num_commitments(self.V.len() as u64);
+
+        let n1 = self.num_vars;
+        self.transcript.commit_point(b"A_I1", &proof.A_I1);
+        self.transcript.commit_point(b"A_O1", &proof.A_O1);
+        self.transcript.commit_point(b"S1", &proof.S1);
+
+        // Add the randomized constraints, which may allocate more
+        // multipliers
+        self = self.create_randomized_constraints()?;
+
+        let n = self.num_vars;
+        let padded_n = n.next_power_of_two();
+        let pad = padded_n - n;
+        if self.bp_gens.gens_capacity < padded_n || self.bp_gens.party_capacity < 1 {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+        // We are performing a single-party circuit proof, so party index is 0.
+        let gens = self.bp_gens.share(0);
+
+        self.transcript.commit_point(b"A_I2", &proof.A_I2);
+        self.transcript.commit_point(b"A_O2", &proof.A_O2);
+        self.transcript.commit_point(b"S2", &proof.S2);
+
+        let y = self.transcript.challenge_scalar(b"y");
+        let z = self.transcript.challenge_scalar(b"z");
+
+        self.transcript.commit_point(b"T_1", &proof.T_1);
+        self.transcript.commit_point(b"T_3", &proof.T_3);
+        self.transcript.commit_point(b"T_4", &proof.T_4);
+        self.transcript.commit_point(b"T_5", &proof.T_5);
+        self.transcript.commit_point(b"T_6", &proof.T_6);
+
+        let x = self.transcript.challenge_scalar(b"x");
+        let u = self.transcript.challenge_scalar(b"u");
+
+        self.transcript.commit_scalar(b"t_x", &proof.t_x);
+        self.transcript
+            .commit_scalar(b"t_x_blinding", &proof.t_x_blinding);
+        self.transcript
+            .commit_scalar(b"e_blinding", &proof.e_blinding);
+
+        let w = self.transcript.challenge_scalar(b"w");
+
+        let (wL, wR, wO, wV, wc) = super::flatten(&self.constraints, &z, n, self.V.len());
+
+        // Get IPP variables
+        let (u_sq, u_inv_sq, s) = proof
+            .ipp_proof
+            .verification_scalars(padded_n, self.transcript)
+            .map_err(|_| R1CSError::VerificationError)?;
+
+        let a = proof.ipp_proof.final_scalars().0;
+        let b = proof.ipp_proof.final_scalars().1;
+
+        let y_inv = y.invert();
+        let y_inv_vec: Vec<Scalar> = math::powers(y_inv, padded_n).collect();
+        let yneg_wR: Vec<Scalar> = wR
+            .into_iter()
+            .zip(y_inv_vec.iter())
+            .map(|(wRi, exp_y_inv)| wRi * exp_y_inv)
+            .chain(iter::repeat(Scalar::zero()).take(pad))
+            .collect();
+
+        let delta = math::inner_product(&yneg_wR[0..n], &wL);
+
+        // Challenge value for batching the statements to be verified,
+        // derived from a copy of the transcript after the whole proof.
+        let r = self.transcript.clone().challenge_scalar(b"r");
+
+        let xx = x * x;
+        let rxx = r * xx;
+        let xxx = x * xx;
+
+        // Decompose the final check into the scalars for each point
+        let T_scalars = [r * x, rxx * x, rxx * xx, rxx * xxx, rxx * xx * xx];
+        let T_points = [proof.T_1, proof.T_3, proof.T_4, proof.T_5, proof.T_6];
+
+        // The second-phase multipliers, and the padded ones, are
+        // committed with the generators scaled by u.
+        let u_for_g = iter::repeat(Scalar::one())
+            .take(n1)
+            .chain(iter::repeat(u).take(padded_n - n1));
+        let u_for_h = u_for_g.clone();
+
+        // The padded multipliers have zero weights in wL and wO.
+        let g_scalars: Vec<Scalar> = yneg_wR
+            .iter()
+            .zip(s.iter())
+            .zip(u_for_g)
+            .map(|((yneg_wRi, s_i), u_or_1)| u_or_1 * (x * yneg_wRi - a * s_i))
+            .collect();
+
+        let h_scalars: Vec<Scalar> = y_inv_vec
+            .iter()
+            .zip(s.iter().rev())
+            .zip(wL.into_iter().chain(iter::repeat(Scalar::zero()).take(pad)))
+            .zip(wO.into_iter().chain(iter::repeat(Scalar::zero()).take(pad)))
+            .zip(u_for_h)
+            .map(|((((y_inv_i, s_i_inv), wLi), wOi), u_or_1)| {
+                u_or_1 * (y_inv_i * (x * wLi + wOi - b * s_i_inv) - Scalar::one())
+            }).collect();
+
+        let mega_check = multiscalar::optional_multiscalar_mul(
+            iter::once(x) // A_I1
+                .chain(iter::once(xx)) // A_O1
+                .chain(iter::once(xxx)) // S1
+                .chain(iter::once(u * x)) // A_I2
+                .chain(iter::once(u * xx)) // A_O2
+                .chain(iter::once(u * xxx)) // S2
+                .chain(wV.iter().map(|wVi| wVi * rxx)) // V
+                .chain(T_scalars.iter().cloned()) // T_points
+                .chain(iter::once(
+                    w * (proof.t_x - a * b) + r * (xx * (wc + delta) - proof.t_x),
+                )) // B
+                .chain(iter::once(-proof.e_blinding - r * proof.t_x_blinding)) // B_blinding
+                .chain(g_scalars) // G
+                .chain(h_scalars) // H
+                .chain(u_sq.iter().cloned()) // ipp_proof.L_vec
+                .chain(u_inv_sq.iter().cloned()), // ipp_proof.R_vec
+            iter::once(proof.A_I1.decompress())
+                .chain(iter::once(proof.A_O1.decompress()))
+                .chain(iter::once(proof.S1.decompress()))
+                .chain(iter::once(proof.A_I2.decompress()))
+                .chain(iter::once(proof.A_O2.decompress()))
+                .chain(iter::once(proof.S2.decompress()))
+                .chain(self.V.iter().map(|V_i| V_i.decompress()))
+                .chain(T_points.iter().map(|T_i| T_i.decompress()))
+                .chain(iter::once(Some(self.pc_gens.B)))
+                .chain(iter::once(Some(self.pc_gens.B_blinding)))
+                .chain(gens.G(padded_n).map(|&G_i| Some(G_i)))
+                .chain(gens.H(padded_n).map(|&H_i| Some(H_i)))
+                .chain(proof.ipp_proof.lr_points().map(|(L, _)| L.decompress()))
+                .chain(proof.ipp_proof.lr_points().map(|(_, R)| R.decompress())),
+        ).ok_or_else(|| R1CSError::VerificationError)?;
+
+        if !mega_check.is_identity() {
+            return Err(R1CSError::VerificationError);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,346 @@
+//! The `async_session` module contains futures which drive the
+//! [`dealer`](::aggregation::dealer) and [`party`](::aggregation::party)
+//! state machines over asynchronous channels.
+//!
+//! Each party is connected to the dealer by a channel, made of a
+//! `Sink` of the messages it sends and a `Stream` of the messages it
+//! receives, such as the two halves of a framed network connection.
+//! The party on the \\(j\\)-th channel of the dealer is assigned the
+//! position \\(j\\).  An [`AsyncDealer`] sends the positions and the
+//! two challenges, and collects the commitments and the proof shares
+//! of every party, and an [`AsyncParty`] answers each message of the
+//! dealer, so that the aggregation runs without any round logic in
+//! the caller.
+//!
+//! This module is only available with the `futures` feature.
+
+use futures::future::{self, Future};
+use futures::{Sink, Stream};
+
+use errors::MPCError;
+use range_proof::dealer::DealerAwaitingBitCommitments;
+use range_proof::messages::*;
+use range_proof::party::PartyAwaitingPosition;
+use range_proof::RangeProof;
+
+/// An error of an asynchronous session.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SessionError<E> {
+    /// The protocol failed, for instance because of a malformed
+    /// proof share.
+    Protocol(MPCError),
+    /// A channel returned an error.
+    Channel(E),
+    /// A channel closed before the end of the protocol.  On the dealer
+    /// side, `party` is the index of the channel; on the party side,
+    /// whose only channel leads to the dealer, it is `None`.
+    Disconnected {
+        /// The index of the party whose channel closed
+        party: Option<usize>,
+    },
+    /// A message arrived out of order, with the same convention for
+    /// `party` as [`SessionError::Disconnected`].
+    UnexpectedMessage {
+        /// The index of the party which sent the message
+        party: Option<usize>,
+    },
+}
+
+impl<E> From<MPCError> for SessionError<E> {
+    fn from(e: MPCError) -> SessionError<E> {
+        SessionError::Protocol(e)
+    }
+}
+
+/// A future returned by the sessions.
+pub type SessionFuture<'a, T, E> = Box<dyn Future<Item = T, Error = SessionError<E>> + 'a>;
+
+/// Drives a dealer over one channel to each party.
+pub struct AsyncDealer<S, R> {
+    channels: Vec<(S, R)>,
+}
+
+impl<S, R, E> AsyncDealer<S, R>
+where
+    S: Sink<SinkItem = DealerMessage, SinkError = E>,
+    R: Stream<Item = PartyMessage, Error = E>,
+{
+    /// Creates a session with the `channels` to the parties, in the
+    /// order of their positions.
+    pub fn new(channels: Vec<(S, R)>) -> AsyncDealer<S, R> {
+        AsyncDealer { channels }
+    }
+
+    /// Runs the protocol with the `dealer`, resolving to the
+    /// aggregated proof once every party sent its proof share.
+    ///
+    /// The number of channels must be the number of parties of the
+    /// dealer.
+    pub fn run<'a>(
+        self,
+        dealer: DealerAwaitingBitCommitments<'a, 'a>,
+    ) -> SessionFuture<'a, RangeProof, E>
+    where
+        S: 'a,
+        R: 'a,
+        E: 'a,
+    {
+        let m = self.channels.len();
+        let positions = (0..m).map(DealerMessage::Position).collect();
+
+        let session = broadcast(self.channels, positions)
+            .and_then(|channels| collect(channels, PartyMessage::bit_commitment))
+            .and_then(move |(channels, bit_commitments)| {
+                let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
+                let challenges = vec![DealerMessage::BitChallenge(bit_challenge); m];
+                Ok((channels, dealer, challenges))
+            }).and_then(|(channels, dealer, challenges)| {
+                broadcast(channels, challenges).map(move |channels| (channels, dealer))
+            }).and_then(|(channels, dealer)| {
+                collect(channels, PartyMessage::poly_commitment).map(move |round| (round, dealer))
+            }).and_then(move |((channels, poly_commitments), dealer)| {
+                let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
+                let challenges = vec![DealerMessage::PolyChallenge(poly_challenge); m];
+                Ok((channels, dealer, challenges))
+            }).and_then(|(channels, dealer, challenges)| {
+                broadcast(channels, challenges).map(move |channels| (channels, dealer))
+            }).and_then(|(channels, dealer)| {
+                collect(channels, PartyMessage::proof_share)
+                    .map(move |(_, proof_shares)| (dealer, proof_shares))
+            }).and_then(|(dealer, proof_shares)| {
+                dealer
+                    .receive_shares(&proof_shares)
+                    .map_err(SessionError::Protocol)
+            });
+        Box::new(session)
+    }
+}
+
+/// Drives a party over its channel to the dealer.
+pub struct AsyncParty<S, R> {
+    sink: S,
+    stream: R,
+}
+
+impl<S, R, E> AsyncParty<S, R>
+where
+    S: Sink<SinkItem = PartyMessage, SinkError = E>,
+    R: Stream<Item = DealerMessage, Error = E>,
+{
+    /// Creates a session with the `sink` and `stream` of the channel
+    /// to the dealer.
+    pub fn new(sink: S, stream: R) -> AsyncParty<S, R> {
+        AsyncParty { sink, stream }
+    }
+
+    /// Runs the protocol with the `party`, resolving once the party
+    /// sent its proof share.
+    pub fn run<'a>(self, party: PartyAwaitingPosition<'a>) -> SessionFuture<'a, (), E>
+    where
+        S: 'a,
+        R: 'a,
+        E: 'a,
+    {
+        let sink = self.sink;
+        let session = receive(self.stream, DealerMessage::position)
+            .and_then(move |(stream, j)| {
+                let (party, bit_commitment) = party.assign_position(j)?;
+                Ok((stream, party, bit_commitment))
+            }).and_then(move |(stream, party, bit_commitment)| {
+                sink.send(PartyMessage::BitCommitment(bit_commitment))
+                    .map_err(SessionError::Channel)
+                    .map(move |sink| (sink, stream, party))
+            }).and_then(|(sink, stream, party)| {
+                receive(stream, DealerMessage::bit_challenge).map(move |round| (sink, round, party))
+            }).and_then(|(sink, (stream, bit_challenge), party)| {
+                let (party, poly_commitment) = party.apply_challenge(&bit_challenge);
+                sink.send(PartyMessage::PolyCommitment(poly_commitment))
+                    .map_err(SessionError::Channel)
+                    .map(move |sink| (sink, stream, party))
+            }).and_then(|(sink, stream, party)| {
+                receive(stream, DealerMessage::poly_challenge)
+                    .map(move |round| (sink, round, party))
+            }).and_then(|(sink, (_, poly_challenge), party)| {
+                party
+                    .apply_challenge(&poly_challenge)
+                    .map_err(SessionError::Protocol)
+                    .map(|proof_share| (sink, proof_share))
+            }).and_then(|(sink, proof_share)| {
+                sink.send(PartyMessage::ProofShare(proof_share))
+                    .map_err(SessionError::Channel)
+                    .map(|_| ())
+            });
+        Box::new(session)
+    }
+}
+
+/// Sends the `j`-th message to the `j`-th party.
+fn broadcast<'a, S, R, E>(
+    channels: Vec<(S, R)>,
+    messages: Vec<DealerMessage>,
+) -> SessionFuture<'a, Vec<(S, R)>, E>
+where
+    S: Sink<SinkItem = DealerMessage, SinkError = E> + 'a,
+    R: 'a,
+    E: 'a,
+{
+    let sends = channels
+        .into_iter()
+        .zip(messages)
+        .map(|((sink, stream), message)| {
+            sink.send(message)
+                .map_err(SessionError::Channel)
+                .map(move |sink| (sink, stream))
+        });
+    Box::new(future::join_all(sends))
+}
+
+/// The channels to the parties, and the messages received from them
+/// in a round.
+type Round<S, R, T> = (Vec<(S, R)>, Vec<T>);
+
+/// Receives one message from every party, and extracts its content
+/// with `extract`, which returns `None` for a message of another
+/// round.
+fn collect<'a, S, R, E, T>(
+    channels: Vec<(S, R)>,
+    extract: fn(PartyMessage) -> Option<T>,
+) -> SessionFuture<'a, Round<S, R, T>, E>
+where
+    S: 'a,
+    R: Stream<Item = PartyMessage, Error = E> + 'a,
+    E: 'a,
+    T: 'a,
+{
+    let receives = channels
+        .into_iter()
+        .enumerate()
+        .map(move |(j, (sink, stream))| {
+            stream
+                .into_future()
+                .map_err(|(e, _)| SessionError::Channel(e))
+                .and_then(move |(message, stream)| {
+                    let message = message.ok_or(SessionError::Disconnected { party: Some(j) })?;
+                    let content = extract(message)
+                        .ok_or(SessionError::UnexpectedMessage { party: Some(j) })?;
+                    Ok(((sink, stream), content))
+                })
+        });
+    Box::new(future::join_all(receives).map(|round| round.into_iter().unzip()))
+}
+
+/// Receives one message from the dealer, and extracts its content
+/// with `extract`, which returns `None` for a message of another
+/// round.
+fn receive<'a, R, E, T>(
+    stream: R,
+    extract: fn(DealerMessage) -> Option<T>,
+) -> SessionFuture<'a, (R, T), E>
+where
+    R: Stream<Item = DealerMessage, Error = E> + 'a,
+    E: 'a,
+    T: 'a,
+{
+    let received = stream
+        .into_future()
+        .map_err(|(e, _)| SessionError::Channel(e))
+        .and_then(move |(message, stream)| {
+            let message = message.ok_or(SessionError::Disconnected { party: None })?;
+            let content =
+                extract(message).ok_or(SessionError::UnexpectedMessage { party: None })?;
+            Ok((stream, content))
+        });
+    Box::new(received)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+    use futures::sync::mpsc;
+    use merlin::Transcript;
+    use rand;
+
+    use generators::{BulletproofGens, PedersenGens};
+    use range_proof::dealer::Dealer;
+    use range_proof::party::Party;
+
+    #[test]
+    fn aggregation_over_async_channels() {
+        let (n, m) = (16, 4);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        let mut dealer_channels = Vec::new();
+        let mut party_sessions = Vec::new();
+        let mut value_commitments = Vec::new();
+        for v in 0..m as u64 {
+            let (to_party, from_dealer) = mpsc::unbounded();
+            let (to_dealer, from_party) = mpsc::unbounded();
+            dealer_channels.push((to_party.sink_map_err(|_| ()), from_party));
+
+            let blinding = Scalar::random(&mut rng);
+            value_commitments.push(pc_gens.commit(Scalar::from(v), blinding).compress());
+            let party = Party::new(&bp_gens, &pc_gens, v, blinding, n).unwrap();
+            let session = AsyncParty::new(to_dealer.sink_map_err(|_| ()), from_dealer);
+            party_sessions.push(session.run(party));
+        }
+
+        let mut transcript = Transcript::new(b"AsyncSessionTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        let dealer_session = AsyncDealer::new(dealer_channels).run(dealer);
+
+        let (proof, _) = dealer_session
+            .join(future::join_all(party_sessions))
+            .wait()
+            .unwrap();
+
+        let mut transcript = Transcript::new(b"AsyncSessionTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn dealer_detects_disconnected_party() {
+        let (n, m) = (16, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        let mut dealer_channels = Vec::new();
+        let mut party_sessions = Vec::new();
+        let mut hung_up = Vec::new();
+        for j in 0..m {
+            let (to_party, from_dealer) = mpsc::unbounded();
+            let (to_dealer, from_party) = mpsc::unbounded();
+            dealer_channels.push((to_party.sink_map_err(|_| ()), from_party));
+            if j == 0 {
+                let party = Party::new(&bp_gens, &pc_gens, 1, Scalar::one(), n).unwrap();
+                let session = AsyncParty::new(to_dealer.sink_map_err(|_| ()), from_dealer);
+                party_sessions.push(session.run(party));
+            } else {
+                // Party 1 receives its position, but hangs up before
+                // sending its bit commitment
+                hung_up.push(from_dealer);
+            }
+        }
+
+        let mut transcript = Transcript::new(b"AsyncSessionTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        let result = AsyncDealer::new(dealer_channels)
+            .run(dealer)
+            .select2(future::join_all(party_sessions))
+            .wait();
+
+        match result {
+            Err(future::Either::A((e, _))) => {
+                assert_eq!(e, SessionError::Disconnected { party: Some(1) })
+            }
+            _ => panic!("The dealer did not detect the disconnected party"),
+        }
+    }
+}
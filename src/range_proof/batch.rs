@@ -0,0 +1,568 @@
+//! The `batch` module contains the [`BatchVerifier`], which defers
+//! the verification of range proofs so that many proofs can be
+//! checked with a single multiscalar multiplication.
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use merlin::Transcript;
+use rand;
+
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use multiscalar;
+use range_proof::{RangeProof, RangeProofHeader, VerificationTerms};
+
+/// A queued proof together with the statement it proves.
+struct BatchEntry {
+    proof: RangeProof,
+    transcript: Transcript,
+    value_commitments: Vec<CompressedRistretto>,
+    n: usize,
+}
+
+/// Accumulates range proofs as they arrive and verifies all of them
+/// at once.
+///
+/// Each proof's verification equation is multiplied by a random
+/// weight and the equations are summed, so that all proofs are
+/// checked by a single multiscalar multiplication in which the
+/// generators shared between proofs appear only once.  If the
+/// combined check fails, at least one of the proofs is invalid.
+///
+/// # Mixed Sizes
+///
+/// The queued proofs may have different bitsizes and aggregation
+/// sizes, for instance single 32-bit proofs together with aggregated
+/// 64-bit proofs.  Each proof only uses the first \\(n\\) generators
+/// of its first \\(m\\) parties, so its scalars are added into the
+/// matching prefix of the shared generator slices, and each proof
+/// replays its own transcript.  The `bp_gens` must be large enough
+/// for the largest bitsize and aggregation size in the batch.
+///
+/// Proofs in the encoding from
+/// [`RangeProof::to_bytes_with_header`](::RangeProof::to_bytes_with_header)
+/// can be queued with [`BatchVerifier::add_with_header`], which takes
+/// the bitsize from the header.
+///
+/// # Example
+/// ```
+/// extern crate rand;
+/// use rand::thread_rng;
+///
+/// extern crate curve25519_dalek;
+/// use curve25519_dalek::scalar::Scalar;
+///
+/// extern crate merlin;
+/// use merlin::Transcript;
+///
+/// extern crate bulletproofs;
+/// use bulletproofs::{BatchVerifier, BulletproofGens, PedersenGens, RangeProof};
+///
+/// # fn main() {
+/// let pc_gens = PedersenGens::default();
+/// let bp_gens = BulletproofGens::new(64, 1);
+/// let mut batch = BatchVerifier::new(&bp_gens, &pc_gens);
+///
+/// for v in 0..4u64 {
+///     let blinding = Scalar::random(&mut thread_rng());
+///     let (proof, committed_value) = RangeProof::prove_single(
+///         &bp_gens,
+///         &pc_gens,
+///         &mut Transcript::new(b"doctest example"),
+///         v,
+///         &blinding,
+///         32,
+///     ).expect("A real program could handle errors");
+///
+///     batch.add(proof, Transcript::new(b"doctest example"), vec![committed_value], 32);
+/// }
+///
+/// assert!(batch.verify_all().is_ok());
+/// # }
+/// ```
+pub struct BatchVerifier<'g> {
+    bp_gens: &'g BulletproofGens,
+    pc_gens: &'g PedersenGens,
+    entries: Vec<BatchEntry>,
+}
+
+impl<'g> BatchVerifier<'g> {
+    /// Creates an empty batch verifier using the given generators.
+    pub fn new(bp_gens: &'g BulletproofGens, pc_gens: &'g PedersenGens) -> Self {
+        BatchVerifier {
+            bp_gens,
+            pc_gens,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Queues a `proof` that the values committed to by
+    /// `value_commitments` are in the range \\([0, 2^n)\\).
+    ///
+    /// The `transcript` must have the same initial state as the one
+    /// passed to the prover, and is used when the batch is verified.
+    pub fn add(
+        &mut self,
+        proof: RangeProof,
+        transcript: Transcript,
+        value_commitments: Vec<CompressedRistretto>,
+        n: usize,
+    ) {
+        self.entries.push(BatchEntry {
+            proof,
+            transcript,
+            value_commitments,
+            n,
+        });
+    }
+
+    /// Parses a proof encoded with a [`RangeProofHeader`] and queues
+    /// it, using the bitsize given by the header.
+    ///
+    /// Returns an error if the proof cannot be parsed, or if the
+    /// number of `value_commitments`, padded to a power of two, does
+    /// not match the aggregation size given by the header.
+    pub fn add_with_header(
+        &mut self,
+        proof_bytes: &[u8],
+        transcript: Transcript,
+        value_commitments: Vec<CompressedRistretto>,
+    ) -> Result<RangeProofHeader, ProofError> {
+        let (proof, header) = RangeProof::from_bytes_with_header(proof_bytes)?;
+        if value_commitments.is_empty() || value_commitments.len().next_power_of_two() != header.m()
+        {
+            return Err(ProofError::ProofSizeMismatch);
+        }
+        self.add(proof, transcript, value_commitments, header.n());
+        Ok(header)
+    }
+
+    /// Returns the number of queued proofs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no proofs are queued.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Verifies all queued proofs with one combined check.
+    ///
+    /// Returns `Ok(())` if all proofs are valid.  An empty batch is
+    /// trivially valid.
+    pub fn verify_all(self) -> Result<(), ProofError> {
+        check_batch(self.bp_gens, self.pc_gens, &self.entries)
+    }
+
+    /// Verifies all queued proofs on multiple threads.
+    ///
+    /// The queued proofs are split into chunks of
+    /// `options.chunk_size` consecutive proofs, and each chunk is
+    /// verified with one combined check.  With `options.max_threads`
+    /// set, the chunks are verified on a pool of that many threads,
+    /// built for this call, and otherwise on the current rayon thread
+    /// pool.  This is the global pool, unless the function is called
+    /// inside [`ThreadPool::install`](::rayon::ThreadPool::install),
+    /// which avoids building a pool per call.
+    ///
+    /// With [`FailureMode::FailFast`], the remaining chunks are
+    /// abandoned as soon as one chunk fails, and the error is
+    /// [`ProofError::VerificationError`].  With
+    /// [`FailureMode::FindAll`], the proofs in every failed chunk are
+    /// verified individually, and the error is a
+    /// [`ProofError::BatchVerificationError`] listing the indexes (in
+    /// the order the proofs were added) of all invalid proofs.
+    /// Returns [`ProofError::ThreadPoolError`] if the pool of
+    /// `options.max_threads` threads could not be built.
+    ///
+    /// This function is only available with the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn verify_all_parallel(self, options: &BatchOptions) -> Result<(), ProofError> {
+        use rayon::prelude::*;
+        use rayon::ThreadPoolBuilder;
+        use std::slice;
+
+        let (bp_gens, pc_gens) = (self.bp_gens, self.pc_gens);
+        let entries = &self.entries;
+        let chunk_size = options.chunk_size.max(1);
+
+        let verify = || match options.failure_mode {
+            FailureMode::FailFast => entries
+                .par_chunks(chunk_size)
+                .map(|chunk| check_batch(bp_gens, pc_gens, chunk))
+                .find_any(|result| result.is_err())
+                .unwrap_or(Ok(())),
+            FailureMode::FindAll => {
+                let mut bad_proofs: Vec<usize> = entries
+                    .par_chunks(chunk_size)
+                    .enumerate()
+                    .flat_map(|(c, chunk)| {
+                        if check_batch(bp_gens, pc_gens, chunk).is_ok() {
+                            return Vec::new();
+                        }
+                        chunk
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, entry)| {
+                                check_batch(bp_gens, pc_gens, slice::from_ref(*entry)).is_err()
+                            }).map(|(i, _)| c * chunk_size + i)
+                            .collect()
+                    }).collect();
+                bad_proofs.sort();
+
+                if bad_proofs.is_empty() {
+                    Ok(())
+                } else {
+                    Err(ProofError::BatchVerificationError { bad_proofs })
+                }
+            }
+        };
+
+        match options.max_threads {
+            Some(max_threads) => ThreadPoolBuilder::new()
+                .num_threads(max_threads)
+                .build()
+                .map_err(|_| ProofError::ThreadPoolError)?
+                .install(verify),
+            None => verify(),
+        }
+    }
+}
+
+/// Verifies the given queued proofs with one combined check.
+fn check_batch(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    entries: &[BatchEntry],
+) -> Result<(), ProofError> {
+    let mut rng = rand::thread_rng();
+    let mut batch = BatchTerms::new();
+
+    for entry in entries.iter() {
+        let terms = entry.proof.verification_terms(
+            bp_gens,
+            &mut entry.transcript.clone(),
+            &entry.value_commitments,
+            entry.n,
+        )?;
+
+        // Random weight for this proof's verification equation
+        batch.add_terms(&terms, Scalar::random(&mut rng));
+    }
+
+    batch.verify(bp_gens, pc_gens)
+}
+
+/// A weighted sum of verification equations, which is checked with a
+/// single multiscalar multiplication.
+///
+/// Besides the terms of range proofs, other equations over the
+/// Pedersen generators and arbitrary points can be added, so that
+/// proofs composed with range proofs are checked by the same
+/// multiplication.
+pub(crate) struct BatchTerms {
+    /// Scalar for the Pedersen generator \\(B\\).
+    pub(crate) B_scalar: Scalar,
+    /// Scalar for the Pedersen generator \\(\tilde{B}\\).
+    pub(crate) B_blinding_scalar: Scalar,
+    /// Scalars for the generators of each party, indexed as [j][i]
+    g_scalars: Vec<Vec<Scalar>>,
+    h_scalars: Vec<Vec<Scalar>>,
+    dynamic_scalars: Vec<Scalar>,
+    dynamic_points: Vec<CompressedRistretto>,
+}
+
+impl BatchTerms {
+    /// Creates an empty sum, which is trivially the identity.
+    pub(crate) fn new() -> BatchTerms {
+        BatchTerms {
+            B_scalar: Scalar::zero(),
+            B_blinding_scalar: Scalar::zero(),
+            g_scalars: Vec::new(),
+            h_scalars: Vec::new(),
+            dynamic_scalars: Vec::new(),
+            dynamic_points: Vec::new(),
+        }
+    }
+
+    /// Adds the verification equation of a range proof, multiplied
+    /// by `weight`.
+    pub(crate) fn add_terms(&mut self, terms: &VerificationTerms, weight: Scalar) {
+        let (n, m) = (terms.n, terms.m);
+
+        self.B_scalar += weight * terms.B_scalar;
+        self.B_blinding_scalar += weight * terms.B_blinding_scalar;
+
+        if self.g_scalars.len() < m {
+            self.g_scalars.resize(m, Vec::new());
+            self.h_scalars.resize(m, Vec::new());
+        }
+        for j in 0..m {
+            if self.g_scalars[j].len() < n {
+                self.g_scalars[j].resize(n, Scalar::zero());
+                self.h_scalars[j].resize(n, Scalar::zero());
+            }
+            for i in 0..n {
+                self.g_scalars[j][i] += weight * terms.g_scalars[j * n + i];
+                self.h_scalars[j][i] += weight * terms.h_scalars[j * n + i];
+            }
+        }
+
+        self.dynamic_scalars
+            .extend(terms.dynamic_scalars.iter().map(|s| weight * s));
+        self.dynamic_points.extend(terms.dynamic_points.iter());
+    }
+
+    /// Adds the term `scalar * point`.
+    pub(crate) fn add_point(&mut self, scalar: Scalar, point: CompressedRistretto) {
+        self.dynamic_scalars.push(scalar);
+        self.dynamic_points.push(point);
+    }
+
+    /// Checks that the sum is the identity.
+    pub(crate) fn verify(
+        self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+    ) -> Result<(), ProofError> {
+        // Flatten the terms for the multiscalar multiplication, which
+        // needs iterators of exactly known length.
+        let mut scalars = self.dynamic_scalars;
+        let mut points: Vec<Option<RistrettoPoint>> =
+            self.dynamic_points.iter().map(|P| P.decompress()).collect();
+
+        scalars.push(self.B_scalar);
+        points.push(Some(pc_gens.B));
+        scalars.push(self.B_blinding_scalar);
+        points.push(Some(pc_gens.B_blinding));
+
+        let g_and_h = self.g_scalars.into_iter().zip(self.h_scalars.into_iter());
+        for (j, (g_j, h_j)) in g_and_h.enumerate() {
+            let share = bp_gens.share(j);
+            points.extend(share.G(g_j.len()).map(|&G_i| Some(G_i)));
+            points.extend(share.H(h_j.len()).map(|&H_i| Some(H_i)));
+            scalars.extend(g_j);
+            scalars.extend(h_j);
+        }
+
+        let mega_check = multiscalar::optional_multiscalar_mul(scalars, points)
+            .ok_or_else(|| ProofError::VerificationError)?;
+
+        if mega_check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+}
+
+/// Chooses what [`BatchVerifier::verify_all_parallel`] does when a
+/// chunk of proofs fails to verify.
+#[cfg(feature = "rayon")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FailureMode {
+    /// Stop as soon as any chunk fails, to minimize the latency of
+    /// rejecting an invalid batch.
+    FailFast,
+    /// Verify every chunk, and find the invalid proofs in the
+    /// chunks that failed.
+    FindAll,
+}
+
+/// Tuning options for [`BatchVerifier::verify_all_parallel`].
+#[cfg(feature = "rayon")]
+#[derive(Copy, Clone, Debug)]
+pub struct BatchOptions {
+    /// The number of proofs verified together by one combined check.
+    ///
+    /// Larger chunks amortize the shared generators better, while
+    /// smaller chunks spread the work over more threads.
+    pub chunk_size: usize,
+    /// The number of threads of a pool built to verify the chunks,
+    /// or `None` to verify them on the current rayon thread pool.
+    pub max_threads: Option<usize>,
+    /// What to do when a chunk fails to verify.
+    pub failure_mode: FailureMode,
+}
+
+#[cfg(feature = "rayon")]
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            chunk_size: 16,
+            max_threads: None,
+            failure_mode: FailureMode::FailFast,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prove(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        values: &[u64],
+        n: usize,
+    ) -> (RangeProof, Vec<CompressedRistretto>) {
+        let mut rng = rand::thread_rng();
+        let blindings: Vec<_> = values.iter().map(|_| Scalar::random(&mut rng)).collect();
+        let mut transcript = Transcript::new(b"BatchVerifierTest");
+        RangeProof::prove_multiple(bp_gens, pc_gens, &mut transcript, values, &blindings, n)
+            .unwrap()
+    }
+
+    #[test]
+    fn batch_of_valid_proofs() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+
+        let mut batch = BatchVerifier::new(&bp_gens, &pc_gens);
+        assert!(batch.is_empty());
+
+        let (proof, Vs) = prove(&bp_gens, &pc_gens, &[1, 2], 32);
+        batch.add(proof, Transcript::new(b"BatchVerifierTest"), Vs, 32);
+        let (proof, Vs) = prove(&bp_gens, &pc_gens, &[3], 64);
+        batch.add(proof, Transcript::new(b"BatchVerifierTest"), Vs, 64);
+        let (proof, Vs) = prove(&bp_gens, &pc_gens, &[4, 5, 6, 7], 8);
+        batch.add(proof, Transcript::new(b"BatchVerifierTest"), Vs, 8);
+
+        assert_eq!(batch.len(), 3);
+        assert!(batch.verify_all().is_ok());
+    }
+
+    #[test]
+    fn batch_of_mixed_sizes_with_headers() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+
+        let mut batch = BatchVerifier::new(&bp_gens, &pc_gens);
+        for &(n, m) in [(32, 1), (64, 2), (10, 4), (20, 1), (16, 3)].iter() {
+            let values: Vec<u64> = (0..m as u64).collect();
+            let (proof, Vs) = prove(&bp_gens, &pc_gens, &values, n);
+            let bytes = proof.to_bytes_with_header(n, m).unwrap();
+            let header = batch
+                .add_with_header(&bytes, Transcript::new(b"BatchVerifierTest"), Vs)
+                .unwrap();
+            assert_eq!((header.n(), header.m()), (n, m.next_power_of_two()));
+        }
+        assert!(batch.verify_all().is_ok());
+
+        // The number of commitments must match the header
+        let (proof, mut Vs) = prove(&bp_gens, &pc_gens, &[1, 2], 16);
+        let bytes = proof.to_bytes_with_header(16, 2).unwrap();
+        Vs.pop();
+        let mut batch = BatchVerifier::new(&bp_gens, &pc_gens);
+        assert_eq!(
+            batch.add_with_header(&bytes, Transcript::new(b"BatchVerifierTest"), Vs),
+            Err(ProofError::ProofSizeMismatch)
+        );
+        assert!(batch.is_empty());
+    }
+
+    #[test]
+    fn batch_with_invalid_proof() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+
+        let mut batch = BatchVerifier::new(&bp_gens, &pc_gens);
+
+        let (proof, Vs) = prove(&bp_gens, &pc_gens, &[1, 2], 32);
+        batch.add(proof, Transcript::new(b"BatchVerifierTest"), Vs, 32);
+        // This proof is checked against the wrong label
+        let (proof, Vs) = prove(&bp_gens, &pc_gens, &[3], 32);
+        batch.add(proof, Transcript::new(b"WrongLabel"), Vs, 32);
+
+        assert_eq!(batch.verify_all(), Err(ProofError::VerificationError));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_batch_finds_all_failures() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+
+        let make_batch = || {
+            let mut batch = BatchVerifier::new(&bp_gens, &pc_gens);
+            for k in 0..7 {
+                let (proof, Vs) = prove(&bp_gens, &pc_gens, &[k, k + 1], 32);
+                // Proofs 2 and 5 are checked against the wrong label
+                let label: &'static [u8] = if k == 2 || k == 5 {
+                    b"WrongLabel"
+                } else {
+                    b"BatchVerifierTest"
+                };
+                batch.add(proof, Transcript::new(label), Vs, 32);
+            }
+            batch
+        };
+
+        let options = BatchOptions {
+            chunk_size: 2,
+            max_threads: None,
+            failure_mode: FailureMode::FindAll,
+        };
+        assert_eq!(
+            make_batch().verify_all_parallel(&options),
+            Err(ProofError::BatchVerificationError {
+                bad_proofs: vec![2, 5]
+            })
+        );
+
+        // The same pool can run several verifications.
+        let pool = ::rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        for _ in 0..2 {
+            assert_eq!(
+                pool.install(|| make_batch().verify_all_parallel(&options)),
+                Err(ProofError::BatchVerificationError {
+                    bad_proofs: vec![2, 5]
+                })
+            );
+        }
+
+        let options = BatchOptions {
+            max_threads: Some(1),
+            ..options
+        };
+        assert_eq!(
+            make_batch().verify_all_parallel(&options),
+            Err(ProofError::BatchVerificationError {
+                bad_proofs: vec![2, 5]
+            })
+        );
+
+        let options = BatchOptions {
+            failure_mode: FailureMode::FailFast,
+            ..options
+        };
+        assert_eq!(
+            make_batch().verify_all_parallel(&options),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_batch_of_valid_proofs() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+
+        let mut batch = BatchVerifier::new(&bp_gens, &pc_gens);
+        for k in 0..5 {
+            let (proof, Vs) = prove(&bp_gens, &pc_gens, &[k], 32);
+            batch.add(proof, Transcript::new(b"BatchVerifierTest"), Vs, 32);
+        }
+
+        let options = BatchOptions {
+            max_threads: Some(1),
+            ..BatchOptions::default()
+        };
+        assert!(batch.verify_all_parallel(&options).is_ok());
+    }
+}
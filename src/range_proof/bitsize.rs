@@ -0,0 +1,100 @@
+//! The `bitsize` module contains types representing the bitsize of a
+//! range proof at the type level.
+//!
+//! The functions of [`RangeProof`](::RangeProof) that take a
+//! [`BitSize`] type parameter, such as
+//! [`RangeProof::prove_single_bits`](::RangeProof::prove_single_bits),
+//! cannot be called with an unsupported bitsize, since [`BitSize`] is
+//! only implemented for the supported ones.
+//!
+//! The [`ProvableValue`] trait maps the unsigned integer types to
+//! their bitsizes.
+
+/// A bitsize supported by range proofs, known at compile time.
+///
+/// This trait is sealed and is implemented only for [`Bits8`],
+/// [`Bits16`], [`Bits32`], [`Bits64`] and [`Bits128`].
+pub trait BitSize: private::Sealed {
+    /// The bitsize \\(n\\) of the range \\([0, 2^n)\\).
+    const N: usize;
+}
+
+/// The bitsize \\(n = 8\\).
+#[derive(Copy, Clone, Debug)]
+pub enum Bits8 {}
+
+/// The bitsize \\(n = 16\\).
+#[derive(Copy, Clone, Debug)]
+pub enum Bits16 {}
+
+/// The bitsize \\(n = 32\\).
+#[derive(Copy, Clone, Debug)]
+pub enum Bits32 {}
+
+/// The bitsize \\(n = 64\\).
+#[derive(Copy, Clone, Debug)]
+pub enum Bits64 {}
+
+/// The bitsize \\(n = 128\\).
+#[derive(Copy, Clone, Debug)]
+pub enum Bits128 {}
+
+impl BitSize for Bits8 {
+    const N: usize = 8;
+}
+
+impl BitSize for Bits16 {
+    const N: usize = 16;
+}
+
+impl BitSize for Bits32 {
+    const N: usize = 32;
+}
+
+impl BitSize for Bits64 {
+    const N: usize = 64;
+}
+
+impl BitSize for Bits128 {
+    const N: usize = 128;
+}
+
+/// An unsigned integer type whose values can be proven to be in
+/// range, with the bitsize of the proof determined by the type.
+///
+/// This is used by [`RangeProof::prove_value`](::RangeProof::prove_value)
+/// so that the bitsize cannot disagree with the type of the value.
+pub trait ProvableValue: Copy + Into<u128> {
+    /// The bitsize of the range of the type.
+    type Bits: BitSize;
+}
+
+impl ProvableValue for u8 {
+    type Bits = Bits8;
+}
+
+impl ProvableValue for u16 {
+    type Bits = Bits16;
+}
+
+impl ProvableValue for u32 {
+    type Bits = Bits32;
+}
+
+impl ProvableValue for u64 {
+    type Bits = Bits64;
+}
+
+impl ProvableValue for u128 {
+    type Bits = Bits128;
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for super::Bits8 {}
+    impl Sealed for super::Bits16 {}
+    impl Sealed for super::Bits32 {}
+    impl Sealed for super::Bits64 {}
+    impl Sealed for super::Bits128 {}
+}
@@ -0,0 +1,181 @@
+//! The `blame` module contains the evidence of a malformed
+//! [`ProofShare`], which lets a third party check that a party of the
+//! aggregated multiparty computation protocol misbehaved.
+//!
+//! When the aggregated proof does not verify, the dealer audits each
+//! proof share, and returns a [`MisbehaviorEvidence`] for each share
+//! which fails its audit, in
+//! [`MPCError::MalformedProofShares`](::aggregation::MPCError::MalformedProofShares).
+//! The evidence names the audit check which failed, and holds the
+//! commitments of every party and the bad share.  Anyone with the
+//! generators and a transcript in the same initial state as the
+//! dealer's can recompute the challenges from the commitments and
+//! audit the share again with [`MisbehaviorEvidence::verify`].
+//!
+//! A [`ShareAuditor`] audits the shares one by one, for instance as
+//! they arrive, in the dealer or outside of it.
+//!
+//! The evidence shows that the share does not match the commitments
+//! it was checked against.  Binding the share and the commitments to
+//! the party which sent them, for instance by signing the messages,
+//! is left to the transport.
+
+use merlin::Transcript;
+
+use errors::MPCError;
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::dealer::Dealer;
+use range_proof::messages::{
+    BitChallenge, BitCommitment, PolyChallenge, PolyCommitment, ProofShare,
+};
+
+/// A check of the audit of a [`ProofShare`].
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AuditFailure {
+    /// The vectors \\(\mathbf{l}\\) and \\(\mathbf{r}\\) of the share
+    /// do not have the padded bitsize.
+    ShareSize,
+    /// The evaluation \\(t\_x\\) is not the inner product
+    /// \\(\langle \mathbf{l}, \mathbf{r} \rangle\\).
+    InnerProduct,
+    /// The vectors \\(\mathbf{l}\\) and \\(\mathbf{r}\\) do not open
+    /// the commitments \\(A\_j\\) and \\(S\_j\\) at the challenge
+    /// \\(x\\).
+    VectorCommitment,
+    /// The value commitment \\(V\_j\\) is not a valid point.
+    ValueCommitment,
+    /// The evaluation \\(t\_x\\) does not open the commitments
+    /// \\(V\_j\\), \\(T\_{1,j}\\) and \\(T\_{2,j}\\) at the challenge
+    /// \\(x\\).
+    PolyEvaluation,
+}
+
+/// The evidence that the party at position \\(j\\) sent a malformed
+/// [`ProofShare`].
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct MisbehaviorEvidence {
+    party: usize,
+    n: usize,
+    failure: AuditFailure,
+    bit_commitments: Vec<BitCommitment>,
+    poly_commitments: Vec<PolyCommitment>,
+    proof_share: ProofShare,
+}
+
+impl MisbehaviorEvidence {
+    /// Returns the position of the party which sent the share.
+    pub fn party(&self) -> usize {
+        self.party
+    }
+
+    /// Returns the audit check which the share failed.
+    pub fn failure(&self) -> AuditFailure {
+        self.failure
+    }
+
+    /// Returns the malformed share.
+    pub fn proof_share(&self) -> &ProofShare {
+        &self.proof_share
+    }
+
+    /// Checks the evidence: recomputes the challenges from the
+    /// commitments of all the parties, as the dealer did, and audits
+    /// the share again.
+    ///
+    /// The `transcript` must be in the same initial state as the one
+    /// passed to [`Dealer::new`], and the generators must be the ones
+    /// of the aggregation.  Returns [`MPCError::InvalidEvidence`] if
+    /// the share does not fail the claimed check.
+    pub fn verify(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+    ) -> Result<(), MPCError> {
+        if self.party >= self.bit_commitments.len() {
+            return Err(MPCError::InvalidEvidence);
+        }
+        let auditor = ShareAuditor::new(
+            bp_gens,
+            pc_gens,
+            transcript,
+            self.n,
+            self.bit_commitments.clone(),
+            self.poly_commitments.clone(),
+        )?;
+        match auditor.audit_share(self.party, &self.proof_share) {
+            Err(ref evidence) if evidence.failure == self.failure => Ok(()),
+            _ => Err(MPCError::InvalidEvidence),
+        }
+    }
+}
+
+/// Audits the [`ProofShare`]s of the parties individually.
+pub struct ShareAuditor<'g> {
+    pub(super) bp_gens: &'g BulletproofGens,
+    pub(super) pc_gens: &'g PedersenGens,
+    pub(super) n: usize,
+    pub(super) bit_commitments: Vec<BitCommitment>,
+    pub(super) bit_challenge: BitChallenge,
+    pub(super) poly_commitments: Vec<PolyCommitment>,
+    pub(super) poly_challenge: PolyChallenge,
+}
+
+impl<'g> ShareAuditor<'g> {
+    /// Creates an auditor for the shares of an aggregation of values
+    /// of bitsize `n`, given the commitments of all the parties.
+    ///
+    /// The challenges are recomputed from the commitments, as the
+    /// dealer did, so the `transcript` must be in the same initial
+    /// state as the one passed to [`Dealer::new`], and the generators
+    /// must be the ones of the aggregation.
+    pub fn new(
+        bp_gens: &'g BulletproofGens,
+        pc_gens: &'g PedersenGens,
+        transcript: &mut Transcript,
+        n: usize,
+        bit_commitments: Vec<BitCommitment>,
+        poly_commitments: Vec<PolyCommitment>,
+    ) -> Result<ShareAuditor<'g>, MPCError> {
+        let m = bit_commitments.len();
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, m)?;
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments.clone())?;
+        let (_, poly_challenge) = dealer.receive_poly_commitments(poly_commitments.clone())?;
+        Ok(ShareAuditor {
+            bp_gens,
+            pc_gens,
+            n,
+            bit_commitments,
+            bit_challenge,
+            poly_commitments,
+            poly_challenge,
+        })
+    }
+
+    /// Audits the `share` of the party at position `j`, returning the
+    /// evidence against the party if the share is malformed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `j` is not the position of a party.
+    pub fn audit_share(&self, j: usize, share: &ProofShare) -> Result<(), MisbehaviorEvidence> {
+        let audit = share.audit_share(
+            self.bp_gens,
+            self.pc_gens,
+            self.n,
+            j,
+            &self.bit_commitments[j],
+            &self.bit_challenge,
+            &self.poly_commitments[j],
+            &self.poly_challenge,
+        );
+        audit.map_err(|failure| MisbehaviorEvidence {
+            party: j,
+            n: self.n,
+            failure,
+            bit_commitments: self.bit_commitments.clone(),
+            poly_commitments: self.poly_commitments.clone(),
+            proof_share: share.clone(),
+        })
+    }
+}
@@ -0,0 +1,338 @@
+//! The `builder` module contains a fluent interface for creating
+//! range proofs, produced by [`RangeProof::builder`].
+
+use clear_on_drop::clear::Clear;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::{self, CryptoRng, RngCore};
+
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::RangeProof;
+
+/// A builder for single or aggregated range proofs.
+///
+/// The builder collects the bitsize, the transcript label, the
+/// generators and the values to prove, checks that they are
+/// consistent, and then runs
+/// [`RangeProof::prove_multiple`](::RangeProof::prove_multiple).
+///
+/// If no generators are supplied, the builder uses the default
+/// [`PedersenGens`] and creates [`BulletproofGens`] just large
+/// enough for the proof.
+///
+/// # Example
+/// ```
+/// extern crate rand;
+/// use rand::thread_rng;
+///
+/// extern crate curve25519_dalek;
+/// use curve25519_dalek::scalar::Scalar;
+///
+/// extern crate merlin;
+/// use merlin::Transcript;
+///
+/// extern crate bulletproofs;
+/// use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+///
+/// # fn main() {
+/// let mut rng = thread_rng();
+///
+/// let (proof, commitments) = RangeProof::builder()
+///     .bitsize(32)
+///     .label(b"doctest example")
+///     .value(4242344947u64, Scalar::random(&mut rng))
+///     .value(3718732727u64, Scalar::random(&mut rng))
+///     .build()
+///     .expect("A real program could handle errors");
+///
+/// let pc_gens = PedersenGens::default();
+/// let bp_gens = BulletproofGens::new(32, 2);
+/// let mut verifier_transcript = Transcript::new(b"doctest example");
+/// assert!(
+///     proof
+///         .verify_multiple(&bp_gens, &pc_gens, &mut verifier_transcript, &commitments, 32)
+///         .is_ok()
+/// );
+/// # }
+/// ```
+pub struct RangeProofBuilder<'g> {
+    n: usize,
+    label: &'static [u8],
+    bp_gens: Option<&'g BulletproofGens>,
+    pc_gens: Option<&'g PedersenGens>,
+    values: Vec<u64>,
+    blindings: Vec<Scalar>,
+}
+
+impl<'g> RangeProofBuilder<'g> {
+    /// Creates a builder for a 64-bit range proof with transcript
+    /// label `b"RangeProof"` and no values.
+    pub fn new() -> Self {
+        RangeProofBuilder {
+            n: 64,
+            label: b"RangeProof",
+            bp_gens: None,
+            pc_gens: None,
+            values: Vec::new(),
+            blindings: Vec::new(),
+        }
+    }
+
+    /// Sets the bitsize `n` of the range \\([0, 2^n)\\).
+    pub fn bitsize(mut self, n: usize) -> Self {
+        self.n = n;
+        self
+    }
+
+    /// Sets the label of the transcript used to create the proof.
+    ///
+    /// The verifier must create its transcript with the same label.
+    pub fn label(mut self, label: &'static [u8]) -> Self {
+        self.label = label;
+        self
+    }
+
+    /// Uses the given generators instead of the default ones.
+    pub fn generators(mut self, bp_gens: &'g BulletproofGens, pc_gens: &'g PedersenGens) -> Self {
+        self.bp_gens = Some(bp_gens);
+        self.pc_gens = Some(pc_gens);
+        self
+    }
+
+    /// Adds a value `v` with blinding factor `v_blinding` to the proof.
+    pub fn value(mut self, v: u64, v_blinding: Scalar) -> Self {
+        self.values.push(v);
+        self.blindings.push(v_blinding);
+        self
+    }
+
+    /// Checks the parameters and creates the proof, returning it
+    /// together with the commitments to the values, in the order the
+    /// values were added.
+    pub fn build(self) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        self.build_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Checks the parameters and creates the proof like
+    /// [`RangeProofBuilder::build`], using `rng` as the source of
+    /// external randomness.
+    pub fn build_with_rng<T: RngCore + CryptoRng>(
+        self,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        let n = self.n;
+        // The aggregation is padded to a power of two.
+        let m = self.values.len().next_power_of_two();
+
+        if n == 0 || n > 128 {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if self.values.is_empty() {
+            return Err(ProofError::InvalidAggregation);
+        }
+        if let Some(bp_gens) = self.bp_gens {
+            if bp_gens.gens_capacity < n.next_power_of_two() || bp_gens.party_capacity < m {
+                return Err(ProofError::InvalidGeneratorsLength);
+            }
+        }
+
+        let default_pc_gens;
+        let pc_gens = match self.pc_gens {
+            Some(pc_gens) => pc_gens,
+            None => {
+                default_pc_gens = PedersenGens::default();
+                &default_pc_gens
+            }
+        };
+        let default_bp_gens;
+        let bp_gens = match self.bp_gens {
+            Some(bp_gens) => bp_gens,
+            None => {
+                default_bp_gens = BulletproofGens::new(n.next_power_of_two(), m);
+                &default_bp_gens
+            }
+        };
+
+        let mut transcript = Transcript::new(self.label);
+        RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            &mut transcript,
+            &self.values,
+            &self.blindings,
+            n,
+            rng,
+        )
+    }
+}
+
+impl<'g> Default for RangeProofBuilder<'g> {
+    fn default() -> Self {
+        RangeProofBuilder::new()
+    }
+}
+
+/// Overwrite secrets with null bytes when they go out of scope.
+impl<'g> Drop for RangeProofBuilder<'g> {
+    fn drop(&mut self) {
+        // See the comment in `PartyAwaitingBitChallenge` for why the
+        // vectors are cleared element by element.
+        for v in self.values.iter_mut() {
+            v.clear();
+        }
+        for e in self.blindings.iter_mut() {
+            e.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_checks_parameters() {
+        let mut rng = rand::thread_rng();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let pc_gens = PedersenGens::default();
+
+        let result = RangeProof::builder()
+            .bitsize(129)
+            .value(1, Scalar::random(&mut rng))
+            .build();
+        assert_eq!(result.unwrap_err(), ProofError::InvalidBitsize);
+
+        let result = RangeProof::builder().build();
+        assert_eq!(result.unwrap_err(), ProofError::InvalidAggregation);
+
+        let (proof, commitments) = RangeProof::builder()
+            .bitsize(32)
+            .value(1, Scalar::random(&mut rng))
+            .value(2, Scalar::random(&mut rng))
+            .value(3, Scalar::random(&mut rng))
+            .build()
+            .unwrap();
+        assert_eq!(commitments.len(), 3);
+        assert!(
+            proof
+                .verify_multiple(
+                    &BulletproofGens::new(32, 4),
+                    &pc_gens,
+                    &mut Transcript::new(b"RangeProof"),
+                    &commitments,
+                    32,
+                ).is_ok()
+        );
+
+        let result = RangeProof::builder()
+            .generators(&bp_gens, &pc_gens)
+            .value(1, Scalar::random(&mut rng))
+            .build();
+        assert_eq!(result.unwrap_err(), ProofError::InvalidGeneratorsLength);
+
+        let result = RangeProof::builder()
+            .bitsize(32)
+            .generators(&bp_gens, &pc_gens)
+            .value(1, Scalar::random(&mut rng))
+            .value(2, Scalar::random(&mut rng))
+            .build();
+        assert_eq!(result.unwrap_err(), ProofError::InvalidGeneratorsLength);
+    }
+
+    #[test]
+    fn builder_pads_bitsize() {
+        let mut rng = rand::thread_rng();
+
+        let (proof, commitments) = RangeProof::builder()
+            .bitsize(48)
+            .label(b"BuilderTest")
+            .value(1 << 47, Scalar::random(&mut rng))
+            .build()
+            .unwrap();
+
+        let bp_gens = BulletproofGens::new(64, 1);
+        let pc_gens = PedersenGens::default();
+        let mut transcript = Transcript::new(b"BuilderTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 48)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn builder_accepts_128_bit_ranges() {
+        let mut rng = rand::thread_rng();
+
+        let (proof, commitments) = RangeProof::builder()
+            .bitsize(128)
+            .label(b"BuilderTest")
+            .value(u64::max_value(), Scalar::random(&mut rng))
+            .build()
+            .unwrap();
+
+        let bp_gens = BulletproofGens::new(128, 1);
+        let pc_gens = PedersenGens::default();
+        let mut transcript = Transcript::new(b"BuilderTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 128)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn builder_with_rng() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        // With a fixed RNG, the proof only depends on the parameters
+        // and the witness.
+        let blinding = Scalar::random(&mut rand::thread_rng());
+        let build = || {
+            RangeProof::builder()
+                .bitsize(32)
+                .label(b"BuilderTest")
+                .value(1234, blinding)
+                .build_with_rng(&mut StdRng::from_seed([0u8; 32]))
+                .unwrap()
+        };
+        let (proof_1, commitments) = build();
+        let (proof_2, _) = build();
+        assert_eq!(proof_1, proof_2);
+
+        let bp_gens = BulletproofGens::new(32, 1);
+        let pc_gens = PedersenGens::default();
+        let mut transcript = Transcript::new(b"BuilderTest");
+        assert!(
+            proof_1
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn builder_with_generators() {
+        let mut rng = rand::thread_rng();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let pc_gens = PedersenGens::default();
+
+        let (proof, commitments) = RangeProof::builder()
+            .bitsize(16)
+            .label(b"BuilderTest")
+            .generators(&bp_gens, &pc_gens)
+            .value(1000, Scalar::random(&mut rng))
+            .value(2000, Scalar::random(&mut rng))
+            .build()
+            .unwrap();
+
+        let mut transcript = Transcript::new(b"BuilderTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 16)
+                .is_ok()
+        );
+    }
+}
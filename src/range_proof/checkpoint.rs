@@ -0,0 +1,167 @@
+//! The `checkpoint` module contains the encoding of the checkpoints
+//! of the dealer and party states, which let an interrupted
+//! aggregation be resumed after a process restart.
+//!
+//! A checkpoint is an opaque byte string: the encoded state, followed
+//! by a 32-byte tag computed by a Merlin transcript over the kind of
+//! state, a secret checkpoint key and the encoded state.  A checkpoint
+//! is only restored as the kind of state it was taken from, and only
+//! if its tag verifies under the same key, so a checkpoint which was
+//! tampered with in storage is rejected.
+//!
+//! The tag authenticates the checkpoint, but does not encrypt it:
+//! the checkpoints of party states contain the party's value and
+//! blinding factors in the clear, and must be stored as carefully as
+//! the value itself.
+
+use byteorder::{ByteOrder, LittleEndian};
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use subtle::ConstantTimeEq;
+
+use errors::MPCError;
+use scalar;
+use util::read32;
+
+/// The size of the tag at the end of a checkpoint.
+const TAG_SIZE: usize = 32;
+
+/// Encodes a state into a checkpoint.
+pub(super) struct CheckpointWriter {
+    buf: Vec<u8>,
+}
+
+impl CheckpointWriter {
+    pub(super) fn new() -> CheckpointWriter {
+        CheckpointWriter { buf: Vec::new() }
+    }
+
+    pub(super) fn write_usize(&mut self, x: usize) {
+        let mut bytes = [0u8; 8];
+        LittleEndian::write_u64(&mut bytes, x as u64);
+        self.buf.extend_from_slice(&bytes);
+    }
+
+    pub(super) fn write_u128(&mut self, x: u128) {
+        self.write_usize(x as u64 as usize);
+        self.write_usize((x >> 64) as u64 as usize);
+    }
+
+    pub(super) fn write_scalar(&mut self, x: &Scalar) {
+        self.buf.extend_from_slice(x.as_bytes());
+    }
+
+    /// Writes the length of `xs`, followed by its elements.
+    pub(super) fn write_scalars(&mut self, xs: &[Scalar]) {
+        self.write_usize(xs.len());
+        for x in xs {
+            self.write_scalar(x);
+        }
+    }
+
+    pub(super) fn write_point(&mut self, point: &CompressedRistretto) {
+        self.buf.extend_from_slice(point.as_bytes());
+    }
+
+    /// Writes the length of `bytes`, followed by the bytes.
+    pub(super) fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_usize(bytes.len());
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Appends the tag of the checkpoint of the given `kind` of state
+    /// under `key`, and returns the checkpoint.
+    pub(super) fn finish(mut self, kind: &'static [u8], key: &[u8]) -> Vec<u8> {
+        let tag = compute_tag(kind, key, &self.buf);
+        self.buf.extend_from_slice(&tag);
+        self.buf
+    }
+}
+
+/// Decodes a state from a checkpoint whose tag was verified.
+pub(super) struct CheckpointReader<'c> {
+    data: &'c [u8],
+}
+
+impl<'c> CheckpointReader<'c> {
+    /// Verifies the tag of a `checkpoint` of the given `kind` of state
+    /// under `key`, and returns a reader for the encoded state.
+    pub(super) fn new(
+        checkpoint: &'c [u8],
+        kind: &'static [u8],
+        key: &[u8],
+    ) -> Result<CheckpointReader<'c>, MPCError> {
+        if checkpoint.len() < TAG_SIZE {
+            return Err(MPCError::InvalidCheckpoint);
+        }
+        let (data, tag) = checkpoint.split_at(checkpoint.len() - TAG_SIZE);
+        let expected_tag = compute_tag(kind, key, data);
+        if expected_tag[..].ct_eq(tag).unwrap_u8() != 1 {
+            return Err(MPCError::InvalidCheckpoint);
+        }
+        Ok(CheckpointReader { data })
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'c [u8], MPCError> {
+        if self.data.len() < len {
+            return Err(MPCError::InvalidCheckpoint);
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    pub(super) fn read_usize(&mut self) -> Result<usize, MPCError> {
+        Ok(LittleEndian::read_u64(self.take(8)?) as usize)
+    }
+
+    pub(super) fn read_u128(&mut self) -> Result<u128, MPCError> {
+        let low = self.read_usize()? as u128;
+        let high = self.read_usize()? as u128;
+        Ok(low | (high << 64))
+    }
+
+    pub(super) fn read_scalar(&mut self) -> Result<Scalar, MPCError> {
+        scalar::decode(self.take(32)?).map_err(|_| MPCError::InvalidCheckpoint)
+    }
+
+    pub(super) fn read_scalars(&mut self) -> Result<Vec<Scalar>, MPCError> {
+        let len = self.read_usize()?;
+        // Check the length before allocating the vector
+        if len > self.data.len() / 32 {
+            return Err(MPCError::InvalidCheckpoint);
+        }
+        (0..len).map(|_| self.read_scalar()).collect()
+    }
+
+    pub(super) fn read_point(&mut self) -> Result<CompressedRistretto, MPCError> {
+        Ok(CompressedRistretto(read32(self.take(32)?)))
+    }
+
+    pub(super) fn read_bytes(&mut self) -> Result<&'c [u8], MPCError> {
+        let len = self.read_usize()?;
+        self.take(len)
+    }
+
+    /// Checks that the whole state was read.
+    pub(super) fn finish(self) -> Result<(), MPCError> {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(MPCError::InvalidCheckpoint)
+        }
+    }
+}
+
+/// Computes the tag of the encoded state `data` of the given `kind`
+/// of state under `key`.
+fn compute_tag(kind: &'static [u8], key: &[u8], data: &[u8]) -> [u8; TAG_SIZE] {
+    let mut transcript = Transcript::new(b"Bulletproofs MPC checkpoint");
+    transcript.commit_bytes(b"kind", kind);
+    transcript.commit_bytes(b"key", key);
+    transcript.commit_bytes(b"state", data);
+    let mut tag = [0u8; TAG_SIZE];
+    transcript.challenge_bytes(b"tag", &mut tag);
+    tag
+}
@@ -3,40 +3,77 @@
 //!
 //! For more explanation of how the `dealer`, `party`, and `messages` modules orchestrate the protocol execution, see
 //! [the API for the aggregated multiparty computation protocol](../aggregation/index.html#api-for-the-aggregated-multiparty-computation-protocol).
+//!
+//! Each dealer state exposes the aggregated commitments and the
+//! challenges formed so far, so that parties or external auditors can
+//! recompute the Fiat-Shamir challenges and detect a deviating dealer
+//! before the final proof is released.
+//!
+//! Each state can be saved with its `to_checkpoint` function and
+//! restored with `from_checkpoint`, so that a dealer can resume the
+//! protocol after a process restart.  A checkpoint is an opaque byte
+//! string authenticated under a secret checkpoint key, and holds the
+//! parameters and the messages received so far.  The dealer is
+//! restored by replaying them on a transcript in the same initial
+//! state as the one passed to [`Dealer::new`], which recomputes the
+//! same challenges.
+//!
+//! The aggregation size of a proof must be a power of two.  When the
+//! number of parties is not, the dealer pads the aggregation with
+//! dummy parties, which prove the value \\(0\\) with all-zero blinding
+//! factors.  Their messages are computed by the dealer itself, and
+//! depend only on the generators and the challenges, so they are
+//! neither sent nor checkpointed.
+
+use std::cmp;
 
-use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use merlin::Transcript;
+use rand::{self, Rng};
 
-use errors::MPCError;
+use errors::{MPCError, ProofError};
 use generators::{BulletproofGens, PedersenGens};
 use inner_product_proof;
 use range_proof::RangeProof;
 use transcript::TranscriptProtocol;
 
-use util;
+use math;
 
+use super::blame::{MisbehaviorEvidence, ShareAuditor};
+use super::checkpoint::{CheckpointReader, CheckpointWriter};
 use super::messages::*;
+use super::party::{Party, PartyAwaitingPolyChallenge};
 
 /// Used to construct a dealer for the aggregated rangeproof MPC protocol.
 pub struct Dealer {}
 
 impl Dealer {
-    /// Creates a new dealer coordinating `m` parties proving `n`-bit ranges.
+    /// Creates a new dealer coordinating `num_parties` parties proving
+    /// `n`-bit ranges.
+    ///
+    /// If `num_parties` is not a power of two, the aggregation is
+    /// padded up to the next power of two \\(m\\) with dummy parties,
+    /// at the positions from `num_parties` to \\(m - 1\\).  A dummy
+    /// party commits to the value \\(0\\) with the blinding factor
+    /// \\(0\\), so its value commitment is the identity point, which
+    /// [`RangeProof::verify_multiple`] appends to the value commitments
+    /// of the parties itself.
     pub fn new<'a, 'b>(
         bp_gens: &'b BulletproofGens,
         pc_gens: &'b PedersenGens,
         transcript: &'a mut Transcript,
         n: usize,
-        m: usize,
+        num_parties: usize,
     ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
-        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+        if n == 0 || n > 128 {
             return Err(MPCError::InvalidBitsize);
         }
-        if !m.is_power_of_two() {
+        if num_parties == 0 {
             return Err(MPCError::InvalidAggregation);
         }
-        if bp_gens.gens_capacity < n {
+        let m = num_parties.next_power_of_two();
+        if bp_gens.gens_capacity < n.next_power_of_two() {
             return Err(MPCError::InvalidGeneratorsLength);
         }
         if bp_gens.party_capacity < m {
@@ -57,7 +94,7 @@ impl Dealer {
         // state.
         let initial_transcript = transcript.clone();
 
-        transcript.rangeproof_domain_sep(n as u64, m as u64);
+        commit_parameters(transcript, bp_gens, n, m);
 
         Ok(DealerAwaitingBitCommitments {
             bp_gens,
@@ -66,8 +103,29 @@ impl Dealer {
             initial_transcript,
             n,
             m,
+            num_parties,
         })
     }
+
+    /// Creates a new dealer for the `session`, coordinating one party
+    /// per identity of the session, proving `n`-bit ranges.
+    ///
+    /// The session is bound into the `transcript` before the dealer
+    /// is created, so the transcripts given to the parties'
+    /// [`ChallengeVerifier`](::aggregation::party::ChallengeVerifier)s,
+    /// to the verifier of the proof, and to restore a checkpoint or
+    /// check [`MisbehaviorEvidence`], must have the same session bound
+    /// with [`SessionBinding::bind`].
+    pub fn new_with_session<'a, 'b>(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        n: usize,
+        session: &SessionBinding,
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        session.bind(transcript);
+        Dealer::new(bp_gens, pc_gens, transcript, n, session.num_parties())
+    }
 }
 
 /// A dealer waiting for the parties to send their [`BitCommitment`]s.
@@ -79,39 +137,71 @@ pub struct DealerAwaitingBitCommitments<'a, 'b> {
     /// that it can attempt to verify the aggregated proof at the end.
     initial_transcript: Transcript,
     n: usize,
+    /// The aggregation size, padded to a power of two
     m: usize,
+    num_parties: usize,
 }
 
 impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
+    /// Saves the state in a checkpoint authenticated under `key`.
+    pub fn to_checkpoint(&self, key: &[u8]) -> Vec<u8> {
+        let mut writer = CheckpointWriter::new();
+        writer.write_usize(self.n);
+        writer.write_usize(self.num_parties);
+        writer.finish(b"DealerAwaitingBitCommitments", key)
+    }
+
+    /// Restores a state saved with
+    /// [`to_checkpoint`](DealerAwaitingBitCommitments::to_checkpoint),
+    /// using the same generators as the dealer it was taken from and a
+    /// `transcript` in the same initial state.
+    ///
+    /// Returns [`MPCError::InvalidCheckpoint`] if the checkpoint is
+    /// malformed or does not verify under `key`.
+    pub fn from_checkpoint(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        checkpoint: &[u8],
+        key: &[u8],
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        let mut reader = CheckpointReader::new(checkpoint, b"DealerAwaitingBitCommitments", key)?;
+        let dealer = restore_dealer(bp_gens, pc_gens, transcript, &mut reader)?;
+        reader.finish()?;
+        Ok(dealer)
+    }
+
     /// Receive each party's [`BitCommitment`]s and compute the [`BitChallenge`].
     pub fn receive_bit_commitments(
         self,
-        bit_commitments: Vec<BitCommitment>,
+        mut bit_commitments: Vec<BitCommitment>,
     ) -> Result<(DealerAwaitingPolyCommitments<'a, 'b>, BitChallenge), MPCError> {
-        if self.m != bit_commitments.len() {
+        if self.num_parties != bit_commitments.len() {
             return Err(MPCError::WrongNumBitCommitments);
         }
 
-        // Commit each V_j individually
-        for vc in bit_commitments.iter() {
-            self.transcript.commit_point(b"V", &vc.V_j);
+        let mut dummies = Vec::with_capacity(self.m - self.num_parties);
+        for j in self.num_parties..self.m {
+            let dummy = Party::dummy(self.bp_gens, self.pc_gens, self.n)?;
+            let (dummy, bit_commitment) = dummy.assign_position(j)?;
+            dummies.push(dummy);
+            bit_commitments.push(bit_commitment);
         }
 
-        // Commit aggregated A_j, S_j
-        let A: RistrettoPoint = bit_commitments.iter().map(|vc| vc.A_j).sum();
-        self.transcript.commit_point(b"A", &A.compress());
-
-        let S: RistrettoPoint = bit_commitments.iter().map(|vc| vc.S_j).sum();
-        self.transcript.commit_point(b"S", &S.compress());
+        let (A, S, bit_challenge) = commit_bit_commitments(self.transcript, &bit_commitments);
 
-        let y = self.transcript.challenge_scalar(b"y");
-        let z = self.transcript.challenge_scalar(b"z");
-        let bit_challenge = BitChallenge { y, z };
+        let (dummies, dummy_poly_commitments): (Vec<_>, Vec<_>) = dummies
+            .into_iter()
+            .map(|dummy| {
+                dummy.apply_challenge_with_blindings(&bit_challenge, Scalar::zero(), Scalar::zero())
+            })
+            .unzip();
 
         Ok((
             DealerAwaitingPolyCommitments {
                 n: self.n,
                 m: self.m,
+                num_parties: self.num_parties,
                 transcript: self.transcript,
                 initial_transcript: self.initial_transcript,
                 bp_gens: self.bp_gens,
@@ -120,6 +210,8 @@ impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
                 bit_commitments,
                 A,
                 S,
+                dummies,
+                dummy_poly_commitments,
             },
             bit_challenge,
         ))
@@ -131,6 +223,7 @@ impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
 pub struct DealerAwaitingPolyCommitments<'a, 'b> {
     n: usize,
     m: usize,
+    num_parties: usize,
     transcript: &'a mut Transcript,
     initial_transcript: Transcript,
     bp_gens: &'b BulletproofGens,
@@ -141,33 +234,96 @@ pub struct DealerAwaitingPolyCommitments<'a, 'b> {
     A: RistrettoPoint,
     /// Aggregated commitment to the parties' bit blindings
     S: RistrettoPoint,
+    /// The states of the dummy parties which pad the aggregation
+    dummies: Vec<PartyAwaitingPolyChallenge>,
+    dummy_poly_commitments: Vec<PolyCommitment>,
 }
 
 impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
+    /// Saves the state in a checkpoint authenticated under `key`.
+    pub fn to_checkpoint(&self, key: &[u8]) -> Vec<u8> {
+        let mut writer = CheckpointWriter::new();
+        writer.write_usize(self.n);
+        writer.write_usize(self.num_parties);
+        for bit_commitment in self.bit_commitments[..self.num_parties].iter() {
+            writer.write_bytes(&bit_commitment.to_bytes());
+        }
+        writer.finish(b"DealerAwaitingPolyCommitments", key)
+    }
+
+    /// Restores a state saved with
+    /// [`to_checkpoint`](DealerAwaitingPolyCommitments::to_checkpoint),
+    /// using the same generators as the dealer it was taken from and a
+    /// `transcript` in the same initial state.
+    ///
+    /// Returns [`MPCError::InvalidCheckpoint`] if the checkpoint is
+    /// malformed or does not verify under `key`.
+    pub fn from_checkpoint(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        checkpoint: &[u8],
+        key: &[u8],
+    ) -> Result<DealerAwaitingPolyCommitments<'a, 'b>, MPCError> {
+        let mut reader = CheckpointReader::new(checkpoint, b"DealerAwaitingPolyCommitments", key)?;
+        let dealer = restore_dealer(bp_gens, pc_gens, transcript, &mut reader)?;
+        let bit_commitments =
+            read_messages(&mut reader, dealer.num_parties, BitCommitment::from_bytes)?;
+        reader.finish()?;
+
+        let (dealer, _) = dealer.receive_bit_commitments(bit_commitments)?;
+        Ok(dealer)
+    }
+
+    /// Returns the aggregated commitment \\(A\\) to the parties' bits.
+    pub fn A(&self) -> CompressedRistretto {
+        self.A.compress()
+    }
+
+    /// Returns the aggregated commitment \\(S\\) to the parties' bit
+    /// blindings.
+    pub fn S(&self) -> CompressedRistretto {
+        self.S.compress()
+    }
+
+    /// Returns the [`BitChallenge`] sent to the parties.
+    pub fn bit_challenge(&self) -> &BitChallenge {
+        &self.bit_challenge
+    }
+
+    /// Returns the [`BitCommitment`]s of the parties, followed by
+    /// those of the dummy parties, which the dealer can broadcast so
+    /// that each party can check the [`BitChallenge`] with a
+    /// [`ChallengeVerifier`](::aggregation::party::ChallengeVerifier).
+    pub fn bit_commitments(&self) -> &[BitCommitment] {
+        &self.bit_commitments
+    }
+
     /// Receive [`PolyCommitment`]s from the parties and compute the
     /// [`PolyChallenge`].
     pub fn receive_poly_commitments(
         self,
-        poly_commitments: Vec<PolyCommitment>,
+        mut poly_commitments: Vec<PolyCommitment>,
     ) -> Result<(DealerAwaitingProofShares<'a, 'b>, PolyChallenge), MPCError> {
-        if self.m != poly_commitments.len() {
+        if self.num_parties != poly_commitments.len() {
             return Err(MPCError::WrongNumPolyCommitments);
         }
+        poly_commitments.extend_from_slice(&self.dummy_poly_commitments);
 
-        // Commit sums of T_1_j's and T_2_j's
-        let T_1: RistrettoPoint = poly_commitments.iter().map(|pc| pc.T_1_j).sum();
-        let T_2: RistrettoPoint = poly_commitments.iter().map(|pc| pc.T_2_j).sum();
+        let (T_1, T_2, poly_challenge) =
+            commit_poly_commitments(self.transcript, &poly_commitments);
 
-        self.transcript.commit_point(b"T_1", &T_1.compress());
-        self.transcript.commit_point(b"T_2", &T_2.compress());
-
-        let x = self.transcript.challenge_scalar(b"x");
-        let poly_challenge = PolyChallenge { x };
+        let dummy_shares = self
+            .dummies
+            .into_iter()
+            .map(|dummy| dummy.apply_challenge(&poly_challenge))
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok((
             DealerAwaitingProofShares {
                 n: self.n,
                 m: self.m,
+                num_parties: self.num_parties,
                 transcript: self.transcript,
                 initial_transcript: self.initial_transcript,
                 bp_gens: self.bp_gens,
@@ -180,6 +336,7 @@ impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
                 poly_commitments,
                 T_1,
                 T_2,
+                dummy_shares,
             },
             poly_challenge,
         ))
@@ -192,6 +349,7 @@ impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
 pub struct DealerAwaitingProofShares<'a, 'b> {
     n: usize,
     m: usize,
+    num_parties: usize,
     transcript: &'a mut Transcript,
     initial_transcript: Transcript,
     bp_gens: &'b BulletproofGens,
@@ -204,18 +362,113 @@ pub struct DealerAwaitingProofShares<'a, 'b> {
     S: RistrettoPoint,
     T_1: RistrettoPoint,
     T_2: RistrettoPoint,
+    /// The proof shares of the dummy parties
+    dummy_shares: Vec<ProofShare>,
 }
 
 impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
+    /// Saves the state in a checkpoint authenticated under `key`.
+    pub fn to_checkpoint(&self, key: &[u8]) -> Vec<u8> {
+        let mut writer = CheckpointWriter::new();
+        writer.write_usize(self.n);
+        writer.write_usize(self.num_parties);
+        for bit_commitment in self.bit_commitments[..self.num_parties].iter() {
+            writer.write_bytes(&bit_commitment.to_bytes());
+        }
+        for poly_commitment in self.poly_commitments[..self.num_parties].iter() {
+            writer.write_bytes(&poly_commitment.to_bytes());
+        }
+        writer.finish(b"DealerAwaitingProofShares", key)
+    }
+
+    /// Restores a state saved with
+    /// [`to_checkpoint`](DealerAwaitingProofShares::to_checkpoint),
+    /// using the same generators as the dealer it was taken from and a
+    /// `transcript` in the same initial state.
+    ///
+    /// Returns [`MPCError::InvalidCheckpoint`] if the checkpoint is
+    /// malformed or does not verify under `key`.
+    pub fn from_checkpoint(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        checkpoint: &[u8],
+        key: &[u8],
+    ) -> Result<DealerAwaitingProofShares<'a, 'b>, MPCError> {
+        let mut reader = CheckpointReader::new(checkpoint, b"DealerAwaitingProofShares", key)?;
+        let dealer = restore_dealer(bp_gens, pc_gens, transcript, &mut reader)?;
+        let num_parties = dealer.num_parties;
+        let bit_commitments = read_messages(&mut reader, num_parties, BitCommitment::from_bytes)?;
+        let poly_commitments = read_messages(&mut reader, num_parties, PolyCommitment::from_bytes)?;
+        reader.finish()?;
+
+        let (dealer, _) = dealer.receive_bit_commitments(bit_commitments)?;
+        let (dealer, _) = dealer.receive_poly_commitments(poly_commitments)?;
+        Ok(dealer)
+    }
+
+    /// Returns the aggregated commitment \\(A\\) to the parties' bits.
+    pub fn A(&self) -> CompressedRistretto {
+        self.A.compress()
+    }
+
+    /// Returns the aggregated commitment \\(S\\) to the parties' bit
+    /// blindings.
+    pub fn S(&self) -> CompressedRistretto {
+        self.S.compress()
+    }
+
+    /// Returns the aggregated commitment \\(T\_1\\) to the
+    /// parties' polynomial coefficients.
+    pub fn T_1(&self) -> CompressedRistretto {
+        self.T_1.compress()
+    }
+
+    /// Returns the aggregated commitment \\(T\_2\\) to the
+    /// parties' polynomial coefficients.
+    pub fn T_2(&self) -> CompressedRistretto {
+        self.T_2.compress()
+    }
+
+    /// Returns the [`BitChallenge`] sent to the parties.
+    pub fn bit_challenge(&self) -> &BitChallenge {
+        &self.bit_challenge
+    }
+
+    /// Returns the [`BitCommitment`]s of the parties, followed by
+    /// those of the dummy parties, which the dealer can broadcast so
+    /// that each party can check the [`BitChallenge`] with a
+    /// [`ChallengeVerifier`](::aggregation::party::ChallengeVerifier).
+    pub fn bit_commitments(&self) -> &[BitCommitment] {
+        &self.bit_commitments
+    }
+
+    /// Returns the [`PolyChallenge`] sent to the parties.
+    pub fn poly_challenge(&self) -> &PolyChallenge {
+        &self.poly_challenge
+    }
+
+    /// Returns the [`PolyCommitment`]s of the parties, followed by
+    /// those of the dummy parties, which the dealer can broadcast so
+    /// that each party can check the [`PolyChallenge`].
+    pub fn poly_commitments(&self) -> &[PolyCommitment] {
+        &self.poly_commitments
+    }
+
     /// Assembles proof shares into an `RangeProof`.
     ///
     /// Used as a helper function by `receive_trusted_shares` (which
     /// just hands back the result) and `receive_shares` (which
     /// validates the proof shares.
     fn assemble_shares(&mut self, proof_shares: &[ProofShare]) -> Result<RangeProof, MPCError> {
-        if self.m != proof_shares.len() {
+        if self.num_parties != proof_shares.len() {
             return Err(MPCError::WrongNumProofShares);
         }
+        let proof_shares: Vec<_> = proof_shares
+            .iter()
+            .chain(self.dummy_shares.iter())
+            .cloned()
+            .collect();
 
         let t_x: Scalar = proof_shares.iter().map(|ps| ps.t_x).sum();
         let t_x_blinding: Scalar = proof_shares.iter().map(|ps| ps.t_x_blinding).sum();
@@ -230,9 +483,10 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
         let w = self.transcript.challenge_scalar(b"w");
         let Q = w * self.pc_gens.B;
 
-        let Hprime_factors: Vec<Scalar> = util::exp_iter(self.bit_challenge.y.invert())
-            .take(self.n * self.m)
-            .collect();
+        // The parties' vectors are padded to a power of two
+        let padded_n = self.n.next_power_of_two();
+        let Hprime_factors: Vec<Scalar> =
+            math::powers(self.bit_challenge.y.invert(), padded_n * self.m).collect();
 
         let l_vec: Vec<Scalar> = proof_shares
             .iter()
@@ -247,8 +501,8 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
             self.transcript,
             &Q,
             &Hprime_factors,
-            self.bp_gens.G(self.n, self.m).cloned().collect(),
-            self.bp_gens.H(self.n, self.m).cloned().collect(),
+            self.bp_gens.G(padded_n, self.m).cloned().collect(),
+            self.bp_gens.H(padded_n, self.m).cloned().collect(),
             l_vec,
             r_vec,
         );
@@ -272,7 +526,9 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
     /// If the aggregated proof fails to validate, this function
     /// audits the submitted shares to determine which shares were
     /// invalid.  This information is returned as part of the
-    /// [`MPCError`].
+    /// [`MPCError`], together with the
+    /// [`MisbehaviorEvidence`](::aggregation::blame::MisbehaviorEvidence)
+    /// against each party, which a third party can check.
     ///
     /// If the proof shares are known to be trusted, for instance when
     /// performing local aggregation,
@@ -284,30 +540,26 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
         let Vs: Vec<_> = self.bit_commitments.iter().map(|vc| vc.V_j).collect();
 
         // See comment in `Dealer::new` for why we use `initial_transcript`
-        let transcript = &mut self.initial_transcript;
-        if proof
-            .verify_multiple(self.bp_gens, self.pc_gens, transcript, &Vs, self.n)
-            .is_ok()
-        {
+        let verified = proof
+            .verify_multiple(
+                self.bp_gens,
+                self.pc_gens,
+                &mut self.initial_transcript,
+                &Vs,
+                self.n,
+            ).is_ok();
+        if verified {
             Ok(proof)
         } else {
-            // Proof verification failed. Now audit the parties:
-            let mut bad_shares = Vec::new();
-            for j in 0..self.m {
-                match proof_shares[j].audit_share(
-                    &self.bp_gens,
-                    &self.pc_gens,
-                    j,
-                    &self.bit_commitments[j],
-                    &self.bit_challenge,
-                    &self.poly_commitments[j],
-                    &self.poly_challenge,
-                ) {
-                    Ok(_) => {}
-                    Err(_) => bad_shares.push(j),
-                }
-            }
-            Err(MPCError::MalformedProofShares { bad_shares })
+            // Proof verification failed. Now audit the parties,
+            // in parallel with the `rayon` feature:
+            let auditor = self.auditor();
+            let audits = super::map_parties((0..self.num_parties).collect(), |j| {
+                auditor.audit_share(j, &proof_shares[j])
+            });
+            Err(malformed_shares(
+                audits.into_iter().filter_map(|audit| audit.err()).collect(),
+            ))
         }
     }
 
@@ -331,4 +583,187 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
     ) -> Result<RangeProof, MPCError> {
         self.assemble_shares(proof_shares)
     }
+
+    /// Assemble the final aggregated [`RangeProof`] from the given
+    /// `proof_shares`, auditing them according to the `policy`.
+    ///
+    /// With [`AuditPolicy::Always`], this is
+    /// [`receive_shares`](DealerAwaitingProofShares::receive_shares),
+    /// and with [`AuditPolicy::Never`], this is
+    /// [`receive_trusted_shares`](DealerAwaitingProofShares::receive_trusted_shares).
+    /// With [`AuditPolicy::Sample`], the shares of randomly chosen
+    /// parties are audited individually, and the proof is assembled
+    /// without verification if they are all well-formed: a malformed
+    /// share outside the sample gives an invalid proof.
+    pub fn receive_shares_with_policy(
+        mut self,
+        proof_shares: &[ProofShare],
+        policy: AuditPolicy,
+    ) -> Result<RangeProof, MPCError> {
+        let k = match policy {
+            AuditPolicy::Always => return self.receive_shares(proof_shares),
+            AuditPolicy::Never => return self.receive_trusted_shares(proof_shares),
+            AuditPolicy::Sample(k) => k,
+        };
+        if self.num_parties != proof_shares.len() {
+            return Err(MPCError::WrongNumProofShares);
+        }
+
+        let evidence: Vec<_> = {
+            let auditor = self.auditor();
+            sample_positions(&mut rand::thread_rng(), self.num_parties, k)
+                .into_iter()
+                .filter_map(|j| auditor.audit_share(j, &proof_shares[j]).err())
+                .collect()
+        };
+        if evidence.is_empty() {
+            self.assemble_shares(proof_shares)
+        } else {
+            Err(malformed_shares(evidence))
+        }
+    }
+
+    /// Returns an auditor for the shares of the parties.
+    pub fn auditor(&self) -> ShareAuditor<'b> {
+        ShareAuditor {
+            bp_gens: self.bp_gens,
+            pc_gens: self.pc_gens,
+            n: self.n,
+            bit_commitments: self.bit_commitments.clone(),
+            bit_challenge: self.bit_challenge,
+            poly_commitments: self.poly_commitments.clone(),
+            poly_challenge: self.poly_challenge,
+        }
+    }
+
+    /// Audits the `share` of the party at position `j`, returning the
+    /// evidence against the party if the share is malformed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `j` is not the position of a party.
+    pub fn audit_share(&self, j: usize, share: &ProofShare) -> Result<(), MisbehaviorEvidence> {
+        self.auditor().audit_share(j, share)
+    }
+}
+
+/// How the dealer audits the [`ProofShare`]s of the parties, in
+/// [`receive_shares_with_policy`](DealerAwaitingProofShares::receive_shares_with_policy).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AuditPolicy {
+    /// Verify the aggregated proof, and audit every share if it does
+    /// not verify.
+    Always,
+    /// Trust the shares, without any verification.
+    Never,
+    /// Audit the shares of this many randomly chosen parties.
+    Sample(usize),
+}
+
+/// Commits the parameters of the aggregation to the transcript.
+pub(super) fn commit_parameters(
+    transcript: &mut Transcript,
+    bp_gens: &BulletproofGens,
+    n: usize,
+    m: usize,
+) {
+    transcript.rangeproof_domain_sep(n as u64, m as u64);
+    for salt in bp_gens.salts() {
+        transcript.generators_salt(salt);
+    }
+}
+
+/// Commits the [`BitCommitment`]s of the parties to the transcript,
+/// and returns the aggregated \\(A, S\\) and the [`BitChallenge`].
+pub(super) fn commit_bit_commitments(
+    transcript: &mut Transcript,
+    bit_commitments: &[BitCommitment],
+) -> (RistrettoPoint, RistrettoPoint, BitChallenge) {
+    // Commit each V_j individually
+    for vc in bit_commitments.iter() {
+        transcript.commit_point(b"V", &vc.V_j);
+    }
+
+    // Commit aggregated A_j, S_j
+    let A: RistrettoPoint = bit_commitments.iter().map(|vc| vc.A_j).sum();
+    transcript.commit_point(b"A", &A.compress());
+
+    let S: RistrettoPoint = bit_commitments.iter().map(|vc| vc.S_j).sum();
+    transcript.commit_point(b"S", &S.compress());
+
+    let y = transcript.challenge_scalar(b"y");
+    let z = transcript.challenge_scalar(b"z");
+    (A, S, BitChallenge { y, z })
+}
+
+/// Commits the [`PolyCommitment`]s of the parties to the transcript,
+/// and returns the aggregated \\(T\_1, T\_2\\) and the
+/// [`PolyChallenge`].
+pub(super) fn commit_poly_commitments(
+    transcript: &mut Transcript,
+    poly_commitments: &[PolyCommitment],
+) -> (RistrettoPoint, RistrettoPoint, PolyChallenge) {
+    // Commit sums of T_1_j's and T_2_j's
+    let T_1: RistrettoPoint = poly_commitments.iter().map(|pc| pc.T_1_j).sum();
+    let T_2: RistrettoPoint = poly_commitments.iter().map(|pc| pc.T_2_j).sum();
+
+    transcript.commit_point(b"T_1", &T_1.compress());
+    transcript.commit_point(b"T_2", &T_2.compress());
+
+    let x = transcript.challenge_scalar(b"x");
+    (T_1, T_2, PolyChallenge { x })
+}
+
+/// Returns the error for the parties with malformed shares.
+fn malformed_shares(evidence: Vec<MisbehaviorEvidence>) -> MPCError {
+    MPCError::MalformedProofShares {
+        bad_shares: evidence.iter().map(|e| e.party()).collect(),
+        evidence,
+    }
+}
+
+/// Returns `k` distinct positions out of \\(m\\), or all of them if
+/// \\(k \geq m\\), in increasing order.
+fn sample_positions<R: Rng>(rng: &mut R, m: usize, k: usize) -> Vec<usize> {
+    let mut positions: Vec<usize> = (0..m).collect();
+    let k = cmp::min(k, m);
+    // A partial Fisher-Yates shuffle
+    for i in 0..k {
+        let r = rng.gen_range(i, m);
+        positions.swap(i, r);
+    }
+    positions.truncate(k);
+    positions.sort();
+    positions
+}
+
+/// Reads the parameters of a dealer checkpoint and creates the
+/// dealer in its first state.
+fn restore_dealer<'a, 'b>(
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    transcript: &'a mut Transcript,
+    reader: &mut CheckpointReader,
+) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+    let n = reader.read_usize()?;
+    let num_parties = reader.read_usize()?;
+    Dealer::new(bp_gens, pc_gens, transcript, n, num_parties)
+}
+
+/// Reads the `num_parties` messages of a round from a dealer
+/// checkpoint.
+fn read_messages<T, F>(
+    reader: &mut CheckpointReader,
+    num_parties: usize,
+    from_bytes: F,
+) -> Result<Vec<T>, MPCError>
+where
+    F: Fn(&[u8]) -> Result<T, ProofError>,
+{
+    let mut messages = Vec::new();
+    for _ in 0..num_parties {
+        let message = from_bytes(reader.read_bytes()?).map_err(|_| MPCError::InvalidCheckpoint)?;
+        messages.push(message);
+    }
+    Ok(messages)
 }
@@ -0,0 +1,440 @@
+//! The `delegation` module lets a verifier with little computing
+//! power hand the expensive part of range proof verification to an
+//! untrusted helper.
+//!
+//! The verifier replays the proof on its transcript, which only needs
+//! scalar arithmetic, and obtains a [`DelegationRequest`] from
+//! [`RangeProof::delegate_verification`](::RangeProof::delegate_verification).
+//! The request holds the scalars and points of the verification
+//! equation, split into chunks.  The helper computes the multiscalar
+//! multiplication of each chunk and returns the partial results in a
+//! [`DelegationResponse`].  Finally, the verifier calls
+//! [`DelegationRequest::check_delegation`], which checks the partial
+//! results against the verification equation and recomputes a few
+//! randomly chosen chunks itself.
+//!
+//! # Soundness
+//!
+//! The soundness of a delegated verification is bounded by the spot
+//! checks: with \\(k\\) chunks and \\(s\\) spot checks, a helper which
+//! lies about a single chunk goes unnoticed with probability
+//! \\(1 - s/k\\).  Choosing \\(s = k\\) gives full verification, and
+//! saves no work.
+//!
+//! The verifier also multiplies the scalars of each chunk \\(i\\) by a
+//! secret random weight \\(w\_i\\) before handing them to the helper,
+//! and requires the partial results \\(Q\_i\\) to satisfy
+//! \\(\sum\_i w\_i^{-1} Q\_i = 0\\).  This only helps against a helper
+//! which cannot recompute the unweighted scalars.  They are derived
+//! from the proof and the value commitments alone, so a helper which
+//! knows them, for instance because they are published, divides each
+//! weighted scalar by its unweighted one to learn \\(w\_i\\), and can
+//! then make the partial results of an invalid proof pass the weighted
+//! check.  Only a helper which sees nothing but the request is forced
+//! to guess how its lies are scaled.
+
+use std::iter;
+use std::ops::Range;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, VartimeMultiscalarMul};
+use rand::{self, Rng};
+
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::VerificationTerms;
+use scalar;
+
+/// The multiscalar multiplication of a range proof verification, as
+/// handed by the verifier to a helper.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DelegationRequest {
+    n: usize,
+    m: usize,
+    num_chunks: usize,
+    /// Scalars for `dynamic_points`, then \\(\tilde{B}\\), \\(B\\),
+    /// \\(\mathbf{G}\\) and \\(\mathbf{H}\\), each multiplied by the
+    /// weight of its chunk.
+    scalars: Vec<Scalar>,
+    dynamic_points: Vec<CompressedRistretto>,
+    /// The secret weights of the chunks, which are known only to the
+    /// verifier and never sent to the helper.
+    #[serde(skip)]
+    weights: Vec<Scalar>,
+}
+
+/// The partial results computed by a helper for a [`DelegationRequest`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DelegationResponse {
+    partial_results: Vec<CompressedRistretto>,
+}
+
+impl DelegationRequest {
+    /// Creates a request from the terms of a verification equation.
+    pub(crate) fn new(terms: VerificationTerms, num_chunks: usize) -> DelegationRequest {
+        let mut scalars = terms.dynamic_scalars;
+        scalars.push(terms.B_blinding_scalar);
+        scalars.push(terms.B_scalar);
+        scalars.extend_from_slice(&terms.g_scalars);
+        scalars.extend_from_slice(&terms.h_scalars);
+
+        let num_chunks = num_chunks.max(1).min(scalars.len());
+        let weights = scalar::random_vec(&mut rand::thread_rng(), num_chunks);
+
+        let mut request = DelegationRequest {
+            n: terms.n,
+            m: terms.m,
+            num_chunks,
+            scalars,
+            dynamic_points: terms.dynamic_points,
+            weights,
+        };
+        for i in 0..num_chunks {
+            let (range, w_i) = (request.chunk_range(i), request.weights[i]);
+            for s in &mut request.scalars[range] {
+                *s *= w_i;
+            }
+        }
+        request
+    }
+
+    /// Returns the number of chunks the computation is split into.
+    pub fn num_chunks(&self) -> usize {
+        self.num_chunks
+    }
+
+    /// Computes the partial results of all chunks.  This is the work
+    /// done by the helper.
+    pub fn compute(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+    ) -> Result<DelegationResponse, ProofError> {
+        self.check_num_chunks()?;
+        let partial_results = (0..self.num_chunks)
+            .map(|i| self.chunk_result(i, bp_gens, pc_gens).map(|P| P.compress()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(DelegationResponse { partial_results })
+    }
+
+    /// Checks the partial results returned by a helper, recomputing
+    /// `spot_checks` randomly chosen chunks.
+    ///
+    /// Returns `Ok(())` if the weighted results sum to the identity and
+    /// all the recomputed chunks match.  See the [module
+    /// documentation](index.html#soundness) for what this implies
+    /// about the proof: unless the helper cannot know the proof, it is
+    /// only as sound as the `spot_checks`.
+    ///
+    /// Returns `ProofError::FormatError` if the request was not created
+    /// by [`RangeProof::delegate_verification`](::RangeProof::delegate_verification),
+    /// for instance if it was deserialized, since it then lacks the
+    /// secret weights.
+    pub fn check_delegation(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        response: &DelegationResponse,
+        spot_checks: usize,
+    ) -> Result<(), ProofError> {
+        self.check_num_chunks()?;
+        if self.weights.len() != self.num_chunks
+            || response.partial_results.len() != self.num_chunks
+        {
+            return Err(ProofError::FormatError);
+        }
+
+        let partial_results = response
+            .partial_results
+            .iter()
+            .map(|P| P.decompress())
+            .collect::<Option<Vec<_>>>()
+            .ok_or(ProofError::FormatError)?;
+
+        let mut inverse_weights = self.weights.clone();
+        scalar::batch_invert(&mut inverse_weights);
+        let sum = RistrettoPoint::vartime_multiscalar_mul(&inverse_weights, &partial_results);
+        if sum != RistrettoPoint::identity() {
+            return Err(ProofError::VerificationError);
+        }
+
+        // Choose distinct chunks with a partial Fisher-Yates shuffle.
+        let mut rng = rand::thread_rng();
+        let mut chunks: Vec<usize> = (0..self.num_chunks).collect();
+        for j in 0..spot_checks.min(self.num_chunks) {
+            let k = rng.gen_range(j, self.num_chunks);
+            chunks.swap(j, k);
+
+            let i = chunks[j];
+            if self.chunk_result(i, bp_gens, pc_gens)? != partial_results[i] {
+                return Err(ProofError::VerificationError);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the number of chunks, which may come from untrusted
+    /// bytes, is between one and the number of terms.
+    fn check_num_chunks(&self) -> Result<(), ProofError> {
+        if self.num_chunks == 0 || self.num_chunks > self.scalars.len() {
+            return Err(ProofError::FormatError);
+        }
+        Ok(())
+    }
+
+    /// Returns the range of term indices in chunk `i`.
+    fn chunk_range(&self, i: usize) -> Range<usize> {
+        let len = self.scalars.len();
+        let chunk_size = (len + self.num_chunks - 1) / self.num_chunks;
+        let start = (i * chunk_size).min(len);
+        let end = (start + chunk_size).min(len);
+        start..end
+    }
+
+    /// Computes the multiscalar multiplication of chunk `i`.
+    fn chunk_result(
+        &self,
+        i: usize,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+    ) -> Result<RistrettoPoint, ProofError> {
+        let (n, m) = (self.n.next_power_of_two(), self.m);
+        if bp_gens.gens_capacity < n || bp_gens.party_capacity < m {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        // The request may have been deserialized from untrusted bytes.
+        if self.scalars.len() != self.dynamic_points.len() + 2 + 2 * n * m {
+            return Err(ProofError::FormatError);
+        }
+
+        let range = self.chunk_range(i);
+        let num_dynamic = self.dynamic_points.len();
+
+        // Only decompress the dynamic points which are in the chunk.
+        let dynamic_range = range.start.min(num_dynamic)..range.end.min(num_dynamic);
+        let static_start = range.start.max(num_dynamic) - num_dynamic;
+        let static_end = range.end.max(num_dynamic) - num_dynamic;
+
+        let points: Vec<Option<RistrettoPoint>> = self.dynamic_points[dynamic_range]
+            .iter()
+            .map(|P| P.decompress())
+            .chain(
+                iter::once(&pc_gens.B_blinding)
+                    .chain(iter::once(&pc_gens.B))
+                    .chain(bp_gens.G(n, m))
+                    .chain(bp_gens.H(n, m))
+                    .skip(static_start)
+                    .take(static_end - static_start)
+                    .map(|&P| Some(P)),
+            ).collect();
+
+        RistrettoPoint::optional_multiscalar_mul(&self.scalars[range], points)
+            .ok_or(ProofError::VerificationError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bincode;
+    use merlin::Transcript;
+    use range_proof::RangeProof;
+
+    fn proof_for(
+        value: u64,
+        claimed_value: u64,
+    ) -> (RangeProof, CompressedRistretto, BulletproofGens) {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let (proof, _) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"DelegationTest"),
+            value,
+            &blinding,
+            32,
+        ).unwrap();
+        let V = pc_gens
+            .commit(Scalar::from(claimed_value), blinding)
+            .compress();
+        (proof, V, bp_gens)
+    }
+
+    fn request_for(value: u64, claimed_value: u64) -> (DelegationRequest, BulletproofGens) {
+        let (proof, V, bp_gens) = proof_for(value, claimed_value);
+        let request = proof
+            .delegate_verification(
+                &bp_gens,
+                &mut Transcript::new(b"DelegationTest"),
+                &[V],
+                32,
+                8,
+            ).unwrap();
+        (request, bp_gens)
+    }
+
+    #[test]
+    fn honest_helper_valid_proof() {
+        let pc_gens = PedersenGens::default();
+        let (request, bp_gens) = request_for(1234, 1234);
+        assert_eq!(request.num_chunks(), 8);
+
+        let response = request.compute(&bp_gens, &pc_gens).unwrap();
+        assert!(
+            request
+                .check_delegation(&bp_gens, &pc_gens, &response, 2)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn honest_helper_invalid_proof() {
+        let pc_gens = PedersenGens::default();
+        let (request, bp_gens) = request_for(1234, 1235);
+
+        let response = request.compute(&bp_gens, &pc_gens).unwrap();
+        assert_eq!(
+            request.check_delegation(&bp_gens, &pc_gens, &response, 0),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn cheating_helper_is_caught() {
+        let pc_gens = PedersenGens::default();
+        let (request, bp_gens) = request_for(1234, 1235);
+
+        // Adjust the first partial result so that the sum is the
+        // identity, without knowing the weights.
+        let mut response = request.compute(&bp_gens, &pc_gens).unwrap();
+        let points: Vec<RistrettoPoint> = response
+            .partial_results
+            .iter()
+            .map(|P| P.decompress().unwrap())
+            .collect();
+        let sum = points
+            .iter()
+            .fold(RistrettoPoint::identity(), |sum, P| sum + P);
+        response.partial_results[0] = (points[0] - sum).compress();
+        assert_eq!(
+            request.check_delegation(&bp_gens, &pc_gens, &response, 0),
+            Err(ProofError::VerificationError)
+        );
+
+        // Shifting a point between two chunks of a valid proof also
+        // changes the weighted sum.
+        let (request, bp_gens) = request_for(1234, 1234);
+        let mut response = request.compute(&bp_gens, &pc_gens).unwrap();
+        let shift = pc_gens.B;
+        let Q_0 = response.partial_results[0].decompress().unwrap();
+        let Q_1 = response.partial_results[1].decompress().unwrap();
+        response.partial_results[0] = (Q_0 + shift).compress();
+        response.partial_results[1] = (Q_1 - shift).compress();
+        assert_eq!(
+            request.check_delegation(&bp_gens, &pc_gens, &response, 0),
+            Err(ProofError::VerificationError)
+        );
+
+        response.partial_results.pop();
+        assert_eq!(
+            request.check_delegation(&bp_gens, &pc_gens, &response, 8),
+            Err(ProofError::FormatError)
+        );
+    }
+
+    #[test]
+    fn helper_knowing_the_proof_is_only_caught_by_spot_checks() {
+        let pc_gens = PedersenGens::default();
+        let (proof, V, bp_gens) = proof_for(1234, 1235);
+        let request = proof
+            .delegate_verification(
+                &bp_gens,
+                &mut Transcript::new(b"DelegationTest"),
+                &[V],
+                32,
+                8,
+            ).unwrap();
+
+        // The helper replays the transcript to recompute the
+        // unweighted scalars, and divides to learn the weights.
+        let terms = proof
+            .verification_terms(&bp_gens, &mut Transcript::new(b"DelegationTest"), &[V], 32)
+            .unwrap();
+        let mut unweighted = terms.dynamic_scalars;
+        unweighted.push(terms.B_blinding_scalar);
+        unweighted.push(terms.B_scalar);
+        unweighted.extend_from_slice(&terms.g_scalars);
+        unweighted.extend_from_slice(&terms.h_scalars);
+        let weights: Vec<Scalar> = (0..request.num_chunks())
+            .map(|i| {
+                let t = request.chunk_range(i).start;
+                request.scalars[t] * unweighted[t].invert()
+            }).collect();
+        assert_eq!(weights, request.weights);
+
+        // It then moves the failure of the verification equation into
+        // the last chunk, where the weighted check cannot see it.
+        let mut response = request.compute(&bp_gens, &pc_gens).unwrap();
+        let points: Vec<RistrettoPoint> = response
+            .partial_results
+            .iter()
+            .map(|P| P.decompress().unwrap())
+            .collect();
+        let mut inverse_weights = weights.clone();
+        scalar::batch_invert(&mut inverse_weights);
+        let failure = RistrettoPoint::vartime_multiscalar_mul(&inverse_weights, &points);
+        assert!(failure != RistrettoPoint::identity());
+        response.partial_results[7] = (points[7] - weights[7] * failure).compress();
+
+        assert!(
+            request
+                .check_delegation(&bp_gens, &pc_gens, &response, 0)
+                .is_ok()
+        );
+        assert_eq!(
+            request.check_delegation(&bp_gens, &pc_gens, &response, 8),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn malformed_request_is_rejected() {
+        let pc_gens = PedersenGens::default();
+        let (request, bp_gens) = request_for(1234, 1234);
+        let response = request.compute(&bp_gens, &pc_gens).unwrap();
+
+        for &num_chunks in &[0, request.scalars.len() + 1] {
+            let mut malformed = request.clone();
+            malformed.num_chunks = num_chunks;
+            assert_eq!(
+                malformed.compute(&bp_gens, &pc_gens).unwrap_err(),
+                ProofError::FormatError
+            );
+            assert_eq!(
+                malformed.check_delegation(&bp_gens, &pc_gens, &response, 0),
+                Err(ProofError::FormatError)
+            );
+        }
+
+        // The helper can compute a deserialized request, but it lacks
+        // the weights needed to check the response.
+        let bytes = bincode::serialize(&request).unwrap();
+        let deserialized: DelegationRequest = bincode::deserialize(&bytes).unwrap();
+        let response = deserialized.compute(&bp_gens, &pc_gens).unwrap();
+        assert!(
+            request
+                .check_delegation(&bp_gens, &pc_gens, &response, 8)
+                .is_ok()
+        );
+        assert_eq!(
+            deserialized.check_delegation(&bp_gens, &pc_gens, &response, 8),
+            Err(ProofError::FormatError)
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! The `header` module contains a self-describing encoding of a
+//! [`RangeProof`](::RangeProof), which carries its bitsize and
+//! aggregation size.
+//!
+//! The plain encoding from [`RangeProof::to_bytes`] only determines
+//! the product \\(n \cdot m\\) of the padded bitsize and the
+//! aggregation size, so the verifier must be told \\(n\\) out of band.
+//! The encoding with a header prefixes the proof with two bytes,
+//! \\(n\\) and \\(\lg m\\), which are checked against the length of
+//! the inner-product argument when the proof is parsed.
+
+use super::RangeProof;
+use errors::ProofError;
+
+/// The bitsize and aggregation size of a [`RangeProof`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RangeProofHeader {
+    n: usize,
+    m: usize,
+}
+
+impl RangeProofHeader {
+    /// The length of the encoded header in bytes.
+    pub const SIZE: usize = 2;
+
+    /// Creates a header for a proof of `m` values in the range
+    /// \\([0, 2^n)\\).
+    ///
+    /// Returns an error if `n` is zero or greater than \\(128\\), or if
+    /// `m` is not a power of two.
+    pub fn new(n: usize, m: usize) -> Result<RangeProofHeader, ProofError> {
+        if n == 0 || n > 128 {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if !m.is_power_of_two() || m > 1 << 31 {
+            return Err(ProofError::InvalidAggregation);
+        }
+        Ok(RangeProofHeader { n, m })
+    }
+
+    /// Returns the bitsize \\(n\\) of the proof.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns the aggregation size \\(m\\) of the proof.
+    pub fn m(&self) -> usize {
+        self.m
+    }
+
+    /// Checks that the length of the inner-product argument of `proof`
+    /// matches \\(n \cdot m\\), with \\(n\\) padded to a power of two.
+    pub fn check(&self, proof: &RangeProof) -> Result<(), ProofError> {
+        if self.n.next_power_of_two() * self.m == 1 << proof.implied_log_size() {
+            Ok(())
+        } else {
+            Err(ProofError::ProofSizeMismatch)
+        }
+    }
+
+    /// Serializes the header as the bytes \\(n\\) and \\(\lg m\\).
+    pub fn to_bytes(&self) -> [u8; 2] {
+        [self.n as u8, self.m.trailing_zeros() as u8]
+    }
+
+    /// Deserializes a header from its two bytes.
+    pub fn from_bytes(bytes: [u8; 2]) -> Result<RangeProofHeader, ProofError> {
+        if bytes[1] > 31 {
+            return Err(ProofError::FormatError);
+        }
+        RangeProofHeader::new(bytes[0] as usize, 1 << bytes[1]).map_err(|_| ProofError::FormatError)
+    }
+}
+
+impl RangeProof {
+    /// Serializes the proof, prefixed with a header carrying its
+    /// bitsize `n` and aggregation size `m`.
+    ///
+    /// `m` is the number of values of the proof.  If it is not a power
+    /// of two, the header carries the padded aggregation size.
+    ///
+    /// Returns an error if the parameters are invalid or do not match
+    /// the length of the proof.
+    pub fn to_bytes_with_header(&self, n: usize, m: usize) -> Result<Vec<u8>, ProofError> {
+        if m == 0 {
+            return Err(ProofError::InvalidAggregation);
+        }
+        let header = RangeProofHeader::new(n, m.next_power_of_two())?;
+        header.check(self)?;
+
+        let mut buf = header.to_bytes().to_vec();
+        buf.extend_from_slice(&self.to_bytes());
+        Ok(buf)
+    }
+
+    /// Deserializes a proof encoded by
+    /// [`RangeProof::to_bytes_with_header`], returning it together with
+    /// its header.
+    ///
+    /// Returns [`ProofError::ProofSizeMismatch`] if the header does not
+    /// match the length of the proof.
+    pub fn from_bytes_with_header(
+        slice: &[u8],
+    ) -> Result<(RangeProof, RangeProofHeader), ProofError> {
+        if slice.len() < RangeProofHeader::SIZE {
+            return Err(ProofError::FormatError);
+        }
+        let header = RangeProofHeader::from_bytes([slice[0], slice[1]])?;
+        let proof = RangeProof::from_bytes(&slice[RangeProofHeader::SIZE..])?;
+        header.check(&proof)?;
+        Ok((proof, header))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+    use merlin::Transcript;
+    use rand;
+
+    use generators::{BulletproofGens, PedersenGens};
+
+    #[test]
+    fn header_roundtrip() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 2);
+        let blindings: Vec<_> = (0..2)
+            .map(|_| Scalar::random(&mut rand::thread_rng()))
+            .collect();
+
+        let (proof, value_commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"HeaderTest"),
+            &[100, 200],
+            &blindings,
+            12,
+        ).unwrap();
+
+        let bytes = proof.to_bytes_with_header(12, 2).unwrap();
+        let (proof, header) = RangeProof::from_bytes_with_header(&bytes).unwrap();
+        assert_eq!((header.n(), header.m()), (12, 2));
+        assert_eq!(value_commitments.len(), header.m());
+        assert!(
+            proof
+                .verify_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"HeaderTest"),
+                    &value_commitments,
+                    header.n(),
+                ).is_ok()
+        );
+
+        // The header must agree with the length of the proof
+        assert_eq!(
+            proof.to_bytes_with_header(32, 2),
+            Err(ProofError::ProofSizeMismatch)
+        );
+        let mut bytes = bytes;
+        bytes[1] = 0;
+        assert_eq!(
+            RangeProof::from_bytes_with_header(&bytes).map(|_| ()),
+            Err(ProofError::ProofSizeMismatch)
+        );
+    }
+}
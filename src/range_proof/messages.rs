@@ -3,14 +3,33 @@
 //!
 //! For more explanation of how the `dealer`, `party`, and `messages` modules orchestrate the protocol execution, see
 //! [the API for the aggregated multiparty computation protocol](../aggregation/index.html#api-for-the-aggregated-multiparty-computation-protocol).
+//!
+//! To run the protocol across machines, every message can be sent
+//! either with serde or in a canonical byte encoding, given by its
+//! `to_bytes` and `from_bytes` functions.  Points are encoded
+//! compressed and scalars in their canonical 32-byte form, and
+//! decoding fails with
+//! [`ProofError::InvalidPoint`](::ProofError::InvalidPoint) on an
+//! invalid point,
+//! [`ProofError::InvalidScalar`](::ProofError::InvalidScalar) on a
+//! non-canonical scalar and
+//! [`ProofError::InvalidLength`](::ProofError::InvalidLength) on an
+//! encoding of the wrong length.
 
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::{self, RngCore};
 
+use errors::ProofError;
 use generators::{BulletproofGens, PedersenGens};
+use range_proof::blame::AuditFailure;
+use scalar;
+use transcript::TranscriptProtocol;
+use util::read32;
 
 /// A commitment to the bits of a party's value.
-#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
 pub struct BitCommitment {
     pub(super) V_j: CompressedRistretto,
     pub(super) A_j: RistrettoPoint,
@@ -24,8 +43,64 @@ pub struct BitChallenge {
     pub(super) z: Scalar,
 }
 
+impl BitCommitment {
+    /// Serializes the commitment into the 96 bytes of the points
+    /// \\(V\_j, A\_j, S\_j\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(96);
+        buf.extend_from_slice(self.V_j.as_bytes());
+        buf.extend_from_slice(self.A_j.compress().as_bytes());
+        buf.extend_from_slice(self.S_j.compress().as_bytes());
+        buf
+    }
+
+    /// Deserializes the commitment from a byte slice.
+    pub fn from_bytes(slice: &[u8]) -> Result<BitCommitment, ProofError> {
+        if slice.len() != 96 {
+            return Err(ProofError::InvalidLength {
+                length: slice.len(),
+            });
+        }
+        Ok(BitCommitment {
+            V_j: CompressedRistretto(read32(slice)),
+            A_j: decode_point(slice, 1)?,
+            S_j: decode_point(slice, 2)?,
+        })
+    }
+}
+
+impl BitChallenge {
+    /// Serializes the challenge into the 64 bytes of \\(y, z\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        scalar::encode_vec(&[self.y, self.z])
+    }
+
+    /// Deserializes the challenge from a byte slice.
+    pub fn from_bytes(slice: &[u8]) -> Result<BitChallenge, ProofError> {
+        if slice.len() != 64 {
+            return Err(ProofError::InvalidLength {
+                length: slice.len(),
+            });
+        }
+        Ok(BitChallenge {
+            y: scalar::decode_element(slice, 0)?,
+            z: scalar::decode_element(slice, 1)?,
+        })
+    }
+
+    /// Returns the challenge \\(y\\).
+    pub fn y(&self) -> Scalar {
+        self.y
+    }
+
+    /// Returns the challenge \\(z\\).
+    pub fn z(&self) -> Scalar {
+        self.z
+    }
+}
+
 /// A commitment to a party's polynomial coefficents.
-#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
 pub struct PolyCommitment {
     pub(super) T_1_j: RistrettoPoint,
     pub(super) T_2_j: RistrettoPoint,
@@ -37,9 +112,57 @@ pub struct PolyChallenge {
     pub(super) x: Scalar,
 }
 
+impl PolyCommitment {
+    /// Serializes the commitment into the 64 bytes of the points
+    /// \\(T\_{1,j}, T\_{2,j}\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(64);
+        buf.extend_from_slice(self.T_1_j.compress().as_bytes());
+        buf.extend_from_slice(self.T_2_j.compress().as_bytes());
+        buf
+    }
+
+    /// Deserializes the commitment from a byte slice.
+    pub fn from_bytes(slice: &[u8]) -> Result<PolyCommitment, ProofError> {
+        if slice.len() != 64 {
+            return Err(ProofError::InvalidLength {
+                length: slice.len(),
+            });
+        }
+        Ok(PolyCommitment {
+            T_1_j: decode_point(slice, 0)?,
+            T_2_j: decode_point(slice, 1)?,
+        })
+    }
+}
+
+impl PolyChallenge {
+    /// Serializes the challenge into the 32 bytes of \\(x\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.x.as_bytes().to_vec()
+    }
+
+    /// Deserializes the challenge from a byte slice.
+    pub fn from_bytes(slice: &[u8]) -> Result<PolyChallenge, ProofError> {
+        if slice.len() != 32 {
+            return Err(ProofError::InvalidLength {
+                length: slice.len(),
+            });
+        }
+        Ok(PolyChallenge {
+            x: scalar::decode_element(slice, 0)?,
+        })
+    }
+
+    /// Returns the challenge \\(x\\).
+    pub fn x(&self) -> Scalar {
+        self.x
+    }
+}
+
 /// A party's proof share, ready for aggregation into the final
 /// [`RangeProof`](::RangeProof).
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub struct ProofShare {
     pub(super) t_x: Scalar,
     pub(super) t_x_blinding: Scalar,
@@ -49,26 +172,89 @@ pub struct ProofShare {
 }
 
 impl ProofShare {
+    /// Creates a share from the evaluations \\(t\_x, \tilde{t}\_x,
+    /// \tilde{e}\\) and the vectors \\(\mathbf{l}, \mathbf{r}\\), for
+    /// instance as computed by a
+    /// [`PartySecrets`](::aggregation::secrets::PartySecrets)
+    /// implementation.
+    pub fn new(
+        t_x: Scalar,
+        t_x_blinding: Scalar,
+        e_blinding: Scalar,
+        l_vec: Vec<Scalar>,
+        r_vec: Vec<Scalar>,
+    ) -> ProofShare {
+        ProofShare {
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            l_vec,
+            r_vec,
+        }
+    }
+
+    /// Serializes the share into \\(3 + 2n\\) scalars, for vectors of
+    /// length \\(n\\):
+    /// * the scalars \\(t\_x, \tilde{t}\_x, \tilde{e}\\),
+    /// * the \\(n\\) scalars of \\(\mathbf{l}\\),
+    /// * the \\(n\\) scalars of \\(\mathbf{r}\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity((3 + 2 * self.l_vec.len()) * 32);
+        buf.extend_from_slice(self.t_x.as_bytes());
+        buf.extend_from_slice(self.t_x_blinding.as_bytes());
+        buf.extend_from_slice(self.e_blinding.as_bytes());
+        buf.extend_from_slice(&scalar::encode_vec(&self.l_vec));
+        buf.extend_from_slice(&scalar::encode_vec(&self.r_vec));
+        buf
+    }
+
+    /// Deserializes the share from a byte slice.
+    pub fn from_bytes(slice: &[u8]) -> Result<ProofShare, ProofError> {
+        if slice.len() % 32 != 0 || slice.len() < 3 * 32 || (slice.len() / 32 - 3) % 2 != 0 {
+            return Err(ProofError::InvalidLength {
+                length: slice.len(),
+            });
+        }
+        let n = (slice.len() / 32 - 3) / 2;
+        let decode_vec = |start: usize| -> Result<Vec<Scalar>, ProofError> {
+            (start..start + n)
+                .map(|i| scalar::decode_element(slice, i))
+                .collect()
+        };
+        Ok(ProofShare {
+            t_x: scalar::decode_element(slice, 0)?,
+            t_x_blinding: scalar::decode_element(slice, 1)?,
+            e_blinding: scalar::decode_element(slice, 2)?,
+            l_vec: decode_vec(3)?,
+            r_vec: decode_vec(3 + n)?,
+        })
+    }
+
     /// Audit an individual proof share to determine whether it is
-    /// malformed.
+    /// malformed, and which check it fails.
     pub(super) fn audit_share(
         &self,
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
+        n: usize,
         j: usize,
         bit_commitment: &BitCommitment,
         bit_challenge: &BitChallenge,
         poly_commitment: &PolyCommitment,
         poly_challenge: &PolyChallenge,
-    ) -> Result<(), ()> {
+    ) -> Result<(), AuditFailure> {
         use std::iter;
 
         use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
 
-        use inner_product_proof::inner_product;
+        use math::{self, inner_product};
         use util;
 
-        let n = self.l_vec.len();
+        // The vectors are padded to a power of two
+        let padded_n = n.next_power_of_two();
+        if self.l_vec.len() != padded_n || self.r_vec.len() != padded_n {
+            return Err(AuditFailure::ShareSize);
+        }
         let (y, z) = (&bit_challenge.y, &bit_challenge.z);
         let x = &poly_challenge.x;
 
@@ -76,20 +262,20 @@ impl ProofShare {
         let zz = z * z;
         let minus_z = -z;
         let z_j = util::scalar_exp_vartime(z, j as u64); // z^j
-        let y_jn = util::scalar_exp_vartime(y, (j * n) as u64); // y^(j*n)
+        let y_jn = util::scalar_exp_vartime(y, (j * padded_n) as u64); // y^(j*n)
         let y_jn_inv = y_jn.invert(); // y^(-j*n)
         let y_inv = y.invert(); // y^(-1)
 
         if self.t_x != inner_product(&self.l_vec, &self.r_vec) {
-            return Err(());
+            return Err(AuditFailure::InnerProduct);
         }
 
         let g = self.l_vec.iter().map(|l_i| minus_z - l_i);
         let h = self
             .r_vec
             .iter()
-            .zip(util::exp_iter(Scalar::from(2u64)))
-            .zip(util::exp_iter(y_inv))
+            .zip(super::padded_powers_of_2(n))
+            .zip(math::exp_iter(y_inv))
             .map(|((r_i, exp_2), exp_y_inv)| {
                 z + exp_y_inv * y_jn_inv * (-r_i) + exp_y_inv * y_jn_inv * (zz * z_j * exp_2)
             });
@@ -103,17 +289,20 @@ impl ProofShare {
             iter::once(&bit_commitment.A_j)
                 .chain(iter::once(&bit_commitment.S_j))
                 .chain(iter::once(&pc_gens.B_blinding))
-                .chain(bp_gens.share(j).G(n))
-                .chain(bp_gens.share(j).H(n)),
+                .chain(bp_gens.share(j).G(padded_n))
+                .chain(bp_gens.share(j).H(padded_n)),
         );
         if !P_check.is_identity() {
-            return Err(());
+            return Err(AuditFailure::VectorCommitment);
         }
 
-        let V_j = bit_commitment.V_j.decompress().ok_or(())?;
+        let V_j = bit_commitment
+            .V_j
+            .decompress()
+            .ok_or(AuditFailure::ValueCommitment)?;
 
-        let sum_of_powers_y = util::sum_of_powers(&y, n);
-        let sum_of_powers_2 = util::sum_of_powers(&Scalar::from(2u64), n);
+        let sum_of_powers_y = math::sum_of_powers(&y, padded_n);
+        let sum_of_powers_2 = math::sum_of_powers_of_2(n);
         let delta = (z - zz) * sum_of_powers_y * y_jn - z * zz * sum_of_powers_2 * z_j;
         let t_check = RistrettoPoint::vartime_multiscalar_mul(
             iter::once(zz * z_j)
@@ -131,7 +320,152 @@ impl ProofShare {
         if t_check.is_identity() {
             Ok(())
         } else {
-            Err(())
+            Err(AuditFailure::PolyEvaluation)
+        }
+    }
+}
+
+/// The context of an aggregation session: a nonce chosen for the
+/// session, and the identity of the party at each position.
+///
+/// The dealer broadcasts the binding to the parties, and everyone
+/// [`bind`](SessionBinding::bind)s it into their transcript before
+/// the protocol starts, so that the challenges, and the proof, depend
+/// on the session and on which party holds which position.  The
+/// messages of a party in one session are then of no use in another
+/// session, even with the same parameters.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct SessionBinding {
+    nonce: [u8; 32],
+    identities: Vec<Vec<u8>>,
+}
+
+impl SessionBinding {
+    /// Creates a binding for a session with the given `nonce`, in
+    /// which the party at position \\(j\\) has the identity
+    /// `identities[j]`, for instance the bytes of its public key.
+    pub fn new(nonce: [u8; 32], identities: Vec<Vec<u8>>) -> SessionBinding {
+        SessionBinding { nonce, identities }
+    }
+
+    /// Creates a binding for a session with a random nonce.
+    pub fn random(identities: Vec<Vec<u8>>) -> SessionBinding {
+        let mut nonce = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        SessionBinding::new(nonce, identities)
+    }
+
+    /// Returns the nonce of the session.
+    pub fn nonce(&self) -> &[u8; 32] {
+        &self.nonce
+    }
+
+    /// Returns the number of parties of the session.
+    pub fn num_parties(&self) -> usize {
+        self.identities.len()
+    }
+
+    /// Returns the identity of the party at position `j`, which the
+    /// party can compare with its own before taking part.
+    pub fn identity(&self, j: usize) -> Option<&[u8]> {
+        self.identities.get(j).map(|identity| &identity[..])
+    }
+
+    /// Commits the session nonce, and the position and identity of
+    /// each party, to the `transcript`.
+    pub fn bind(&self, transcript: &mut Transcript) {
+        transcript.mpc_session_domain_sep(&self.nonce, self.identities.len() as u64);
+        for (j, identity) in self.identities.iter().enumerate() {
+            transcript.mpc_party_identity(j as u64, identity);
+        }
+    }
+}
+
+/// A message sent by the dealer to a party, for transports which
+/// deliver the messages of every round over the same channel.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum DealerMessage {
+    /// The position of the party in the aggregation.
+    Position(usize),
+    /// The challenge of the first round.
+    BitChallenge(BitChallenge),
+    /// The challenge of the second round.
+    PolyChallenge(PolyChallenge),
+}
+
+/// A message sent by a party to the dealer, for transports which
+/// deliver the messages of every round over the same channel.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum PartyMessage {
+    /// The commitment of the first round.
+    BitCommitment(BitCommitment),
+    /// The commitment of the second round.
+    PolyCommitment(PolyCommitment),
+    /// The proof share.
+    ProofShare(ProofShare),
+}
+
+impl DealerMessage {
+    /// Returns the position, or `None` for a message of another round.
+    pub fn position(self) -> Option<usize> {
+        match self {
+            DealerMessage::Position(j) => Some(j),
+            _ => None,
+        }
+    }
+
+    /// Returns the bit challenge, or `None` for a message of another
+    /// round.
+    pub fn bit_challenge(self) -> Option<BitChallenge> {
+        match self {
+            DealerMessage::BitChallenge(challenge) => Some(challenge),
+            _ => None,
+        }
+    }
+
+    /// Returns the poly challenge, or `None` for a message of another
+    /// round.
+    pub fn poly_challenge(self) -> Option<PolyChallenge> {
+        match self {
+            DealerMessage::PolyChallenge(challenge) => Some(challenge),
+            _ => None,
         }
     }
 }
+
+impl PartyMessage {
+    /// Returns the bit commitment, or `None` for a message of another
+    /// round.
+    pub fn bit_commitment(self) -> Option<BitCommitment> {
+        match self {
+            PartyMessage::BitCommitment(commitment) => Some(commitment),
+            _ => None,
+        }
+    }
+
+    /// Returns the poly commitment, or `None` for a message of another
+    /// round.
+    pub fn poly_commitment(self) -> Option<PolyCommitment> {
+        match self {
+            PartyMessage::PolyCommitment(commitment) => Some(commitment),
+            _ => None,
+        }
+    }
+
+    /// Returns the proof share, or `None` for a message of another
+    /// round.
+    pub fn proof_share(self) -> Option<ProofShare> {
+        match self {
+            PartyMessage::ProofShare(share) => Some(share),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes the compressed point at position `index` of a slice of
+/// 32-byte elements.
+fn decode_point(slice: &[u8], index: usize) -> Result<RistrettoPoint, ProofError> {
+    CompressedRistretto(read32(&slice[index * 32..]))
+        .decompress()
+        .ok_or(ProofError::InvalidPoint { index })
+}
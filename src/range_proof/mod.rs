@@ -1,29 +1,66 @@
 #![allow(non_snake_case)]
-#![doc(include = "../docs/range-proof-protocol.md")]
+#![cfg_attr(feature = "nightly", doc(include = "../docs/range-proof-protocol.md"))]
 
-use rand;
+use rand::{self, CryptoRng, RngCore};
 
+use std::cmp;
+use std::hash::{Hash, Hasher};
 use std::iter;
 
-use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+use curve25519_dalek::traits::{Identity, IsIdentity};
 use merlin::Transcript;
+use subtle::ConstantTimeEq;
 
+use commitment::Opening;
 use errors::ProofError;
 use generators::{BulletproofGens, PedersenGens};
 use inner_product_proof::InnerProductProof;
+use math;
+use multiscalar;
+use range_proof::bitsize::{BitSize, ProvableValue};
+use range_proof::delegation::DelegationRequest;
+use scalar;
 use transcript::TranscriptProtocol;
-use util;
 
 use serde::de::Visitor;
 use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 
 // Modules for MPC protocol
 
+#[cfg(feature = "futures")]
+pub mod async_session;
+pub mod bitsize;
+pub mod blame;
 pub mod dealer;
+pub mod delegation;
 pub mod messages;
 pub mod party;
+pub mod secrets;
+#[cfg(feature = "service")]
+pub mod service;
+pub mod session_manager;
+pub mod threshold;
+pub mod transport;
+
+mod batch;
+mod builder;
+mod checkpoint;
+mod header;
+mod report;
+mod rewind;
+mod session;
+
+pub use self::batch::BatchVerifier;
+pub(crate) use self::batch::BatchTerms;
+#[cfg(feature = "rayon")]
+pub use self::batch::{BatchOptions, FailureMode};
+pub use self::builder::RangeProofBuilder;
+pub use self::header::RangeProofHeader;
+pub use self::report::VerificationReport;
+pub use self::rewind::MEMO_SIZE;
+pub use self::session::{RangeProver, RangeVerifier, VerifierNonce};
 
 /// The `RangeProof` struct represents a proof that one or more values
 /// are in a range.
@@ -34,13 +71,20 @@ pub mod party;
 ///
 /// The bitsize of the range, as well as the list of commitments to
 /// the values, are not included in the proof, and must be known to
-/// the verifier.
+/// the verifier.  The encoding from
+/// [`RangeProof::to_bytes_with_header`] carries the bitsize and
+/// aggregation size, see [`RangeProofHeader`].
 ///
-/// This implementation requires that both the bitsize `n` and the
-/// aggregation size `m` be powers of two, so that `n = 8, 16, 32, 64`
-/// and `m = 1, 2, 4, 8, 16, ...`.  Note that the aggregation size is
-/// not given as an explicit parameter, but is determined by the
-/// number of values or commitments passed to the prover or verifier.
+/// This implementation requires that the bitsize `n` be at most
+/// \\(128\\).  A bitsize which is not a power of two is padded
+/// internally to the next power of two, so the proof has the same size
+/// as for the padded bitsize.  Likewise, an aggregation size `m` which
+/// is not a power of two is padded with values \\(0\\) committed with
+/// the blinding factor \\(0\\), whose commitments are the identity
+/// point and are neither returned by the prover nor passed to the
+/// verifier.  Note that the aggregation size is not given as an
+/// explicit parameter, but is determined by the number of values or
+/// commitments passed to the prover or verifier.
 ///
 /// # Note
 ///
@@ -69,6 +113,12 @@ pub struct RangeProof {
 }
 
 impl RangeProof {
+    /// Returns a [`RangeProofBuilder`] for creating a proof with a
+    /// fluent interface.
+    pub fn builder<'g>() -> RangeProofBuilder<'g> {
+        RangeProofBuilder::new()
+    }
+
     /// Create a rangeproof for a given pair of value `v` and
     /// blinding scalar `v_blinding`.
     /// This is a convenience wrapper around [`RangeProof::prove_multiple`].
@@ -138,6 +188,27 @@ impl RangeProof {
         Ok((p, Vs[0]))
     }
 
+    /// Create a rangeproof for a given pair of value `v` and blinding
+    /// scalar `v_blinding`, using a fresh transcript with the given
+    /// `label`, and return the serialized proof.
+    ///
+    /// This is a convenience wrapper around [`RangeProof::prove_single`]
+    /// and [`RangeProof::to_bytes`] for the simplest integrations; the
+    /// proof can be checked with [`RangeProof::verify_single_from_bytes`].
+    pub fn prove_single_to_bytes(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        label: &'static [u8],
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+    ) -> Result<(Vec<u8>, CompressedRistretto), ProofError> {
+        let mut transcript = Transcript::new(label);
+        let (proof, V) =
+            RangeProof::prove_single(bp_gens, pc_gens, &mut transcript, v, v_blinding, n)?;
+        Ok((proof.to_bytes(), V))
+    }
+
     /// Create a rangeproof for a set of values.
     ///
     /// # Example
@@ -199,6 +270,113 @@ impl RangeProof {
         values: &[u64],
         blindings: &[Scalar],
         n: usize,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Create a rangeproof for a set of values, using `rng` as the
+    /// source of external randomness.
+    ///
+    /// The blinding factors of the proof are not sampled from `rng`
+    /// directly, but from one `TranscriptRng` per value, which is
+    /// bound to the state of the `transcript` and to the blinding
+    /// factor of the value, and which is seeded with output from
+    /// `rng`.  The blinding factors therefore stay unpredictable as
+    /// long as either the witness is secret or `rng` is sound.
+    ///
+    /// With the `rayon` feature, the work of the parties is spread
+    /// over the rayon thread pool.
+    pub fn prove_multiple_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        let values: Vec<u128> = values.iter().map(|&v| v.into()).collect();
+        RangeProof::prove_values(bp_gens, pc_gens, transcript, &values, blindings, n, rng)
+    }
+
+    /// Create a rangeproof for a given pair of `u128` value `v` and
+    /// blinding scalar `v_blinding`.
+    ///
+    /// This is a variant of [`RangeProof::prove_single`] for bitsizes
+    /// up to \\(n = 128\\), which need a `bp_gens` with a
+    /// `gens_capacity` of at least the bitsize.
+    pub fn prove_single_u128(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u128,
+        v_blinding: &Scalar,
+        n: usize,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        let (p, Vs) =
+            RangeProof::prove_multiple_u128(bp_gens, pc_gens, transcript, &[v], &[*v_blinding], n)?;
+        Ok((p, Vs[0]))
+    }
+
+    /// Create a rangeproof for a set of `u128` values.
+    ///
+    /// This is a variant of [`RangeProof::prove_multiple`] for bitsizes
+    /// up to \\(n = 128\\).  The proofs are verified with
+    /// [`RangeProof::verify_multiple`] like any other.
+    pub fn prove_multiple_u128(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u128],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        let mut rng = rand::thread_rng();
+        RangeProof::prove_values(bp_gens, pc_gens, transcript, values, blindings, n, &mut rng)
+    }
+
+    /// Create a rangeproof for the values of a set of [`Opening`]s,
+    /// such as the result of arithmetic on other openings.
+    ///
+    /// Returns [`ProofError::InvalidRange`] if a value is not smaller
+    /// than \\(2^{128}\\), for instance because a subtraction wrapped
+    /// around.  The returned commitments are those of the `openings`.
+    pub fn prove_openings(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        openings: &[Opening],
+        n: usize,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        let values = openings
+            .iter()
+            .map(|opening| scalar::to_u128(&opening.value).ok_or(ProofError::InvalidRange))
+            .collect::<Result<Vec<_>, _>>()?;
+        let blindings: Vec<Scalar> = openings.iter().map(|opening| opening.blinding).collect();
+
+        let mut rng = rand::thread_rng();
+        RangeProof::prove_values(
+            bp_gens, pc_gens, transcript, &values, &blindings, n, &mut rng,
+        )
+    }
+
+    /// Runs the aggregated protocol with one local party per value.
+    fn prove_values<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u128],
+        blindings: &[Scalar],
+        n: usize,
+        rng: &mut T,
     ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
         use self::dealer::*;
         use self::party::*;
@@ -207,45 +385,88 @@ impl RangeProof {
             return Err(ProofError::WrongNumBlindingFactors);
         }
 
+        // Create a `TranscriptRng` for each party from its witness data
+        let mut rngs: Vec<_> = blindings
+            .iter()
+            .map(|v_blinding| {
+                transcript
+                    .build_rng()
+                    .commit_witness_bytes(b"v_blinding", v_blinding.as_bytes())
+                    .finalize(rng)
+            }).collect();
+
         let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, values.len())?;
 
-        let parties: Vec<_> = values
-            .iter()
-            .zip(blindings.iter())
-            .map(|(&v, &v_blinding)| Party::new(bp_gens, pc_gens, v, v_blinding, n))
-            // Collect the iterator of Results into a Result<Vec>, then unwrap it
-            .collect::<Result<Vec<_>, _>>()?;
+        let parties = map_parties(
+            values.iter().zip(blindings).zip(rngs.iter_mut()).collect(),
+            |((&v, &v_blinding), rng)| Party::new_with_rng(bp_gens, pc_gens, v, v_blinding, n, rng),
+        );
+        // Collect the iterator of Results into a Result<Vec>, then unwrap it
+        let parties = parties.into_iter().collect::<Result<Vec<_>, _>>()?;
 
-        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
-            .into_iter()
-            .enumerate()
-            .map(|(j, p)| {
-                p.assign_position(j)
-                    .expect("We already checked the parameters, so this should never happen")
-            }).unzip();
+        let positioned = map_parties(parties.into_iter().enumerate().collect(), |(j, p)| {
+            p.assign_position(j)
+                .expect("We already checked the parameters, so this should never happen")
+        });
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = positioned.into_iter().unzip();
 
         let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
 
         let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
 
-        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
-            .into_iter()
-            .map(|p| p.apply_challenge(&bit_challenge))
-            .unzip();
+        let challenged = map_parties(
+            parties.into_iter().zip(rngs.iter_mut()).collect(),
+            |(p, rng)| p.apply_challenge_with_rng(&bit_challenge, rng),
+        );
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = challenged.into_iter().unzip();
 
         let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
 
-        let proof_shares: Vec<_> = parties
-            .into_iter()
-            .map(|p| p.apply_challenge(&poly_challenge))
-            // Collect the iterator of Results into a Result<Vec>, then unwrap it
-            .collect::<Result<Vec<_>, _>>()?;
+        let proof_shares = map_parties(parties, |p| p.apply_challenge(&poly_challenge));
+        // Collect the iterator of Results into a Result<Vec>, then unwrap it
+        let proof_shares = proof_shares.into_iter().collect::<Result<Vec<_>, _>>()?;
 
         let proof = dealer.receive_trusted_shares(&proof_shares)?;
 
         Ok((proof, value_commitments))
     }
 
+    /// Create a rangeproof for values whose commitments were already
+    /// published, for instance by a wallet.
+    ///
+    /// This is a variant of [`RangeProof::prove_multiple`] which takes
+    /// the value commitments together with their openings, and checks
+    /// that each commitment \\(V\_j\\) opens to \\((v\_j, \tilde{v}\_j)\\)
+    /// before creating the proof.  Returns
+    /// [`ProofError::CommitmentMismatch`] if there are not as many
+    /// commitments as values, or if a commitment does not match its
+    /// opening.
+    pub fn prove_multiple_with_commitments(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<RangeProof, ProofError> {
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+        if value_commitments.len() != values.len() {
+            return Err(ProofError::CommitmentMismatch);
+        }
+        for ((V, &v), &v_blinding) in value_commitments.iter().zip(values).zip(blindings) {
+            if pc_gens.commit(Scalar::from(v), v_blinding).compress() != *V {
+                return Err(ProofError::CommitmentMismatch);
+            }
+        }
+
+        let (proof, _) =
+            RangeProof::prove_multiple(bp_gens, pc_gens, transcript, values, blindings, n)?;
+        Ok(proof)
+    }
+
     /// Verifies a rangeproof for a given value commitment \\(V\\).
     ///
     /// This is a convenience wrapper around `verify_multiple` for the `m=1` case.
@@ -260,7 +481,50 @@ impl RangeProof {
         self.verify_multiple(bp_gens, pc_gens, transcript, &[*V], n)
     }
 
+    /// Parses a serialized rangeproof and verifies it for a given
+    /// value commitment \\(V\\), using a fresh transcript with the
+    /// given `label`.
+    ///
+    /// This is the counterpart of [`RangeProof::prove_single_to_bytes`].
+    pub fn verify_single_from_bytes(
+        proof_bytes: &[u8],
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        label: &'static [u8],
+        V: &CompressedRistretto,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        let proof = RangeProof::from_bytes(proof_bytes)?;
+        let mut transcript = Transcript::new(label);
+        proof.verify_single(bp_gens, pc_gens, &mut transcript, V, n)
+    }
+
+    /// Checks a freshly created rangeproof against its known opening
+    /// \\((v, \tilde{v})\\), before it is published.
+    ///
+    /// The commitment is re-derived from `v` and `v_blinding`, and the
+    /// proof is verified using a fresh transcript with the given
+    /// `label`, exactly as a verifier would.  This catches mistakes
+    /// such as a wrong label, bitsize or blinding factor, which would
+    /// otherwise only surface once the proof is rejected by others.
+    pub fn self_check(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        label: &'static [u8],
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        let V = pc_gens.commit(Scalar::from(v), *v_blinding).compress();
+        let mut transcript = Transcript::new(label);
+        self.verify_single(bp_gens, pc_gens, &mut transcript, &V, n)
+    }
+
     /// Verifies an aggregated rangeproof for the given value commitments.
+    ///
+    /// Verification is deterministic and does not use an RNG: the
+    /// batching challenge is derived from the transcript.
     pub fn verify_multiple(
         &self,
         bp_gens: &BulletproofGens,
@@ -269,23 +533,327 @@ impl RangeProof {
         value_commitments: &[CompressedRistretto],
         n: usize,
     ) -> Result<(), ProofError> {
-        let m = value_commitments.len();
+        self.verify_multiple_iter(bp_gens, pc_gens, transcript, value_commitments, n)
+    }
+
+    /// Verifies an aggregated rangeproof for the value commitments
+    /// yielded by `value_commitments`.
+    ///
+    /// This is a variant of [`RangeProof::verify_multiple`] for callers
+    /// which hold the commitments inside other structures, for instance
+    /// transaction outputs, and would otherwise have to collect them
+    /// into a temporary `Vec`.
+    pub fn verify_multiple_iter<'a, I>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: I,
+        n: usize,
+    ) -> Result<(), ProofError>
+    where
+        I: IntoIterator<Item = &'a CompressedRistretto>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let terms = self.verification_terms(bp_gens, transcript, value_commitments, n)?;
+        let (n, m) = (terms.n, terms.m);
+
+        let mega_check = multiscalar::optional_multiscalar_mul(
+            terms
+                .dynamic_scalars
+                .iter()
+                .chain(iter::once(&terms.B_blinding_scalar))
+                .chain(iter::once(&terms.B_scalar))
+                .chain(terms.g_scalars.iter())
+                .chain(terms.h_scalars.iter()),
+            terms
+                .dynamic_points
+                .iter()
+                .map(|P| P.decompress())
+                .chain(iter::once(Some(pc_gens.B_blinding)))
+                .chain(iter::once(Some(pc_gens.B)))
+                .chain(bp_gens.G(n, m).map(|&x| Some(x)))
+                .chain(bp_gens.H(n, m).map(|&x| Some(x))),
+        ).ok_or_else(|| ProofError::VerificationError)?;
+
+        if mega_check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Create an aggregated rangeproof for three-term commitments
+    /// \\(V\_j = v\_j B + m\_j B\_{meta} + \tilde{v}\_j B\_{blinding}\\),
+    /// proving that each value \\(v\_j\\) lies in the range
+    /// \\([0, 2^n)\\).
+    ///
+    /// The public metadata scalars \\(m\_j\\) are committed to the
+    /// transcript, and the proof is created for the two-term
+    /// commitments \\(V\_j - m\_j B\_{meta}\\).  Returns the proof and
+    /// the three-term commitments.
+    pub fn prove_multiple_with_metadata(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        metadata: &[Scalar],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        if values.len() != metadata.len() {
+            return Err(ProofError::WrongNumMetadata);
+        }
+        transcript.metadata_domain_sep(metadata.len() as u64);
+        for m in metadata.iter() {
+            transcript.commit_scalar(b"meta", m);
+        }
+
+        let (proof, _) =
+            RangeProof::prove_multiple(bp_gens, pc_gens, transcript, values, blindings, n)?;
+        let value_commitments = values
+            .iter()
+            .zip(metadata.iter())
+            .zip(blindings.iter())
+            .map(|((&v, &m), &v_blinding)| {
+                pc_gens
+                    .commit_with_metadata(Scalar::from(v), m, v_blinding)
+                    .compress()
+            }).collect();
+        Ok((proof, value_commitments))
+    }
+
+    /// Verifies an aggregated rangeproof for the given three-term
+    /// commitments and their public metadata scalars.
+    ///
+    /// This is the counterpart of
+    /// [`RangeProof::prove_multiple_with_metadata`]: the metadata is
+    /// committed to the transcript, and the verification equation is
+    /// checked for the commitments \\(V\_j - m\_j B\_{meta}\\).
+    pub fn verify_multiple_with_metadata(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+        metadata: &[Scalar],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        if value_commitments.len() != metadata.len() {
+            return Err(ProofError::WrongNumMetadata);
+        }
+        transcript.metadata_domain_sep(metadata.len() as u64);
+        for m in metadata.iter() {
+            transcript.commit_scalar(b"meta", m);
+        }
+
+        let value_commitments = value_commitments
+            .iter()
+            .zip(metadata.iter())
+            .map(|(V, m)| {
+                V.decompress()
+                    .map(|V| (V - m * pc_gens.B_meta).compress())
+                    .ok_or(ProofError::FormatError)
+            }).collect::<Result<Vec<_>, _>>()?;
+        self.verify_multiple(bp_gens, pc_gens, transcript, &value_commitments, n)
+    }
+
+    /// Create a proof that the value `v` committed with blinding
+    /// factor `v_blinding` lies in the range \\([min, max]\\).
+    ///
+    /// The commitment \\(V = vB + \tilde{v}B\_{blinding}\\) is offset
+    /// into the two commitments
+    /// \\[
+    /// \begin{aligned}
+    ///   V - min \cdot B &= (v - min)B + \tilde{v}B\_{blinding}, \\\\
+    ///   max \cdot B - V &= (max - v)B - \tilde{v}B\_{blinding},
+    /// \end{aligned}
+    /// \\]
+    /// and an aggregated proof shows that both \\(v - min\\) and
+    /// \\(max - v\\) are non-negative.  The bitsize is the bit length
+    /// of \\(max - min\\), and the `bp_gens` must have a party
+    /// capacity of at least \\(2\\).
+    ///
+    /// Returns the proof and the commitment \\(V\\), to be checked
+    /// with [`RangeProof::verify_range`].
+    pub fn prove_range(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        min: u64,
+        max: u64,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        if min > max || v < min || v > max {
+            return Err(ProofError::InvalidRange);
+        }
+        transcript.range_bounds_domain_sep(min, max);
+
+        let (proof, _) = RangeProof::prove_multiple(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[v - min, max - v],
+            &[*v_blinding, -v_blinding],
+            range_bitsize(min, max),
+        )?;
+        let V = pc_gens.commit(Scalar::from(v), *v_blinding).compress();
+        Ok((proof, V))
+    }
+
+    /// Verifies a proof that the value committed in `V` lies in the
+    /// range \\([min, max]\\).
+    ///
+    /// This is the counterpart of [`RangeProof::prove_range`].
+    pub fn verify_range(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        min: u64,
+        max: u64,
+    ) -> Result<(), ProofError> {
+        if min > max {
+            return Err(ProofError::InvalidRange);
+        }
+        transcript.range_bounds_domain_sep(min, max);
+
+        let V = V.decompress().ok_or(ProofError::FormatError)?;
+        let value_commitments = [
+            (V - Scalar::from(min) * pc_gens.B).compress(),
+            (Scalar::from(max) * pc_gens.B - V).compress(),
+        ];
+        self.verify_multiple(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &value_commitments,
+            range_bitsize(min, max),
+        )
+    }
+
+    /// Replays the proof on the `transcript`, like `verify_multiple`,
+    /// but instead of computing the final multiscalar multiplication
+    /// returns it as a [`DelegationRequest`](::delegation::DelegationRequest) split into `num_chunks`
+    /// chunks, to be computed by an untrusted helper.
+    ///
+    /// See the [`delegation`](::delegation) module for the protocol
+    /// and its soundness.
+    pub fn delegate_verification(
+        &self,
+        bp_gens: &BulletproofGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+        n: usize,
+        num_chunks: usize,
+    ) -> Result<DelegationRequest, ProofError> {
+        let terms = self.verification_terms(bp_gens, transcript, value_commitments, n)?;
+        Ok(DelegationRequest::new(terms, num_chunks))
+    }
+
+    /// Replays the proof on the `transcript` and computes the terms of
+    /// the verification equation, which holds if the weighted sum of
+    /// the terms is the identity.
+    ///
+    /// The terms are kept separate so that the equations of several
+    /// proofs can be combined into a single multiscalar multiplication.
+    pub(crate) fn verification_terms<'a, I>(
+        &self,
+        bp_gens: &BulletproofGens,
+        transcript: &mut Transcript,
+        value_commitments: I,
+        n: usize,
+    ) -> Result<VerificationTerms, ProofError>
+    where
+        I: IntoIterator<Item = &'a CompressedRistretto>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let checks = self.verification_checks(bp_gens, transcript, value_commitments, n)?;
+        let (x, c) = (checks.x, checks.c);
+
+        Ok(VerificationTerms {
+            n: checks.n,
+            m: checks.m,
+            dynamic_scalars: iter::once(Scalar::one())
+                .chain(iter::once(x))
+                .chain(iter::once(c * x))
+                .chain(iter::once(c * x * x))
+                .chain(checks.x_sq)
+                .chain(checks.x_inv_sq)
+                .chain(checks.value_commitment_scalars.iter().map(|s| c * s))
+                .collect(),
+            dynamic_points: iter::once(self.A)
+                .chain(iter::once(self.S))
+                .chain(iter::once(self.T_1))
+                .chain(iter::once(self.T_2))
+                .chain(self.ipp_proof.lr_points().map(|(L, _)| *L))
+                .chain(self.ipp_proof.lr_points().map(|(_, R)| *R))
+                .chain(checks.value_commitments)
+                .collect(),
+            B_scalar: checks.ipp_B_scalar + c * checks.t_x_B_scalar,
+            B_blinding_scalar: -self.e_blinding - c * self.t_x_blinding,
+            g_scalars: checks.g_scalars,
+            h_scalars: checks.h_scalars,
+        })
+    }
+
+    /// Replays the proof on the `transcript` and computes the scalars
+    /// of the two checks of the verification, before they are combined
+    /// with the batching challenge \\(c\\).
+    fn verification_checks<'a, I>(
+        &self,
+        bp_gens: &BulletproofGens,
+        transcript: &mut Transcript,
+        value_commitments: I,
+        n: usize,
+    ) -> Result<VerificationChecks, ProofError>
+    where
+        I: IntoIterator<Item = &'a CompressedRistretto>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let value_commitments = value_commitments.into_iter();
+        let num_values = value_commitments.len();
+        let mut Vs: Vec<_> = value_commitments.cloned().collect();
+        // `ExactSizeIterator` is a safe trait, so its length may be wrong.
+        if Vs.len() != num_values {
+            return Err(ProofError::FormatError);
+        }
+        if num_values == 0 {
+            return Err(ProofError::InvalidAggregation);
+        }
+        // The prover pads the aggregation to a power of two with
+        // commitments to zero with a zero blinding factor.
+        let m = num_values.next_power_of_two();
+        Vs.resize(m, CompressedRistretto::identity());
 
         // First, replay the "interactive" protocol using the proof
         // data to recompute all challenges.
-        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+        if n == 0 || n > 128 {
             return Err(ProofError::InvalidBitsize);
         }
-        if bp_gens.gens_capacity < n {
+        // The bit vectors are padded to a power of two, with zero
+        // weights for the padding bits.
+        let padded_n = n.next_power_of_two();
+        if bp_gens.gens_capacity < padded_n {
             return Err(ProofError::InvalidGeneratorsLength);
         }
         if bp_gens.party_capacity < m {
             return Err(ProofError::InvalidGeneratorsLength);
         }
+        // The inner-product argument has one round per bit of n*m,
+        // so the proof itself tells us what n*m the prover used.
+        if padded_n * m != 1 << self.implied_log_size() {
+            return Err(ProofError::ProofSizeMismatch);
+        }
 
         transcript.rangeproof_domain_sep(n as u64, m as u64);
+        for salt in bp_gens.salts() {
+            transcript.generators_salt(salt);
+        }
 
-        for V in value_commitments.iter() {
+        for V in Vs.iter() {
             transcript.commit_point(b"V", V);
         }
         transcript.commit_point(b"A", &self.A);
@@ -307,101 +875,302 @@ impl RangeProof {
 
         let w = transcript.challenge_scalar(b"w");
 
-        let mut rng = transcript.build_rng().finalize(&mut rand::thread_rng());
+        let (x_sq, x_inv_sq, s) = self
+            .ipp_proof
+            .verification_scalars(padded_n * m, transcript)?;
 
-        // Challenge value for batching statements to be verified
-        let c = Scalar::random(&mut rng);
-
-        let (x_sq, x_inv_sq, s) = self.ipp_proof.verification_scalars(n * m, transcript)?;
+        // Challenge value for batching statements to be verified.  It
+        // is derived from a copy of the transcript, which binds the
+        // whole proof at this point, so verification needs no RNG and
+        // leaves the transcript in the same state as the prover's.
+        let c = transcript.clone().challenge_scalar(b"c");
         let s_inv = s.iter().rev();
 
-        let a = self.ipp_proof.a;
-        let b = self.ipp_proof.b;
+        let (a, b) = self.ipp_proof.final_scalars();
 
         // Construct concat_z_and_2, an iterator of the values of
         // z^0 * \vec(2)^n || z^1 * \vec(2)^n || ... || z^(m-1) * \vec(2)^n
-        let powers_of_2: Vec<Scalar> = util::exp_iter(Scalar::from(2u64)).take(n).collect();
-        let concat_z_and_2: Vec<Scalar> = util::exp_iter(z)
+        let powers_of_2: Vec<Scalar> = padded_powers_of_2(n).collect();
+        let concat_z_and_2: Vec<Scalar> = math::exp_iter(z)
             .take(m)
             .flat_map(|exp_z| powers_of_2.iter().map(move |exp_2| exp_2 * exp_z))
             .collect();
 
         let g = s.iter().map(|s_i| minus_z - a * s_i);
         let h = s_inv
-            .zip(util::exp_iter(y.invert()))
+            .zip(math::exp_iter(y.invert()))
             .zip(concat_z_and_2.iter())
             .map(|((s_i_inv, exp_y_inv), z_and_2)| z + exp_y_inv * (zz * z_and_2 - b * s_i_inv));
 
-        let value_commitment_scalars = util::exp_iter(z).take(m).map(|z_exp| c * zz * z_exp);
-        let basepoint_scalar = w * (self.t_x - a * b) + c * (delta(n, m, &y, &z) - self.t_x);
-
-        let mega_check = RistrettoPoint::optional_multiscalar_mul(
-            iter::once(Scalar::one())
-                .chain(iter::once(x))
-                .chain(iter::once(c * x))
-                .chain(iter::once(c * x * x))
-                .chain(x_sq.iter().cloned())
-                .chain(x_inv_sq.iter().cloned())
-                .chain(iter::once(-self.e_blinding - c * self.t_x_blinding))
-                .chain(iter::once(basepoint_scalar))
-                .chain(g)
-                .chain(h)
-                .chain(value_commitment_scalars),
-            iter::once(self.A.decompress())
-                .chain(iter::once(self.S.decompress()))
-                .chain(iter::once(self.T_1.decompress()))
-                .chain(iter::once(self.T_2.decompress()))
-                .chain(self.ipp_proof.L_vec.iter().map(|L| L.decompress()))
-                .chain(self.ipp_proof.R_vec.iter().map(|R| R.decompress()))
-                .chain(iter::once(Some(pc_gens.B_blinding)))
-                .chain(iter::once(Some(pc_gens.B)))
-                .chain(bp_gens.G(n, m).map(|&x| Some(x)))
-                .chain(bp_gens.H(n, m).map(|&x| Some(x)))
-                .chain(value_commitments.iter().map(|V| V.decompress())),
-        ).ok_or_else(|| ProofError::VerificationError)?;
-
-        if mega_check.is_identity() {
-            Ok(())
-        } else {
-            Err(ProofError::VerificationError)
-        }
+        Ok(VerificationChecks {
+            n: padded_n,
+            m,
+            value_commitments: Vs,
+            x,
+            c,
+            x_sq,
+            x_inv_sq,
+            value_commitment_scalars: math::powers(z, m).map(|z_exp| zz * z_exp).collect(),
+            t_x_B_scalar: delta(n, m, &y, &z) - self.t_x,
+            ipp_B_scalar: w * (self.t_x - a * b),
+            g_scalars: g.collect(),
+            h_scalars: h.collect(),
+        })
     }
 
-    /// Serializes the proof into a byte array of \\(2 \lg n + 9\\)
-    /// 32-byte elements, where \\(n\\) is the number of secret bits.
+    /// Create a rangeproof for a given pair of value `v` and blinding
+    /// scalar `v_blinding`, with the bitsize given by the type
+    /// parameter `B`.
     ///
-    /// # Layout
+    /// This is a variant of [`RangeProof::prove_single`] which cannot
+    /// fail with [`ProofError::InvalidBitsize`].
     ///
-    /// The layout of the range proof encoding is:
+    /// # Example
+    /// ```
+    /// extern crate rand;
+    /// use rand::thread_rng;
     ///
-    /// * four compressed Ristretto points \\(A,S,T_1,T_2\\),
-    /// * three scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
-    /// * \\(n\\) pairs of compressed Ristretto points \\(L_0,R_0\dots,L_{n-1},R_{n-1}\\),
-    /// * two scalars \\(a, b\\).
-    pub fn to_bytes(&self) -> Vec<u8> {
-        // 7 elements: points A, S, T1, T2, scalars tx, tx_bl, e_bl.
-        let mut buf = Vec::with_capacity(7 * 32 + self.ipp_proof.serialized_size());
-        buf.extend_from_slice(self.A.as_bytes());
-        buf.extend_from_slice(self.S.as_bytes());
-        buf.extend_from_slice(self.T_1.as_bytes());
-        buf.extend_from_slice(self.T_2.as_bytes());
-        buf.extend_from_slice(self.t_x.as_bytes());
-        buf.extend_from_slice(self.t_x_blinding.as_bytes());
-        buf.extend_from_slice(self.e_blinding.as_bytes());
-        // XXX this costs an extra alloc
-        buf.extend_from_slice(self.ipp_proof.to_bytes().as_slice());
-        buf
-    }
-
-    /// Deserializes the proof from a byte slice.
+    /// extern crate curve25519_dalek;
+    /// use curve25519_dalek::scalar::Scalar;
     ///
-    /// Returns an error if the byte slice cannot be parsed into a `RangeProof`.
+    /// extern crate merlin;
+    /// use merlin::Transcript;
+    ///
+    /// extern crate bulletproofs;
+    /// use bulletproofs::bitsize::Bits32;
+    /// use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+    ///
+    /// # fn main() {
+    /// let pc_gens = PedersenGens::default();
+    /// let bp_gens = BulletproofGens::new(64, 1);
+    /// let blinding = Scalar::random(&mut thread_rng());
+    ///
+    /// let mut prover_transcript = Transcript::new(b"doctest example");
+    /// let (proof, committed_value) = RangeProof::prove_single_bits::<Bits32>(
+    ///     &bp_gens,
+    ///     &pc_gens,
+    ///     &mut prover_transcript,
+    ///     1037578891u64,
+    ///     &blinding,
+    /// ).expect("A real program could handle errors");
+    ///
+    /// let mut verifier_transcript = Transcript::new(b"doctest example");
+    /// assert!(
+    ///     proof
+    ///         .verify_single_bits::<Bits32>(
+    ///             &bp_gens,
+    ///             &pc_gens,
+    ///             &mut verifier_transcript,
+    ///             &committed_value,
+    ///         ).is_ok()
+    /// );
+    /// # }
+    /// ```
+    pub fn prove_single_bits<B: BitSize>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        RangeProof::prove_single(bp_gens, pc_gens, transcript, v, v_blinding, B::N)
+    }
+
+    /// Create a rangeproof for a set of values, with the bitsize given
+    /// by the type parameter `B`.
+    ///
+    /// This is a variant of [`RangeProof::prove_multiple`] which cannot
+    /// fail with [`ProofError::InvalidBitsize`].
+    pub fn prove_multiple_bits<B: BitSize>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple(bp_gens, pc_gens, transcript, values, blindings, B::N)
+    }
+
+    /// Verifies a rangeproof for a given value commitment \\(V\\), with
+    /// the bitsize given by the type parameter `B`.
+    pub fn verify_single_bits<B: BitSize>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+    ) -> Result<(), ProofError> {
+        self.verify_single(bp_gens, pc_gens, transcript, V, B::N)
+    }
+
+    /// Verifies an aggregated rangeproof for the given value
+    /// commitments, with the bitsize given by the type parameter `B`.
+    pub fn verify_multiple_bits<B: BitSize>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+    ) -> Result<(), ProofError> {
+        self.verify_multiple(bp_gens, pc_gens, transcript, value_commitments, B::N)
+    }
+
+    /// Create a rangeproof that the value `v` lies in the range of its
+    /// type, e.g. \\([0, 2^{32})\\) for a `u32`, with blinding scalar
+    /// `v_blinding`.
+    ///
+    /// The proof can be verified with [`RangeProof::verify_value`]
+    /// using the same value type.
+    pub fn prove_value<T: ProvableValue>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: T,
+        v_blinding: &Scalar,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        let n = T::Bits::N;
+        RangeProof::prove_single_u128(bp_gens, pc_gens, transcript, v.into(), v_blinding, n)
+    }
+
+    /// Verifies a rangeproof created by [`RangeProof::prove_value`]
+    /// for a value of type `T` with commitment \\(V\\).
+    pub fn verify_value<T: ProvableValue>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+    ) -> Result<(), ProofError> {
+        self.verify_single_bits::<T::Bits>(bp_gens, pc_gens, transcript, V)
+    }
+
+    /// Returns \\(\lg(n \cdot m)\\), the base-2 logarithm of the total
+    /// number of bits proven, as implied by the length of the
+    /// inner-product argument.
+    ///
+    /// Since the bitsize \\(n\\) and aggregation size \\(m\\) are not
+    /// included in the proof, this can be used to sanity-check the
+    /// parameters after deserialization.
+    pub fn implied_log_size(&self) -> usize {
+        self.ipp_proof.rounds()
+    }
+
+    /// Returns the commitment \\(A\\) to the bits of the values.
+    pub fn A(&self) -> CompressedRistretto {
+        self.A
+    }
+
+    /// Returns the commitment \\(S\\) to the blinding factors of
+    /// the bits.
+    pub fn S(&self) -> CompressedRistretto {
+        self.S
+    }
+
+    /// Returns the commitment \\(T_1\\) to the \\(t_1\\)
+    /// coefficient of \\(t(x)\\).
+    pub fn T_1(&self) -> CompressedRistretto {
+        self.T_1
+    }
+
+    /// Returns the commitment \\(T_2\\) to the \\(t_2\\)
+    /// coefficient of \\(t(x)\\).
+    pub fn T_2(&self) -> CompressedRistretto {
+        self.T_2
+    }
+
+    /// Returns the evaluation \\(t_x\\) of the polynomial
+    /// \\(t(x)\\) at the challenge point \\(x\\).
+    pub fn t_x(&self) -> Scalar {
+        self.t_x
+    }
+
+    /// Returns the blinding factor \\(\tilde{t}_x\\) of the
+    /// synthetic commitment to \\(t(x)\\).
+    pub fn t_x_blinding(&self) -> Scalar {
+        self.t_x_blinding
+    }
+
+    /// Returns the blinding factor \\(\tilde{e}\\) of the synthetic
+    /// commitment to the inner-product arguments.
+    pub fn e_blinding(&self) -> Scalar {
+        self.e_blinding
+    }
+
+    /// Returns the inner-product argument of the proof.
+    pub fn ipp_proof(&self) -> &InnerProductProof {
+        &self.ipp_proof
+    }
+
+    /// Serializes the proof into a byte array of \\(2 \lg n + 9\\)
+    /// 32-byte elements, where \\(n\\) is the number of secret bits.
+    ///
+    /// # Layout
+    ///
+    /// The layout of the range proof encoding is:
+    ///
+    /// * four compressed Ristretto points \\(A,S,T_1,T_2\\),
+    /// * three scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
+    /// * \\(n\\) pairs of compressed Ristretto points \\(L_0,R_0\dots,L_{n-1},R_{n-1}\\),
+    /// * two scalars \\(a, b\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; self.serialized_size()];
+        self.write_bytes(&mut buf)
+            .expect("The buffer has the size of the encoding");
+        buf
+    }
+
+    /// Returns the size in bytes of the encoding of the proof, which
+    /// is \\(32 \cdot (2 \lg n + 9)\\) bytes.
+    pub fn serialized_size(&self) -> usize {
+        // 7 elements: points A, S, T1, T2, scalars tx, tx_bl, e_bl.
+        7 * 32 + self.ipp_proof.serialized_size()
+    }
+
+    /// Writes the proof into the start of `out` in the encoding of
+    /// [`RangeProof::to_bytes`], without allocating, and returns the
+    /// number of bytes written, which is the
+    /// [`serialized_size`](RangeProof::serialized_size).
+    ///
+    /// Returns [`ProofError::BufferTooSmall`] if `out` is shorter than
+    /// the encoding.
+    pub fn write_bytes(&self, out: &mut [u8]) -> Result<usize, ProofError> {
+        let size = self.serialized_size();
+        if out.len() < size {
+            return Err(ProofError::BufferTooSmall { size });
+        }
+        let elements = [
+            self.A.as_bytes(),
+            self.S.as_bytes(),
+            self.T_1.as_bytes(),
+            self.T_2.as_bytes(),
+            self.t_x.as_bytes(),
+            self.t_x_blinding.as_bytes(),
+            self.e_blinding.as_bytes(),
+        ];
+        for (chunk, element) in out.chunks_mut(32).zip(elements.iter()) {
+            chunk.copy_from_slice(*element);
+        }
+        self.ipp_proof.write_bytes(&mut out[7 * 32..size])?;
+        Ok(size)
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns [`ProofError::InvalidLength`] if the byte slice does not
+    /// have the length of an encoded `RangeProof`, and
+    /// [`ProofError::InvalidScalar`] with the index of the 32-byte
+    /// element if a scalar is not canonical.  The points are only
+    /// decompressed during verification, see
+    /// [`RangeProof::from_bytes_strict`].
     pub fn from_bytes(slice: &[u8]) -> Result<RangeProof, ProofError> {
+        let invalid_length = ProofError::InvalidLength {
+            length: slice.len(),
+        };
         if slice.len() % 32 != 0 {
-            return Err(ProofError::FormatError);
+            return Err(invalid_length);
         }
         if slice.len() < 7 * 32 {
-            return Err(ProofError::FormatError);
+            return Err(invalid_length);
         }
 
         use util::read32;
@@ -411,14 +1180,17 @@ impl RangeProof {
         let T_1 = CompressedRistretto(read32(&slice[2 * 32..]));
         let T_2 = CompressedRistretto(read32(&slice[3 * 32..]));
 
-        let t_x = Scalar::from_canonical_bytes(read32(&slice[4 * 32..]))
-            .ok_or(ProofError::FormatError)?;
-        let t_x_blinding = Scalar::from_canonical_bytes(read32(&slice[5 * 32..]))
-            .ok_or(ProofError::FormatError)?;
-        let e_blinding = Scalar::from_canonical_bytes(read32(&slice[6 * 32..]))
-            .ok_or(ProofError::FormatError)?;
+        let t_x = scalar::decode_element(slice, 4)?;
+        let t_x_blinding = scalar::decode_element(slice, 5)?;
+        let e_blinding = scalar::decode_element(slice, 6)?;
 
-        let ipp_proof = InnerProductProof::from_bytes(&slice[7 * 32..])?;
+        // Report the errors of the inner-product argument relative to
+        // the whole encoding.
+        let ipp_proof = InnerProductProof::from_bytes(&slice[7 * 32..]).map_err(|e| match e {
+            ProofError::InvalidLength { .. } => invalid_length,
+            ProofError::InvalidScalar { index } => ProofError::InvalidScalar { index: index + 7 },
+            e => e,
+        })?;
 
         Ok(RangeProof {
             A,
@@ -431,6 +1203,118 @@ impl RangeProof {
             ipp_proof,
         })
     }
+
+    /// Deserializes the proof from a byte slice, like
+    /// [`RangeProof::from_bytes`], and also checks that all the points
+    /// of the proof are valid compressed Ristretto points, instead of
+    /// leaving it to the verifier.
+    ///
+    /// Returns [`ProofError::InvalidPoint`] with the index of the
+    /// 32-byte element of the first invalid point.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<RangeProof, ProofError> {
+        let proof = RangeProof::from_bytes(slice)?;
+
+        let points = [proof.A, proof.S, proof.T_1, proof.T_2];
+        if let Some(index) = points.iter().position(|P| P.decompress().is_none()) {
+            return Err(ProofError::InvalidPoint { index });
+        }
+        // The IPP points follow the three scalars.
+        if let Some(index) = proof.ipp_proof.first_invalid_point() {
+            return Err(ProofError::InvalidPoint { index: index + 7 });
+        }
+        Ok(proof)
+    }
+}
+
+/// The terms of the verification equation of a [`RangeProof`].
+///
+/// The proof is valid if the multiscalar multiplication of the
+/// dynamic scalars and points, together with the scalars for the
+/// Pedersen generators and the first \\(n\\) Bulletproofs generators of
+/// each of the \\(m\\) parties, is the identity.
+pub(crate) struct VerificationTerms {
+    /// The bitsize of the proof, padded to a power of two.
+    pub(crate) n: usize,
+    /// The aggregation size of the proof.
+    pub(crate) m: usize,
+    /// Scalars for the points given by the proof and the commitments.
+    pub(crate) dynamic_scalars: Vec<Scalar>,
+    /// The points \\(A, S, T_1, T_2\\), the IPP points
+    /// \\(L_0,\dots,L_{k-1}, R_0,\dots,R_{k-1}\\), and the value
+    /// commitments \\(V_0,\dots,V_{m-1}\\).
+    pub(crate) dynamic_points: Vec<CompressedRistretto>,
+    /// Scalar for the Pedersen generator \\(B\\).
+    pub(crate) B_scalar: Scalar,
+    /// Scalar for the Pedersen generator \\(\tilde{B}\\).
+    pub(crate) B_blinding_scalar: Scalar,
+    /// Scalars for the \\(n \cdot m\\) generators \\(\mathbf{G}\\).
+    pub(crate) g_scalars: Vec<Scalar>,
+    /// Scalars for the \\(n \cdot m\\) generators \\(\mathbf{H}\\).
+    pub(crate) h_scalars: Vec<Scalar>,
+}
+
+/// The scalars of the two checks of the verification of a
+/// [`RangeProof`]: the check that \\(t_x\\) and \\(\tilde{t}_x\\)
+/// open the commitment to \\(t(x)\\), and the check of the
+/// inner-product argument.
+///
+/// The scalars of the first check, for the points \\(T_1, T_2\\), the
+/// value commitments, \\(B\\) and \\(\tilde{B}\\), are
+/// \\(x, x^2, z^2 z^j, \delta(y,z) - t_x, -\tilde{t}_x\\).  The
+/// scalars of the second check, for the points \\(A, S\\), the IPP
+/// points, \\(B\\), \\(\tilde{B}\\) and the generators
+/// \\(\mathbf{G}, \mathbf{H}\\), are
+/// \\(1, x, u_j^2, u_j^{-2}, w(t_x - ab), -\tilde{e}\\) and the
+/// generator scalars.
+struct VerificationChecks {
+    /// The bitsize of the proof, padded to a power of two.
+    n: usize,
+    /// The aggregation size of the proof.
+    m: usize,
+    /// The value commitments \\(V_0,\dots,V_{m-1}\\).
+    value_commitments: Vec<CompressedRistretto>,
+    /// The challenge \\(x\\).
+    x: Scalar,
+    /// The batching challenge \\(c\\) of the two checks.
+    c: Scalar,
+    /// Scalars \\(u_j^2\\) for the IPP points \\(L_j\\).
+    x_sq: Vec<Scalar>,
+    /// Scalars \\(u_j^{-2}\\) for the IPP points \\(R_j\\).
+    x_inv_sq: Vec<Scalar>,
+    /// Scalars \\(z^2 z^j\\) for the value commitments.
+    value_commitment_scalars: Vec<Scalar>,
+    /// Scalar for \\(B\\) in the check of \\(t(x)\\).
+    t_x_B_scalar: Scalar,
+    /// Scalar for \\(B\\) in the check of the inner-product argument.
+    ipp_B_scalar: Scalar,
+    /// Scalars for the \\(n \cdot m\\) generators \\(\mathbf{G}\\).
+    g_scalars: Vec<Scalar>,
+    /// Scalars for the \\(n \cdot m\\) generators \\(\mathbf{H}\\).
+    h_scalars: Vec<Scalar>,
+}
+
+/// Returns the smallest bitsize \\(n \geq 1\\) such that
+/// \\(max - min < 2^n\\).
+fn range_bitsize(min: u64, max: u64) -> usize {
+    let width = max - min;
+    cmp::max(64 - width.leading_zeros() as usize, 1)
+}
+
+/// Compares the canonical encodings of the proofs in constant time.
+impl PartialEq for RangeProof {
+    fn eq(&self, other: &RangeProof) -> bool {
+        self.to_bytes()[..].ct_eq(&other.to_bytes()[..]).unwrap_u8() == 1
+    }
+}
+
+impl Eq for RangeProof {}
+
+/// Hashes the canonical encoding of the proof, so that equal proofs
+/// have equal hashes.
+impl Hash for RangeProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
 }
 
 impl Serialize for RangeProof {
@@ -472,14 +1356,51 @@ impl<'de> Deserialize<'de> for RangeProof {
 /// \\[
 /// \delta(y,z) = (z - z^{2}) \langle \mathbf{1}, {\mathbf{y}}^{n \cdot m} \rangle - \sum_{j=0}^{m-1} z^{j+3} \cdot \langle \mathbf{1}, {\mathbf{2}}^{n \cdot m} \rangle
 /// \\]
+/// where the powers of \\(y\\) run over the padded length of the
+/// bit vectors.
 fn delta(n: usize, m: usize, y: &Scalar, z: &Scalar) -> Scalar {
-    let sum_y = util::sum_of_powers(y, n * m);
-    let sum_2 = util::sum_of_powers(&Scalar::from(2u64), n);
-    let sum_z = util::sum_of_powers(z, m);
+    let sum_y = math::sum_of_powers(y, n.next_power_of_two() * m);
+    let sum_2 = math::sum_of_powers_of_2(n);
+    let sum_z = math::sum_of_powers(z, m);
 
     (z - z * z) * sum_y - z * z * z * sum_2 * sum_z
 }
 
+/// Returns the weights \\(2^0, \dots, 2^{n-1}\\) of the bits of an
+/// `n`-bit value, followed by zeros up to the next power of two.
+///
+/// Bitsizes which are not a power of two are proved by padding the
+/// bit vectors, and the zero weights leave the padding bits out of
+/// the value.
+pub(crate) fn padded_powers_of_2(n: usize) -> impl Iterator<Item = Scalar> {
+    math::powers(Scalar::from(2u64), n)
+        .chain(iter::repeat(Scalar::zero()).take(n.next_power_of_two() - n))
+}
+
+/// Applies `f` to the state of each party, spreading the parties over
+/// the rayon thread pool, and returns the results in order.
+#[cfg(feature = "rayon")]
+fn map_parties<T, U, F>(parties: Vec<T>, f: F) -> Vec<U>
+where
+    T: Send,
+    U: Send,
+    F: Fn(T) -> U + Send + Sync,
+{
+    use rayon::prelude::*;
+
+    parties.into_par_iter().map(f).collect()
+}
+
+/// Applies `f` to the state of each party, and returns the results
+/// in order.
+#[cfg(not(feature = "rayon"))]
+fn map_parties<T, U, F>(parties: Vec<T>, f: F) -> Vec<U>
+where
+    F: Fn(T) -> U,
+{
+    parties.into_iter().map(f).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -612,6 +1533,828 @@ mod tests {
         singleparty_create_and_verify_helper(64, 8);
     }
 
+    #[test]
+    fn create_and_verify_n_10_m_2() {
+        singleparty_create_and_verify_helper(10, 2);
+    }
+
+    #[test]
+    fn create_and_verify_n_48_m_4() {
+        singleparty_create_and_verify_helper(48, 4);
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_3() {
+        singleparty_create_and_verify_helper(32, 3);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_5() {
+        singleparty_create_and_verify_helper(64, 5);
+    }
+
+    #[test]
+    fn padding_bits_are_not_counted() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        // 1024 fits in the padded 16 bits, but not in 10 bits
+        let (proof, V) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"PaddingTest"),
+            1024,
+            &blinding,
+            10,
+        ).unwrap();
+        assert!(
+            proof
+                .verify_single(&bp_gens, &pc_gens, &mut Transcript::new(b"PaddingTest"), &V, 10)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn blindings_are_bound_to_the_witness() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+
+        // Even with a fixed external RNG, proofs for different
+        // witnesses use different blinding factors.
+        let prove = |blinding: Scalar| {
+            RangeProof::prove_multiple_with_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"TranscriptRngTest"),
+                &[7],
+                &[blinding],
+                32,
+                &mut StdRng::from_seed([0u8; 32]),
+            ).unwrap()
+        };
+        let (proof_1, V_1) = prove(Scalar::from(1u64));
+        let (proof_2, _) = prove(Scalar::from(2u64));
+        assert!(proof_1.S != proof_2.S);
+
+        assert!(
+            proof_1
+                .verify_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"TranscriptRngTest"),
+                    &V_1,
+                    32,
+                ).is_ok()
+        );
+    }
+
+    #[test]
+    fn verification_is_deterministic() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let mut prover_transcript = Transcript::new(b"DeterministicTest");
+        let (proof, V) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut prover_transcript,
+            7,
+            &blinding,
+            32,
+        ).unwrap();
+
+        // Verification leaves the transcript in the same state as the
+        // prover's, so the proof can be chained with further proofs.
+        let mut verifier_transcript = Transcript::new(b"DeterministicTest");
+        assert!(
+            proof
+                .verify_single(&bp_gens, &pc_gens, &mut verifier_transcript, &V, 32)
+                .is_ok()
+        );
+        assert_eq!(
+            prover_transcript.challenge_scalar(b"next"),
+            verifier_transcript.challenge_scalar(b"next")
+        );
+    }
+
+    #[test]
+    fn prove_over_existing_commitments() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+        let blindings: Vec<_> = (0..2).map(|_| Scalar::random(&mut rng)).collect();
+        let values = [7u64, 11u64];
+
+        // Commitments published before the proof is made
+        let commitments: Vec<_> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(&v, &v_blinding)| pc_gens.commit(Scalar::from(v), v_blinding).compress())
+            .collect();
+
+        let proof = RangeProof::prove_multiple_with_commitments(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"ExistingTest"),
+            &commitments,
+            &values,
+            &blindings,
+            32,
+        ).unwrap();
+        assert!(
+            proof
+                .verify_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"ExistingTest"),
+                    &commitments,
+                    32
+                ).is_ok()
+        );
+
+        assert_eq!(
+            RangeProof::prove_multiple_with_commitments(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"ExistingTest"),
+                &commitments,
+                &[7, 12],
+                &blindings,
+                32,
+            ).map(|_| ()),
+            Err(ProofError::CommitmentMismatch)
+        );
+        assert!(
+            party::Party::new_with_commitment(
+                &bp_gens,
+                &pc_gens,
+                commitments[0],
+                7,
+                blindings[1],
+                32
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn prove_to_bytes_and_verify_from_bytes() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let (proof_bytes, V) = RangeProof::prove_single_to_bytes(
+            &bp_gens,
+            &pc_gens,
+            b"OneShotTest",
+            1037578891u64,
+            &blinding,
+            32,
+        ).unwrap();
+
+        assert!(
+            RangeProof::verify_single_from_bytes(
+                &proof_bytes,
+                &bp_gens,
+                &pc_gens,
+                b"OneShotTest",
+                &V,
+                32
+            ).is_ok()
+        );
+        assert_eq!(
+            RangeProof::verify_single_from_bytes(
+                &proof_bytes,
+                &bp_gens,
+                &pc_gens,
+                b"OtherLabel",
+                &V,
+                32
+            ),
+            Err(ProofError::VerificationError)
+        );
+        assert_eq!(
+            RangeProof::verify_single_from_bytes(
+                &proof_bytes[1..],
+                &bp_gens,
+                &pc_gens,
+                b"OneShotTest",
+                &V,
+                32
+            ),
+            Err(ProofError::InvalidLength { length: 607 })
+        );
+    }
+
+    #[test]
+    fn accessors_match_serialization() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+        let mut transcript = Transcript::new(b"AccessorTest");
+        let (proof, _) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 7, &blinding, 32)
+                .unwrap();
+
+        let bytes = proof.to_bytes();
+        assert_eq!(proof.A().as_bytes(), &bytes[0..32]);
+        assert_eq!(proof.S().as_bytes(), &bytes[32..64]);
+        assert_eq!(proof.T_1().as_bytes(), &bytes[64..96]);
+        assert_eq!(proof.T_2().as_bytes(), &bytes[96..128]);
+        assert_eq!(proof.t_x().as_bytes(), &bytes[128..160]);
+        assert_eq!(proof.t_x_blinding().as_bytes(), &bytes[160..192]);
+        assert_eq!(proof.e_blinding().as_bytes(), &bytes[192..224]);
+        assert_eq!(proof.ipp_proof().to_bytes(), &bytes[224..]);
+
+        let ipp_proof = proof.ipp_proof();
+        assert_eq!(ipp_proof.L_vec().len(), 5);
+        assert_eq!(ipp_proof.R_vec().len(), 5);
+        for (j, (L, R)) in ipp_proof.lr_points().enumerate() {
+            assert_eq!(L, &ipp_proof.L_vec()[j]);
+            assert_eq!(R, &ipp_proof.R_vec()[j]);
+        }
+    }
+
+    #[test]
+    fn parsing_errors_locate_the_element() {
+        use errors::MPCError;
+        use std::error::Error;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+        let mut transcript = Transcript::new(b"ParsingTest");
+        let (proof, _) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 7, &blinding, 32)
+                .unwrap();
+        let bytes = proof.to_bytes();
+
+        // Scalars of the range proof, then the final scalar a of the
+        // inner-product argument, after its 5 pairs of points.
+        for &index in &[4, 5, 6, 17] {
+            let mut corrupted = bytes.clone();
+            corrupted[index * 32 + 31] = 0xff;
+            assert_eq!(
+                RangeProof::from_bytes(&corrupted).unwrap_err(),
+                ProofError::InvalidScalar { index }
+            );
+        }
+        assert_eq!(
+            RangeProof::from_bytes(&bytes[..bytes.len() - 32]).unwrap_err(),
+            ProofError::InvalidLength { length: 576 }
+        );
+
+        let mut bit_commitment = [0u8; 96];
+        bit_commitment[64] = 0xff;
+        assert_eq!(
+            messages::BitCommitment::from_bytes(&bit_commitment).unwrap_err(),
+            ProofError::InvalidPoint { index: 2 }
+        );
+
+        let error = ProofError::ProvingError(MPCError::MaliciousDealer);
+        assert_eq!(
+            error.source().unwrap().to_string(),
+            MPCError::MaliciousDealer.to_string()
+        );
+    }
+
+    #[test]
+    fn strict_parsing_checks_points() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+        let mut transcript = Transcript::new(b"StrictParsingTest");
+        let (proof, _) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 7, &blinding, 32)
+                .unwrap();
+        let mut bytes = proof.to_bytes();
+        assert_eq!(RangeProof::from_bytes_strict(&bytes).unwrap(), proof);
+
+        // T_1, then R_0 after the three scalars and L_0.
+        bytes[2 * 32 + 31] = 0xff;
+        bytes[8 * 32 + 31] = 0xff;
+        assert!(RangeProof::from_bytes(&bytes).is_ok());
+        assert_eq!(
+            RangeProof::from_bytes_strict(&bytes).unwrap_err(),
+            ProofError::InvalidPoint { index: 2 }
+        );
+
+        bytes[2 * 32 + 31] = proof.T_1().as_bytes()[31];
+        assert_eq!(
+            RangeProof::from_bytes_strict(&bytes).unwrap_err(),
+            ProofError::InvalidPoint { index: 8 }
+        );
+        assert_eq!(
+            InnerProductProof::from_bytes_strict(&bytes[7 * 32..]).unwrap_err(),
+            ProofError::InvalidPoint { index: 1 }
+        );
+    }
+
+    #[test]
+    fn write_bytes_into_buffer() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+        let mut transcript = Transcript::new(b"WriteBytesTest");
+        let (proof, _) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 7, &blinding, 32)
+                .unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(proof.serialized_size(), bytes.len());
+
+        let mut buf = [0u8; 1024];
+        assert_eq!(proof.write_bytes(&mut buf), Ok(bytes.len()));
+        assert_eq!(&buf[..bytes.len()], &bytes[..]);
+        assert!(buf[bytes.len()..].iter().all(|&byte| byte == 0));
+        assert_eq!(
+            proof.write_bytes(&mut buf[..bytes.len() - 1]),
+            Err(ProofError::BufferTooSmall { size: bytes.len() })
+        );
+
+        let ipp_proof = proof.ipp_proof();
+        let ipp_size = ipp_proof.serialized_size();
+        assert_eq!(ipp_proof.write_bytes(&mut buf), Ok(ipp_size));
+        assert_eq!(&buf[..ipp_size], &ipp_proof.to_bytes()[..]);
+    }
+
+    #[test]
+    fn proofs_compare_by_encoding() {
+        use std::collections::HashSet;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+        let mut transcript = Transcript::new(b"EqualityTest");
+        let (proof, _) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 7, &blinding, 32)
+                .unwrap();
+        let mut transcript = Transcript::new(b"EqualityTest");
+        let (other_proof, _) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 7, &blinding, 32)
+                .unwrap();
+
+        let decoded = RangeProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert_eq!(proof, decoded);
+        assert_eq!(proof.ipp_proof(), decoded.ipp_proof());
+        assert_ne!(proof, other_proof);
+        assert_ne!(proof.ipp_proof(), other_proof.ipp_proof());
+
+        let proofs: HashSet<RangeProof> = vec![proof, decoded, other_proof].into_iter().collect();
+        assert_eq!(proofs.len(), 2);
+    }
+
+    #[test]
+    fn prove_and_verify_with_typed_bitsize() {
+        use self::bitsize::{Bits16, Bits8};
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let mut rng = rand::thread_rng();
+        let blindings: Vec<_> = (0..2).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"TypedBitsizeTest");
+        let (proof, value_commitments) = RangeProof::prove_multiple_bits::<Bits16>(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[65535, 255],
+            &blindings,
+        ).unwrap();
+
+        let mut transcript = Transcript::new(b"TypedBitsizeTest");
+        assert!(
+            proof
+                .verify_multiple_bits::<Bits16>(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments
+                ).is_ok()
+        );
+
+        let mut transcript = Transcript::new(b"TypedBitsizeTest");
+        assert!(
+            proof
+                .verify_multiple_bits::<Bits8>(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments
+                ).is_err()
+        );
+    }
+
+    #[test]
+    fn prove_and_verify_by_value_type() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let mut transcript = Transcript::new(b"ValueTypeTest");
+        let (proof, V) =
+            RangeProof::prove_value(&bp_gens, &pc_gens, &mut transcript, 42u32, &blinding).unwrap();
+        assert_eq!(proof.implied_log_size(), 5);
+
+        let mut transcript = Transcript::new(b"ValueTypeTest");
+        assert!(
+            proof
+                .verify_value::<u32>(&bp_gens, &pc_gens, &mut transcript, &V)
+                .is_ok()
+        );
+
+        let mut transcript = Transcript::new(b"ValueTypeTest");
+        assert!(
+            proof
+                .verify_value::<u64>(&bp_gens, &pc_gens, &mut transcript, &V)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn prove_and_verify_u128_values() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(128, 2);
+        let mut rng = rand::thread_rng();
+
+        let values = [u128::max_value(), 1u128 << 100];
+        let blindings: Vec<_> = (0..2).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"U128Test");
+        let (proof, value_commitments) = RangeProof::prove_multiple_u128(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            128,
+        ).unwrap();
+        assert_eq!(proof.implied_log_size(), 8);
+
+        let mut transcript = Transcript::new(b"U128Test");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, 128)
+                .is_ok()
+        );
+
+        // A 101-bit value is not in a 64-bit range
+        let (v, v_blinding) = (values[1], &blindings[1]);
+        let mut transcript = Transcript::new(b"U128Test");
+        let (proof, V) =
+            RangeProof::prove_single_u128(&bp_gens, &pc_gens, &mut transcript, v, v_blinding, 64)
+                .unwrap();
+        let mut transcript = Transcript::new(b"U128Test");
+        assert!(
+            proof
+                .verify_single(&bp_gens, &pc_gens, &mut transcript, &V, 64)
+                .is_err()
+        );
+
+        let v_blinding = &blindings[0];
+        let mut transcript = Transcript::new(b"U128Test");
+        let (proof, V) =
+            RangeProof::prove_value(&bp_gens, &pc_gens, &mut transcript, 7u128, v_blinding)
+                .unwrap();
+        let mut transcript = Transcript::new(b"U128Test");
+        assert!(
+            proof
+                .verify_value::<u128>(&bp_gens, &pc_gens, &mut transcript, &V)
+                .is_ok()
+        );
+
+        let mut transcript = Transcript::new(b"U128Test");
+        let result =
+            RangeProof::prove_single_u128(&bp_gens, &pc_gens, &mut transcript, 7, v_blinding, 129);
+        assert_eq!(result.unwrap_err(), ProofError::InvalidBitsize);
+    }
+
+    #[test]
+    fn prove_and_verify_openings() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+
+        let input = Opening::new(100, Scalar::random(&mut rng));
+        let output = Opening::new(58, Scalar::random(&mut rng));
+        let change = &input - &output;
+        let (I, O) = (pc_gens.commit_opening(&input), pc_gens.commit_opening(&output));
+
+        let mut transcript = Transcript::new(b"OpeningsTest");
+        let openings = [output, change];
+        let (proof, value_commitments) =
+            RangeProof::prove_openings(&bp_gens, &pc_gens, &mut transcript, &openings, 32)
+                .unwrap();
+        assert_eq!(value_commitments, vec![O.compress(), (I - O).compress()]);
+
+        let mut transcript = Transcript::new(b"OpeningsTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, 32)
+                .is_ok()
+        );
+
+        // A negative difference wraps around the group order
+        let mut transcript = Transcript::new(b"OpeningsTest");
+        let negative = [&openings[0] - &input];
+        assert_eq!(
+            RangeProof::prove_openings(&bp_gens, &pc_gens, &mut transcript, &negative, 32)
+                .unwrap_err(),
+            ProofError::InvalidRange
+        );
+    }
+
+    #[test]
+    fn verify_rejects_mismatched_sizes() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let mut rng = rand::thread_rng();
+
+        let values = [1u64, 2u64];
+        let blindings: Vec<_> = (0..2).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"SizeMismatchTest");
+        let (proof, value_commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            32,
+        ).unwrap();
+
+        assert_eq!(proof.implied_log_size(), 6);
+
+        // Wrong bitsize
+        let mut transcript = Transcript::new(b"SizeMismatchTest");
+        assert_eq!(
+            proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, 64),
+            Err(ProofError::ProofSizeMismatch)
+        );
+
+        // Wrong number of commitments
+        let mut transcript = Transcript::new(b"SizeMismatchTest");
+        assert_eq!(
+            proof.verify_multiple(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &value_commitments[..1],
+                32
+            ),
+            Err(ProofError::ProofSizeMismatch)
+        );
+
+        // Trading bits for parties still gets caught by the proof itself
+        let mut transcript = Transcript::new(b"SizeMismatchTest");
+        let four_commitments = [
+            value_commitments[0],
+            value_commitments[1],
+            value_commitments[0],
+            value_commitments[1],
+        ];
+        assert_eq!(
+            proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &four_commitments, 16),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn self_check_catches_mistakes() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let (proof, _) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"SelfCheckTest"),
+            77,
+            &blinding,
+            32,
+        ).unwrap();
+
+        assert!(
+            proof
+                .self_check(&bp_gens, &pc_gens, b"SelfCheckTest", 77, &blinding, 32)
+                .is_ok()
+        );
+        assert!(
+            proof
+                .self_check(&bp_gens, &pc_gens, b"WrongLabel", 77, &blinding, 32)
+                .is_err()
+        );
+        assert!(
+            proof
+                .self_check(&bp_gens, &pc_gens, b"SelfCheckTest", 78, &blinding, 32)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn prove_and_verify_with_metadata() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+        let blindings: Vec<_> = (0..2).map(|_| Scalar::random(&mut rng)).collect();
+        let metadata = [Scalar::from(840u64), Scalar::from(978u64)];
+
+        let (proof, value_commitments) = RangeProof::prove_multiple_with_metadata(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"MetadataTest"),
+            &[100, 200],
+            &metadata,
+            &blindings,
+            32,
+        ).unwrap();
+        assert_eq!(
+            value_commitments[0],
+            pc_gens
+                .commit_with_metadata(Scalar::from(100u64), metadata[0], blindings[0])
+                .compress()
+        );
+
+        let mut transcript = Transcript::new(b"MetadataTest");
+        assert!(
+            proof
+                .verify_multiple_with_metadata(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    &metadata,
+                    32,
+                ).is_ok()
+        );
+
+        // The metadata is bound to the proof
+        let mut transcript = Transcript::new(b"MetadataTest");
+        assert!(
+            proof
+                .verify_multiple_with_metadata(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    &[metadata[1], metadata[0]],
+                    32,
+                ).is_err()
+        );
+    }
+
+    #[test]
+    fn prove_and_verify_range() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 2);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let (proof, V) = RangeProof::prove_range(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"RangeTest"),
+            1234,
+            &blinding,
+            1000,
+            5000,
+        ).unwrap();
+        assert_eq!(V, pc_gens.commit(Scalar::from(1234u64), blinding).compress());
+
+        assert!(
+            proof
+                .verify_range(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"RangeTest"),
+                    &V,
+                    1000,
+                    5000
+                ).is_ok()
+        );
+        // The bounds are bound to the proof
+        assert!(
+            proof
+                .verify_range(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"RangeTest"),
+                    &V,
+                    1235,
+                    5000
+                ).is_err()
+        );
+
+        assert_eq!(
+            RangeProof::prove_range(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"RangeTest"),
+                999,
+                &blinding,
+                1000,
+                5000,
+            ).map(|_| ()),
+            Err(ProofError::InvalidRange)
+        );
+    }
+
+    #[test]
+    fn range_bitsize_fits_width() {
+        assert_eq!(range_bitsize(7, 7), 1);
+        assert_eq!(range_bitsize(0, 255), 8);
+        assert_eq!(range_bitsize(1000, 1256), 9);
+        assert_eq!(range_bitsize(1000, 5000), 12);
+        assert_eq!(range_bitsize(0, 1 << 32), 33);
+        assert_eq!(range_bitsize(0, u64::max_value()), 64);
+    }
+
+    #[test]
+    fn verify_commitments_from_iterator() {
+        struct Output {
+            commitment: CompressedRistretto,
+        }
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+        let blindings: Vec<_> = (0..2).map(|_| Scalar::random(&mut rng)).collect();
+
+        let (proof, value_commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"IterTest"),
+            &[5, 6],
+            &blindings,
+            32,
+        ).unwrap();
+        let outputs: Vec<_> = value_commitments
+            .iter()
+            .map(|&commitment| Output { commitment })
+            .collect();
+
+        let mut transcript = Transcript::new(b"IterTest");
+        assert!(
+            proof
+                .verify_multiple_iter(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    outputs.iter().map(|o| &o.commitment),
+                    32,
+                ).is_ok()
+        );
+
+        let mut transcript = Transcript::new(b"IterTest");
+        assert!(
+            proof
+                .verify_multiple_iter(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    outputs.iter().rev().map(|o| &o.commitment),
+                    32,
+                ).is_err()
+        );
+    }
+
+    #[test]
+    fn salted_generators_are_bound_to_the_proof() {
+        let pc_gens = PedersenGens::default();
+        let base_gens = BulletproofGens::new(32, 1);
+        let epoch_gens = base_gens.salted(b"epoch 7");
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let (proof, V) = RangeProof::prove_single(
+            &epoch_gens,
+            &pc_gens,
+            &mut Transcript::new(b"SaltTest"),
+            1234,
+            &blinding,
+            32,
+        ).unwrap();
+
+        // The verifier re-derives the generators from the recorded salt
+        let verifier_gens = base_gens.salted(epoch_gens.salts()[0].as_slice());
+        let mut transcript = Transcript::new(b"SaltTest");
+        assert!(
+            proof
+                .verify_single(&verifier_gens, &pc_gens, &mut transcript, &V, 32)
+                .is_ok()
+        );
+
+        for gens in [base_gens.clone(), base_gens.salted(b"epoch 8")].iter() {
+            let mut transcript = Transcript::new(b"SaltTest");
+            assert!(
+                proof
+                    .verify_single(gens, &pc_gens, &mut transcript, &V, 32)
+                    .is_err()
+            );
+        }
+    }
+
     #[test]
     fn detect_dishonest_party_during_aggregation() {
         use self::dealer::*;
@@ -674,8 +2417,24 @@ mod tests {
         let share3 = party3.apply_challenge(&poly_challenge).unwrap();
 
         match dealer.receive_shares(&[share0, share1, share2, share3]) {
-            Err(MPCError::MalformedProofShares { bad_shares }) => {
+            Err(MPCError::MalformedProofShares {
+                bad_shares,
+                evidence,
+            }) => {
                 assert_eq!(bad_shares, vec![1, 3]);
+
+                // A third party can check the evidence against the
+                // initial transcript, but not against another one
+                for (j, evidence) in bad_shares.iter().zip(&evidence) {
+                    assert_eq!(evidence.party(), *j);
+                    let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+                    assert!(evidence.verify(&bp_gens, &pc_gens, &mut transcript).is_ok());
+                    let mut transcript = Transcript::new(b"AnotherTranscript");
+                    assert_eq!(
+                        evidence.verify(&bp_gens, &pc_gens, &mut transcript),
+                        Err(MPCError::InvalidEvidence)
+                    );
+                }
             }
             Err(_) => {
                 panic!("Got wrong error type from malformed shares");
@@ -686,6 +2445,656 @@ mod tests {
         }
     }
 
+    #[test]
+    fn audit_dealer_challenges() {
+        use self::dealer::*;
+        use self::party::*;
+
+        let (n, m) = (32, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        let parties: Vec<_> = [3u64, 4u64]
+            .iter()
+            .map(|&v| Party::new(&bp_gens, &pc_gens, v, Scalar::random(&mut rng), n).unwrap())
+            .collect();
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .enumerate()
+            .map(|(j, p)| p.assign_position(j).unwrap())
+            .unzip();
+        let Vs: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+        let mut transcript = Transcript::new(b"AuditTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+
+        // The auditor replays the transcript from the published values.
+        let mut audit = Transcript::new(b"AuditTest");
+        audit.rangeproof_domain_sep(n as u64, m as u64);
+        for V in Vs.iter() {
+            audit.commit_point(b"V", V);
+        }
+        audit.commit_point(b"A", &dealer.A());
+        audit.commit_point(b"S", &dealer.S());
+        assert_eq!(audit.challenge_scalar(b"y"), dealer.bit_challenge().y());
+        assert_eq!(audit.challenge_scalar(b"z"), bit_challenge.z());
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&bit_challenge))
+            .unzip();
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments).unwrap();
+
+        audit.commit_point(b"T_1", &dealer.T_1());
+        audit.commit_point(b"T_2", &dealer.T_2());
+        assert_eq!(audit.challenge_scalar(b"x"), dealer.poly_challenge().x());
+
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+            .collect();
+        let (A, S) = (dealer.A(), dealer.S());
+        let proof = dealer.receive_shares(&proof_shares).unwrap();
+        assert_eq!((proof.A, proof.S), (A, S));
+    }
+
+    #[test]
+    fn parties_verify_dealer_challenges() {
+        use self::dealer::*;
+        use self::messages::*;
+        use self::party::*;
+        use errors::MPCError;
+
+        let (n, m) = (16, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        let parties: Vec<_> = [8u64, 9u64]
+            .iter()
+            .map(|&v| Party::new(&bp_gens, &pc_gens, v, Scalar::random(&mut rng), n).unwrap())
+            .collect();
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .enumerate()
+            .map(|(j, p)| p.assign_position(j).unwrap())
+            .unzip();
+
+        // Party 1 checks the challenges with the dealer's initial transcript
+        let mut transcript = Transcript::new(b"ChallengeTest");
+        let mut verifier = ChallengeVerifier::new(&bp_gens, transcript.clone(), n, m);
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        let (dealer, bit_challenge) = dealer
+            .receive_bit_commitments(bit_commitments.clone())
+            .unwrap();
+
+        // A biased challenge, or a list of commitments without the
+        // party's own, is rejected
+        let biased = BitChallenge {
+            y: bit_challenge.y + Scalar::one(),
+            z: bit_challenge.z,
+        };
+        let forged = [bit_commitments[0], bit_commitments[0]];
+        assert_eq!(
+            verifier.clone().verify_bit_challenge(
+                1,
+                &bit_commitments[1],
+                &bit_commitments,
+                &biased
+            ),
+            Err(MPCError::MaliciousDealer)
+        );
+        assert_eq!(
+            verifier
+                .clone()
+                .verify_bit_challenge(1, &bit_commitments[1], &forged, &bit_challenge),
+            Err(MPCError::MaliciousDealer)
+        );
+
+        let commitments = dealer.bit_commitments();
+        assert!(
+            verifier
+                .verify_bit_challenge(1, &bit_commitments[1], commitments, &bit_challenge)
+                .is_ok()
+        );
+
+        let (_, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&bit_challenge))
+            .unzip();
+        let (dealer, poly_challenge) = dealer
+            .receive_poly_commitments(poly_commitments.clone())
+            .unwrap();
+
+        let biased = PolyChallenge {
+            x: poly_challenge.x + Scalar::one(),
+        };
+        let commitments = dealer.poly_commitments();
+        assert_eq!(
+            verifier
+                .clone()
+                .verify_poly_challenge(1, &poly_commitments[1], commitments, &biased),
+            Err(MPCError::MaliciousDealer)
+        );
+        assert!(
+            verifier
+                .verify_poly_challenge(1, &poly_commitments[1], commitments, &poly_challenge)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn receive_shares_with_audit_policies() {
+        use self::blame::ShareAuditor;
+        use self::dealer::*;
+        use self::party::*;
+        use errors::MPCError;
+
+        let (n, m) = (32, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        let policies = [
+            AuditPolicy::Always,
+            AuditPolicy::Never,
+            AuditPolicy::Sample(1),
+            AuditPolicy::Sample(2),
+        ];
+        for policy in policies.iter() {
+            // Party 1 is dishonest and uses a 64-bit value
+            let parties: Vec<_> = [7u64, u64::max_value()]
+                .iter()
+                .map(|&v| Party::new(&bp_gens, &pc_gens, v, Scalar::one(), n).unwrap())
+                .collect();
+            let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
+                .into_iter()
+                .enumerate()
+                .map(|(j, p)| p.assign_position(j).unwrap())
+                .unzip();
+
+            let mut transcript = Transcript::new(b"AuditPolicyTest");
+            let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+            let (dealer, bit_challenge) = dealer
+                .receive_bit_commitments(bit_commitments.clone())
+                .unwrap();
+            let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+                .into_iter()
+                .map(|p| p.apply_challenge(&bit_challenge))
+                .unzip();
+            let (dealer, poly_challenge) = dealer
+                .receive_poly_commitments(poly_commitments.clone())
+                .unwrap();
+            let proof_shares: Vec<_> = parties
+                .into_iter()
+                .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+                .collect();
+
+            // The shares can also be audited outside the dealer
+            let mut transcript = Transcript::new(b"AuditPolicyTest");
+            let auditor = ShareAuditor::new(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                n,
+                bit_commitments,
+                poly_commitments,
+            ).unwrap();
+            assert!(auditor.audit_share(0, &proof_shares[0]).is_ok());
+            assert_eq!(
+                auditor.audit_share(1, &proof_shares[1]).unwrap_err(),
+                dealer.audit_share(1, &proof_shares[1]).unwrap_err()
+            );
+
+            let result = dealer.receive_shares_with_policy(&proof_shares, *policy);
+            match (policy, result) {
+                (AuditPolicy::Never, Ok(_)) | (AuditPolicy::Sample(1), Ok(_)) => {}
+                (_, Err(MPCError::MalformedProofShares { bad_shares, .. })) => {
+                    assert_eq!(bad_shares, vec![1]);
+                }
+                _ => panic!("The malformed share was not detected"),
+            }
+        }
+    }
+
+    #[test]
+    fn aggregation_padded_with_dummy_parties() {
+        use self::dealer::*;
+        use self::party::*;
+        use errors::MPCError;
+
+        let (n, m) = (16, 4);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+        let key = b"checkpoint key";
+
+        let mut transcript = Transcript::new(b"DummyPartiesTest");
+        assert_eq!(
+            Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, 0).err(),
+            Some(MPCError::InvalidAggregation)
+        );
+
+        // Three parties, padded with one dummy party
+        let parties: Vec<_> = [1u64, 2u64, 3u64]
+            .iter()
+            .map(|&v| Party::new(&bp_gens, &pc_gens, v, Scalar::random(&mut rng), n).unwrap())
+            .collect();
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .enumerate()
+            .map(|(j, p)| p.assign_position(j).unwrap())
+            .unzip();
+        let mut Vs: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+        let mut transcript = Transcript::new(b"DummyPartiesTest");
+        let mut challenge_verifier = ChallengeVerifier::new(&bp_gens, transcript.clone(), n, 3);
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, 3).unwrap();
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+        assert_eq!(dealer.bit_commitments().len(), m);
+        assert!(
+            challenge_verifier
+                .verify_bit_challenge(
+                    0,
+                    &dealer.bit_commitments()[0],
+                    dealer.bit_commitments(),
+                    &bit_challenge,
+                ).is_ok()
+        );
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&bit_challenge))
+            .unzip();
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments).unwrap();
+
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+            .collect();
+
+        // The dummy party is recreated when the dealer is restored
+        let dealer_checkpoint = dealer.to_checkpoint(key);
+        let mut transcript = Transcript::new(b"DummyPartiesTest");
+        let dealer = DealerAwaitingProofShares::from_checkpoint(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &dealer_checkpoint,
+            key,
+        ).unwrap();
+        assert_eq!(
+            dealer.receive_shares(&proof_shares[..2]).err(),
+            Some(MPCError::WrongNumProofShares)
+        );
+
+        let mut transcript = Transcript::new(b"DummyPartiesTest");
+        let dealer = DealerAwaitingProofShares::from_checkpoint(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &dealer_checkpoint,
+            key,
+        ).unwrap();
+        let proof = dealer.receive_shares(&proof_shares).unwrap();
+
+        // The verifier appends the identity for the dummy party itself,
+        // so passing it explicitly gives the same result
+        let mut transcript = Transcript::new(b"DummyPartiesTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs, n)
+                .is_ok()
+        );
+        Vs.push(CompressedRistretto::identity());
+        let mut transcript = Transcript::new(b"DummyPartiesTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs, n)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn aggregation_with_multiple_values_per_party() {
+        use self::dealer::*;
+        use self::party::*;
+        use errors::MPCError;
+
+        let (n, m) = (32, 4);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+        let key = b"checkpoint key";
+
+        assert_eq!(
+            Party::new_multiple(&bp_gens, &pc_gens, &[], n).err(),
+            Some(MPCError::InvalidAggregation)
+        );
+
+        // A wallet with three outputs, and a party with one value
+        let outputs: Vec<_> = [10u64, 20u64, 30u64]
+            .iter()
+            .map(|&v| (v, Scalar::random(&mut rng)))
+            .collect();
+        let wallet = Party::new_multiple(&bp_gens, &pc_gens, &outputs, n).unwrap();
+        assert_eq!(wallet.num_values(), 3);
+        let party = Party::new(&bp_gens, &pc_gens, 40, Scalar::random(&mut rng), n).unwrap();
+
+        let mut transcript = Transcript::new(b"MultipleValuesTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        let (wallet, mut bit_commitments) = wallet.assign_positions(0).unwrap();
+        let (party, bit_commitment) = party.assign_position(3).unwrap();
+        assert_eq!(bit_commitments.len(), 3);
+        bit_commitments.push(bit_commitment);
+        let Vs: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+
+        // The combined state is checkpointed as a whole
+        let checkpoint = wallet.to_checkpoint(key);
+        let wallet =
+            PartyAwaitingBitChallenges::from_checkpoint(&pc_gens, &checkpoint, key).unwrap();
+        assert_eq!(
+            PartyAwaitingPolyChallenges::from_checkpoint(&checkpoint, key).err(),
+            Some(MPCError::InvalidCheckpoint)
+        );
+
+        let (wallet, mut poly_commitments) = wallet.apply_challenge(&bit_challenge);
+        let (party, poly_commitment) = party.apply_challenge(&bit_challenge);
+        poly_commitments.push(poly_commitment);
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments).unwrap();
+
+        let mut proof_shares = wallet.apply_challenge(&poly_challenge).unwrap();
+        proof_shares.push(party.apply_challenge(&poly_challenge).unwrap());
+        let proof = dealer.receive_shares(&proof_shares).unwrap();
+
+        let mut transcript = Transcript::new(b"MultipleValuesTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs, n)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn aggregation_bound_to_session() {
+        use self::dealer::*;
+        use self::messages::*;
+        use self::party::*;
+
+        let (n, m) = (16, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        let identities = vec![b"alice".to_vec(), b"bob".to_vec()];
+        let session = SessionBinding::random(identities.clone());
+        assert_eq!(session.num_parties(), 2);
+        assert_eq!(session.identity(1), Some(&b"bob"[..]));
+        assert_eq!(session.identity(2), None);
+
+        let parties: Vec<_> = [5u64, 6u64]
+            .iter()
+            .map(|&v| Party::new(&bp_gens, &pc_gens, v, Scalar::random(&mut rng), n).unwrap())
+            .collect();
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .enumerate()
+            .map(|(j, p)| p.assign_position(j).unwrap())
+            .unzip();
+        let Vs: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+        let mut transcript = Transcript::new(b"SessionBindingTest");
+        let dealer =
+            Dealer::new_with_session(&bp_gens, &pc_gens, &mut transcript, n, &session).unwrap();
+        let (dealer, bit_challenge) = dealer
+            .receive_bit_commitments(bit_commitments.clone())
+            .unwrap();
+
+        // The parties bind the session before checking the challenge
+        let mut initial = Transcript::new(b"SessionBindingTest");
+        session.bind(&mut initial);
+        let mut verifier = ChallengeVerifier::new(&bp_gens, initial, n, m);
+        assert!(
+            verifier
+                .verify_bit_challenge(0, &bit_commitments[0], &bit_commitments, &bit_challenge)
+                .is_ok()
+        );
+
+        // The same commitments give other challenges in another session
+        let other = SessionBinding::random(identities.clone());
+        let mut transcript = Transcript::new(b"SessionBindingTest");
+        let other_dealer =
+            Dealer::new_with_session(&bp_gens, &pc_gens, &mut transcript, n, &other).unwrap();
+        let (_, other_challenge) = other_dealer
+            .receive_bit_commitments(bit_commitments.clone())
+            .unwrap();
+        assert_ne!(other_challenge.y(), bit_challenge.y());
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&bit_challenge))
+            .unzip();
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments).unwrap();
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+            .collect();
+        let proof = dealer.receive_shares(&proof_shares).unwrap();
+
+        // The proof only verifies with the session and the identities
+        // in the same positions
+        let mut transcript = Transcript::new(b"SessionBindingTest");
+        session.bind(&mut transcript);
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs, n)
+                .is_ok()
+        );
+        let swapped =
+            SessionBinding::new(*session.nonce(), identities.iter().rev().cloned().collect());
+        let mut transcript = Transcript::new(b"SessionBindingTest");
+        swapped.bind(&mut transcript);
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs, n)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn aggregation_over_serialized_messages() {
+        use self::dealer::*;
+        use self::messages::*;
+        use self::party::*;
+        use bincode;
+
+        let (n, m) = (16, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        // The parties send their messages in the byte encoding, and
+        // the dealer sends the challenges with serde.
+        let parties: Vec<_> = [5u64, 6u64]
+            .iter()
+            .map(|&v| Party::new(&bp_gens, &pc_gens, v, Scalar::random(&mut rng), n).unwrap())
+            .collect();
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .enumerate()
+            .map(|(j, p)| p.assign_position(j).unwrap())
+            .unzip();
+        let bit_commitments: Vec<_> = bit_commitments
+            .iter()
+            .map(|c| BitCommitment::from_bytes(&c.to_bytes()).unwrap())
+            .collect();
+        let Vs: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+        let mut transcript = Transcript::new(b"SerializedMessagesTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+        let bit_challenge: BitChallenge =
+            bincode::deserialize(&bincode::serialize(&bit_challenge).unwrap()).unwrap();
+        let decoded = BitChallenge::from_bytes(&bit_challenge.to_bytes()).unwrap();
+        assert_eq!(decoded.y(), bit_challenge.y());
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&bit_challenge))
+            .unzip();
+        let poly_commitments: Vec<_> = poly_commitments
+            .iter()
+            .map(|c| PolyCommitment::from_bytes(&c.to_bytes()).unwrap())
+            .collect();
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments).unwrap();
+        let poly_challenge: PolyChallenge =
+            bincode::deserialize(&bincode::serialize(&poly_challenge).unwrap()).unwrap();
+        let decoded = PolyChallenge::from_bytes(&poly_challenge.to_bytes()).unwrap();
+        assert_eq!(decoded.x(), poly_challenge.x());
+
+        let share_bytes: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap().to_bytes())
+            .collect();
+        assert_eq!(share_bytes[0].len(), (3 + 2 * n) * 32);
+        let proof_shares: Vec<_> = share_bytes
+            .iter()
+            .map(|bytes| ProofShare::from_bytes(bytes).unwrap())
+            .collect();
+        let proof = dealer.receive_shares(&proof_shares).unwrap();
+
+        let mut transcript = Transcript::new(b"SerializedMessagesTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs, n)
+                .is_ok()
+        );
+
+        // Malformed encodings are rejected
+        assert!(ProofShare::from_bytes(&share_bytes[0][32..]).is_err());
+        assert!(PolyChallenge::from_bytes(&[0xff; 32]).is_err());
+        assert!(BitCommitment::from_bytes(&[0xff; 96]).is_err());
+    }
+
+    #[test]
+    fn aggregation_resumed_from_checkpoints() {
+        use self::dealer::*;
+        use self::party::*;
+        use errors::MPCError;
+
+        let (n, m) = (16, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+        let key = b"checkpoint key";
+
+        // Every state is saved, dropped, and restored before the next step
+        let checkpoints: Vec<_> = [7u64, 8u64]
+            .iter()
+            .map(|&v| {
+                Party::new(&bp_gens, &pc_gens, v, Scalar::random(&mut rng), n)
+                    .unwrap()
+                    .to_checkpoint(key)
+            }).collect();
+        let mut transcript = Transcript::new(b"CheckpointTest");
+        let dealer_checkpoint = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m)
+            .unwrap()
+            .to_checkpoint(key);
+
+        let mut bit_commitments = Vec::new();
+        let mut party_checkpoints = Vec::new();
+        for (j, checkpoint) in checkpoints.iter().enumerate() {
+            let party = PartyAwaitingPosition::from_checkpoint(&bp_gens, &pc_gens, checkpoint, key)
+                .unwrap();
+            let (party, bit_commitment) = party.assign_position(j).unwrap();
+            bit_commitments.push(bit_commitment);
+            party_checkpoints.push(party.to_checkpoint(key));
+        }
+        let Vs: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+        let mut transcript = Transcript::new(b"CheckpointTest");
+        let dealer = DealerAwaitingBitCommitments::from_checkpoint(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &dealer_checkpoint,
+            key,
+        ).unwrap();
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+        let dealer_checkpoint = dealer.to_checkpoint(key);
+
+        let mut poly_commitments = Vec::new();
+        let checkpoints = party_checkpoints;
+        let mut party_checkpoints = Vec::new();
+        for checkpoint in checkpoints.iter() {
+            let party =
+                PartyAwaitingBitChallenge::from_checkpoint(&pc_gens, checkpoint, key).unwrap();
+            let (party, poly_commitment) = party.apply_challenge(&bit_challenge);
+            poly_commitments.push(poly_commitment);
+            party_checkpoints.push(party.to_checkpoint(key));
+        }
+
+        let mut transcript = Transcript::new(b"CheckpointTest");
+        let dealer = DealerAwaitingPolyCommitments::from_checkpoint(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &dealer_checkpoint,
+            key,
+        ).unwrap();
+        assert_eq!(dealer.bit_challenge().y(), bit_challenge.y());
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments).unwrap();
+        let dealer_checkpoint = dealer.to_checkpoint(key);
+
+        let proof_shares: Vec<_> = party_checkpoints
+            .iter()
+            .map(|checkpoint| {
+                PartyAwaitingPolyChallenge::from_checkpoint(checkpoint, key)
+                    .unwrap()
+                    .apply_challenge(&poly_challenge)
+                    .unwrap()
+            }).collect();
+
+        let mut transcript = Transcript::new(b"CheckpointTest");
+        let dealer = DealerAwaitingProofShares::from_checkpoint(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &dealer_checkpoint,
+            key,
+        ).unwrap();
+        assert_eq!(dealer.poly_challenge().x(), poly_challenge.x());
+        let proof = dealer.receive_shares(&proof_shares).unwrap();
+
+        let mut transcript = Transcript::new(b"CheckpointTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs, n)
+                .is_ok()
+        );
+
+        // Checkpoints are only restored with the same key, as the same
+        // kind of state, and without modifications
+        let checkpoint = &party_checkpoints[0];
+        let mut tampered = checkpoint.clone();
+        tampered[0] ^= 1;
+        assert_eq!(
+            PartyAwaitingPolyChallenge::from_checkpoint(checkpoint, b"another key").err(),
+            Some(MPCError::InvalidCheckpoint)
+        );
+        assert_eq!(
+            PartyAwaitingPolyChallenge::from_checkpoint(&tampered, key).err(),
+            Some(MPCError::InvalidCheckpoint)
+        );
+        assert_eq!(
+            PartyAwaitingBitChallenge::from_checkpoint(&pc_gens, checkpoint, key).err(),
+            Some(MPCError::InvalidCheckpoint)
+        );
+    }
+
     #[test]
     fn detect_dishonest_dealer_during_aggregation() {
         use self::dealer::*;
@@ -9,18 +9,54 @@
 //! For more explanation of how the `dealer`, `party`, and `messages`
 //! modules orchestrate the protocol execution, see the documentation
 //! in the [`aggregation`](::aggregation) module.
+//!
+//! The work which does not depend on the party's position, such as
+//! sampling the blinding factors, is done when the party is created,
+//! so that the first round can be answered quickly once the dealer
+//! assigns the positions.
+//!
+//! A party which contributes several values, such as a wallet
+//! creating several outputs, is created with
+//! [`Party::new_multiple`].  Its values take consecutive positions,
+//! and it sends one combined message per round, holding the messages
+//! of all of its positions, in order.
+//!
+//! Each state can be saved with its `to_checkpoint` function and
+//! restored with `from_checkpoint`, so that a party can resume the
+//! protocol after a process restart.  A checkpoint is an opaque byte
+//! string authenticated under a secret checkpoint key, and is only
+//! restored with the same key.  It is not encrypted: it contains the
+//! party's value and blinding factors, and must be stored as
+//! carefully as the value itself.
+//!
+//! A checkpoint must also be restored at most once.  The blinding
+//! factors are sampled when the party is created, not when it answers
+//! a challenge, so two answers from the same checkpoint to two
+//! different challenges share their blinding factors, and together
+//! reveal \\(\mathbf{a}\_L\\), and with it the value \\(v\\) and its
+//! blinding factor \\(\tilde{v}\\).  Since a checkpoint is only a byte
+//! string, this cannot be enforced here: the caller must discard a
+//! checkpoint, or mark it as used, before sending the message
+//! computed from it.
 
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::MultiscalarMul;
+use curve25519_dalek::traits::{Identity, MultiscalarMul};
+use merlin::Transcript;
 
 use clear_on_drop::clear::Clear;
 use errors::MPCError;
 use generators::{BulletproofGens, PedersenGens};
-use rand;
+use math;
+use rand::{self, CryptoRng, RngCore};
+use scalar::{self, ScalarVec};
 use std::iter;
+use std::mem;
+use std::ptr;
 use util;
 
+use super::checkpoint::{CheckpointReader, CheckpointWriter};
+use super::dealer::{commit_bit_commitments, commit_parameters, commit_poly_commitments};
 use super::messages::*;
 
 /// Used to construct a party for the aggregated rangeproof MPC protocol.
@@ -28,6 +64,10 @@ pub struct Party {}
 
 impl Party {
     /// Constructs a `PartyAwaitingPosition` with the given rangeproof parameters.
+    ///
+    /// This samples the blinding factors for the bit commitments, so
+    /// that [`assign_position`](PartyAwaitingPosition::assign_position)
+    /// only has to do the work which depends on the position.
     pub fn new<'a>(
         bp_gens: &'a BulletproofGens,
         pc_gens: &'a PedersenGens,
@@ -35,14 +75,42 @@ impl Party {
         v_blinding: Scalar,
         n: usize,
     ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
-        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+        Party::new_with_rng(bp_gens, pc_gens, v.into(), v_blinding, n, &mut rand::thread_rng())
+    }
+
+    /// Constructs a `PartyAwaitingPosition`, sampling the blinding
+    /// factors for the bit commitments from `rng`.
+    ///
+    /// The value is a `u128`, so that ranges of up to \\(n = 128\\)
+    /// bits can be proven.
+    ///
+    /// A party running inside a single prover should pass a
+    /// `TranscriptRng` bound to the proof transcript and the witness,
+    /// so that the blinding factors do not depend on the quality of
+    /// the system RNG alone.
+    pub fn new_with_rng<'a, T: RngCore + CryptoRng>(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        v: u128,
+        v_blinding: Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
+        if n == 0 || n > 128 {
             return Err(MPCError::InvalidBitsize);
         }
-        if bp_gens.gens_capacity < n {
+        // The bit vectors are padded to a power of two
+        let padded_n = n.next_power_of_two();
+        if bp_gens.gens_capacity < padded_n {
             return Err(MPCError::InvalidGeneratorsLength);
         }
 
-        let V = pc_gens.commit(v.into(), v_blinding).compress();
+        let V = pc_gens.commit(scalar::from_u128(v), v_blinding).compress();
+
+        let a_blinding = Scalar::random(rng);
+        let s_blinding = Scalar::random(rng);
+        let s_L = ScalarVec::from(scalar::random_vec(rng, padded_n));
+        let s_R = ScalarVec::from(scalar::random_vec(rng, padded_n));
 
         Ok(PartyAwaitingPosition {
             bp_gens,
@@ -51,8 +119,89 @@ impl Party {
             v,
             v_blinding,
             V,
+            a_blinding,
+            s_blinding,
+            s_L,
+            s_R,
+        })
+    }
+
+    /// Constructs a `PartyAwaitingPosition` for a value commitment `V`
+    /// which was already published, for instance by a wallet.
+    ///
+    /// Returns [`MPCError::CommitmentMismatch`] if `V` does not open
+    /// to `v` with blinding factor `v_blinding`.
+    pub fn new_with_commitment<'a>(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        V: CompressedRistretto,
+        v: u64,
+        v_blinding: Scalar,
+        n: usize,
+    ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
+        let party = Party::new(bp_gens, pc_gens, v, v_blinding, n)?;
+        if party.V != V {
+            return Err(MPCError::CommitmentMismatch);
+        }
+        Ok(party)
+    }
+
+    /// Constructs a dummy party, which the dealer uses to pad an
+    /// aggregation to a power of two.
+    ///
+    /// The dummy party proves the value \\(0\\), and all of its
+    /// blinding factors are zero, so its value commitment is the
+    /// identity and its messages depend only on the generators and the
+    /// challenges.
+    pub(crate) fn dummy<'a>(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        n: usize,
+    ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
+        if n == 0 || n > 128 {
+            return Err(MPCError::InvalidBitsize);
+        }
+        let padded_n = n.next_power_of_two();
+        if bp_gens.gens_capacity < padded_n {
+            return Err(MPCError::InvalidGeneratorsLength);
+        }
+
+        Ok(PartyAwaitingPosition {
+            bp_gens,
+            pc_gens,
+            n,
+            v: 0,
+            v_blinding: Scalar::zero(),
+            V: CompressedRistretto::identity(),
+            a_blinding: Scalar::zero(),
+            s_blinding: Scalar::zero(),
+            s_L: ScalarVec::from(vec![Scalar::zero(); padded_n]),
+            s_R: ScalarVec::from(vec![Scalar::zero(); padded_n]),
         })
     }
+
+    /// Constructs a `PartyAwaitingPositions` for a party which
+    /// contributes several values, given as `(value, blinding)` pairs,
+    /// to the aggregation.
+    ///
+    /// The values take consecutive positions, and the party sends one
+    /// combined message per round, covering all of its positions.
+    /// Returns [`MPCError::InvalidAggregation`] if `values` is empty.
+    pub fn new_multiple<'a>(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        values: &[(u64, Scalar)],
+        n: usize,
+    ) -> Result<PartyAwaitingPositions<'a>, MPCError> {
+        if values.is_empty() {
+            return Err(MPCError::InvalidAggregation);
+        }
+        let parties = values
+            .iter()
+            .map(|&(v, v_blinding)| Party::new(bp_gens, pc_gens, v, v_blinding, n))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PartyAwaitingPositions { parties })
+    }
 }
 
 /// A party waiting for the dealer to assign their position in the aggregation.
@@ -60,34 +209,95 @@ pub struct PartyAwaitingPosition<'a> {
     bp_gens: &'a BulletproofGens,
     pc_gens: &'a PedersenGens,
     n: usize,
-    v: u64,
+    v: u128,
     v_blinding: Scalar,
     V: CompressedRistretto,
+    a_blinding: Scalar,
+    s_blinding: Scalar,
+    s_L: ScalarVec,
+    s_R: ScalarVec,
 }
 
 impl<'a> PartyAwaitingPosition<'a> {
+    /// Replaces the blinding factors of the bit commitments, for
+    /// proofs whose nonces are derived from a rewind key.
+    pub(crate) fn with_blindings(mut self, a_blinding: Scalar, s_blinding: Scalar) -> Self {
+        self.a_blinding = a_blinding;
+        self.s_blinding = s_blinding;
+        self
+    }
+
+    /// Saves the state in a checkpoint authenticated under `key`.
+    pub fn to_checkpoint(&self, key: &[u8]) -> Vec<u8> {
+        let mut writer = CheckpointWriter::new();
+        writer.write_usize(self.n);
+        writer.write_u128(self.v);
+        writer.write_scalar(&self.v_blinding);
+        writer.write_point(&self.V);
+        writer.write_scalar(&self.a_blinding);
+        writer.write_scalar(&self.s_blinding);
+        writer.write_scalars(&self.s_L);
+        writer.write_scalars(&self.s_R);
+        writer.finish(b"PartyAwaitingPosition", key)
+    }
+
+    /// Restores a state saved with
+    /// [`to_checkpoint`](PartyAwaitingPosition::to_checkpoint), using
+    /// the same generators as the party it was taken from.
+    ///
+    /// The checkpoint must be restored at most once: running the
+    /// protocol twice from it, with two different positions or
+    /// challenges, reveals `v` and `v_blinding`.
+    ///
+    /// Returns [`MPCError::InvalidCheckpoint`] if the checkpoint is
+    /// malformed or does not verify under `key`.
+    pub fn from_checkpoint(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        checkpoint: &[u8],
+        key: &[u8],
+    ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
+        let mut reader = CheckpointReader::new(checkpoint, b"PartyAwaitingPosition", key)?;
+        let party = PartyAwaitingPosition {
+            bp_gens,
+            pc_gens,
+            n: reader.read_usize()?,
+            v: reader.read_u128()?,
+            v_blinding: reader.read_scalar()?,
+            V: reader.read_point()?,
+            a_blinding: reader.read_scalar()?,
+            s_blinding: reader.read_scalar()?,
+            s_L: ScalarVec::from(reader.read_scalars()?),
+            s_R: ScalarVec::from(reader.read_scalars()?),
+        };
+        reader.finish()?;
+
+        check_bitsize(party.n, &[party.s_L.len(), party.s_R.len()])?;
+        if bp_gens.gens_capacity < party.n.next_power_of_two() {
+            return Err(MPCError::InvalidGeneratorsLength);
+        }
+        Ok(party)
+    }
+
     /// Assigns a position in the aggregated proof to this party,
     /// allowing the party to commit to the bits of their value.
     pub fn assign_position(
-        self,
+        mut self,
         j: usize,
     ) -> Result<(PartyAwaitingBitChallenge<'a>, BitCommitment), MPCError> {
-        // XXX use transcript RNG
-        let mut rng = rand::thread_rng();
-
         if self.bp_gens.party_capacity <= j {
             return Err(MPCError::InvalidGeneratorsLength);
         }
 
         let bp_share = self.bp_gens.share(j);
+        let padded_n = self.n.next_power_of_two();
 
-        let a_blinding = Scalar::random(&mut rng);
         // Compute A = <a_L, G> + <a_R, H> + a_blinding * B_blinding
-        let mut A = self.pc_gens.B_blinding * a_blinding;
+        let mut A = self.pc_gens.B_blinding * self.a_blinding;
 
         use subtle::{Choice, ConditionallySelectable};
         let mut i = 0;
-        for (G_i, H_i) in bp_share.G(self.n).zip(bp_share.H(self.n)) {
+        for (G_i, H_i) in bp_share.G(padded_n).zip(bp_share.H(padded_n)) {
             // If v_i = 0, we add a_L[i] * G[i] + a_R[i] * H[i] = - H[i]
             // If v_i = 1, we add a_L[i] * G[i] + a_R[i] * H[i] =   G[i]
             let v_i = Choice::from(((self.v >> i) & 1) as u8);
@@ -97,16 +307,14 @@ impl<'a> PartyAwaitingPosition<'a> {
             i += 1;
         }
 
-        let s_blinding = Scalar::random(&mut rng);
-        let s_L: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(&mut rng)).collect();
-        let s_R: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(&mut rng)).collect();
-
         // Compute S = <s_L, G> + <s_R, H> + s_blinding * B_blinding
         let S = RistrettoPoint::multiscalar_mul(
-            iter::once(&s_blinding).chain(s_L.iter()).chain(s_R.iter()),
+            iter::once(&self.s_blinding)
+                .chain(self.s_L.iter())
+                .chain(self.s_R.iter()),
             iter::once(&self.pc_gens.B_blinding)
-                .chain(bp_share.G(self.n))
-                .chain(bp_share.H(self.n)),
+                .chain(bp_share.G(padded_n))
+                .chain(bp_share.H(padded_n)),
         );
 
         // Return next state and all commitments
@@ -121,10 +329,10 @@ impl<'a> PartyAwaitingPosition<'a> {
             v_blinding: self.v_blinding,
             pc_gens: self.pc_gens,
             j,
-            a_blinding,
-            s_blinding,
-            s_L,
-            s_R,
+            a_blinding: self.a_blinding,
+            s_blinding: self.s_blinding,
+            s_L: mem::replace(&mut self.s_L, ScalarVec::default()).into_vec(),
+            s_R: mem::replace(&mut self.s_R, ScalarVec::default()).into_vec(),
         };
         Ok((next_state, bit_commitment))
     }
@@ -133,8 +341,12 @@ impl<'a> PartyAwaitingPosition<'a> {
 /// Overwrite secrets with null bytes when they go out of scope.
 impl<'a> Drop for PartyAwaitingPosition<'a> {
     fn drop(&mut self) {
-        self.v.clear();
+        clear_value(&mut self.v);
         self.v_blinding.clear();
+        self.a_blinding.clear();
+        self.s_blinding.clear();
+
+        // Note: s_L and s_R are cleared within their own Drop impls.
     }
 }
 
@@ -142,7 +354,7 @@ impl<'a> Drop for PartyAwaitingPosition<'a> {
 /// and is waiting for the aggregated value challenge from the dealer.
 pub struct PartyAwaitingBitChallenge<'a> {
     n: usize, // bitsize of the range
-    v: u64,
+    v: u128,
     v_blinding: Scalar,
     j: usize,
     pc_gens: &'a PedersenGens,
@@ -153,43 +365,103 @@ pub struct PartyAwaitingBitChallenge<'a> {
 }
 
 impl<'a> PartyAwaitingBitChallenge<'a> {
+    /// Saves the state in a checkpoint authenticated under `key`.
+    pub fn to_checkpoint(&self, key: &[u8]) -> Vec<u8> {
+        let mut writer = CheckpointWriter::new();
+        writer.write_usize(self.n);
+        writer.write_u128(self.v);
+        writer.write_scalar(&self.v_blinding);
+        writer.write_usize(self.j);
+        writer.write_scalar(&self.a_blinding);
+        writer.write_scalar(&self.s_blinding);
+        writer.write_scalars(&self.s_L);
+        writer.write_scalars(&self.s_R);
+        writer.finish(b"PartyAwaitingBitChallenge", key)
+    }
+
+    /// Restores a state saved with
+    /// [`to_checkpoint`](PartyAwaitingBitChallenge::to_checkpoint),
+    /// using the same generators as the party it was taken from.
+    ///
+    /// The checkpoint must be restored at most once: answering two
+    /// different bit challenges from it reveals `v` and `v_blinding`.
+    ///
+    /// Returns [`MPCError::InvalidCheckpoint`] if the checkpoint is
+    /// malformed or does not verify under `key`.
+    pub fn from_checkpoint(
+        pc_gens: &'a PedersenGens,
+        checkpoint: &[u8],
+        key: &[u8],
+    ) -> Result<PartyAwaitingBitChallenge<'a>, MPCError> {
+        let mut reader = CheckpointReader::new(checkpoint, b"PartyAwaitingBitChallenge", key)?;
+        let party = PartyAwaitingBitChallenge {
+            n: reader.read_usize()?,
+            v: reader.read_u128()?,
+            v_blinding: reader.read_scalar()?,
+            j: reader.read_usize()?,
+            pc_gens,
+            a_blinding: reader.read_scalar()?,
+            s_blinding: reader.read_scalar()?,
+            s_L: reader.read_scalars()?,
+            s_R: reader.read_scalars()?,
+        };
+        reader.finish()?;
+
+        check_bitsize(party.n, &[party.s_L.len(), party.s_R.len()])?;
+        Ok(party)
+    }
+
     /// Receive a [`BitChallenge`] from the dealer and use it to
     /// compute commitments to the party's polynomial coefficients.
     pub fn apply_challenge(
         self,
         vc: &BitChallenge,
     ) -> (PartyAwaitingPolyChallenge, PolyCommitment) {
-        let mut rng = rand::thread_rng();
+        self.apply_challenge_with_rng(vc, &mut rand::thread_rng())
+    }
 
-        let n = self.n;
+    /// Receive a [`BitChallenge`] from the dealer, sampling the
+    /// blinding factors for the polynomial commitments from `rng`.
+    pub fn apply_challenge_with_rng<T: RngCore + CryptoRng>(
+        self,
+        vc: &BitChallenge,
+        rng: &mut T,
+    ) -> (PartyAwaitingPolyChallenge, PolyCommitment) {
+        let t_1_blinding = Scalar::random(rng);
+        let t_2_blinding = Scalar::random(rng);
+        self.apply_challenge_with_blindings(vc, t_1_blinding, t_2_blinding)
+    }
+
+    /// Receive a [`BitChallenge`] from the dealer, using the given
+    /// blinding factors for the polynomial commitments.
+    pub(crate) fn apply_challenge_with_blindings(
+        self,
+        vc: &BitChallenge,
+        t_1_blinding: Scalar,
+        t_2_blinding: Scalar,
+    ) -> (PartyAwaitingPolyChallenge, PolyCommitment) {
+        let n = self.n.next_power_of_two();
         let offset_y = util::scalar_exp_vartime(&vc.y, (self.j * n) as u64);
         let offset_z = util::scalar_exp_vartime(&vc.z, self.j as u64);
 
         // Calculate t by calculating vectors l0, l1, r0, r1 and multiplying
-        let mut l_poly = util::VecPoly1::zero(n);
-        let mut r_poly = util::VecPoly1::zero(n);
-
         let zz = vc.z * vc.z;
-        let mut exp_y = offset_y; // start at y^j
-        let mut exp_2 = Scalar::one(); // start at 2^0 = 1
-        for i in 0..n {
-            let a_L_i = Scalar::from((self.v >> i) & 1);
-            let a_R_i = a_L_i - Scalar::one();
-
-            l_poly.0[i] = a_L_i - vc.z;
-            l_poly.1[i] = self.s_L[i];
-            r_poly.0[i] = exp_y * (a_R_i + vc.z) + zz * offset_z * exp_2;
-            r_poly.1[i] = exp_y * self.s_R[i];
-
-            exp_y *= vc.y; // y^i -> y^(i+1)
-            exp_2 = exp_2 + exp_2; // 2^i -> 2^(i+1)
-        }
+        let z_n = ScalarVec::splat(vc.z, n);
+        let a_L: ScalarVec = (0..n).map(|i| Scalar::from(((self.v >> i) & 1) as u64)).collect();
+        let a_R = &a_L - &ScalarVec::splat(Scalar::one(), n);
+        // y^(j*n+i) and 2^i, for i in 0..n, with zero weights for the padding bits
+        let exp_y: ScalarVec = math::powers(vc.y, n).map(|y_i| offset_y * y_i).collect();
+        let exp_2: ScalarVec = super::padded_powers_of_2(self.n).collect();
+
+        let l_poly = util::VecPoly1(a_L - &z_n, ScalarVec::from(self.s_L.clone()));
+        let r_poly = util::VecPoly1(
+            (a_R + &z_n) * &exp_y + &(exp_2 * (zz * offset_z)),
+            ScalarVec::from(self.s_R.clone()) * &exp_y,
+        );
 
         let t_poly = l_poly.inner_product(&r_poly);
 
         // Generate x by committing to T_1, T_2 (line 49-54)
-        let t_1_blinding = Scalar::random(&mut rng);
-        let t_2_blinding = Scalar::random(&mut rng);
         let T_1 = self.pc_gens.commit(t_poly.1, t_1_blinding);
         let T_2 = self.pc_gens.commit(t_poly.2, t_2_blinding);
 
@@ -218,7 +490,7 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
 /// Overwrite secrets with null bytes when they go out of scope.
 impl<'a> Drop for PartyAwaitingBitChallenge<'a> {
     fn drop(&mut self) {
-        self.v.clear();
+        clear_value(&mut self.v);
         self.v_blinding.clear();
         self.a_blinding.clear();
         self.s_blinding.clear();
@@ -253,6 +525,75 @@ pub struct PartyAwaitingPolyChallenge {
 }
 
 impl PartyAwaitingPolyChallenge {
+    /// Saves the state in a checkpoint authenticated under `key`.
+    pub fn to_checkpoint(&self, key: &[u8]) -> Vec<u8> {
+        let mut writer = CheckpointWriter::new();
+        writer.write_scalar(&self.z);
+        writer.write_scalar(&self.offset_z);
+        writer.write_scalars(&self.l_poly.0);
+        writer.write_scalars(&self.l_poly.1);
+        writer.write_scalars(&self.r_poly.0);
+        writer.write_scalars(&self.r_poly.1);
+        writer.write_scalar(&self.t_poly.0);
+        writer.write_scalar(&self.t_poly.1);
+        writer.write_scalar(&self.t_poly.2);
+        writer.write_scalar(&self.v_blinding);
+        writer.write_scalar(&self.a_blinding);
+        writer.write_scalar(&self.s_blinding);
+        writer.write_scalar(&self.t_1_blinding);
+        writer.write_scalar(&self.t_2_blinding);
+        writer.finish(b"PartyAwaitingPolyChallenge", key)
+    }
+
+    /// Restores a state saved with
+    /// [`to_checkpoint`](PartyAwaitingPolyChallenge::to_checkpoint).
+    ///
+    /// The checkpoint must be restored at most once: answering two
+    /// different poly challenges from it reveals `v` and `v_blinding`.
+    ///
+    /// Returns [`MPCError::InvalidCheckpoint`] if the checkpoint is
+    /// malformed or does not verify under `key`.
+    pub fn from_checkpoint(
+        checkpoint: &[u8],
+        key: &[u8],
+    ) -> Result<PartyAwaitingPolyChallenge, MPCError> {
+        let mut reader = CheckpointReader::new(checkpoint, b"PartyAwaitingPolyChallenge", key)?;
+        let party = PartyAwaitingPolyChallenge {
+            z: reader.read_scalar()?,
+            offset_z: reader.read_scalar()?,
+            l_poly: util::VecPoly1(
+                ScalarVec::from(reader.read_scalars()?),
+                ScalarVec::from(reader.read_scalars()?),
+            ),
+            r_poly: util::VecPoly1(
+                ScalarVec::from(reader.read_scalars()?),
+                ScalarVec::from(reader.read_scalars()?),
+            ),
+            t_poly: util::Poly2(
+                reader.read_scalar()?,
+                reader.read_scalar()?,
+                reader.read_scalar()?,
+            ),
+            v_blinding: reader.read_scalar()?,
+            a_blinding: reader.read_scalar()?,
+            s_blinding: reader.read_scalar()?,
+            t_1_blinding: reader.read_scalar()?,
+            t_2_blinding: reader.read_scalar()?,
+        };
+        reader.finish()?;
+
+        let n = party.l_poly.0.len();
+        let lengths = [
+            party.l_poly.1.len(),
+            party.r_poly.0.len(),
+            party.r_poly.1.len(),
+        ];
+        if !n.is_power_of_two() || lengths.iter().any(|&len| len != n) {
+            return Err(MPCError::InvalidCheckpoint);
+        }
+        Ok(party)
+    }
+
     /// Receive a [`PolyChallenge`] from the dealer and compute the
     /// party's proof share.
     pub fn apply_challenge(self, pc: &PolyChallenge) -> Result<ProofShare, MPCError> {
@@ -297,3 +638,308 @@ impl Drop for PartyAwaitingPolyChallenge {
         // are cleared within their own Drop impls.
     }
 }
+
+/// A party contributing several values, waiting for the dealer to
+/// assign the position of its first value.
+pub struct PartyAwaitingPositions<'a> {
+    parties: Vec<PartyAwaitingPosition<'a>>,
+}
+
+impl<'a> PartyAwaitingPositions<'a> {
+    /// Returns the number of values, and so of positions, of the party.
+    pub fn num_values(&self) -> usize {
+        self.parties.len()
+    }
+
+    /// Saves the state in a checkpoint authenticated under `key`.
+    pub fn to_checkpoint(&self, key: &[u8]) -> Vec<u8> {
+        let checkpoints: Vec<_> = self.parties.iter().map(|p| p.to_checkpoint(key)).collect();
+        write_checkpoints(&checkpoints, b"PartyAwaitingPositions", key)
+    }
+
+    /// Restores a state saved with
+    /// [`to_checkpoint`](PartyAwaitingPositions::to_checkpoint), using
+    /// the same generators as the party it was taken from.
+    ///
+    /// As with [`PartyAwaitingPosition::from_checkpoint`], the
+    /// checkpoint must be restored at most once, or the values and
+    /// their blinding factors are revealed.
+    ///
+    /// Returns [`MPCError::InvalidCheckpoint`] if the checkpoint is
+    /// malformed or does not verify under `key`.
+    pub fn from_checkpoint(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        checkpoint: &[u8],
+        key: &[u8],
+    ) -> Result<PartyAwaitingPositions<'a>, MPCError> {
+        let parties = read_checkpoints(checkpoint, b"PartyAwaitingPositions", key, |c| {
+            PartyAwaitingPosition::from_checkpoint(bp_gens, pc_gens, c, key)
+        })?;
+        Ok(PartyAwaitingPositions { parties })
+    }
+
+    /// Assigns the positions from `j` to \\(j + k - 1\\) to the
+    /// \\(k\\) values of this party, in order, and returns the
+    /// [`BitCommitment`]s for all of them.
+    pub fn assign_positions(
+        self,
+        j: usize,
+    ) -> Result<(PartyAwaitingBitChallenges<'a>, Vec<BitCommitment>), MPCError> {
+        let positioned = self
+            .parties
+            .into_iter()
+            .enumerate()
+            .map(|(i, party)| party.assign_position(j + i))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (parties, bit_commitments) = positioned.into_iter().unzip();
+        Ok((PartyAwaitingBitChallenges { parties }, bit_commitments))
+    }
+}
+
+/// A party contributing several values, which has committed to their
+/// bits and is waiting for the [`BitChallenge`].
+pub struct PartyAwaitingBitChallenges<'a> {
+    parties: Vec<PartyAwaitingBitChallenge<'a>>,
+}
+
+impl<'a> PartyAwaitingBitChallenges<'a> {
+    /// Saves the state in a checkpoint authenticated under `key`.
+    pub fn to_checkpoint(&self, key: &[u8]) -> Vec<u8> {
+        let checkpoints: Vec<_> = self.parties.iter().map(|p| p.to_checkpoint(key)).collect();
+        write_checkpoints(&checkpoints, b"PartyAwaitingBitChallenges", key)
+    }
+
+    /// Restores a state saved with
+    /// [`to_checkpoint`](PartyAwaitingBitChallenges::to_checkpoint),
+    /// using the same generators as the party it was taken from.
+    ///
+    /// As with [`PartyAwaitingBitChallenge::from_checkpoint`], the
+    /// checkpoint must be restored at most once, or the values and
+    /// their blinding factors are revealed.
+    ///
+    /// Returns [`MPCError::InvalidCheckpoint`] if the checkpoint is
+    /// malformed or does not verify under `key`.
+    pub fn from_checkpoint(
+        pc_gens: &'a PedersenGens,
+        checkpoint: &[u8],
+        key: &[u8],
+    ) -> Result<PartyAwaitingBitChallenges<'a>, MPCError> {
+        let parties = read_checkpoints(checkpoint, b"PartyAwaitingBitChallenges", key, |c| {
+            PartyAwaitingBitChallenge::from_checkpoint(pc_gens, c, key)
+        })?;
+        Ok(PartyAwaitingBitChallenges { parties })
+    }
+
+    /// Receive a [`BitChallenge`] from the dealer and use it to
+    /// compute the [`PolyCommitment`]s for all the values.
+    pub fn apply_challenge(
+        self,
+        vc: &BitChallenge,
+    ) -> (PartyAwaitingPolyChallenges, Vec<PolyCommitment>) {
+        self.apply_challenge_with_rng(vc, &mut rand::thread_rng())
+    }
+
+    /// Receive a [`BitChallenge`] from the dealer, sampling the
+    /// blinding factors for the polynomial commitments from `rng`.
+    pub fn apply_challenge_with_rng<T: RngCore + CryptoRng>(
+        self,
+        vc: &BitChallenge,
+        rng: &mut T,
+    ) -> (PartyAwaitingPolyChallenges, Vec<PolyCommitment>) {
+        let (parties, poly_commitments) = self
+            .parties
+            .into_iter()
+            .map(|party| party.apply_challenge_with_rng(vc, rng))
+            .unzip();
+        (PartyAwaitingPolyChallenges { parties }, poly_commitments)
+    }
+}
+
+/// A party contributing several values, which has committed to their
+/// polynomial coefficients and is waiting for the [`PolyChallenge`].
+pub struct PartyAwaitingPolyChallenges {
+    parties: Vec<PartyAwaitingPolyChallenge>,
+}
+
+impl PartyAwaitingPolyChallenges {
+    /// Saves the state in a checkpoint authenticated under `key`.
+    pub fn to_checkpoint(&self, key: &[u8]) -> Vec<u8> {
+        let checkpoints: Vec<_> = self.parties.iter().map(|p| p.to_checkpoint(key)).collect();
+        write_checkpoints(&checkpoints, b"PartyAwaitingPolyChallenges", key)
+    }
+
+    /// Restores a state saved with
+    /// [`to_checkpoint`](PartyAwaitingPolyChallenges::to_checkpoint).
+    ///
+    /// As with [`PartyAwaitingPolyChallenge::from_checkpoint`], the
+    /// checkpoint must be restored at most once, or the values and
+    /// their blinding factors are revealed.
+    ///
+    /// Returns [`MPCError::InvalidCheckpoint`] if the checkpoint is
+    /// malformed or does not verify under `key`.
+    pub fn from_checkpoint(
+        checkpoint: &[u8],
+        key: &[u8],
+    ) -> Result<PartyAwaitingPolyChallenges, MPCError> {
+        let parties = read_checkpoints(checkpoint, b"PartyAwaitingPolyChallenges", key, |c| {
+            PartyAwaitingPolyChallenge::from_checkpoint(c, key)
+        })?;
+        Ok(PartyAwaitingPolyChallenges { parties })
+    }
+
+    /// Receive a [`PolyChallenge`] from the dealer and compute the
+    /// [`ProofShare`]s for all the values.
+    pub fn apply_challenge(self, pc: &PolyChallenge) -> Result<Vec<ProofShare>, MPCError> {
+        self.parties
+            .into_iter()
+            .map(|party| party.apply_challenge(pc))
+            .collect()
+    }
+}
+
+/// Recomputes the Fiat-Shamir challenges of the dealer, so that a
+/// party does not have to trust them.
+///
+/// The dealer derives the [`BitChallenge`] and the [`PolyChallenge`]
+/// from the commitments of all the parties.  If it broadcasts these
+/// commitments along with each challenge, each party can replay the
+/// dealer's transcript, starting from the same initial state, and
+/// reject a challenge which was not derived from them, or a list of
+/// commitments which does not contain its own.
+#[derive(Clone)]
+pub struct ChallengeVerifier {
+    transcript: Transcript,
+    m: usize,
+}
+
+impl ChallengeVerifier {
+    /// Creates a verifier for the challenges of an aggregation of `m`
+    /// values of bitsize `n`, with the generators of the aggregation
+    /// and a `transcript` in the same initial state as the one passed
+    /// to [`Dealer::new`](::aggregation::dealer::Dealer::new).
+    ///
+    /// `m` is the number of parties passed to the dealer.  If it is not
+    /// a power of two, the verifier pads it like the dealer, and
+    /// expects the broadcast commitments to include those of the dummy
+    /// parties.
+    ///
+    /// If the dealer was created with
+    /// [`Dealer::new_with_session`](::aggregation::dealer::Dealer::new_with_session),
+    /// the session must be bound into the `transcript` first.
+    pub fn new(
+        bp_gens: &BulletproofGens,
+        mut transcript: Transcript,
+        n: usize,
+        m: usize,
+    ) -> ChallengeVerifier {
+        let m = m.next_power_of_two();
+        commit_parameters(&mut transcript, bp_gens, n, m);
+        ChallengeVerifier { transcript, m }
+    }
+
+    /// Checks that the `bit_challenge` was derived from the
+    /// `bit_commitments` of all the parties, and that the party at
+    /// position `j` sent the `bit_commitment`.
+    ///
+    /// Returns [`MPCError::MaliciousDealer`] if the check fails.
+    pub fn verify_bit_challenge(
+        &mut self,
+        j: usize,
+        bit_commitment: &BitCommitment,
+        bit_commitments: &[BitCommitment],
+        bit_challenge: &BitChallenge,
+    ) -> Result<(), MPCError> {
+        if bit_commitments.len() != self.m {
+            return Err(MPCError::WrongNumBitCommitments);
+        }
+        if bit_commitments.get(j) != Some(bit_commitment) {
+            return Err(MPCError::MaliciousDealer);
+        }
+
+        let (_, _, expected) = commit_bit_commitments(&mut self.transcript, bit_commitments);
+        if expected.y != bit_challenge.y || expected.z != bit_challenge.z {
+            return Err(MPCError::MaliciousDealer);
+        }
+        Ok(())
+    }
+
+    /// Checks that the `poly_challenge` was derived from the
+    /// `poly_commitments` of all the parties, and that the party at
+    /// position `j` sent the `poly_commitment`.  The bit challenge
+    /// must have been checked first.
+    ///
+    /// Returns [`MPCError::MaliciousDealer`] if the check fails.
+    pub fn verify_poly_challenge(
+        &mut self,
+        j: usize,
+        poly_commitment: &PolyCommitment,
+        poly_commitments: &[PolyCommitment],
+        poly_challenge: &PolyChallenge,
+    ) -> Result<(), MPCError> {
+        if poly_commitments.len() != self.m {
+            return Err(MPCError::WrongNumPolyCommitments);
+        }
+        if poly_commitments.get(j) != Some(poly_commitment) {
+            return Err(MPCError::MaliciousDealer);
+        }
+
+        let (_, _, expected) = commit_poly_commitments(&mut self.transcript, poly_commitments);
+        if expected.x != poly_challenge.x {
+            return Err(MPCError::MaliciousDealer);
+        }
+        Ok(())
+    }
+}
+
+/// Encodes the checkpoints of the states of the values of a party
+/// contributing several values into one checkpoint.
+fn write_checkpoints(checkpoints: &[Vec<u8>], kind: &'static [u8], key: &[u8]) -> Vec<u8> {
+    let mut writer = CheckpointWriter::new();
+    writer.write_usize(checkpoints.len());
+    for checkpoint in checkpoints {
+        writer.write_bytes(checkpoint);
+    }
+    writer.finish(kind, key)
+}
+
+/// Decodes a checkpoint written by `write_checkpoints`, restoring the
+/// state of each value with `restore`.
+fn read_checkpoints<T, F>(
+    checkpoint: &[u8],
+    kind: &'static [u8],
+    key: &[u8],
+    restore: F,
+) -> Result<Vec<T>, MPCError>
+where
+    F: Fn(&[u8]) -> Result<T, MPCError>,
+{
+    let mut reader = CheckpointReader::new(checkpoint, kind, key)?;
+    let len = reader.read_usize()?;
+    if len == 0 {
+        return Err(MPCError::InvalidCheckpoint);
+    }
+    let mut states = Vec::new();
+    for _ in 0..len {
+        states.push(restore(reader.read_bytes()?)?);
+    }
+    reader.finish()?;
+    Ok(states)
+}
+
+/// Checks the bitsize `n` and the lengths of the bit vectors of a
+/// restored state.
+fn check_bitsize(n: usize, lengths: &[usize]) -> Result<(), MPCError> {
+    if n == 0 || n > 128 || lengths.iter().any(|&len| len != n.next_power_of_two()) {
+        return Err(MPCError::InvalidCheckpoint);
+    }
+    Ok(())
+}
+
+/// Overwrites the value `v` with zero.
+///
+/// `clear_on_drop` only implements `Clear` for `u128` on nightly, so
+/// this uses a volatile write, which is not optimized away.
+pub(super) fn clear_value(v: &mut u128) {
+    unsafe { ptr::write_volatile(v, 0) }
+}
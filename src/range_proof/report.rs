@@ -0,0 +1,281 @@
+//! Diagnostic verification of range proofs, which reports which
+//! check of the verification failed.
+//!
+//! [`RangeProof::verify_multiple`] combines all the checks of the
+//! verification into a single multiscalar multiplication, so a
+//! rejected proof only gives a [`ProofError::VerificationError`].
+//! [`RangeProof::verify_with_report`] evaluates the check of the
+//! commitment to \\(t(x)\\) and the check of the inner-product argument
+//! separately, and lists the points which do not decompress.  It is
+//! slower than ordinary verification, and meant for debugging.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use merlin::Transcript;
+
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use multiscalar;
+use range_proof::RangeProof;
+
+/// The outcome of each check of the verification of a [`RangeProof`],
+/// returned by [`RangeProof::verify_with_report`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationReport {
+    /// The indices of the 32-byte elements of the
+    /// [proof encoding](RangeProof::to_bytes) which are not valid
+    /// points.
+    pub invalid_proof_points: Vec<usize>,
+    /// The indices of the value commitments which are not valid
+    /// points.
+    pub invalid_value_commitments: Vec<usize>,
+    /// Whether \\(t_x\\) and \\(\tilde{t}_x\\) open the commitment to
+    /// \\(t(x)\\) given by \\(T_1\\), \\(T_2\\) and the value
+    /// commitments, or `None` if one of these points is invalid.
+    pub t_x_check: Option<bool>,
+    /// Whether the inner-product argument proves that \\(t_x\\) is the
+    /// inner product of the vectors committed by \\(A\\) and \\(S\\),
+    /// or `None` if one of these points or of the IPP points is
+    /// invalid.
+    pub ipp_check: Option<bool>,
+}
+
+impl VerificationReport {
+    /// Returns whether the proof is valid, that is whether both checks
+    /// passed.
+    pub fn is_valid(&self) -> bool {
+        self.t_x_check == Some(true) && self.ipp_check == Some(true)
+    }
+}
+
+impl RangeProof {
+    /// Verifies an aggregated rangeproof for the given value
+    /// commitments, evaluating each check of the verification
+    /// separately.
+    ///
+    /// This takes the same arguments, and leaves the `transcript` in
+    /// the same state, as [`RangeProof::verify_multiple`], which
+    /// accepts the proof exactly when the returned report
+    /// [`is_valid`](VerificationReport::is_valid).  Returns an error if
+    /// the proof cannot be checked at all, for instance because its
+    /// size does not match `n` and the number of commitments.
+    pub fn verify_with_report(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+        n: usize,
+    ) -> Result<VerificationReport, ProofError> {
+        let checks = self.verification_checks(bp_gens, transcript, value_commitments, n)?;
+        let (n, m, x) = (checks.n, checks.m, checks.x);
+
+        let A = self.A.decompress();
+        let S = self.S.decompress();
+        let T_1 = self.T_1.decompress();
+        let T_2 = self.T_2.decompress();
+        let L: Vec<_> = self
+            .ipp_proof
+            .lr_points()
+            .map(|(L, _)| L.decompress())
+            .collect();
+        let R: Vec<_> = self
+            .ipp_proof
+            .lr_points()
+            .map(|(_, R)| R.decompress())
+            .collect();
+        // The value commitments, padded with the identity like the
+        // scalars of the checks.
+        let V: Vec<_> = checks
+            .value_commitments
+            .iter()
+            .map(|V| V.decompress())
+            .collect();
+
+        // The points A, S, T_1, T_2 are the first four elements of the
+        // encoding, and the pairs L_j, R_j follow the three scalars.
+        let mut invalid_proof_points = Vec::new();
+        for (index, P) in [A, S, T_1, T_2].iter().enumerate() {
+            if P.is_none() {
+                invalid_proof_points.push(index);
+            }
+        }
+        for (j, (L_j, R_j)) in L.iter().zip(R.iter()).enumerate() {
+            if L_j.is_none() {
+                invalid_proof_points.push(7 + 2 * j);
+            }
+            if R_j.is_none() {
+                invalid_proof_points.push(8 + 2 * j);
+            }
+        }
+        let invalid_value_commitments = V
+            .iter()
+            .enumerate()
+            .filter(|(_, V_j)| V_j.is_none())
+            .map(|(j, _)| j)
+            .collect();
+
+        let t_x_check = multiscalar::optional_multiscalar_mul(
+            [x, x * x]
+                .iter()
+                .chain(checks.value_commitment_scalars.iter())
+                .chain([checks.t_x_B_scalar, -self.t_x_blinding].iter()),
+            [T_1, T_2]
+                .iter()
+                .cloned()
+                .chain(V)
+                .chain([Some(pc_gens.B), Some(pc_gens.B_blinding)].iter().cloned()),
+        ).map(|P| P.is_identity());
+
+        let ipp_check = multiscalar::optional_multiscalar_mul(
+            [Scalar::one(), x]
+                .iter()
+                .chain(checks.x_sq.iter())
+                .chain(checks.x_inv_sq.iter())
+                .chain([checks.ipp_B_scalar, -self.e_blinding].iter())
+                .chain(checks.g_scalars.iter())
+                .chain(checks.h_scalars.iter()),
+            [A, S]
+                .iter()
+                .cloned()
+                .chain(L)
+                .chain(R)
+                .chain([Some(pc_gens.B), Some(pc_gens.B_blinding)].iter().cloned())
+                .chain(bp_gens.G(n, m).map(|&G_i| Some(G_i)))
+                .chain(bp_gens.H(n, m).map(|&H_i| Some(H_i))),
+        ).map(|P| P.is_identity());
+
+        Ok(VerificationReport {
+            invalid_proof_points,
+            invalid_value_commitments,
+            t_x_check,
+            ipp_check,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand;
+
+    use range_proof::dealer::Dealer;
+    use range_proof::party::Party;
+
+    fn report(proof: &RangeProof, value_commitments: &[CompressedRistretto]) -> VerificationReport {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        proof
+            .verify_with_report(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"ReportTest"),
+                value_commitments,
+                32,
+            ).unwrap()
+    }
+
+    /// Creates a proof for the 64-bit value `v` as if it were a 32-bit
+    /// value, without auditing the share of the party.
+    fn prove_unchecked(v: u64) -> (RangeProof, CompressedRistretto) {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut transcript = Transcript::new(b"ReportTest");
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, 32, 1).unwrap();
+        let party = Party::new(&bp_gens, &pc_gens, v, blinding, 32).unwrap();
+        let (party, bit_commitment) = party.assign_position(0).unwrap();
+        let V = bit_commitment.V_j;
+        let (dealer, bit_challenge) = dealer
+            .receive_bit_commitments(vec![bit_commitment])
+            .unwrap();
+        let (party, poly_commitment) = party.apply_challenge(&bit_challenge);
+        let (dealer, poly_challenge) = dealer
+            .receive_poly_commitments(vec![poly_commitment])
+            .unwrap();
+        let share = party.apply_challenge(&poly_challenge).unwrap();
+        let proof = dealer.receive_trusted_shares(&[share]).unwrap();
+        (proof, V)
+    }
+
+    #[test]
+    fn report_of_valid_proof() {
+        let (proof, V) = prove_unchecked(7);
+        let valid = report(&proof, &[V]);
+        assert!(valid.is_valid());
+        assert_eq!(valid.t_x_check, Some(true));
+        assert_eq!(valid.ipp_check, Some(true));
+    }
+
+    #[test]
+    fn report_of_padded_aggregation() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 4);
+        let mut rng = rand::thread_rng();
+        let blindings: Vec<_> = (0..3).map(|_| Scalar::random(&mut rng)).collect();
+
+        let (proof, value_commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"ReportTest"),
+            &[1, 2, 3],
+            &blindings,
+            32,
+        ).unwrap();
+        let padded = proof
+            .verify_with_report(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"ReportTest"),
+                &value_commitments,
+                32,
+            ).unwrap();
+        assert!(padded.is_valid());
+    }
+
+    #[test]
+    fn report_locates_failed_checks() {
+        // The bits of a value out of range do not add up to the value,
+        // but the inner-product argument over them is sound.
+        let (proof, V) = prove_unchecked(1 << 40);
+        let out_of_range = report(&proof, &[V]);
+        assert!(!out_of_range.is_valid());
+        assert_eq!(out_of_range.t_x_check, Some(false));
+        assert_eq!(out_of_range.ipp_check, Some(true));
+
+        // The points L_j come after the challenges of the check of
+        // t(x), so replacing one only breaks the inner-product
+        // argument.
+        let (proof, V) = prove_unchecked(7);
+        let mut bytes = proof.to_bytes();
+        let A = proof.A();
+        bytes[7 * 32..8 * 32].copy_from_slice(A.as_bytes());
+        let tampered = report(&RangeProof::from_bytes(&bytes).unwrap(), &[V]);
+        assert!(!tampered.is_valid());
+        assert_eq!(tampered.t_x_check, Some(true));
+        assert_eq!(tampered.ipp_check, Some(false));
+    }
+
+    #[test]
+    fn report_locates_invalid_points() {
+        let (proof, V) = prove_unchecked(7);
+        let mut bytes = proof.to_bytes();
+        bytes[2 * 32 + 31] = 0xff;
+        bytes[8 * 32 + 31] = 0xff;
+        let proof = RangeProof::from_bytes(&bytes).unwrap();
+
+        let corrupted = report(&proof, &[V]);
+        assert_eq!(corrupted.invalid_proof_points, vec![2, 8]);
+        assert!(corrupted.invalid_value_commitments.is_empty());
+        assert_eq!(corrupted.t_x_check, None);
+        assert_eq!(corrupted.ipp_check, None);
+
+        let invalid = report(&proof, &[CompressedRistretto([0xff; 32])]);
+        assert_eq!(invalid.invalid_value_commitments, vec![0]);
+    }
+}
@@ -0,0 +1,530 @@
+//! Rewindable range proofs, whose nonces are derived from a rewind
+//! key, so that the holder of the key can recover the committed value
+//! and blinding factor from the proof.
+//!
+//! The proof is created like an ordinary single-value proof, except
+//! for four of its blinding factors, which are derived from the rewind
+//! key, the value commitment \\(V\\), and the state of the transcript:
+//!
+//! * \\(\tilde{a} = \alpha + v\\), so that
+//!   \\(\tilde{e} = \tilde{a} + \rho x\\) reveals \\(v\\) to whoever
+//!   knows \\(\alpha\\) and \\(\rho\\);
+//! * \\(\tilde{s} = \rho\\);
+//! * \\(\tilde{t}\_1 = \tau\_1\\) and \\(\tilde{t}\_2 = \tau\_2\\), so
+//!   that \\(\tilde{t}(x) = z^2 \tilde{v} + \tau\_1 x + \tau\_2 x^2\\)
+//!   reveals \\(\tilde{v}\\).
+//!
+//! The proof is verified like any other single-value proof.  Without
+//! the rewind key, the nonces are pseudorandom, so the proof hides the
+//! value as well as an ordinary one.  The nonces are bound to the
+//! transcript, so proving the same commitment on two different
+//! transcripts does not reuse them.
+//!
+//! The rest of the prover randomness, the blinding vectors
+//! \\(\mathbf{s}\_L\\) and \\(\mathbf{s}\_R\\), is drawn from the same
+//! rewind-keyed transcript.  Proving the same commitment twice on the
+//! same transcript therefore gives the same proof, rather than two
+//! proofs which share \\(\tau\_1\\) and \\(\tau\_2\\) but answer different
+//! challenges, from which \\(\tilde{v}\\) could be solved for.
+//!
+//! A proof can also carry a memo of [`MEMO_SIZE`] bytes, which is
+//! hidden in the same way.  Since \\(\tilde{t}(x)\\) then no longer
+//! determines \\(\tilde{v}\\), the blinding factor of a proof with a
+//! memo is derived from the rewind key, the value and a random salt
+//! instead of being chosen by the prover.  The salt keeps two outputs
+//! of the same value from having the same commitment, which would
+//! make them linkable.  The value, the salt and the first 9 bytes of
+//! the memo are added to \\(\alpha\\), and the remaining 31 bytes to
+//! \\(\tau\_1\\).
+
+#![allow(non_snake_case)]
+
+use byteorder::{ByteOrder, LittleEndian};
+use clear_on_drop::clear::Clear;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::{self, CryptoRng, RngCore};
+
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::dealer::Dealer;
+use range_proof::party::Party;
+use range_proof::RangeProof;
+use scalar;
+use transcript::TranscriptProtocol;
+
+/// The size in bytes of the memo carried by
+/// [`RangeProof::prove_single_with_memo`].
+pub const MEMO_SIZE: usize = 40;
+
+/// The size in bytes of the salt of the blinding factor of a proof
+/// with a memo.
+const SALT_SIZE: usize = 14;
+
+/// The number of bytes of payload hidden in each nonce, which is the
+/// most that fits in a scalar without reduction.
+const PAYLOAD_SIZE: usize = 31;
+
+/// The number of bytes of the memo hidden in \\(\alpha\\), after the
+/// value and the salt.
+const MEMO_HEAD_SIZE: usize = PAYLOAD_SIZE - 8 - SALT_SIZE;
+
+/// The nonces of a rewindable proof.
+struct RewindNonces {
+    alpha: Scalar,
+    rho: Scalar,
+    tau_1: Scalar,
+    tau_2: Scalar,
+    /// The source of the remaining prover randomness.
+    rng: NonceRng,
+}
+
+/// An RNG which draws its output from the rewind-keyed transcript
+/// the nonces were derived from, so that a rewindable proof is a
+/// deterministic function of its inputs.
+struct NonceRng(Transcript);
+
+impl RngCore for NonceRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        LittleEndian::read_u32(&bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        LittleEndian::read_u64(&bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.challenge_bytes(b"rng", dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for NonceRng {}
+
+impl RewindNonces {
+    /// Derives the nonces for the commitment `V` from the
+    /// `rewind_key` and the initial state of the `transcript`, which
+    /// is left unchanged.
+    fn derive(
+        transcript: &Transcript,
+        rewind_key: &[u8; 32],
+        V: &CompressedRistretto,
+    ) -> RewindNonces {
+        let mut transcript = transcript.clone();
+        transcript.rewind_domain_sep();
+        transcript.commit_bytes(b"rewind-key", rewind_key);
+        transcript.commit_point(b"V", V);
+        let alpha = transcript.challenge_scalar(b"alpha");
+        let rho = transcript.challenge_scalar(b"rho");
+        let tau_1 = transcript.challenge_scalar(b"tau_1");
+        let tau_2 = transcript.challenge_scalar(b"tau_2");
+        RewindNonces {
+            alpha,
+            rho,
+            tau_1,
+            tau_2,
+            rng: NonceRng(transcript),
+        }
+    }
+
+    /// Derives the blinding factor of a proof with a memo for the
+    /// value `v` from the `rewind_key`, the `salt` and the initial
+    /// state of the `transcript`, which is left unchanged.
+    fn memo_blinding(
+        transcript: &Transcript,
+        rewind_key: &[u8; 32],
+        v: u64,
+        salt: &[u8],
+    ) -> Scalar {
+        let mut transcript = transcript.clone();
+        transcript.rewind_domain_sep();
+        transcript.commit_bytes(b"rewind-key", rewind_key);
+        transcript.commit_scalar(b"v", &Scalar::from(v));
+        transcript.commit_bytes(b"salt", salt);
+        transcript.challenge_scalar(b"v_blinding")
+    }
+}
+
+/// Converts at most [`PAYLOAD_SIZE`] bytes of payload to a scalar.
+fn payload_to_scalar(payload: &[u8]) -> Scalar {
+    let mut bytes = [0u8; 32];
+    bytes[..payload.len()].copy_from_slice(payload);
+    Scalar::from_bytes_mod_order(bytes)
+}
+
+/// Converts a scalar back to [`PAYLOAD_SIZE`] bytes of payload, or
+/// returns `None` if it is too large to be one.
+fn scalar_to_payload(x: &Scalar) -> Option<[u8; PAYLOAD_SIZE]> {
+    let bytes = x.as_bytes();
+    if bytes[PAYLOAD_SIZE..].iter().any(|&b| b != 0) {
+        return None;
+    }
+    let mut payload = [0u8; PAYLOAD_SIZE];
+    payload.copy_from_slice(&bytes[..PAYLOAD_SIZE]);
+    Some(payload)
+}
+
+impl Drop for RewindNonces {
+    fn drop(&mut self) {
+        self.alpha.clear();
+        self.rho.clear();
+        self.tau_1.clear();
+        self.tau_2.clear();
+    }
+}
+
+impl RangeProof {
+    /// Create a rangeproof for a given pair of value `v` and blinding
+    /// scalar `v_blinding`, which can be rewound with
+    /// [`RangeProof::rewind`] by the holder of `rewind_key` to recover
+    /// `v` and `v_blinding`.
+    ///
+    /// The proof is verified with [`RangeProof::verify_single`].
+    pub fn prove_single_rewindable(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+        rewind_key: &[u8; 32],
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        let V = pc_gens.commit(Scalar::from(v), *v_blinding).compress();
+        let mut nonces = RewindNonces::derive(transcript, rewind_key, &V);
+        nonces.alpha += Scalar::from(v);
+
+        let proof =
+            RangeProof::prove_with_nonces(bp_gens, pc_gens, transcript, v, v_blinding, n, nonces)?;
+        Ok((proof, V))
+    }
+
+    /// Create a rangeproof for the value `v` carrying an encrypted
+    /// `memo`, which can be rewound with [`RangeProof::rewind_memo`]
+    /// by the holder of `rewind_key` to recover `v`, its blinding
+    /// factor and the `memo`.
+    ///
+    /// The blinding factor is derived from `rewind_key`, the value, a
+    /// random salt and the transcript, and returned together with the
+    /// proof and the commitment.  Proving the same value twice gives
+    /// two different commitments.  The proof is verified with
+    /// [`RangeProof::verify_single`].
+    pub fn prove_single_with_memo(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        n: usize,
+        rewind_key: &[u8; 32],
+        memo: &[u8; MEMO_SIZE],
+    ) -> Result<(RangeProof, CompressedRistretto, Scalar), ProofError> {
+        let mut payload = [0u8; PAYLOAD_SIZE];
+        LittleEndian::write_u64(&mut payload[..8], v);
+        rand::thread_rng().fill_bytes(&mut payload[8..8 + SALT_SIZE]);
+        payload[8 + SALT_SIZE..].copy_from_slice(&memo[..MEMO_HEAD_SIZE]);
+
+        let v_blinding =
+            RewindNonces::memo_blinding(transcript, rewind_key, v, &payload[8..8 + SALT_SIZE]);
+        let V = pc_gens.commit(Scalar::from(v), v_blinding).compress();
+        let mut nonces = RewindNonces::derive(transcript, rewind_key, &V);
+        nonces.alpha += payload_to_scalar(&payload);
+        nonces.tau_1 += payload_to_scalar(&memo[MEMO_HEAD_SIZE..]);
+        payload.clear();
+
+        let proof =
+            RangeProof::prove_with_nonces(bp_gens, pc_gens, transcript, v, &v_blinding, n, nonces)?;
+        Ok((proof, V, v_blinding))
+    }
+
+    /// Creates a single-value proof using the given `nonces` as the
+    /// blinding factors of the bit and polynomial commitments, and
+    /// drawing the blinding vectors from their RNG.
+    fn prove_with_nonces(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+        mut nonces: RewindNonces,
+    ) -> Result<RangeProof, ProofError> {
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, 1)?;
+        let party =
+            Party::new_with_rng(bp_gens, pc_gens, v.into(), *v_blinding, n, &mut nonces.rng)?
+                .with_blindings(nonces.alpha, nonces.rho);
+
+        let (party, bit_commitment) = party.assign_position(0)?;
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(vec![bit_commitment])?;
+
+        let (party, poly_commitment) =
+            party.apply_challenge_with_blindings(&bit_challenge, nonces.tau_1, nonces.tau_2);
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(vec![poly_commitment])?;
+
+        let proof_share = party.apply_challenge(&poly_challenge)?;
+        Ok(dealer.receive_trusted_shares(&[proof_share])?)
+    }
+
+    /// Recovers the value and blinding factor committed in `V` from a
+    /// proof created by [`RangeProof::prove_single_rewindable`] with
+    /// the same `rewind_key`.
+    ///
+    /// The `transcript` must have the same initial state as the one
+    /// passed to the prover.  Rewinding does not verify the proof.
+    ///
+    /// Returns [`ProofError::VerificationError`] if the recovered
+    /// opening does not match `V`, for instance because the proof was
+    /// created with another key.
+    pub fn rewind(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        n: usize,
+        rewind_key: &[u8; 32],
+    ) -> Result<(u64, Scalar), ProofError> {
+        let nonces = RewindNonces::derive(transcript, rewind_key, V);
+        let (z, x) = self.replay_challenges(bp_gens, transcript, V, n);
+
+        // e_blinding = alpha + v + rho x
+        let v = self.e_blinding - nonces.alpha - nonces.rho * x;
+        // t_x_blinding = z^2 v_blinding + tau_1 x + tau_2 x^2
+        let v_blinding =
+            (self.t_x_blinding - nonces.tau_1 * x - nonces.tau_2 * x * x) * (z * z).invert();
+
+        let v = match scalar::to_u128(&v) {
+            Some(v) if v <= u64::max_value() as u128 => v as u64,
+            _ => return Err(ProofError::VerificationError),
+        };
+        if pc_gens.commit(Scalar::from(v), v_blinding).compress() != *V {
+            return Err(ProofError::VerificationError);
+        }
+        Ok((v, v_blinding))
+    }
+
+    /// Recovers the value, the blinding factor and the memo from a
+    /// proof created by [`RangeProof::prove_single_with_memo`] with
+    /// the same `rewind_key`.
+    ///
+    /// The `transcript` must have the same initial state as the one
+    /// passed to the prover.  Rewinding does not verify the proof.
+    ///
+    /// Returns [`ProofError::VerificationError`] if the recovered
+    /// opening does not match `V`, for instance because the proof was
+    /// created with another key.
+    pub fn rewind_memo(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        n: usize,
+        rewind_key: &[u8; 32],
+    ) -> Result<(u64, Scalar, [u8; MEMO_SIZE]), ProofError> {
+        let initial_transcript = transcript.clone();
+        let nonces = RewindNonces::derive(transcript, rewind_key, V);
+        let (z, x) = self.replay_challenges(bp_gens, transcript, V, n);
+
+        // e_blinding = alpha + (v || salt || memo[..9]) + rho x
+        let payload = self.e_blinding - nonces.alpha - nonces.rho * x;
+        let payload = scalar_to_payload(&payload).ok_or(ProofError::VerificationError)?;
+        let v = LittleEndian::read_u64(&payload[..8]);
+        let salt = &payload[8..8 + SALT_SIZE];
+        let v_blinding = RewindNonces::memo_blinding(&initial_transcript, rewind_key, v, salt);
+        if pc_gens.commit(Scalar::from(v), v_blinding).compress() != *V {
+            return Err(ProofError::VerificationError);
+        }
+
+        // t_x_blinding = z^2 v_blinding + (tau_1 + memo[9..]) x + tau_2 x^2
+        let t_1_blinding =
+            (self.t_x_blinding - z * z * v_blinding - nonces.tau_2 * x * x) * x.invert();
+        let memo_tail = t_1_blinding - nonces.tau_1;
+        let memo_tail = scalar_to_payload(&memo_tail).ok_or(ProofError::VerificationError)?;
+
+        let mut memo = [0u8; MEMO_SIZE];
+        memo[..MEMO_HEAD_SIZE].copy_from_slice(&payload[8 + SALT_SIZE..]);
+        memo[MEMO_HEAD_SIZE..].copy_from_slice(&memo_tail);
+        Ok((v, v_blinding, memo))
+    }
+
+    /// Replays the transcript of the prover of a single-value proof,
+    /// returning the challenges \\(z\\) and \\(x\\).
+    fn replay_challenges(
+        &self,
+        bp_gens: &BulletproofGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        n: usize,
+    ) -> (Scalar, Scalar) {
+        transcript.rangeproof_domain_sep(n as u64, 1);
+        for salt in bp_gens.salts() {
+            transcript.generators_salt(salt);
+        }
+        transcript.commit_point(b"V", V);
+        transcript.commit_point(b"A", &self.A);
+        transcript.commit_point(b"S", &self.S);
+        transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+        transcript.commit_point(b"T_1", &self.T_1);
+        transcript.commit_point(b"T_2", &self.T_2);
+        let x = transcript.challenge_scalar(b"x");
+        (z, x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand;
+
+    #[test]
+    fn rewind_recovers_the_opening() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let v_blinding = Scalar::random(&mut rand::thread_rng());
+        let rewind_key = [7u8; 32];
+
+        let mut transcript = Transcript::new(b"RewindTest");
+        let (proof, V) = RangeProof::prove_single_rewindable(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            1_000_000,
+            &v_blinding,
+            64,
+            &rewind_key,
+        ).unwrap();
+
+        let mut transcript = Transcript::new(b"RewindTest");
+        assert!(
+            proof
+                .verify_single(&bp_gens, &pc_gens, &mut transcript, &V, 64)
+                .is_ok()
+        );
+
+        let mut transcript = Transcript::new(b"RewindTest");
+        let opening = proof.rewind(&bp_gens, &pc_gens, &mut transcript, &V, 64, &rewind_key);
+        assert_eq!(opening, Ok((1_000_000, v_blinding)));
+
+        let mut transcript = Transcript::new(b"RewindTest");
+        let opening = proof.rewind(&bp_gens, &pc_gens, &mut transcript, &V, 64, &[8u8; 32]);
+        assert_eq!(opening, Err(ProofError::VerificationError));
+
+        let mut transcript = Transcript::new(b"OtherTranscript");
+        let opening = proof.rewind(&bp_gens, &pc_gens, &mut transcript, &V, 64, &rewind_key);
+        assert_eq!(opening, Err(ProofError::VerificationError));
+    }
+
+    #[test]
+    fn reproving_gives_the_same_proof() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let v_blinding = Scalar::random(&mut rand::thread_rng());
+        let rewind_key = [7u8; 32];
+
+        let prove = || {
+            let mut transcript = Transcript::new(b"RewindTest");
+            RangeProof::prove_single_rewindable(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                37,
+                &v_blinding,
+                32,
+                &rewind_key,
+            ).unwrap()
+        };
+        let (proof, V) = prove();
+        let (reproof, reV) = prove();
+        assert_eq!(V, reV);
+        assert_eq!(proof.to_bytes(), reproof.to_bytes());
+    }
+
+    #[test]
+    fn rewind_recovers_the_memo() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let rewind_key = [7u8; 32];
+        let mut memo = [0u8; MEMO_SIZE];
+        for (i, b) in memo.iter_mut().enumerate() {
+            *b = 0xff - i as u8;
+        }
+
+        let mut transcript = Transcript::new(b"MemoTest");
+        let (proof, V, v_blinding) = RangeProof::prove_single_with_memo(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            4242,
+            32,
+            &rewind_key,
+            &memo,
+        ).unwrap();
+        let expected = pc_gens.commit(Scalar::from(4242u64), v_blinding);
+        assert_eq!(V, expected.compress());
+
+        let mut transcript = Transcript::new(b"MemoTest");
+        assert!(
+            proof
+                .verify_single(&bp_gens, &pc_gens, &mut transcript, &V, 32)
+                .is_ok()
+        );
+
+        let mut transcript = Transcript::new(b"MemoTest");
+        let (v, blinding, recovered) = proof
+            .rewind_memo(&bp_gens, &pc_gens, &mut transcript, &V, 32, &rewind_key)
+            .unwrap();
+        assert_eq!((v, blinding), (4242, v_blinding));
+        assert_eq!(&recovered[..], &memo[..]);
+
+        let mut transcript = Transcript::new(b"MemoTest");
+        let opening = proof.rewind_memo(&bp_gens, &pc_gens, &mut transcript, &V, 32, &[8u8; 32]);
+        assert!(opening.is_err());
+    }
+
+    #[test]
+    fn memo_commitments_are_unlinkable() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let rewind_key = [7u8; 32];
+        let memo = [0x5au8; MEMO_SIZE];
+
+        let prove = || {
+            let mut transcript = Transcript::new(b"MemoTest");
+            RangeProof::prove_single_with_memo(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                4242,
+                32,
+                &rewind_key,
+                &memo,
+            ).unwrap()
+        };
+        let (proof, V, v_blinding) = prove();
+        let (other_proof, other_V, other_v_blinding) = prove();
+        assert_ne!(V, other_V);
+        assert_ne!(v_blinding, other_v_blinding);
+
+        for &(ref proof, ref V, ref v_blinding) in &[
+            (proof, V, v_blinding),
+            (other_proof, other_V, other_v_blinding),
+        ] {
+            let mut transcript = Transcript::new(b"MemoTest");
+            let (v, blinding, recovered) = proof
+                .rewind_memo(&bp_gens, &pc_gens, &mut transcript, V, 32, &rewind_key)
+                .unwrap();
+            assert_eq!((v, &blinding), (4242, v_blinding));
+            assert_eq!(&recovered[..], &memo[..]);
+        }
+    }
+}
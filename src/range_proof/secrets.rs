@@ -0,0 +1,457 @@
+//! The `secrets` module separates the computations of a party which
+//! touch its secrets from the rest of the party's work, so that the
+//! secrets can be kept in a hardware wallet or an HSM.
+//!
+//! A [`PartySecrets`] implementation holds the value \\(v\\), its
+//! blinding factor \\(\tilde{v}\\), and the blinding factors and
+//! vectors sampled for the proof, and computes everything which
+//! depends on them.  A [`Host`] runs the state machine of the party
+//! around it: it checks the parameters, routes the messages, and
+//! computes the public inputs of each round from the generators and
+//! the challenges, so that the device only does the work which
+//! requires the secrets.
+//!
+//! [`LocalSecrets`] keeps the secrets in memory, and is the reference
+//! for other implementations.
+
+use std::iter;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::MultiscalarMul;
+use rand::{self, CryptoRng, RngCore};
+
+use clear_on_drop::clear::Clear;
+use errors::MPCError;
+use generators::{BulletproofGens, PedersenGens};
+use math;
+use scalar::{self, ScalarVec};
+use util;
+
+use super::messages::*;
+use super::party::clear_value;
+
+/// The public inputs of the polynomial round of the party at position
+/// \\(j\\), computed by the [`Host`] from the [`BitChallenge`], for
+/// bit vectors of padded length \\(n\\).
+pub struct PolyInputs {
+    /// The challenge \\(z\\)
+    pub z: Scalar,
+    /// The weight \\(z^{2+j}\\) of the value
+    pub value_weight: Scalar,
+    /// The powers \\(y^{jn+i}\\), for \\(i\\) in \\(0..n\\)
+    pub exp_y: Vec<Scalar>,
+    /// The weights \\(z^{2+j} 2^i\\) of the bits, which are zero for
+    /// the padding bits
+    pub exp_2: Vec<Scalar>,
+}
+
+/// The operations of a party which use its secrets.
+///
+/// The [`Host`] calls each function once, in order.  An
+/// implementation which keeps the secrets on a device should forget
+/// them once [`prove_share`](PartySecrets::prove_share) returns.
+pub trait PartySecrets {
+    /// The error returned when the device fails.
+    type Error: From<MPCError>;
+
+    /// Returns the commitment \\(V = v B + \tilde{v} \tilde{B}\\) to
+    /// the value.
+    fn value_commitment(&self, pc_gens: &PedersenGens) -> Result<CompressedRistretto, Self::Error>;
+
+    /// Returns the commitments \\(A\\) to the bits of the value and
+    /// \\(S\\) to the blinding vectors, given the generators `G` and
+    /// `H` of the party's position.
+    fn commit_bits(
+        &mut self,
+        pc_gens: &PedersenGens,
+        G: &[RistrettoPoint],
+        H: &[RistrettoPoint],
+    ) -> Result<(RistrettoPoint, RistrettoPoint), Self::Error>;
+
+    /// Returns the commitments \\(T\_1\\) and \\(T\_2\\) to the
+    /// coefficients of the party's polynomial \\(t(x)\\).
+    fn commit_poly(
+        &mut self,
+        pc_gens: &PedersenGens,
+        inputs: &PolyInputs,
+    ) -> Result<(RistrettoPoint, RistrettoPoint), Self::Error>;
+
+    /// Returns the party's [`ProofShare`] at the challenge \\(x\\),
+    /// which the [`Host`] checked to be nonzero.
+    fn prove_share(&mut self, x: Scalar) -> Result<ProofShare, Self::Error>;
+}
+
+/// Used to construct the host of a party whose secrets are held by a
+/// [`PartySecrets`] implementation.
+pub struct Host {}
+
+impl Host {
+    /// Constructs a `HostAwaitingPosition` for a party proving an
+    /// `n`-bit range with the `secrets`.
+    pub fn new<'a, S: PartySecrets>(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        secrets: S,
+        n: usize,
+    ) -> Result<HostAwaitingPosition<'a, S>, S::Error> {
+        if n == 0 || n > 128 {
+            return Err(MPCError::InvalidBitsize.into());
+        }
+        if bp_gens.gens_capacity < n.next_power_of_two() {
+            return Err(MPCError::InvalidGeneratorsLength.into());
+        }
+        Ok(HostAwaitingPosition {
+            bp_gens,
+            pc_gens,
+            n,
+            secrets,
+        })
+    }
+}
+
+/// A host waiting for the dealer to assign the party's position.
+pub struct HostAwaitingPosition<'a, S: PartySecrets> {
+    bp_gens: &'a BulletproofGens,
+    pc_gens: &'a PedersenGens,
+    n: usize,
+    secrets: S,
+}
+
+impl<'a, S: PartySecrets> HostAwaitingPosition<'a, S> {
+    /// Assigns a position in the aggregated proof to the party, and
+    /// has the secrets commit to the bits of the value.
+    pub fn assign_position(
+        mut self,
+        j: usize,
+    ) -> Result<(HostAwaitingBitChallenge<'a, S>, BitCommitment), S::Error> {
+        if self.bp_gens.party_capacity <= j {
+            return Err(MPCError::InvalidGeneratorsLength.into());
+        }
+
+        let bp_share = self.bp_gens.share(j);
+        let padded_n = self.n.next_power_of_two();
+        let G: Vec<_> = bp_share.G(padded_n).cloned().collect();
+        let H: Vec<_> = bp_share.H(padded_n).cloned().collect();
+
+        let V_j = self.secrets.value_commitment(self.pc_gens)?;
+        let (A_j, S_j) = self.secrets.commit_bits(self.pc_gens, &G, &H)?;
+
+        let next_state = HostAwaitingBitChallenge {
+            pc_gens: self.pc_gens,
+            n: self.n,
+            j,
+            secrets: self.secrets,
+        };
+        Ok((next_state, BitCommitment { V_j, A_j, S_j }))
+    }
+}
+
+/// A host whose party has committed to the bits of its value, waiting
+/// for the [`BitChallenge`].
+pub struct HostAwaitingBitChallenge<'a, S: PartySecrets> {
+    pc_gens: &'a PedersenGens,
+    n: usize,
+    j: usize,
+    secrets: S,
+}
+
+impl<'a, S: PartySecrets> HostAwaitingBitChallenge<'a, S> {
+    /// Receive a [`BitChallenge`] from the dealer, and have the
+    /// secrets commit to the party's polynomial coefficients.
+    pub fn apply_challenge(
+        mut self,
+        vc: &BitChallenge,
+    ) -> Result<(HostAwaitingPolyChallenge<S>, PolyCommitment), S::Error> {
+        let padded_n = self.n.next_power_of_two();
+        let offset_y = util::scalar_exp_vartime(&vc.y, (self.j * padded_n) as u64);
+        let offset_z = util::scalar_exp_vartime(&vc.z, self.j as u64);
+        let value_weight = vc.z * vc.z * offset_z;
+
+        let inputs = PolyInputs {
+            z: vc.z,
+            value_weight,
+            exp_y: math::powers(vc.y, padded_n)
+                .map(|y_i| offset_y * y_i)
+                .collect(),
+            exp_2: super::padded_powers_of_2(self.n)
+                .map(|two_i| value_weight * two_i)
+                .collect(),
+        };
+        let (T_1_j, T_2_j) = self.secrets.commit_poly(self.pc_gens, &inputs)?;
+
+        let next_state = HostAwaitingPolyChallenge {
+            secrets: self.secrets,
+        };
+        Ok((next_state, PolyCommitment { T_1_j, T_2_j }))
+    }
+}
+
+/// A host whose party has committed to its polynomial coefficients,
+/// waiting for the [`PolyChallenge`].
+pub struct HostAwaitingPolyChallenge<S: PartySecrets> {
+    secrets: S,
+}
+
+impl<S: PartySecrets> HostAwaitingPolyChallenge<S> {
+    /// Receive a [`PolyChallenge`] from the dealer, and have the
+    /// secrets compute the party's proof share.
+    pub fn apply_challenge(mut self, pc: &PolyChallenge) -> Result<ProofShare, S::Error> {
+        // Prevent a malicious dealer from annihilating the blinding
+        // factors by supplying a zero challenge.
+        if pc.x == Scalar::zero() {
+            return Err(MPCError::MaliciousDealer.into());
+        }
+        self.secrets.prove_share(pc.x)
+    }
+}
+
+/// A [`PartySecrets`] implementation which keeps the secrets in
+/// memory, and overwrites them when dropped.
+pub struct LocalSecrets {
+    n: usize,
+    v: u128,
+    v_blinding: Scalar,
+    a_blinding: Scalar,
+    s_blinding: Scalar,
+    s_L: ScalarVec,
+    s_R: ScalarVec,
+    poly: Option<LocalPoly>,
+}
+
+/// The secrets of the polynomial round.
+struct LocalPoly {
+    l_poly: util::VecPoly1,
+    r_poly: util::VecPoly1,
+    t_poly: util::Poly2,
+    value_weight: Scalar,
+    t_1_blinding: Scalar,
+    t_2_blinding: Scalar,
+}
+
+impl LocalSecrets {
+    /// Creates the secrets of a party proving that `v` is in an
+    /// `n`-bit range, with the blinding factor `v_blinding`.
+    pub fn new(v: u64, v_blinding: Scalar, n: usize) -> Result<LocalSecrets, MPCError> {
+        LocalSecrets::new_with_rng(v.into(), v_blinding, n, &mut rand::thread_rng())
+    }
+
+    /// Creates the secrets of a party, sampling the blinding factors
+    /// for the bit commitments from `rng`.
+    pub fn new_with_rng<T: RngCore + CryptoRng>(
+        v: u128,
+        v_blinding: Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<LocalSecrets, MPCError> {
+        if n == 0 || n > 128 {
+            return Err(MPCError::InvalidBitsize);
+        }
+        let padded_n = n.next_power_of_two();
+        Ok(LocalSecrets {
+            n,
+            v,
+            v_blinding,
+            a_blinding: Scalar::random(rng),
+            s_blinding: Scalar::random(rng),
+            s_L: ScalarVec::from(scalar::random_vec(rng, padded_n)),
+            s_R: ScalarVec::from(scalar::random_vec(rng, padded_n)),
+            poly: None,
+        })
+    }
+
+    /// Returns the bits \\(\mathbf{a}\_L\\) of the value.
+    fn a_L(&self) -> ScalarVec {
+        let padded_n = self.n.next_power_of_two();
+        (0..padded_n)
+            .map(|i| Scalar::from(((self.v >> i) & 1) as u64))
+            .collect()
+    }
+}
+
+impl PartySecrets for LocalSecrets {
+    type Error = MPCError;
+
+    fn value_commitment(&self, pc_gens: &PedersenGens) -> Result<CompressedRistretto, MPCError> {
+        Ok(pc_gens
+            .commit(scalar::from_u128(self.v), self.v_blinding)
+            .compress())
+    }
+
+    fn commit_bits(
+        &mut self,
+        pc_gens: &PedersenGens,
+        G: &[RistrettoPoint],
+        H: &[RistrettoPoint],
+    ) -> Result<(RistrettoPoint, RistrettoPoint), MPCError> {
+        let padded_n = self.n.next_power_of_two();
+        if G.len() != padded_n || H.len() != padded_n {
+            return Err(MPCError::InvalidGeneratorsLength);
+        }
+
+        // Compute A = <a_L, G> + <a_R, H> + a_blinding * B_blinding
+        let mut A = pc_gens.B_blinding * self.a_blinding;
+
+        use subtle::{Choice, ConditionallySelectable};
+        for (i, (G_i, H_i)) in G.iter().zip(H.iter()).enumerate() {
+            // If v_i = 0, we add a_L[i] * G[i] + a_R[i] * H[i] = - H[i]
+            // If v_i = 1, we add a_L[i] * G[i] + a_R[i] * H[i] =   G[i]
+            let v_i = Choice::from(((self.v >> i) & 1) as u8);
+            let mut point = -H_i;
+            point.conditional_assign(G_i, v_i);
+            A += point;
+        }
+
+        // Compute S = <s_L, G> + <s_R, H> + s_blinding * B_blinding
+        let S = RistrettoPoint::multiscalar_mul(
+            iter::once(&self.s_blinding)
+                .chain(self.s_L.iter())
+                .chain(self.s_R.iter()),
+            iter::once(&pc_gens.B_blinding).chain(G).chain(H),
+        );
+        Ok((A, S))
+    }
+
+    fn commit_poly(
+        &mut self,
+        pc_gens: &PedersenGens,
+        inputs: &PolyInputs,
+    ) -> Result<(RistrettoPoint, RistrettoPoint), MPCError> {
+        let padded_n = self.n.next_power_of_two();
+        if inputs.exp_y.len() != padded_n || inputs.exp_2.len() != padded_n {
+            return Err(MPCError::InvalidBitsize);
+        }
+
+        let z_n = ScalarVec::splat(inputs.z, padded_n);
+        let a_L = self.a_L();
+        let a_R = &a_L - &ScalarVec::splat(Scalar::one(), padded_n);
+        let exp_y = ScalarVec::from(inputs.exp_y.clone());
+        let exp_2 = ScalarVec::from(inputs.exp_2.clone());
+
+        let l_poly = util::VecPoly1(a_L - &z_n, ScalarVec::from(self.s_L.to_vec()));
+        let r_poly = util::VecPoly1(
+            (a_R + &z_n) * &exp_y + &exp_2,
+            ScalarVec::from(self.s_R.to_vec()) * &exp_y,
+        );
+        let t_poly = l_poly.inner_product(&r_poly);
+
+        let mut rng = rand::thread_rng();
+        let t_1_blinding = Scalar::random(&mut rng);
+        let t_2_blinding = Scalar::random(&mut rng);
+        let T_1 = pc_gens.commit(t_poly.1, t_1_blinding);
+        let T_2 = pc_gens.commit(t_poly.2, t_2_blinding);
+
+        self.poly = Some(LocalPoly {
+            l_poly,
+            r_poly,
+            t_poly,
+            value_weight: inputs.value_weight,
+            t_1_blinding,
+            t_2_blinding,
+        });
+        Ok((T_1, T_2))
+    }
+
+    fn prove_share(&mut self, x: Scalar) -> Result<ProofShare, MPCError> {
+        let poly = self
+            .poly
+            .take()
+            .expect("The host commits to the polynomial before proving the share");
+
+        let t_blinding_poly = util::Poly2(
+            poly.value_weight * self.v_blinding,
+            poly.t_1_blinding,
+            poly.t_2_blinding,
+        );
+
+        Ok(ProofShare {
+            t_x: poly.t_poly.eval(x),
+            t_x_blinding: t_blinding_poly.eval(x),
+            e_blinding: self.a_blinding + self.s_blinding * x,
+            l_vec: poly.l_poly.eval(x),
+            r_vec: poly.r_poly.eval(x),
+        })
+    }
+}
+
+/// Overwrite secrets with null bytes when they go out of scope.
+impl Drop for LocalSecrets {
+    fn drop(&mut self) {
+        clear_value(&mut self.v);
+        self.v_blinding.clear();
+        self.a_blinding.clear();
+        self.s_blinding.clear();
+
+        // Note: s_L and s_R are cleared within their own Drop impls.
+    }
+}
+
+/// Overwrite secrets with null bytes when they go out of scope.
+impl Drop for LocalPoly {
+    fn drop(&mut self) {
+        self.value_weight.clear();
+        self.t_1_blinding.clear();
+        self.t_2_blinding.clear();
+
+        // Note: polynomials r_poly, l_poly and t_poly
+        // are cleared within their own Drop impls.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use merlin::Transcript;
+
+    use range_proof::dealer::Dealer;
+    use range_proof::party::Party;
+
+    #[test]
+    fn aggregation_with_delegated_secrets() {
+        let (n, m) = (32, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        // Party 0 keeps its secrets behind a host, party 1 is an
+        // ordinary party
+        let v0_blinding = Scalar::random(&mut rng);
+        let secrets = LocalSecrets::new(1234, v0_blinding, n).unwrap();
+        let host = Host::new(&bp_gens, &pc_gens, secrets, n).unwrap();
+        let party = Party::new(&bp_gens, &pc_gens, 5678, Scalar::random(&mut rng), n).unwrap();
+
+        let mut transcript = Transcript::new(b"PartySecretsTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        let (host, bit_commitment0) = host.assign_position(0).unwrap();
+        let (party, bit_commitment1) = party.assign_position(1).unwrap();
+        let Vs = vec![bit_commitment0.V_j, bit_commitment1.V_j];
+        assert_eq!(
+            Vs[0],
+            pc_gens
+                .commit(Scalar::from(1234u64), v0_blinding)
+                .compress()
+        );
+        let (dealer, bit_challenge) = dealer
+            .receive_bit_commitments(vec![bit_commitment0, bit_commitment1])
+            .unwrap();
+
+        let (host, poly_commitment0) = host.apply_challenge(&bit_challenge).unwrap();
+        let (party, poly_commitment1) = party.apply_challenge(&bit_challenge);
+        let (dealer, poly_challenge) = dealer
+            .receive_poly_commitments(vec![poly_commitment0, poly_commitment1])
+            .unwrap();
+
+        let share0 = host.apply_challenge(&poly_challenge).unwrap();
+        let share1 = party.apply_challenge(&poly_challenge).unwrap();
+        let proof = dealer.receive_shares(&[share0, share1]).unwrap();
+
+        let mut transcript = Transcript::new(b"PartySecretsTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &Vs, n)
+                .is_ok()
+        );
+    }
+}
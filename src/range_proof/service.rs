@@ -0,0 +1,295 @@
+//! The `service` module contains a [`VerificationService`], which
+//! verifies range proofs submitted over a channel on a background
+//! thread, in batches.
+//!
+//! This module is only available with the `service` feature.
+
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, SendError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use merlin::Transcript;
+
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::{BatchVerifier, RangeProof};
+
+/// A range proof submitted to a [`VerificationService`].
+pub struct VerificationRequest {
+    /// An identifier chosen by the caller, returned in the response.
+    pub id: u64,
+    /// The proof to verify.
+    pub proof: RangeProof,
+    /// A transcript with the same initial state as the one passed to
+    /// the prover.
+    pub transcript: Transcript,
+    /// The commitments to the values in the proof.
+    pub value_commitments: Vec<CompressedRistretto>,
+    /// The bitsize of the range.
+    pub n: usize,
+}
+
+/// The result of verifying a [`VerificationRequest`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationResponse {
+    /// The identifier of the request.
+    pub id: u64,
+    /// The verification result for the request.
+    pub result: Result<(), ProofError>,
+}
+
+/// A background verifier of range proofs.
+///
+/// Requests are collected into batches, which are verified when they
+/// reach `max_batch_size` requests or when `max_delay` has passed
+/// since the first request in the batch was received, whichever comes
+/// first.  Each batch is verified with a [`BatchVerifier`]; if the
+/// batch fails, its proofs are verified individually so that each
+/// response carries the result for its own proof.
+///
+/// # Example
+/// ```
+/// extern crate rand;
+/// use rand::thread_rng;
+///
+/// extern crate curve25519_dalek;
+/// use curve25519_dalek::scalar::Scalar;
+///
+/// extern crate merlin;
+/// use merlin::Transcript;
+///
+/// extern crate bulletproofs;
+/// use bulletproofs::service::{VerificationRequest, VerificationService};
+/// use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+///
+/// use std::time::Duration;
+///
+/// # fn main() {
+/// let pc_gens = PedersenGens::default();
+/// let bp_gens = BulletproofGens::new(64, 1);
+///
+/// let (service, responses) =
+///     VerificationService::spawn(bp_gens.clone(), pc_gens, 8, Duration::from_millis(10));
+///
+/// let blinding = Scalar::random(&mut thread_rng());
+/// let (proof, committed_value) = RangeProof::prove_single(
+///     &bp_gens,
+///     &pc_gens,
+///     &mut Transcript::new(b"doctest example"),
+///     1037578891u64,
+///     &blinding,
+///     32,
+/// ).expect("A real program could handle errors");
+///
+/// service
+///     .submit(VerificationRequest {
+///         id: 7,
+///         proof,
+///         transcript: Transcript::new(b"doctest example"),
+///         value_commitments: vec![committed_value],
+///         n: 32,
+///     }).expect("The service is running");
+///
+/// let response = responses.recv().unwrap();
+/// assert_eq!(response.id, 7);
+/// assert!(response.result.is_ok());
+///
+/// service.shutdown();
+/// # }
+/// ```
+pub struct VerificationService {
+    sender: Sender<VerificationRequest>,
+    worker: JoinHandle<()>,
+}
+
+impl VerificationService {
+    /// Starts a verification service on a new thread, returning the
+    /// service handle and the channel on which the responses are
+    /// delivered.
+    pub fn spawn(
+        bp_gens: BulletproofGens,
+        pc_gens: PedersenGens,
+        max_batch_size: usize,
+        max_delay: Duration,
+    ) -> (VerificationService, Receiver<VerificationResponse>) {
+        let (sender, requests) = channel();
+        let (responder, responses) = channel();
+
+        let worker = thread::spawn(move || {
+            run(
+                &bp_gens,
+                &pc_gens,
+                max_batch_size.max(1),
+                max_delay,
+                requests,
+                responder,
+            )
+        });
+
+        (VerificationService { sender, worker }, responses)
+    }
+
+    /// Submits a request for verification.
+    ///
+    /// Returns the request back if the service has stopped.
+    pub fn submit(
+        &self,
+        request: VerificationRequest,
+    ) -> Result<(), SendError<VerificationRequest>> {
+        self.sender.send(request)
+    }
+
+    /// Returns a sender which can be used to submit requests from
+    /// other threads.
+    pub fn sender(&self) -> Sender<VerificationRequest> {
+        self.sender.clone()
+    }
+
+    /// Stops accepting requests, verifies the requests already
+    /// submitted, and waits for the service thread to finish.
+    ///
+    /// The service also stops once the service handle and all
+    /// senders have been dropped.
+    pub fn shutdown(self) {
+        drop(self.sender);
+        self.worker
+            .join()
+            .expect("The verification service thread panicked");
+    }
+}
+
+/// The main loop of the service thread.
+fn run(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    max_batch_size: usize,
+    max_delay: Duration,
+    requests: Receiver<VerificationRequest>,
+    responder: Sender<VerificationResponse>,
+) {
+    // Wait for the first request of each batch without a deadline.
+    while let Ok(first) = requests.recv() {
+        let deadline = Instant::now() + max_delay;
+        let mut batch = vec![first];
+        let mut disconnected = false;
+
+        while batch.len() < max_batch_size {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            match requests.recv_timeout(deadline - now) {
+                Ok(request) => batch.push(request),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
+            }
+        }
+
+        for response in verify_batch(bp_gens, pc_gens, batch) {
+            if responder.send(response).is_err() {
+                // Nobody is listening for the results anymore.
+                return;
+            }
+        }
+
+        if disconnected {
+            return;
+        }
+    }
+}
+
+/// Verifies a batch of requests, falling back to individual
+/// verification if the batch fails.
+fn verify_batch(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    batch: Vec<VerificationRequest>,
+) -> Vec<VerificationResponse> {
+    let mut batch_verifier = BatchVerifier::new(bp_gens, pc_gens);
+    for request in batch.iter() {
+        batch_verifier.add(
+            request.proof.clone(),
+            request.transcript.clone(),
+            request.value_commitments.clone(),
+            request.n,
+        );
+    }
+
+    if batch_verifier.verify_all().is_ok() {
+        return batch
+            .into_iter()
+            .map(|request| VerificationResponse {
+                id: request.id,
+                result: Ok(()),
+            }).collect();
+    }
+
+    batch
+        .into_iter()
+        .map(|mut request| VerificationResponse {
+            id: request.id,
+            result: request.proof.verify_multiple(
+                bp_gens,
+                pc_gens,
+                &mut request.transcript,
+                &request.value_commitments,
+                request.n,
+            ),
+        }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+    use rand;
+
+    #[test]
+    fn service_reports_each_result() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+
+        let (service, responses) =
+            VerificationService::spawn(bp_gens.clone(), pc_gens, 3, Duration::from_millis(50));
+
+        for id in 0..5u64 {
+            let (proof, V) = RangeProof::prove_single(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"ServiceTest"),
+                id,
+                &Scalar::random(&mut rng),
+                32,
+            ).unwrap();
+            // Request 3 is checked against the wrong label
+            let label: &'static [u8] = if id == 3 {
+                b"WrongLabel"
+            } else {
+                b"ServiceTest"
+            };
+            service
+                .submit(VerificationRequest {
+                    id,
+                    proof,
+                    transcript: Transcript::new(label),
+                    value_commitments: vec![V],
+                    n: 32,
+                }).unwrap();
+        }
+        service.shutdown();
+
+        let mut results: Vec<_> = responses.iter().collect();
+        results.sort_by_key(|response| response.id);
+
+        assert_eq!(results.len(), 5);
+        for response in results.iter() {
+            assert_eq!(response.result.is_ok(), response.id != 3);
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! The `session` module contains stateful wrappers around
+//! [`RangeProof`] proving and verification.
+//!
+//! A [`RangeProver`] or [`RangeVerifier`] owns the transcript and
+//! holds the generators and the ordered list of value commitments, so
+//! that the prover and verifier cannot accidentally disagree about
+//! them.
+//!
+//! For interactive protocols, the verifier can issue a
+//! [`VerifierNonce`] which the prover binds into its transcript, so
+//! that a proof made for one session cannot be replayed in another.
+
+use clear_on_drop::clear::Clear;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::{self, RngCore};
+
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::RangeProof;
+use transcript::TranscriptProtocol;
+
+/// A random nonce chosen by a verifier to make a proof fresh.
+///
+/// The verifier obtains a nonce from
+/// [`RangeVerifier::issue_nonce`] and sends it to the prover, which
+/// binds it with [`RangeProver::bind_nonce`] before proving.  A proof
+/// created for one nonce does not verify for any other.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct VerifierNonce([u8; 32]);
+
+impl VerifierNonce {
+    /// Constructs a nonce from its byte representation, for instance
+    /// after receiving it from the verifier.
+    pub fn from_bytes(bytes: [u8; 32]) -> VerifierNonce {
+        VerifierNonce(bytes)
+    }
+
+    /// Returns the byte representation of the nonce.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+/// A prover session for a (possibly aggregated) range proof.
+///
+/// Values are added one at a time with
+/// [`add_value`](RangeProver::add_value), which returns the
+/// commitment to the value, and the proof over all added values is
+/// created with [`prove`](RangeProver::prove).
+pub struct RangeProver<'g> {
+    bp_gens: &'g BulletproofGens,
+    pc_gens: &'g PedersenGens,
+    transcript: Transcript,
+    n: usize,
+    values: Vec<u64>,
+    blindings: Vec<Scalar>,
+}
+
+impl<'g> RangeProver<'g> {
+    /// Creates a prover session for `n`-bit range proofs.
+    ///
+    /// The `transcript` is owned by the session and is consumed when
+    /// the proof is created.
+    pub fn new(
+        bp_gens: &'g BulletproofGens,
+        pc_gens: &'g PedersenGens,
+        transcript: Transcript,
+        n: usize,
+    ) -> Self {
+        RangeProver {
+            bp_gens,
+            pc_gens,
+            transcript,
+            n,
+            values: Vec::new(),
+            blindings: Vec::new(),
+        }
+    }
+
+    /// Adds a value `v` with blinding factor `v_blinding` to the
+    /// session, returning the commitment to the value.
+    ///
+    /// The commitments are ordered in the order the values were
+    /// added, which is the order the verifier must use.
+    pub fn add_value(&mut self, v: u64, v_blinding: Scalar) -> CompressedRistretto {
+        self.values.push(v);
+        self.blindings.push(v_blinding);
+        self.pc_gens.commit(Scalar::from(v), v_blinding).compress()
+    }
+
+    /// Binds a nonce issued by the verifier into the transcript.
+    ///
+    /// This must be called before [`prove`](RangeProver::prove), once
+    /// for each nonce issued by the verifier, in the same order.
+    pub fn bind_nonce(&mut self, nonce: &VerifierNonce) {
+        self.transcript.verifier_nonce(&nonce.0);
+    }
+
+    /// Returns the number of values added to the session so far.
+    pub fn num_values(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Creates a range proof for all values added to the session.
+    pub fn prove(mut self) -> Result<RangeProof, ProofError> {
+        let (proof, _) = RangeProof::prove_multiple(
+            self.bp_gens,
+            self.pc_gens,
+            &mut self.transcript,
+            &self.values,
+            &self.blindings,
+            self.n,
+        )?;
+        Ok(proof)
+    }
+}
+
+/// Overwrite secrets with null bytes when they go out of scope.
+impl<'g> Drop for RangeProver<'g> {
+    fn drop(&mut self) {
+        // See the comment in `PartyAwaitingBitChallenge` for why the
+        // vectors are cleared element by element.
+        for v in self.values.iter_mut() {
+            v.clear();
+        }
+        for e in self.blindings.iter_mut() {
+            e.clear();
+        }
+    }
+}
+
+/// A verifier session for a (possibly aggregated) range proof.
+///
+/// Commitments are added one at a time with
+/// [`add_commitment`](RangeVerifier::add_commitment), in the same
+/// order as the prover added the values, and the proof is checked
+/// with [`verify`](RangeVerifier::verify).
+pub struct RangeVerifier<'g> {
+    bp_gens: &'g BulletproofGens,
+    pc_gens: &'g PedersenGens,
+    transcript: Transcript,
+    n: usize,
+    commitments: Vec<CompressedRistretto>,
+}
+
+impl<'g> RangeVerifier<'g> {
+    /// Creates a verifier session for `n`-bit range proofs.
+    ///
+    /// The `transcript` must have the same initial state as the one
+    /// passed to the prover.
+    pub fn new(
+        bp_gens: &'g BulletproofGens,
+        pc_gens: &'g PedersenGens,
+        transcript: Transcript,
+        n: usize,
+    ) -> Self {
+        RangeVerifier {
+            bp_gens,
+            pc_gens,
+            transcript,
+            n,
+            commitments: Vec::new(),
+        }
+    }
+
+    /// Chooses a random nonce, binds it into the verifier's
+    /// transcript and returns it, to be sent to the prover.
+    ///
+    /// The proof passed to [`verify`](RangeVerifier::verify) must then
+    /// have been created after binding the same nonce with
+    /// [`RangeProver::bind_nonce`].
+    pub fn issue_nonce(&mut self) -> VerifierNonce {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let nonce = VerifierNonce(bytes);
+        self.transcript.verifier_nonce(&nonce.0);
+        nonce
+    }
+
+    /// Adds a value commitment to the session.
+    pub fn add_commitment(&mut self, V: CompressedRistretto) {
+        self.commitments.push(V);
+    }
+
+    /// Returns the commitments added to the session so far.
+    pub fn commitments(&self) -> &[CompressedRistretto] {
+        &self.commitments
+    }
+
+    /// Verifies that `proof` is a valid range proof for all
+    /// commitments added to the session.
+    pub fn verify(mut self, proof: &RangeProof) -> Result<(), ProofError> {
+        proof.verify_multiple(
+            self.bp_gens,
+            self.pc_gens,
+            &mut self.transcript,
+            &self.commitments,
+            self.n,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand;
+
+    #[test]
+    fn session_prove_and_verify() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let mut rng = rand::thread_rng();
+
+        let mut prover = RangeProver::new(&bp_gens, &pc_gens, Transcript::new(b"SessionTest"), 32);
+        let commitments: Vec<_> = [7u64, 1 << 20]
+            .iter()
+            .map(|&v| prover.add_value(v, Scalar::random(&mut rng)))
+            .collect();
+        assert_eq!(prover.num_values(), 2);
+        let proof = prover.prove().unwrap();
+
+        let mut verifier =
+            RangeVerifier::new(&bp_gens, &pc_gens, Transcript::new(b"SessionTest"), 32);
+        for V in commitments.iter() {
+            verifier.add_commitment(*V);
+        }
+        assert!(verifier.verify(&proof).is_ok());
+
+        // Commitments in the wrong order must not verify.
+        let mut verifier =
+            RangeVerifier::new(&bp_gens, &pc_gens, Transcript::new(b"SessionTest"), 32);
+        for V in commitments.iter().rev() {
+            verifier.add_commitment(*V);
+        }
+        assert!(verifier.verify(&proof).is_err());
+    }
+
+    #[test]
+    fn nonce_binds_proof_to_session() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let prove = |nonce: &VerifierNonce| {
+            let mut prover =
+                RangeProver::new(&bp_gens, &pc_gens, Transcript::new(b"NonceTest"), 32);
+            prover.bind_nonce(nonce);
+            let V = prover.add_value(99, blinding);
+            (prover.prove().unwrap(), V)
+        };
+
+        let mut verifier =
+            RangeVerifier::new(&bp_gens, &pc_gens, Transcript::new(b"NonceTest"), 32);
+        let nonce = verifier.issue_nonce();
+        let (proof, V) = prove(&VerifierNonce::from_bytes(nonce.to_bytes()));
+        verifier.add_commitment(V);
+        assert!(verifier.verify(&proof).is_ok());
+
+        // Replaying the proof to a verifier with a fresh nonce fails.
+        let mut verifier =
+            RangeVerifier::new(&bp_gens, &pc_gens, Transcript::new(b"NonceTest"), 32);
+        let fresh_nonce = verifier.issue_nonce();
+        assert!(fresh_nonce != nonce);
+        verifier.add_commitment(V);
+        assert!(verifier.verify(&proof).is_err());
+    }
+}
@@ -0,0 +1,301 @@
+//! The `session_manager` module runs the aggregated multiparty
+//! computation protocol with deadlines on each round, so that a party
+//! which never responds cannot stall the dealer.
+//!
+//! A [`SessionManager`] runs the dealer over a [`TimedTransport`],
+//! waiting at most a fixed timeout for the messages of each round.  If
+//! some parties miss the deadline, the session aborts with a
+//! [`CoordinatorError::Timeout`] naming their indices, or, when
+//! restarts are enabled, starts the protocol over with the remaining
+//! parties.
+//!
+//! A restart sends the remaining parties a new
+//! [`DealerMessage::Position`], in place of the next challenge.  A
+//! party which receives a position while it awaits a challenge must
+//! start over from a fresh
+//! [`PartyAwaitingPosition`](::aggregation::party::PartyAwaitingPosition),
+//! with the same value and blinding factor so that its value
+//! commitment does not change.  The dealer pads the aggregation of
+//! the remaining parties with dummy parties, so the protocol is
+//! restarted as long as any party remains.
+
+use std::time::{Duration, Instant};
+
+use merlin::Transcript;
+
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::dealer::Dealer;
+use range_proof::messages::{DealerMessage, PartyMessage};
+use range_proof::transport::{CoordinatorError, TimedTransport};
+use range_proof::RangeProof;
+
+/// The result of a session.
+#[derive(Clone, Debug)]
+pub struct SessionOutcome {
+    /// The aggregated proof
+    pub proof: RangeProof,
+    /// The indices of the parties whose values are in the proof, in
+    /// the order of their positions
+    pub parties: Vec<usize>,
+    /// The indices of the parties which were left out after missing
+    /// a deadline
+    pub dropped: Vec<usize>,
+}
+
+/// Runs a dealer against a [`TimedTransport`], with a deadline on
+/// each round.
+pub struct SessionManager<T: TimedTransport> {
+    transport: T,
+    round_timeout: Duration,
+    restart: bool,
+}
+
+impl<T: TimedTransport> SessionManager<T> {
+    /// Creates a session manager over the `transport`, which waits at
+    /// most `round_timeout` for the messages of each round, and does
+    /// not restart the protocol.
+    pub fn new(transport: T, round_timeout: Duration) -> SessionManager<T> {
+        SessionManager {
+            transport,
+            round_timeout,
+            restart: false,
+        }
+    }
+
+    /// Sets whether to restart the protocol with the remaining
+    /// parties when some parties miss a deadline.
+    pub fn restart_on_dropout(mut self, restart: bool) -> SessionManager<T> {
+        self.restart = restart;
+        self
+    }
+
+    /// Returns the transport.
+    pub fn into_transport(self) -> T {
+        self.transport
+    }
+
+    /// Runs the protocol with all the parties of the transport, for
+    /// values of bitsize `n`.
+    ///
+    /// The `transcript` is reset to its initial state before each
+    /// restart, so that the proof verifies against a transcript in the
+    /// same initial state.  If the session aborts, the
+    /// [`CoordinatorError::Timeout`] error lists every party which
+    /// missed a deadline, including the parties dropped by earlier
+    /// restarts.
+    pub fn run(
+        &mut self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        n: usize,
+    ) -> Result<SessionOutcome, CoordinatorError<T::Error>> {
+        let initial_transcript = transcript.clone();
+        let mut parties: Vec<usize> = (0..self.transport.num_parties()).collect();
+        let mut dropped = Vec::new();
+        loop {
+            *transcript = initial_transcript.clone();
+            let missing = match self.attempt(bp_gens, pc_gens, transcript, n, &parties) {
+                Ok(proof) => {
+                    return Ok(SessionOutcome {
+                        proof,
+                        parties,
+                        dropped,
+                    })
+                }
+                Err(CoordinatorError::Timeout { missing }) => missing,
+                Err(e) => return Err(e),
+            };
+
+            parties.retain(|j| !missing.contains(j));
+            dropped.extend(missing);
+            dropped.sort();
+            if !self.restart || parties.is_empty() {
+                return Err(CoordinatorError::Timeout { missing: dropped });
+            }
+        }
+    }
+
+    /// Runs the protocol once with the `parties`, the party at
+    /// `parties[j]` taking the position \\(j\\).
+    fn attempt(
+        &mut self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        n: usize,
+        parties: &[usize],
+    ) -> Result<RangeProof, CoordinatorError<T::Error>> {
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, parties.len())?;
+
+        for (j, &party) in parties.iter().enumerate() {
+            self.send(party, DealerMessage::Position(j))?;
+        }
+        let bit_commitments = self.collect(parties, PartyMessage::bit_commitment)?;
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
+
+        for &party in parties {
+            self.send(party, DealerMessage::BitChallenge(bit_challenge))?;
+        }
+        let poly_commitments = self.collect(parties, PartyMessage::poly_commitment)?;
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
+
+        for &party in parties {
+            self.send(party, DealerMessage::PolyChallenge(poly_challenge))?;
+        }
+        let proof_shares = self.collect(parties, PartyMessage::proof_share)?;
+        Ok(dealer.receive_shares(&proof_shares)?)
+    }
+
+    fn send(
+        &mut self,
+        party: usize,
+        message: DealerMessage,
+    ) -> Result<(), CoordinatorError<T::Error>> {
+        self.transport
+            .send(party, message)
+            .map_err(|error| CoordinatorError::Transport { party, error })
+    }
+
+    /// Receives one message from each of the `parties` before the
+    /// deadline of the round, and extracts its content with `extract`,
+    /// which returns `None` for a message of another round.
+    fn collect<U>(
+        &mut self,
+        parties: &[usize],
+        extract: fn(PartyMessage) -> Option<U>,
+    ) -> Result<Vec<U>, CoordinatorError<T::Error>> {
+        let deadline = Instant::now() + self.round_timeout;
+        let mut contents = Vec::with_capacity(parties.len());
+        let mut missing = Vec::new();
+        for &party in parties {
+            let message = self
+                .transport
+                .receive_until(party, deadline)
+                .map_err(|error| CoordinatorError::Transport { party, error })?;
+            match message {
+                Some(message) => {
+                    let content =
+                        extract(message).ok_or(CoordinatorError::UnexpectedMessage { party })?;
+                    contents.push(content);
+                }
+                None => missing.push(party),
+            }
+        }
+
+        if missing.is_empty() {
+            Ok(contents)
+        } else {
+            Err(CoordinatorError::Timeout { missing })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use range_proof::party::Party;
+    use range_proof::transport::{ChannelClosed, ChannelTransport, PartyChannel};
+
+    /// Runs a party over its end of a [`ChannelTransport`], starting
+    /// over whenever it receives a position.  A silent party never
+    /// sends its bit commitment.
+    fn run_party(channel: PartyChannel, v: u64, silent: bool) {
+        let (n, m) = (16, 4);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let blinding = Scalar::from(v + 1);
+
+        let mut awaiting_bit_challenge = None;
+        let mut awaiting_poly_challenge = None;
+        for message in channel.receiver.iter() {
+            let response = match message {
+                DealerMessage::Position(j) => {
+                    let party = Party::new(&bp_gens, &pc_gens, v, blinding, n).unwrap();
+                    let (party, bit_commitment) = party.assign_position(j).unwrap();
+                    awaiting_bit_challenge = Some(party);
+                    awaiting_poly_challenge = None;
+                    PartyMessage::BitCommitment(bit_commitment)
+                }
+                DealerMessage::BitChallenge(bit_challenge) => {
+                    let party = awaiting_bit_challenge.take().unwrap();
+                    let (party, poly_commitment) = party.apply_challenge(&bit_challenge);
+                    awaiting_poly_challenge = Some(party);
+                    PartyMessage::PolyCommitment(poly_commitment)
+                }
+                DealerMessage::PolyChallenge(poly_challenge) => {
+                    let party = awaiting_poly_challenge.take().unwrap();
+                    PartyMessage::ProofShare(party.apply_challenge(&poly_challenge).unwrap())
+                }
+            };
+            if !silent {
+                channel.sender.send(response).unwrap();
+            }
+        }
+    }
+
+    /// Runs a session with four parties, of which parties 1 and 3 are
+    /// silent.
+    fn session_with_dropouts(
+        restart: bool,
+    ) -> Result<SessionOutcome, CoordinatorError<ChannelClosed>> {
+        let mut transport = ChannelTransport::new();
+        let mut threads = Vec::new();
+        for v in 0..4 {
+            let channel = transport.connect();
+            threads.push(thread::spawn(move || run_party(channel, v, v % 2 == 1)));
+        }
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 4);
+        let mut manager =
+            SessionManager::new(transport, Duration::from_millis(500)).restart_on_dropout(restart);
+        let mut transcript = Transcript::new(b"SessionManagerTest");
+        let outcome = manager.run(&bp_gens, &pc_gens, &mut transcript, 16);
+
+        drop(manager);
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        outcome
+    }
+
+    #[test]
+    fn session_aborts_on_dropout() {
+        match session_with_dropouts(false) {
+            Err(CoordinatorError::Timeout { missing }) => assert_eq!(missing, vec![1, 3]),
+            _ => panic!("The session did not time out"),
+        }
+    }
+
+    #[test]
+    fn session_restarts_without_dropped_parties() {
+        let outcome = session_with_dropouts(true).unwrap();
+        assert_eq!(outcome.parties, vec![0, 2]);
+        assert_eq!(outcome.dropped, vec![1, 3]);
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 4);
+        let value_commitments: Vec<_> = outcome
+            .parties
+            .iter()
+            .map(|&j| {
+                let v = j as u64;
+                pc_gens
+                    .commit(Scalar::from(v), Scalar::from(v + 1))
+                    .compress()
+            }).collect();
+        let mut transcript = Transcript::new(b"SessionManagerTest");
+        assert!(
+            outcome
+                .proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, 16)
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! The `threshold` module lets several shareholders, such as the
+//! cosigners of a multisig wallet, hold additive shares of the
+//! blinding factor of a single value in the aggregated multiparty
+//! computation protocol.
+//!
+//! The blinding factor \\(\tilde{v} = \sum\_i \tilde{v}\_i\\) only
+//! enters the proof linearly, through the value commitment
+//! \\(V\_j\\) and the evaluation \\(\tilde{t}\_x\\), so each
+//! shareholder can contribute its part separately:
+//!
+//! * a lead party, which knows the value \\(v\\), runs an ordinary
+//!   [`Party`](::aggregation::party::Party) with its own share of the
+//!   blinding factor, and computes the commitments to the bits of
+//!   \\(v\\);
+//! * each other shareholder runs a [`Shareholder`] with its share, and
+//!   sends a partial message for each round, for the same position as
+//!   the lead party;
+//! * the partial messages are added to the lead party's messages with
+//!   [`combine_bit_commitments`], [`combine_poly_commitments`] and
+//!   [`combine_proof_shares`], and the combined messages are sent to
+//!   the dealer as those of a single party.
+//!
+//! Each shareholder blinds its part of \\(\tilde{t}\_x\\) with its own
+//! polynomial blinding factors, so its partial proof share does not
+//! reveal its share of the blinding factor.  The lead party learns
+//! the value and the bit blinding factors, but none of the other
+//! shares, so no single shareholder can open \\(V\_j\\).
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand::{self, CryptoRng, RngCore};
+
+use clear_on_drop::clear::Clear;
+use errors::MPCError;
+use generators::PedersenGens;
+use util;
+
+use range_proof::messages::{
+    BitChallenge, BitCommitment, PolyChallenge, PolyCommitment, ProofShare,
+};
+
+/// The contribution of a [`Shareholder`] to the [`BitCommitment`] of
+/// its position.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PartialBitCommitment {
+    V_j: CompressedRistretto,
+}
+
+/// The contribution of a [`Shareholder`] to the [`PolyCommitment`] of
+/// its position.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PartialPolyCommitment {
+    T_1_j: RistrettoPoint,
+    T_2_j: RistrettoPoint,
+}
+
+/// The contribution of a [`Shareholder`] to the [`ProofShare`] of its
+/// position.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PartialProofShare {
+    t_x_blinding: Scalar,
+}
+
+/// Used to construct a shareholder of the blinding factor of a value.
+pub struct Shareholder {}
+
+impl Shareholder {
+    /// Constructs a `ShareholderAwaitingPosition` holding the share
+    /// `v_blinding_share` of the blinding factor.
+    pub fn new<'a>(
+        pc_gens: &'a PedersenGens,
+        v_blinding_share: Scalar,
+    ) -> ShareholderAwaitingPosition<'a> {
+        ShareholderAwaitingPosition {
+            pc_gens,
+            v_blinding_share,
+        }
+    }
+}
+
+/// A shareholder waiting for the position of the value.
+pub struct ShareholderAwaitingPosition<'a> {
+    pc_gens: &'a PedersenGens,
+    v_blinding_share: Scalar,
+}
+
+impl<'a> ShareholderAwaitingPosition<'a> {
+    /// Takes the position `j` of the lead party, and commits to the
+    /// share of the blinding factor.
+    pub fn assign_position(
+        self,
+        j: usize,
+    ) -> (ShareholderAwaitingBitChallenge<'a>, PartialBitCommitment) {
+        let V_j = (self.pc_gens.B_blinding * self.v_blinding_share).compress();
+        let next_state = ShareholderAwaitingBitChallenge {
+            pc_gens: self.pc_gens,
+            j,
+            v_blinding_share: self.v_blinding_share,
+        };
+        (next_state, PartialBitCommitment { V_j })
+    }
+}
+
+/// Overwrite secrets with null bytes when they go out of scope.
+impl<'a> Drop for ShareholderAwaitingPosition<'a> {
+    fn drop(&mut self) {
+        self.v_blinding_share.clear();
+    }
+}
+
+/// A shareholder which has committed to its share of the blinding
+/// factor, and is waiting for the [`BitChallenge`].
+pub struct ShareholderAwaitingBitChallenge<'a> {
+    pc_gens: &'a PedersenGens,
+    j: usize,
+    v_blinding_share: Scalar,
+}
+
+impl<'a> ShareholderAwaitingBitChallenge<'a> {
+    /// Receive a [`BitChallenge`] from the dealer and commit to the
+    /// blinding factors of the shareholder's part of the polynomial.
+    pub fn apply_challenge(
+        self,
+        vc: &BitChallenge,
+    ) -> (ShareholderAwaitingPolyChallenge, PartialPolyCommitment) {
+        self.apply_challenge_with_rng(vc, &mut rand::thread_rng())
+    }
+
+    /// Receive a [`BitChallenge`] from the dealer, sampling the
+    /// blinding factors from `rng`.
+    pub fn apply_challenge_with_rng<T: RngCore + CryptoRng>(
+        self,
+        vc: &BitChallenge,
+        rng: &mut T,
+    ) -> (ShareholderAwaitingPolyChallenge, PartialPolyCommitment) {
+        let t_1_blinding = Scalar::random(rng);
+        let t_2_blinding = Scalar::random(rng);
+        let offset_z = util::scalar_exp_vartime(&vc.z, self.j as u64);
+
+        let partial_commitment = PartialPolyCommitment {
+            T_1_j: self.pc_gens.B_blinding * t_1_blinding,
+            T_2_j: self.pc_gens.B_blinding * t_2_blinding,
+        };
+        let next_state = ShareholderAwaitingPolyChallenge {
+            z: vc.z,
+            offset_z,
+            v_blinding_share: self.v_blinding_share,
+            t_1_blinding,
+            t_2_blinding,
+        };
+        (next_state, partial_commitment)
+    }
+}
+
+/// Overwrite secrets with null bytes when they go out of scope.
+impl<'a> Drop for ShareholderAwaitingBitChallenge<'a> {
+    fn drop(&mut self) {
+        self.v_blinding_share.clear();
+    }
+}
+
+/// A shareholder which has committed to its polynomial blinding
+/// factors, and is waiting for the [`PolyChallenge`].
+pub struct ShareholderAwaitingPolyChallenge {
+    z: Scalar,
+    offset_z: Scalar,
+    v_blinding_share: Scalar,
+    t_1_blinding: Scalar,
+    t_2_blinding: Scalar,
+}
+
+impl ShareholderAwaitingPolyChallenge {
+    /// Receive a [`PolyChallenge`] from the dealer and compute the
+    /// shareholder's part of the proof share.
+    pub fn apply_challenge(self, pc: &PolyChallenge) -> Result<PartialProofShare, MPCError> {
+        // Prevent a malicious dealer from annihilating the blinding
+        // factors by supplying a zero challenge.
+        if pc.x == Scalar::zero() {
+            return Err(MPCError::MaliciousDealer);
+        }
+
+        let t_blinding_poly = util::Poly2(
+            self.z * self.z * self.offset_z * self.v_blinding_share,
+            self.t_1_blinding,
+            self.t_2_blinding,
+        );
+        Ok(PartialProofShare {
+            t_x_blinding: t_blinding_poly.eval(pc.x),
+        })
+    }
+}
+
+/// Overwrite secrets with null bytes when they go out of scope.
+impl Drop for ShareholderAwaitingPolyChallenge {
+    fn drop(&mut self) {
+        self.v_blinding_share.clear();
+        self.t_1_blinding.clear();
+        self.t_2_blinding.clear();
+    }
+}
+
+/// Adds the `partials` of the shareholders to the [`BitCommitment`]
+/// of the lead party.
+///
+/// Returns [`MPCError::InvalidPartialMessage`] if a value commitment
+/// is not a valid point.
+pub fn combine_bit_commitments(
+    bit_commitment: &BitCommitment,
+    partials: &[PartialBitCommitment],
+) -> Result<BitCommitment, MPCError> {
+    let mut V_j = bit_commitment
+        .V_j
+        .decompress()
+        .ok_or(MPCError::InvalidPartialMessage)?;
+    for partial in partials {
+        V_j += partial
+            .V_j
+            .decompress()
+            .ok_or(MPCError::InvalidPartialMessage)?;
+    }
+    Ok(BitCommitment {
+        V_j: V_j.compress(),
+        ..*bit_commitment
+    })
+}
+
+/// Adds the `partials` of the shareholders to the [`PolyCommitment`]
+/// of the lead party.
+pub fn combine_poly_commitments(
+    poly_commitment: &PolyCommitment,
+    partials: &[PartialPolyCommitment],
+) -> PolyCommitment {
+    PolyCommitment {
+        T_1_j: poly_commitment.T_1_j + partials.iter().map(|p| p.T_1_j).sum::<RistrettoPoint>(),
+        T_2_j: poly_commitment.T_2_j + partials.iter().map(|p| p.T_2_j).sum::<RistrettoPoint>(),
+    }
+}
+
+/// Adds the `partials` of the shareholders to the [`ProofShare`] of
+/// the lead party.
+pub fn combine_proof_shares(
+    proof_share: &ProofShare,
+    partials: &[PartialProofShare],
+) -> ProofShare {
+    let mut combined = proof_share.clone();
+    combined.t_x_blinding += partials.iter().map(|p| p.t_x_blinding).sum::<Scalar>();
+    combined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use merlin::Transcript;
+
+    use generators::BulletproofGens;
+    use range_proof::dealer::Dealer;
+    use range_proof::party::Party;
+
+    #[test]
+    fn aggregation_with_shared_blinding() {
+        let (n, m) = (32, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        // The value at position 0 has its blinding factor shared
+        // between the lead party and two shareholders
+        let shares: Vec<_> = (0..3).map(|_| Scalar::random(&mut rng)).collect();
+        let v_blinding: Scalar = shares.iter().sum();
+        let lead = Party::new(&bp_gens, &pc_gens, 1000, shares[0], n).unwrap();
+        let holders: Vec<_> = shares[1..]
+            .iter()
+            .map(|&share| Shareholder::new(&pc_gens, share))
+            .collect();
+        let other_blinding = Scalar::random(&mut rng);
+        let other = Party::new(&bp_gens, &pc_gens, 2000, other_blinding, n).unwrap();
+
+        let mut transcript = Transcript::new(b"ThresholdTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        let (lead, bit_commitment) = lead.assign_position(0).unwrap();
+        let (holders, partials): (Vec<_>, Vec<_>) =
+            holders.into_iter().map(|h| h.assign_position(0)).unzip();
+        let bit_commitment = combine_bit_commitments(&bit_commitment, &partials).unwrap();
+        let (other, other_bit_commitment) = other.assign_position(1).unwrap();
+        let (dealer, bit_challenge) = dealer
+            .receive_bit_commitments(vec![bit_commitment, other_bit_commitment])
+            .unwrap();
+
+        let (lead, poly_commitment) = lead.apply_challenge(&bit_challenge);
+        let (holders, partials): (Vec<_>, Vec<_>) = holders
+            .into_iter()
+            .map(|h| h.apply_challenge(&bit_challenge))
+            .unzip();
+        let poly_commitment = combine_poly_commitments(&poly_commitment, &partials);
+        let (other, other_poly_commitment) = other.apply_challenge(&bit_challenge);
+        let (dealer, poly_challenge) = dealer
+            .receive_poly_commitments(vec![poly_commitment, other_poly_commitment])
+            .unwrap();
+
+        let proof_share = lead.apply_challenge(&poly_challenge).unwrap();
+        let partials: Vec<_> = holders
+            .into_iter()
+            .map(|h| h.apply_challenge(&poly_challenge).unwrap())
+            .collect();
+        let proof_share = combine_proof_shares(&proof_share, &partials);
+        let other_proof_share = other.apply_challenge(&poly_challenge).unwrap();
+        let proof = dealer
+            .receive_shares(&[proof_share, other_proof_share])
+            .unwrap();
+
+        let value_commitments = vec![
+            pc_gens.commit(Scalar::from(1000u64), v_blinding).compress(),
+            pc_gens
+                .commit(Scalar::from(2000u64), other_blinding)
+                .compress(),
+        ];
+        let mut transcript = Transcript::new(b"ThresholdTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n)
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! The `transport` module decouples the aggregated multiparty
+//! computation protocol from the delivery of its messages.
+//!
+//! A [`Transport`] sends [`DealerMessage`]s to, and receives
+//! [`PartyMessage`]s from, the party at a given index, over any
+//! medium: in-process channels, TCP connections, a message queue...
+//! A [`Coordinator`] runs a dealer against a transport, assigning the
+//! party at index \\(j\\) the position \\(j\\), so that the round
+//! logic is written once for every transport.
+//!
+//! The [`ChannelTransport`] connects parties running on other threads
+//! of the same process.
+//!
+//! When the positions are assigned ahead of time, for instance by the
+//! order in which the parties joined, a coordinator with
+//! [`preassigned_positions`](Coordinator::preassigned_positions) does
+//! not send them, and each party sends its bit commitment as soon as
+//! it starts, saving the first round trip.  The bit and polynomial
+//! commitments still take a round trip each, since the polynomial
+//! commitments depend on the challenge derived from all the bit
+//! commitments.
+
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use errors::MPCError;
+use range_proof::dealer::DealerAwaitingBitCommitments;
+use range_proof::messages::{DealerMessage, PartyMessage};
+use range_proof::RangeProof;
+
+/// Delivers the messages of the protocol between the dealer and the
+/// parties.
+pub trait Transport {
+    /// The error returned when a message cannot be delivered.
+    type Error;
+
+    /// Returns the number of parties reachable over the transport,
+    /// which are indexed from \\(0\\).
+    fn num_parties(&self) -> usize;
+
+    /// Sends a `message` to the party at index `party`.
+    fn send(&mut self, party: usize, message: DealerMessage) -> Result<(), Self::Error>;
+
+    /// Receives the next message from the party at index `party`,
+    /// blocking until it arrives.
+    fn receive(&mut self, party: usize) -> Result<PartyMessage, Self::Error>;
+}
+
+/// A [`Transport`] which can stop waiting for a message at a
+/// deadline, as needed by a
+/// [`SessionManager`](::aggregation::session_manager::SessionManager).
+pub trait TimedTransport: Transport {
+    /// Receives the next message from the party at index `party`, or
+    /// returns `None` if it does not arrive before the `deadline`.
+    fn receive_until(
+        &mut self,
+        party: usize,
+        deadline: Instant,
+    ) -> Result<Option<PartyMessage>, Self::Error>;
+}
+
+/// An error of a [`Coordinator`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CoordinatorError<E> {
+    /// The protocol failed, for instance because of a malformed
+    /// proof share.
+    Protocol(MPCError),
+    /// The transport failed to deliver a message to or from a party.
+    Transport {
+        /// The index of the party
+        party: usize,
+        /// The error of the transport
+        error: E,
+    },
+    /// A party sent a message of another round.
+    UnexpectedMessage {
+        /// The index of the party
+        party: usize,
+    },
+    /// Some parties did not respond before the deadline of a round.
+    Timeout {
+        /// The indices of the parties which did not respond
+        missing: Vec<usize>,
+    },
+}
+
+impl<E> From<MPCError> for CoordinatorError<E> {
+    fn from(e: MPCError) -> CoordinatorError<E> {
+        CoordinatorError::Protocol(e)
+    }
+}
+
+/// Runs a dealer against a [`Transport`].
+pub struct Coordinator<T: Transport> {
+    transport: T,
+    preassigned: bool,
+}
+
+impl<T: Transport> Coordinator<T> {
+    /// Creates a coordinator over the `transport`, which sends each
+    /// party its position.
+    pub fn new(transport: T) -> Coordinator<T> {
+        Coordinator {
+            transport,
+            preassigned: false,
+        }
+    }
+
+    /// Sets whether the parties know their positions in advance, the
+    /// party at index \\(j\\) taking the position \\(j\\).  If so,
+    /// the coordinator does not send the positions, and waits for the
+    /// bit commitments right away.
+    pub fn preassigned_positions(mut self, preassigned: bool) -> Coordinator<T> {
+        self.preassigned = preassigned;
+        self
+    }
+
+    /// Returns the transport, for instance to run another
+    /// aggregation with the same parties.
+    pub fn into_transport(self) -> T {
+        self.transport
+    }
+
+    /// Runs the protocol with the `dealer`: sends every party its
+    /// position, unless the positions are preassigned, and the two
+    /// challenges, collects the commitments of each round, and returns
+    /// the proof aggregated from the proof shares.
+    ///
+    /// The number of parties of the transport must be the number of
+    /// parties of the dealer.
+    pub fn run(
+        &mut self,
+        dealer: DealerAwaitingBitCommitments,
+    ) -> Result<RangeProof, CoordinatorError<T::Error>> {
+        let m = self.transport.num_parties();
+
+        if !self.preassigned {
+            for j in 0..m {
+                self.send(j, DealerMessage::Position(j))?;
+            }
+        }
+        let bit_commitments = self.collect(PartyMessage::bit_commitment)?;
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
+
+        for j in 0..m {
+            self.send(j, DealerMessage::BitChallenge(bit_challenge))?;
+        }
+        let poly_commitments = self.collect(PartyMessage::poly_commitment)?;
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
+
+        for j in 0..m {
+            self.send(j, DealerMessage::PolyChallenge(poly_challenge))?;
+        }
+        let proof_shares = self.collect(PartyMessage::proof_share)?;
+        Ok(dealer.receive_shares(&proof_shares)?)
+    }
+
+    fn send(&mut self, j: usize, message: DealerMessage) -> Result<(), CoordinatorError<T::Error>> {
+        self.transport
+            .send(j, message)
+            .map_err(|error| CoordinatorError::Transport { party: j, error })
+    }
+
+    /// Receives one message from every party, and extracts its content
+    /// with `extract`, which returns `None` for a message of another
+    /// round.
+    fn collect<U>(
+        &mut self,
+        extract: fn(PartyMessage) -> Option<U>,
+    ) -> Result<Vec<U>, CoordinatorError<T::Error>> {
+        (0..self.transport.num_parties())
+            .map(|j| {
+                let message = self
+                    .transport
+                    .receive(j)
+                    .map_err(|error| CoordinatorError::Transport { party: j, error })?;
+                extract(message).ok_or(CoordinatorError::UnexpectedMessage { party: j })
+            }).collect()
+    }
+}
+
+/// The end of a [`ChannelTransport`] held by a party.
+pub struct PartyChannel {
+    /// Sends messages to the dealer
+    pub sender: Sender<PartyMessage>,
+    /// Receives messages from the dealer
+    pub receiver: Receiver<DealerMessage>,
+}
+
+/// A [`Transport`] over in-process channels.
+#[derive(Default)]
+pub struct ChannelTransport {
+    senders: Vec<Sender<DealerMessage>>,
+    receivers: Vec<Receiver<PartyMessage>>,
+}
+
+/// This error occurs when the other end of a [`ChannelTransport`]
+/// was dropped.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ChannelClosed;
+
+impl ChannelTransport {
+    /// Creates a transport without any party.
+    pub fn new() -> ChannelTransport {
+        ChannelTransport::default()
+    }
+
+    /// Connects the next party, and returns its end of the channels.
+    pub fn connect(&mut self) -> PartyChannel {
+        let (to_party, from_dealer) = mpsc::channel();
+        let (to_dealer, from_party) = mpsc::channel();
+        self.senders.push(to_party);
+        self.receivers.push(from_party);
+        PartyChannel {
+            sender: to_dealer,
+            receiver: from_dealer,
+        }
+    }
+}
+
+impl Transport for ChannelTransport {
+    type Error = ChannelClosed;
+
+    fn num_parties(&self) -> usize {
+        self.senders.len()
+    }
+
+    fn send(&mut self, party: usize, message: DealerMessage) -> Result<(), ChannelClosed> {
+        self.senders[party].send(message).map_err(|_| ChannelClosed)
+    }
+
+    fn receive(&mut self, party: usize) -> Result<PartyMessage, ChannelClosed> {
+        self.receivers[party].recv().map_err(|_| ChannelClosed)
+    }
+}
+
+impl TimedTransport for ChannelTransport {
+    fn receive_until(
+        &mut self,
+        party: usize,
+        deadline: Instant,
+    ) -> Result<Option<PartyMessage>, ChannelClosed> {
+        let now = Instant::now();
+        let timeout = if now < deadline {
+            deadline - now
+        } else {
+            Duration::from_secs(0)
+        };
+        match self.receivers[party].recv_timeout(timeout) {
+            Ok(message) => Ok(Some(message)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(ChannelClosed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    use curve25519_dalek::scalar::Scalar;
+    use merlin::Transcript;
+    use rand;
+
+    use generators::{BulletproofGens, PedersenGens};
+    use range_proof::dealer::Dealer;
+    use range_proof::party::Party;
+
+    /// Runs a party over its end of a [`ChannelTransport`], waiting
+    /// for its position unless it is `preassigned`.
+    fn run_party(
+        channel: PartyChannel,
+        v: u64,
+        blinding: Scalar,
+        n: usize,
+        m: usize,
+        preassigned: Option<usize>,
+    ) {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let party = Party::new(&bp_gens, &pc_gens, v, blinding, n).unwrap();
+        let receive = || channel.receiver.recv().unwrap();
+
+        let j = preassigned.unwrap_or_else(|| receive().position().unwrap());
+        let (party, bit_commitment) = party.assign_position(j).unwrap();
+        let message = PartyMessage::BitCommitment(bit_commitment);
+        channel.sender.send(message).unwrap();
+
+        let bit_challenge = receive().bit_challenge().unwrap();
+        let (party, poly_commitment) = party.apply_challenge(&bit_challenge);
+        let message = PartyMessage::PolyCommitment(poly_commitment);
+        channel.sender.send(message).unwrap();
+
+        let poly_challenge = receive().poly_challenge().unwrap();
+        let proof_share = party.apply_challenge(&poly_challenge).unwrap();
+        let message = PartyMessage::ProofShare(proof_share);
+        channel.sender.send(message).unwrap();
+    }
+
+    /// Aggregates the values of four parties over a [`ChannelTransport`].
+    fn aggregate_over_channel_transport(preassigned: bool) {
+        let (n, m) = (16, 4);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = rand::thread_rng();
+
+        let mut transport = ChannelTransport::new();
+        let mut value_commitments = Vec::new();
+        let mut threads = Vec::new();
+        for v in 0..m as u64 {
+            let blinding = Scalar::random(&mut rng);
+            value_commitments.push(pc_gens.commit(Scalar::from(v), blinding).compress());
+            let channel = transport.connect();
+            let position = if preassigned { Some(v as usize) } else { None };
+            threads.push(thread::spawn(move || {
+                run_party(channel, v, blinding, n, m, position)
+            }));
+        }
+
+        let mut transcript = Transcript::new(b"TransportTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        let proof = Coordinator::new(transport)
+            .preassigned_positions(preassigned)
+            .run(dealer)
+            .unwrap();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let mut transcript = Transcript::new(b"TransportTest");
+        assert!(
+            proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn aggregation_over_channel_transport() {
+        aggregate_over_channel_transport(false);
+    }
+
+    #[test]
+    fn aggregation_with_preassigned_positions() {
+        aggregate_over_channel_transport(true);
+    }
+
+    #[test]
+    fn coordinator_reports_closed_channel() {
+        let (n, m) = (16, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        let mut transport = ChannelTransport::new();
+        let channel = transport.connect();
+        let party = thread::spawn(move || run_party(channel, 1, Scalar::one(), n, m, None));
+        // The second party hangs up before the protocol starts
+        drop(transport.connect());
+
+        let mut transcript = Transcript::new(b"TransportTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        let result = Coordinator::new(transport).run(dealer);
+        assert_eq!(
+            result.err(),
+            Some(CoordinatorError::Transport {
+                party: 1,
+                error: ChannelClosed,
+            })
+        );
+        // The first party is left waiting for its bit challenge
+        assert!(party.join().is_err());
+    }
+}
@@ -0,0 +1,500 @@
+//! The `range_proof_plus` module contains an API for range proofs
+//! using the weighted inner-product argument of Bulletproofs+.
+
+#![allow(non_snake_case)]
+
+use std::iter;
+
+use clear_on_drop::clear::Clear;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{IsIdentity, MultiscalarMul};
+use merlin::Transcript;
+use rand;
+
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use math;
+use multiscalar;
+use range_proof::padded_powers_of_2;
+use transcript::TranscriptProtocol;
+use util;
+use weighted_inner_product_proof::WeightedInnerProductProof;
+
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A proof that one or more committed values are in a range, using
+/// the Bulletproofs+ protocol.
+///
+/// A `RangeProofPlus` proves the same statement as a
+/// [`RangeProof`](::RangeProof), with the same [`BulletproofGens`],
+/// [`PedersenGens`] and transcript, but replaces the inner-product
+/// argument by a zero-knowledge weighted inner-product argument.  The
+/// prover no longer sends \\(S\\), \\(T\_1\\), \\(T\_2\\), \\(t(x)\\)
+/// and its blinding factors, so the proof is \\(2 \lg n + 6\\)
+/// 32-byte elements, or 96 bytes less than a `RangeProof`.
+///
+/// The two proofs are not interchangeable: a `RangeProofPlus` must be
+/// verified with [`RangeProofPlus::verify_multiple`].
+#[derive(Clone, Debug)]
+pub struct RangeProofPlus {
+    /// Commitment to the bits of the value
+    A: CompressedRistretto,
+    /// Weighted inner-product proof for the blinded bit vectors
+    wip_proof: WeightedInnerProductProof,
+}
+
+impl RangeProofPlus {
+    /// Create a rangeproof for a given pair of value `v` and
+    /// blinding scalar `v_blinding`.
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProofPlus::prove_multiple`] for the `m=1` case.
+    pub fn prove_single(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+    ) -> Result<(RangeProofPlus, CompressedRistretto), ProofError> {
+        let (p, Vs) =
+            RangeProofPlus::prove_multiple(bp_gens, pc_gens, transcript, &[v], &[*v_blinding], n)?;
+        Ok((p, Vs[0]))
+    }
+
+    /// Create a rangeproof for a set of values.
+    ///
+    /// The parameters are the same as for
+    /// [`RangeProof::prove_multiple`](::RangeProof::prove_multiple):
+    /// the number of values must be a power of two, and the bitsize
+    /// `n` must be between \\(1\\) and \\(64\\).  Unlike the
+    /// `RangeProof`, the proof is created by a single prover, without
+    /// the MPC protocol.
+    pub fn prove_multiple(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<(RangeProofPlus, Vec<CompressedRistretto>), ProofError> {
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+        let m = values.len();
+        if n == 0 || n > 64 {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if !m.is_power_of_two() {
+            return Err(ProofError::InvalidAggregation);
+        }
+        let padded_n = n.next_power_of_two();
+        if bp_gens.gens_capacity < padded_n || bp_gens.party_capacity < m {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        if n < 64 && values.iter().any(|&v| v >> n != 0) {
+            return Err(ProofError::InvalidRange);
+        }
+
+        transcript.rangeproof_plus_domain_sep(n as u64, m as u64);
+        for salt in bp_gens.salts() {
+            transcript.generators_salt(salt);
+        }
+
+        let value_commitments: Vec<CompressedRistretto> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(&v, &v_blinding)| pc_gens.commit(v.into(), v_blinding).compress())
+            .collect();
+        for V in value_commitments.iter() {
+            transcript.commit_point(b"V", V);
+        }
+
+        let mut rng = blindings
+            .iter()
+            .fold(transcript.build_rng(), |builder, v_blinding| {
+                builder.commit_witness_bytes(b"v_blinding", v_blinding.as_bytes())
+            }).finalize(&mut rand::thread_rng());
+
+        // The bits of value j are at positions j*padded_n..(j+1)*padded_n,
+        // and the padding bits are zero.
+        let nm = padded_n * m;
+        let mut a_L = Vec::with_capacity(nm);
+        for &v in values.iter() {
+            for i in 0..padded_n {
+                if i < n {
+                    a_L.push(Scalar::from((v >> i) & 1));
+                } else {
+                    a_L.push(Scalar::zero());
+                }
+            }
+        }
+        let mut a_R: Vec<Scalar> = a_L.iter().map(|a_L_i| a_L_i - Scalar::one()).collect();
+
+        let mut alpha = Scalar::random(&mut rng);
+        let A = RistrettoPoint::multiscalar_mul(
+            a_L.iter().chain(a_R.iter()).chain(iter::once(&alpha)),
+            bp_gens
+                .G(padded_n, m)
+                .chain(bp_gens.H(padded_n, m))
+                .chain(iter::once(&pc_gens.B_blinding)),
+        ).compress();
+
+        transcript.commit_point(b"A", &A);
+
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+
+        let d = weights(n, m, &z);
+        let y_nm_1 = util::scalar_exp_vartime(&y, (nm + 1) as u64);
+
+        // Blind the bit vectors with z, and fold the weights into the
+        // right vector in reverse order of the powers of y.
+        let powers_of_y: Vec<Scalar> = math::powers(y, nm).collect();
+        let a_L_hat: Vec<Scalar> = a_L.iter().map(|a_L_i| a_L_i - z).collect();
+        let a_R_hat: Vec<Scalar> = a_R
+            .iter()
+            .zip(d.iter())
+            .zip(powers_of_y.iter().rev())
+            .map(|((a_R_i, d_i), exp_y)| a_R_i + z + d_i * exp_y * y)
+            .collect();
+        let mut weighted_blindings = math::powers(z * z, m)
+            .zip(blindings.iter())
+            .map(|(exp_zz, v_blinding)| exp_zz * z * z * v_blinding)
+            .fold(Scalar::zero(), |acc, x| acc + x);
+        let mut alpha_hat = alpha + y_nm_1 * weighted_blindings;
+
+        let wip_proof = WeightedInnerProductProof::create(
+            transcript,
+            &mut rng,
+            &pc_gens.B,
+            &pc_gens.B_blinding,
+            bp_gens.G(padded_n, m).cloned().collect(),
+            bp_gens.H(padded_n, m).cloned().collect(),
+            a_L_hat,
+            a_R_hat,
+            alpha_hat,
+            &y,
+        );
+
+        // Overwrite the bits of the values and the blinding factors
+        // with null bytes.  The blinded vectors are cleared by the
+        // weighted inner-product argument.
+        for e in a_L.iter_mut().chain(a_R.iter_mut()) {
+            e.clear();
+        }
+        alpha.clear();
+        weighted_blindings.clear();
+        alpha_hat.clear();
+
+        Ok((RangeProofPlus { A, wip_proof }, value_commitments))
+    }
+
+    /// Verifies a rangeproof for a given value commitment \\(V\\).
+    ///
+    /// This is a convenience wrapper around
+    /// [`RangeProofPlus::verify_multiple`] for the `m=1` case.
+    pub fn verify_single(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple(bp_gens, pc_gens, transcript, &[*V], n)
+    }
+
+    /// Verifies an aggregated rangeproof for the given value
+    /// commitments.
+    pub fn verify_multiple(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        let m = value_commitments.len();
+
+        if n == 0 || n > 64 {
+            return Err(ProofError::InvalidBitsize);
+        }
+        let padded_n = n.next_power_of_two();
+        if bp_gens.gens_capacity < padded_n || bp_gens.party_capacity < m {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        if !m.is_power_of_two() || padded_n * m != 1 << self.wip_proof.rounds() {
+            return Err(ProofError::ProofSizeMismatch);
+        }
+        let nm = padded_n * m;
+
+        transcript.rangeproof_plus_domain_sep(n as u64, m as u64);
+        for salt in bp_gens.salts() {
+            transcript.generators_salt(salt);
+        }
+
+        for V in value_commitments.iter() {
+            transcript.commit_point(b"V", V);
+        }
+        transcript.commit_point(b"A", &self.A);
+
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+        let zz = z * z;
+
+        let scalars = self.wip_proof.verification_scalars(nm, transcript)?;
+        let (e, ee) = (scalars.e, scalars.e * scalars.e);
+        let (r, s, delta) = (self.wip_proof.r, self.wip_proof.s, self.wip_proof.delta);
+
+        let d = weights(n, m, &z);
+        let y_nm_1 = util::scalar_exp_vartime(&y, (nm + 1) as u64);
+        let sum_z_2j: Scalar = math::powers(zz, m)
+            .map(|exp_zz| exp_zz * zz)
+            .fold(Scalar::zero(), |acc, x| acc + x);
+        let zeta = (z - zz) * y * math::sum_of_powers(&y, nm)
+            - z * y_nm_1 * math::sum_of_powers_of_2(n) * sum_z_2j;
+
+        let powers_of_y: Vec<Scalar> = math::powers(y, nm).collect();
+        let g = scalars
+            .s
+            .iter()
+            .zip(math::exp_iter(y.invert()))
+            .map(|(s_i, exp_y_inv)| -ee * z - r * e * exp_y_inv * s_i);
+        let h = scalars
+            .s
+            .iter()
+            .rev()
+            .zip(d.iter())
+            .zip(powers_of_y.iter().rev())
+            .map(|((s_i_inv, d_i), exp_y)| ee * (z + d_i * exp_y * y) - s * e * s_i_inv);
+
+        let mega_check = multiscalar::optional_multiscalar_mul(
+            iter::once(ee)
+                .chain(math::powers(zz, m).map(|exp_zz| ee * y_nm_1 * exp_zz * zz))
+                .chain(scalars.challenges_sq.iter().map(|e_sq| ee * e_sq))
+                .chain(scalars.challenges_inv_sq.iter().map(|e_inv_sq| ee * e_inv_sq))
+                .chain(iter::once(e))
+                .chain(iter::once(Scalar::one()))
+                .chain(iter::once(ee * zeta - r * y * s))
+                .chain(iter::once(-delta))
+                .chain(g)
+                .chain(h),
+            iter::once(self.A.decompress())
+                .chain(value_commitments.iter().map(|V| V.decompress()))
+                .chain(self.wip_proof.lr_points().map(|(L, _)| L.decompress()))
+                .chain(self.wip_proof.lr_points().map(|(_, R)| R.decompress()))
+                .chain(iter::once(self.wip_proof.A.decompress()))
+                .chain(iter::once(self.wip_proof.B.decompress()))
+                .chain(iter::once(Some(pc_gens.B)))
+                .chain(iter::once(Some(pc_gens.B_blinding)))
+                .chain(bp_gens.G(padded_n, m).map(|&G_i| Some(G_i)))
+                .chain(bp_gens.H(padded_n, m).map(|&H_i| Some(H_i))),
+        ).ok_or_else(|| ProofError::VerificationError)?;
+
+        if mega_check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Serializes the proof into a byte array of \\(2 \lg n + 6\\)
+    /// 32-byte elements, where \\(n\\) is the padded bitsize times
+    /// the number of values.  The layout of the proof is:
+    /// * a compressed Ristretto point \\(A\\),
+    /// * the encoding of the weighted inner-product proof.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(32 + self.wip_proof.serialized_size());
+        buf.extend_from_slice(self.A.as_bytes());
+        buf.extend_from_slice(&self.wip_proof.to_bytes());
+        buf
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `RangeProofPlus`.
+    pub fn from_bytes(slice: &[u8]) -> Result<RangeProofPlus, ProofError> {
+        if slice.len() < 32 {
+            return Err(ProofError::FormatError);
+        }
+        let A = CompressedRistretto(util::read32(&slice[0..]));
+        let wip_proof = WeightedInnerProductProof::from_bytes(&slice[32..])?;
+        Ok(RangeProofPlus { A, wip_proof })
+    }
+}
+
+impl Serialize for RangeProofPlus {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for RangeProofPlus {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct RangeProofPlusVisitor;
+
+        impl<'de> Visitor<'de> for RangeProofPlusVisitor {
+            type Value = RangeProofPlus;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("a valid RangeProofPlus")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<RangeProofPlus, E>
+            where
+                E: serde::de::Error,
+            {
+                RangeProofPlus::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(RangeProofPlusVisitor)
+    }
+}
+
+/// Compute the weights
+/// \\(d\_{j \cdot n + i} = z^{2(j+1)} \cdot 2^i\\) of the bits of
+/// each value, with zero weights for the padding bits.
+fn weights(n: usize, m: usize, z: &Scalar) -> Vec<Scalar> {
+    let zz = z * z;
+    let powers_of_2: Vec<Scalar> = padded_powers_of_2(n).collect();
+    math::powers(zz, m)
+        .flat_map(|exp_zz| powers_of_2.iter().map(move |exp_2| exp_2 * exp_zz * zz))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bincode;
+    use rand::Rng;
+
+    use range_proof::RangeProof;
+
+    fn create_and_verify_helper(n: usize, m: usize) {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 8);
+
+        let (proof_bytes, value_commitments) = {
+            let mut rng = rand::thread_rng();
+
+            let (min, max) = (0u64, ((1u128 << n) - 1) as u64);
+            let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min, max)).collect();
+            let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+            let (proof, value_commitments) = RangeProofPlus::prove_multiple(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"RangeProofPlusTest"),
+                &values,
+                &blindings,
+                n,
+            ).unwrap();
+
+            (bincode::serialize(&proof).unwrap(), value_commitments)
+        };
+
+        let proof: RangeProofPlus = bincode::deserialize(&proof_bytes).unwrap();
+
+        assert!(
+            proof
+                .verify_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"RangeProofPlusTest"),
+                    &value_commitments,
+                    n,
+                ).is_ok()
+        );
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_1() {
+        create_and_verify_helper(32, 1);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_4() {
+        create_and_verify_helper(64, 4);
+    }
+
+    #[test]
+    fn create_and_verify_n_10_m_2() {
+        create_and_verify_helper(10, 2);
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let v_blinding = Scalar::random(&mut rand::thread_rng());
+
+        assert_eq!(
+            RangeProofPlus::prove_single(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"RangeProofPlusTest"),
+                1 << 8,
+                &v_blinding,
+                8,
+            ).map(|_| ()),
+            Err(ProofError::InvalidRange)
+        );
+
+        // A proof for a 16-bit value does not verify as an 8-bit proof
+        let (proof, V) = RangeProofPlus::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"RangeProofPlusTest"),
+            1 << 8,
+            &v_blinding,
+            16,
+        ).unwrap();
+        assert!(
+            proof
+                .verify_single(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"RangeProofPlusTest"),
+                    &V,
+                    8,
+                ).is_err()
+        );
+    }
+
+    #[test]
+    fn proof_is_smaller_than_rangeproof() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let v_blinding = Scalar::random(&mut rand::thread_rng());
+
+        let (proof, _) = RangeProofPlus::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"RangeProofPlusTest"),
+            12345,
+            &v_blinding,
+            64,
+        ).unwrap();
+        let (rangeproof, _) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"RangeProofPlusTest"),
+            12345,
+            &v_blinding,
+            64,
+        ).unwrap();
+
+        assert_eq!(proof.to_bytes().len() + 96, rangeproof.to_bytes().len());
+    }
+}
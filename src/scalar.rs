@@ -0,0 +1,365 @@
+//! The `scalar` module contains helpers for working with vectors of
+//! [`Scalar`]s and their byte encodings, and the [`ScalarVec`] type.
+
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Add, AddAssign, Deref, DerefMut, Mul, MulAssign, Sub, SubAssign};
+
+use clear_on_drop::clear::Clear;
+use curve25519_dalek::scalar::Scalar;
+use rand::{CryptoRng, RngCore};
+
+use errors::ProofError;
+use math;
+
+/// Converts a `u128` into a `Scalar`.
+///
+/// Since \\(2^{128}\\) is smaller than the group order, the conversion
+/// is injective.
+pub fn from_u128(x: u128) -> Scalar {
+    let mut bytes = [0u8; 32];
+    for i in 0..16 {
+        bytes[i] = (x >> (8 * i)) as u8;
+    }
+    Scalar::from_bits(bytes)
+}
+
+/// Converts a `Scalar` into a `u128`, or returns `None` if it is not
+/// smaller than \\(2^{128}\\).
+pub fn to_u128(x: &Scalar) -> Option<u128> {
+    let bytes = x.as_bytes();
+    if bytes[16..].iter().any(|&b| b != 0) {
+        return None;
+    }
+    Some((0..16).fold(0u128, |acc, i| acc | ((bytes[i] as u128) << (8 * i))))
+}
+
+/// Replaces each of the `scalars` by its inverse, using Montgomery's
+/// trick to compute all of them with a single inversion, and returns
+/// the inverse of their product.
+///
+/// Panics if any of the `scalars` is zero.
+pub fn batch_invert(scalars: &mut [Scalar]) -> Scalar {
+    Scalar::batch_invert(scalars)
+}
+
+/// Returns a vector of `n` uniformly random scalars.
+pub fn random_vec<R: RngCore + CryptoRng>(rng: &mut R, n: usize) -> Vec<Scalar> {
+    (0..n).map(|_| Scalar::random(rng)).collect()
+}
+
+/// Decodes a canonical 32-byte encoding of a scalar.
+///
+/// Returns `ProofError::FormatError` if `slice` is not 32 bytes long or
+/// is not the canonical encoding of a scalar.
+pub fn decode(slice: &[u8]) -> Result<Scalar, ProofError> {
+    if slice.len() != 32 {
+        return Err(ProofError::FormatError);
+    }
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(slice);
+    Scalar::from_canonical_bytes(bytes).ok_or(ProofError::FormatError)
+}
+
+/// Decodes the scalar at position `index` of a slice of 32-byte
+/// elements.
+///
+/// Returns `ProofError::InvalidScalar` if the element is not the
+/// canonical encoding of a scalar.
+///
+/// # Panics
+///
+/// Panics if `slice` is shorter than `32 * (index + 1)` bytes.
+pub fn decode_element(slice: &[u8], index: usize) -> Result<Scalar, ProofError> {
+    decode(&slice[index * 32..(index + 1) * 32]).map_err(|_| ProofError::InvalidScalar { index })
+}
+
+/// Encodes `scalars` as the concatenation of their 32-byte canonical
+/// encodings.
+pub fn encode_vec(scalars: &[Scalar]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(scalars.len() * 32);
+    for s in scalars {
+        buf.extend_from_slice(s.as_bytes());
+    }
+    buf
+}
+
+/// Decodes a vector of scalars encoded by [`encode_vec`].
+///
+/// Returns `ProofError::FormatError` if the length of `slice` is not a
+/// multiple of 32 or if any of the encodings is not canonical.
+pub fn decode_vec(slice: &[u8]) -> Result<Vec<Scalar>, ProofError> {
+    if slice.len() % 32 != 0 {
+        return Err(ProofError::FormatError);
+    }
+    slice.chunks(32).map(decode).collect()
+}
+
+/// A vector of scalars with elementwise arithmetic.
+///
+/// `+`, `-` and `*` between two `ScalarVec`s act elementwise, and `*`
+/// by a `Scalar` multiplies every element.  The operators which take
+/// the left operand by value, and the `*Assign` operators, reuse its
+/// allocation.  All operations on two vectors panic if their lengths
+/// differ.
+///
+/// The elements are overwritten with zeros when the vector is dropped,
+/// since the prover uses it for secret values.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ScalarVec(Vec<Scalar>);
+
+impl ScalarVec {
+    /// Creates a vector of `n` zeros.
+    pub fn zero(n: usize) -> ScalarVec {
+        ScalarVec(vec![Scalar::zero(); n])
+    }
+
+    /// Creates a vector of `n` copies of `s`.
+    pub fn splat(s: Scalar, n: usize) -> ScalarVec {
+        ScalarVec(vec![s; n])
+    }
+
+    /// Computes the inner product
+    /// \\({\langle {\mathbf{a}}, {\mathbf{b}} \rangle} = \sum\_{i} a\_i \cdot b\_i\\)
+    /// of `self` and `rhs`.
+    pub fn inner_product(&self, rhs: &ScalarVec) -> Scalar {
+        math::inner_product(self, rhs)
+    }
+
+    /// Returns the Hadamard (elementwise) product of `self` and `rhs`.
+    pub fn hadamard(&self, rhs: &ScalarVec) -> ScalarVec {
+        self.clone() * rhs
+    }
+
+    /// Replaces `self` by its Hadamard product with `rhs`.
+    pub fn hadamard_assign(&mut self, rhs: &ScalarVec) {
+        *self *= rhs;
+    }
+
+    /// Returns the elements as a `Vec`.
+    pub fn into_vec(mut self) -> Vec<Scalar> {
+        mem::replace(&mut self.0, Vec::new())
+    }
+}
+
+fn check_lengths(a: &ScalarVec, b: &ScalarVec) {
+    if a.len() != b.len() {
+        panic!("ScalarVec: lengths of vectors do not match");
+    }
+}
+
+impl From<Vec<Scalar>> for ScalarVec {
+    fn from(v: Vec<Scalar>) -> ScalarVec {
+        ScalarVec(v)
+    }
+}
+
+impl FromIterator<Scalar> for ScalarVec {
+    fn from_iter<I: IntoIterator<Item = Scalar>>(iter: I) -> ScalarVec {
+        ScalarVec(iter.into_iter().collect())
+    }
+}
+
+impl Deref for ScalarVec {
+    type Target = [Scalar];
+
+    fn deref(&self) -> &[Scalar] {
+        &self.0
+    }
+}
+
+impl DerefMut for ScalarVec {
+    fn deref_mut(&mut self) -> &mut [Scalar] {
+        &mut self.0
+    }
+}
+
+impl<'a> AddAssign<&'a ScalarVec> for ScalarVec {
+    fn add_assign(&mut self, rhs: &'a ScalarVec) {
+        check_lengths(self, rhs);
+        for (a_i, b_i) in self.iter_mut().zip(rhs.iter()) {
+            *a_i += b_i;
+        }
+    }
+}
+
+impl<'a> SubAssign<&'a ScalarVec> for ScalarVec {
+    fn sub_assign(&mut self, rhs: &'a ScalarVec) {
+        check_lengths(self, rhs);
+        for (a_i, b_i) in self.iter_mut().zip(rhs.iter()) {
+            *a_i -= b_i;
+        }
+    }
+}
+
+impl<'a> MulAssign<&'a ScalarVec> for ScalarVec {
+    fn mul_assign(&mut self, rhs: &'a ScalarVec) {
+        check_lengths(self, rhs);
+        for (a_i, b_i) in self.iter_mut().zip(rhs.iter()) {
+            *a_i *= b_i;
+        }
+    }
+}
+
+impl MulAssign<Scalar> for ScalarVec {
+    fn mul_assign(&mut self, rhs: Scalar) {
+        for a_i in self.iter_mut() {
+            *a_i *= rhs;
+        }
+    }
+}
+
+impl<'a> Add<&'a ScalarVec> for ScalarVec {
+    type Output = ScalarVec;
+
+    fn add(mut self, rhs: &'a ScalarVec) -> ScalarVec {
+        self += rhs;
+        self
+    }
+}
+
+impl<'a, 'b> Add<&'b ScalarVec> for &'a ScalarVec {
+    type Output = ScalarVec;
+
+    fn add(self, rhs: &'b ScalarVec) -> ScalarVec {
+        self.clone() + rhs
+    }
+}
+
+impl<'a> Sub<&'a ScalarVec> for ScalarVec {
+    type Output = ScalarVec;
+
+    fn sub(mut self, rhs: &'a ScalarVec) -> ScalarVec {
+        self -= rhs;
+        self
+    }
+}
+
+impl<'a, 'b> Sub<&'b ScalarVec> for &'a ScalarVec {
+    type Output = ScalarVec;
+
+    fn sub(self, rhs: &'b ScalarVec) -> ScalarVec {
+        self.clone() - rhs
+    }
+}
+
+impl<'a> Mul<&'a ScalarVec> for ScalarVec {
+    type Output = ScalarVec;
+
+    fn mul(mut self, rhs: &'a ScalarVec) -> ScalarVec {
+        self *= rhs;
+        self
+    }
+}
+
+impl<'a, 'b> Mul<&'b ScalarVec> for &'a ScalarVec {
+    type Output = ScalarVec;
+
+    fn mul(self, rhs: &'b ScalarVec) -> ScalarVec {
+        self.clone() * rhs
+    }
+}
+
+impl Mul<Scalar> for ScalarVec {
+    type Output = ScalarVec;
+
+    fn mul(mut self, rhs: Scalar) -> ScalarVec {
+        self *= rhs;
+        self
+    }
+}
+
+impl<'a> Mul<Scalar> for &'a ScalarVec {
+    type Output = ScalarVec;
+
+    fn mul(self, rhs: Scalar) -> ScalarVec {
+        self.clone() * rhs
+    }
+}
+
+impl Drop for ScalarVec {
+    fn drop(&mut self) {
+        for e in self.0.iter_mut() {
+            e.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand;
+
+    #[test]
+    fn u128_conversion() {
+        assert_eq!(from_u128(0), Scalar::zero());
+        assert_eq!(from_u128(1234567), Scalar::from(1234567u64));
+
+        let x = (5u128 << 64) + 7;
+        let expected = Scalar::from(5u64) * Scalar::from(1u64 << 32) * Scalar::from(1u64 << 32)
+            + Scalar::from(7u64);
+        assert_eq!(from_u128(x), expected);
+        assert_eq!(to_u128(&expected), Some(x));
+        let max = u128::max_value();
+        assert_eq!(to_u128(&from_u128(max)), Some(max));
+        assert_eq!(to_u128(&-Scalar::one()), None);
+    }
+
+    #[test]
+    fn batch_inversion() {
+        let mut rng = rand::thread_rng();
+        let scalars = random_vec(&mut rng, 8);
+
+        let mut inverses = scalars.clone();
+        let product_inv = batch_invert(&mut inverses);
+
+        for (s, s_inv) in scalars.iter().zip(inverses.iter()) {
+            assert_eq!(s * s_inv, Scalar::one());
+        }
+        let product: Scalar = scalars.iter().product();
+        assert_eq!(product * product_inv, Scalar::one());
+    }
+
+    #[test]
+    fn scalar_vec_arithmetic() {
+        let a: ScalarVec = (1..5u64).map(Scalar::from).collect();
+        let b: ScalarVec = (2..6u64).map(Scalar::from).collect();
+
+        let sum: ScalarVec = [3u64, 5, 7, 9].iter().map(|&x| Scalar::from(x)).collect();
+        let diff = ScalarVec::splat(-Scalar::one(), 4);
+        let prod: ScalarVec = [2u64, 6, 12, 20].iter().map(|&x| Scalar::from(x)).collect();
+
+        assert_eq!(&a + &b, sum);
+        assert_eq!(&a - &b, diff);
+        assert_eq!(&a * &b, prod);
+        assert_eq!(a.hadamard(&b), prod);
+        assert_eq!(&a * Scalar::from(2u64), &a + &a);
+        assert_eq!(a.inner_product(&b), Scalar::from(40u64));
+
+        let mut c = a.clone();
+        c.hadamard_assign(&b);
+        c -= &prod;
+        assert_eq!(c, ScalarVec::zero(4));
+    }
+
+    #[test]
+    #[should_panic]
+    fn scalar_vec_length_mismatch() {
+        let _ = ScalarVec::zero(3) + &ScalarVec::zero(4);
+    }
+
+    #[test]
+    fn encoding_roundtrip() {
+        let mut rng = rand::thread_rng();
+        let scalars = random_vec(&mut rng, 5);
+
+        let bytes = encode_vec(&scalars);
+        assert_eq!(bytes.len(), 5 * 32);
+        assert_eq!(decode_vec(&bytes).unwrap(), scalars);
+
+        assert_eq!(decode_vec(&bytes[1..]), Err(ProofError::FormatError));
+        assert_eq!(decode(&[0xff; 32]), Err(ProofError::FormatError));
+        assert_eq!(decode(&[0u8; 31]), Err(ProofError::FormatError));
+    }
+}
@@ -0,0 +1,562 @@
+//! The `sigma` module contains sigma protocols about Pedersen
+//! commitments, made non-interactive with a Merlin transcript.
+//!
+//! The proofs commit their statement to the transcript they are given,
+//! so they can share a transcript with range proofs or other proofs of
+//! a larger protocol, binding all of them together.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+use merlin::Transcript;
+use rand;
+use std::iter;
+
+use commitment::{Commitment, Opening};
+use errors::ProofError;
+use generators::PedersenGens;
+use scalar;
+use transcript::TranscriptProtocol;
+use util::read32;
+
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A proof that two commitments \\(C\_1\\) and \\(C\_2\\) open to the
+/// same value, possibly with different blinding factors.
+///
+/// Since \\(C\_1 - C\_2 = (\tilde{v}\_1 - \tilde{v}\_2) B\_{blinding}\\)
+/// exactly when the values are equal, this is a Schnorr proof of
+/// knowledge of the discrete logarithm of \\(C\_1 - C\_2\\) with
+/// respect to \\(B\_{blinding}\\).  The verifier checks
+/// \\[
+///     s B\_{blinding} = R + c (C\_1 - C\_2),
+/// \\]
+/// where \\(c\\) is the challenge derived from the transcript.
+#[derive(Copy, Clone, Debug)]
+pub struct EqualityProof {
+    /// Commitment \\(R = k B\_{blinding}\\) to the nonce \\(k\\)
+    pub(crate) R: CompressedRistretto,
+    /// Response \\(s = k + c (\tilde{v}\_1 - \tilde{v}\_2)\\)
+    pub(crate) s: Scalar,
+}
+
+impl EqualityProof {
+    /// Creates a proof that the commitments to `v` with blinding
+    /// factors `blinding_1` and `blinding_2` open to the same value,
+    /// returning the proof and the two commitments.
+    pub fn prove(
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        blinding_1: &Scalar,
+        blinding_2: &Scalar,
+    ) -> (EqualityProof, CompressedRistretto, CompressedRistretto) {
+        let C_1 = pc_gens.commit(Scalar::from(v), *blinding_1).compress();
+        let C_2 = pc_gens.commit(Scalar::from(v), *blinding_2).compress();
+
+        transcript.equality_domain_sep();
+        transcript.commit_point(b"C_1", &C_1);
+        transcript.commit_point(b"C_2", &C_2);
+
+        let (R, s) = prove_blinding(pc_gens, transcript, &(blinding_1 - blinding_2));
+        (EqualityProof { R, s }, C_1, C_2)
+    }
+
+    /// Verifies that the commitments `C_1` and `C_2` open to the same
+    /// value.
+    pub fn verify(
+        &self,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        C_1: &CompressedRistretto,
+        C_2: &CompressedRistretto,
+    ) -> Result<(), ProofError> {
+        let c = self.challenge(transcript, C_1, C_2);
+
+        let C_1 = C_1.decompress().ok_or(ProofError::FormatError)?;
+        let C_2 = C_2.decompress().ok_or(ProofError::FormatError)?;
+        check_blinding(pc_gens, &self.R, &self.s, &c, &(C_1 - C_2))
+    }
+
+    /// Replays the proof on the `transcript` for the commitments `C_1`
+    /// and `C_2`, and returns the challenge \\(c\\).
+    pub(crate) fn challenge(
+        &self,
+        transcript: &mut Transcript,
+        C_1: &CompressedRistretto,
+        C_2: &CompressedRistretto,
+    ) -> Scalar {
+        transcript.equality_domain_sep();
+        transcript.commit_point(b"C_1", C_1);
+        transcript.commit_point(b"C_2", C_2);
+        transcript.commit_point(b"R", &self.R);
+
+        transcript.challenge_scalar(b"c")
+    }
+
+    /// Serializes the proof into a 64-byte array.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(self.R.as_bytes());
+        buf[32..].copy_from_slice(self.s.as_bytes());
+        buf
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into an
+    /// `EqualityProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<EqualityProof, ProofError> {
+        if slice.len() != 64 {
+            return Err(ProofError::FormatError);
+        }
+        let R = CompressedRistretto(read32(&slice[..32]));
+        let s = scalar::decode(&slice[32..])?;
+        Ok(EqualityProof { R, s })
+    }
+}
+
+impl Serialize for EqualityProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for EqualityProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct EqualityProofVisitor;
+
+        impl<'de> Visitor<'de> for EqualityProofVisitor {
+            type Value = EqualityProof;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("a valid EqualityProof")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<EqualityProof, E>
+            where
+                E: serde::de::Error,
+            {
+                EqualityProof::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(EqualityProofVisitor)
+    }
+}
+
+/// A proof that a transaction balances, that is, that the values of
+/// its input commitments \\(I\_i\\) equal the values of its output
+/// commitments \\(O\_j\\) plus a public fee \\(f\\).
+///
+/// The excess
+/// \\[
+///     E = \sum\_i I\_i - \sum\_j O\_j - f B
+/// \\]
+/// commits to zero exactly when the transaction balances, so that it
+/// is a multiple of \\(B\_{blinding}\\).  The proof is a Schnorr proof
+/// of knowledge of its discrete logarithm, and the verifier checks
+/// \\[
+///     s B\_{blinding} = R + c E.
+/// \\]
+/// Range proofs for the outputs can be created on the same
+/// transcript, after the balance proof.
+#[derive(Copy, Clone, Debug)]
+pub struct BalanceProof {
+    /// Commitment \\(R = k B\_{blinding}\\) to the nonce \\(k\\)
+    pub(crate) R: CompressedRistretto,
+    /// Response \\(s = k + c \tilde{e}\\), where \\(\tilde{e}\\) is
+    /// the blinding factor of the excess
+    pub(crate) s: Scalar,
+}
+
+impl BalanceProof {
+    /// Creates a proof that the `inputs` balance the `outputs` and the
+    /// `fee`, returning the proof and the excess commitment \\(E\\).
+    ///
+    /// Returns [`ProofError::Unbalanced`] if the values of the inputs
+    /// are not equal to the values of the outputs plus the fee.
+    pub fn prove(
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        inputs: &[Opening],
+        outputs: &[Opening],
+        fee: u64,
+    ) -> Result<(BalanceProof, Commitment), ProofError> {
+        let zero = Opening::new(0, Scalar::zero());
+        let input_sum = inputs.iter().fold(zero.clone(), |sum, input| &sum + input);
+        let output_sum = outputs.iter().fold(zero, |sum, output| &sum + output);
+        let excess = input_sum - output_sum - Opening::new(fee, Scalar::zero());
+        if excess.value != Scalar::zero() {
+            return Err(ProofError::Unbalanced);
+        }
+
+        transcript.balance_domain_sep(inputs.len() as u64, outputs.len() as u64);
+        for input in inputs {
+            transcript.commit_point(b"I", &pc_gens.commit_opening(input).compress());
+        }
+        for output in outputs {
+            transcript.commit_point(b"O", &pc_gens.commit_opening(output).compress());
+        }
+        transcript.commit_scalar(b"fee", &Scalar::from(fee));
+
+        let E = pc_gens.commit_opening(&excess);
+        transcript.commit_point(b"E", &E.compress());
+
+        let (R, s) = prove_blinding(pc_gens, transcript, &excess.blinding);
+        Ok((BalanceProof { R, s }, E))
+    }
+
+    /// Verifies that the `inputs` balance the `outputs` and the `fee`.
+    pub fn verify(
+        &self,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        inputs: &[CompressedRistretto],
+        outputs: &[CompressedRistretto],
+        fee: u64,
+    ) -> Result<(), ProofError> {
+        let E = RistrettoPoint::optional_multiscalar_mul(
+            iter::once(-Scalar::from(fee))
+                .chain(inputs.iter().map(|_| Scalar::one()))
+                .chain(outputs.iter().map(|_| -Scalar::one())),
+            iter::once(Some(pc_gens.B))
+                .chain(inputs.iter().map(|I| I.decompress()))
+                .chain(outputs.iter().map(|O| O.decompress())),
+        ).ok_or(ProofError::FormatError)?;
+
+        let c = self.challenge(transcript, inputs, outputs, fee, &E.compress());
+        check_blinding(pc_gens, &self.R, &self.s, &c, &E)
+    }
+
+    /// Replays the proof on the `transcript` for the excess
+    /// commitment `E`, and returns the challenge \\(c\\).
+    ///
+    /// The caller must check that `E` is the excess of the `inputs`,
+    /// `outputs` and `fee`.
+    pub(crate) fn challenge(
+        &self,
+        transcript: &mut Transcript,
+        inputs: &[CompressedRistretto],
+        outputs: &[CompressedRistretto],
+        fee: u64,
+        E: &CompressedRistretto,
+    ) -> Scalar {
+        transcript.balance_domain_sep(inputs.len() as u64, outputs.len() as u64);
+        for I in inputs {
+            transcript.commit_point(b"I", I);
+        }
+        for O in outputs {
+            transcript.commit_point(b"O", O);
+        }
+        transcript.commit_scalar(b"fee", &Scalar::from(fee));
+        transcript.commit_point(b"E", E);
+        transcript.commit_point(b"R", &self.R);
+
+        transcript.challenge_scalar(b"c")
+    }
+
+    /// Serializes the proof into a 64-byte array.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(self.R.as_bytes());
+        buf[32..].copy_from_slice(self.s.as_bytes());
+        buf
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a
+    /// `BalanceProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<BalanceProof, ProofError> {
+        if slice.len() != 64 {
+            return Err(ProofError::FormatError);
+        }
+        let R = CompressedRistretto(read32(&slice[..32]));
+        let s = scalar::decode(&slice[32..])?;
+        Ok(BalanceProof { R, s })
+    }
+}
+
+impl Serialize for BalanceProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for BalanceProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BalanceProofVisitor;
+
+        impl<'de> Visitor<'de> for BalanceProofVisitor {
+            type Value = BalanceProof;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("a valid BalanceProof")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<BalanceProof, E>
+            where
+                E: serde::de::Error,
+            {
+                BalanceProof::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(BalanceProofVisitor)
+    }
+}
+
+/// Creates a Schnorr proof \\((R, s)\\) of knowledge of `x` such that
+/// the statement \\(P = x B\_{blinding}\\), which the caller has
+/// committed to the `transcript`, holds.
+fn prove_blinding(
+    pc_gens: &PedersenGens,
+    transcript: &mut Transcript,
+    x: &Scalar,
+) -> (CompressedRistretto, Scalar) {
+    let mut rng = transcript
+        .build_rng()
+        .commit_witness_bytes(b"x", x.as_bytes())
+        .finalize(&mut rand::thread_rng());
+    let k = Scalar::random(&mut rng);
+    let R = (k * pc_gens.B_blinding).compress();
+    transcript.commit_point(b"R", &R);
+
+    let c = transcript.challenge_scalar(b"c");
+    (R, k + c * x)
+}
+
+/// Checks a Schnorr proof \\((R, s)\\) created by [`prove_blinding`]
+/// for the statement \\(P = x B\_{blinding}\\) and the challenge `c`.
+fn check_blinding(
+    pc_gens: &PedersenGens,
+    R: &CompressedRistretto,
+    s: &Scalar,
+    c: &Scalar,
+    P: &RistrettoPoint,
+) -> Result<(), ProofError> {
+    // s B_blinding - R - c P = 0
+    let check = RistrettoPoint::optional_multiscalar_mul(
+        &[*s, -Scalar::one(), -c],
+        vec![Some(pc_gens.B_blinding), R.decompress(), Some(*P)],
+    ).ok_or(ProofError::FormatError)?;
+
+    if check.is_identity() {
+        Ok(())
+    } else {
+        Err(ProofError::VerificationError)
+    }
+}
+
+/// Creates a one-out-of-\\(n\\) Schnorr proof \\((c\_i, s\_i)\\) of
+/// knowledge of `x` such that \\(D\_{index} = x B\_{blinding}\\), for
+/// statements `D` which the caller has committed to the `transcript`.
+///
+/// The prover simulates the proofs for the other statements, and the
+/// challenges \\(c\_i\\) must add up to the challenge \\(c\\) derived
+/// from the transcript, with
+/// \\[
+///     R\_i = s\_i B\_{blinding} - c\_i D\_i.
+/// \\]
+/// Returns [`ProofError::CommitmentMismatch`] if `index` is out of
+/// range or the statement does not hold.
+pub(crate) fn prove_one_of_many(
+    pc_gens: &PedersenGens,
+    transcript: &mut Transcript,
+    D: &[RistrettoPoint],
+    index: usize,
+    x: &Scalar,
+) -> Result<(Vec<Scalar>, Vec<Scalar>), ProofError> {
+    let n = D.len();
+    if index >= n || D[index] != x * pc_gens.B_blinding {
+        return Err(ProofError::CommitmentMismatch);
+    }
+
+    let mut rng = transcript
+        .build_rng()
+        .commit_witness_bytes(b"x", x.as_bytes())
+        .finalize(&mut rand::thread_rng());
+    let mut c = scalar::random_vec(&mut rng, n);
+    let mut s = scalar::random_vec(&mut rng, n);
+    let k = Scalar::random(&mut rng);
+
+    for i in 0..n {
+        let R_i = if i == index {
+            k * pc_gens.B_blinding
+        } else {
+            s[i] * pc_gens.B_blinding - c[i] * D[i]
+        };
+        transcript.commit_point(b"R", &R_i.compress());
+    }
+
+    let challenge = transcript.challenge_scalar(b"c");
+    let simulated: Scalar = (0..n).filter(|&i| i != index).map(|i| c[i]).sum();
+    c[index] = challenge - simulated;
+    s[index] = k + c[index] * x;
+
+    Ok((c, s))
+}
+
+/// Checks a one-out-of-\\(n\\) proof \\((c\_i, s\_i)\\) created by
+/// [`prove_one_of_many`] for the statements `D`.
+pub(crate) fn verify_one_of_many(
+    pc_gens: &PedersenGens,
+    transcript: &mut Transcript,
+    D: &[RistrettoPoint],
+    c: &[Scalar],
+    s: &[Scalar],
+) -> Result<(), ProofError> {
+    if c.len() != D.len() || s.len() != D.len() {
+        return Err(ProofError::ProofSizeMismatch);
+    }
+
+    for ((c_i, s_i), D_i) in c.iter().zip(s).zip(D) {
+        let R_i =
+            RistrettoPoint::vartime_multiscalar_mul(&[*s_i, -c_i], &[pc_gens.B_blinding, *D_i]);
+        transcript.commit_point(b"R", &R_i.compress());
+    }
+
+    let challenge = transcript.challenge_scalar(b"c");
+    if c.iter().sum::<Scalar>() == challenge {
+        Ok(())
+    } else {
+        Err(ProofError::VerificationError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use generators::BulletproofGens;
+    use range_proof::RangeProof;
+
+    #[test]
+    fn prove_and_verify_equality() {
+        let pc_gens = PedersenGens::default();
+        let mut rng = rand::thread_rng();
+        let (blinding_1, blinding_2) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+
+        let mut transcript = Transcript::new(b"EqualityTest");
+        let (proof, C_1, C_2) =
+            EqualityProof::prove(&pc_gens, &mut transcript, 37, &blinding_1, &blinding_2);
+        let proof = EqualityProof::from_bytes(&proof.to_bytes()).unwrap();
+
+        let mut transcript = Transcript::new(b"EqualityTest");
+        assert!(
+            proof
+                .verify(&pc_gens, &mut transcript, &C_1, &C_2)
+                .is_ok()
+        );
+
+        let C_3 = pc_gens.commit(Scalar::from(38u64), blinding_2).compress();
+        let mut transcript = Transcript::new(b"EqualityTest");
+        assert_eq!(
+            proof.verify(&pc_gens, &mut transcript, &C_1, &C_3),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn equality_composes_with_range_proof() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+        let (blinding_1, blinding_2) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+
+        let mut transcript = Transcript::new(b"ComposedTest");
+        let (equality, C_1, C_2) =
+            EqualityProof::prove(&pc_gens, &mut transcript, 37, &blinding_1, &blinding_2);
+        let (range_proof, _) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 37, &blinding_2, 32)
+                .unwrap();
+
+        let mut transcript = Transcript::new(b"ComposedTest");
+        assert!(
+            equality
+                .verify(&pc_gens, &mut transcript, &C_1, &C_2)
+                .is_ok()
+        );
+        assert!(
+            range_proof
+                .verify_single(&bp_gens, &pc_gens, &mut transcript, &C_2, 32)
+                .is_ok()
+        );
+
+        // The range proof is bound to the equality proof before it
+        let mut transcript = Transcript::new(b"ComposedTest");
+        assert!(
+            range_proof
+                .verify_single(&bp_gens, &pc_gens, &mut transcript, &C_2, 32)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn balance_composes_with_range_proofs() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+
+        let inputs = [
+            Opening::new(60, Scalar::random(&mut rng)),
+            Opening::new(45, Scalar::random(&mut rng)),
+        ];
+        let outputs = [
+            Opening::new(80, Scalar::random(&mut rng)),
+            Opening::new(22, Scalar::random(&mut rng)),
+        ];
+        let input_commitments: Vec<_> = inputs
+            .iter()
+            .map(|input| pc_gens.commit_opening(input).compress())
+            .collect();
+
+        let mut transcript = Transcript::new(b"BalanceTest");
+        let (balance, E) =
+            BalanceProof::prove(&pc_gens, &mut transcript, &inputs, &outputs, 3).unwrap();
+        let (range_proof, output_commitments) =
+            RangeProof::prove_openings(&bp_gens, &pc_gens, &mut transcript, &outputs, 32).unwrap();
+        let balance = BalanceProof::from_bytes(&balance.to_bytes()).unwrap();
+        let excess = (&inputs[0] + &inputs[1]) - (&outputs[0] + &outputs[1]);
+        assert!(pc_gens.opens_to(&E, &(excess - Opening::new(3, Scalar::zero()))));
+
+        let (I, O) = (&input_commitments, &output_commitments);
+        let mut transcript = Transcript::new(b"BalanceTest");
+        assert!(
+            balance
+                .verify(&pc_gens, &mut transcript, I, O, 3)
+                .is_ok()
+        );
+        assert!(
+            range_proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, O, 32)
+                .is_ok()
+        );
+
+        let mut transcript = Transcript::new(b"BalanceTest");
+        assert_eq!(
+            balance.verify(&pc_gens, &mut transcript, I, O, 4),
+            Err(ProofError::VerificationError)
+        );
+
+        let mut transcript = Transcript::new(b"BalanceTest");
+        let result = BalanceProof::prove(&pc_gens, &mut transcript, &inputs, &outputs, 4);
+        assert_eq!(result.unwrap_err(), ProofError::Unbalanced);
+    }
+}
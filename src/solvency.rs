@@ -0,0 +1,227 @@
+//! The `solvency` module contains a proof of liabilities, with which
+//! an exchange can publish its total liabilities to its customers
+//! without revealing their balances.
+//!
+//! The exchange commits to the balance of each account, and publishes
+//! the commitments together with a commitment to the total.  Each
+//! customer receives the opening of the commitment to their own
+//! balance, and checks that it is included.  The [`SolvencyProof`]
+//! shows that every balance is in range, so that no negative balance
+//! can reduce the total, and that the commitments add up to the
+//! committed total.  The total commitment can then be compared to a
+//! proof of the exchange's assets.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use merlin::Transcript;
+use rand;
+
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::{BatchTerms, RangeProof};
+use sigma::EqualityProof;
+use transcript::TranscriptProtocol;
+
+/// A proof that committed account balances are in the range
+/// \\([0, 2^n)\\) and add up to a committed total.
+///
+/// The accounts are split into chunks, each with an aggregated range
+/// proof, so that the size of the generators does not grow with the
+/// number of accounts.  The chunks are as large as the
+/// `party_capacity` of the generators allows, rounded down to a power
+/// of two, and the account commitments are padded with commitments to
+/// zero to a whole number of chunks.
+///
+/// Since the sum \\(\sum\_i V\_i\\) of the account commitments is a
+/// commitment to the sum of the balances, an [`EqualityProof`] shows
+/// that it opens to the same value as the total commitment.
+///
+/// The verifier checks the range proofs of all chunks and the
+/// equality proof with a single multiscalar multiplication.
+#[derive(Clone, Debug)]
+pub struct SolvencyProof {
+    range_proofs: Vec<RangeProof>,
+    equality_proof: EqualityProof,
+}
+
+impl SolvencyProof {
+    /// Creates a proof that the `balances`, committed with the
+    /// `blindings`, are `n`-bit values which add up to the total
+    /// committed with `total_blinding`.
+    ///
+    /// Returns the proof, the account commitments, and the total
+    /// commitment.  The account commitments start with the
+    /// commitments to the `balances`, in order, followed by the
+    /// padding commitments to zero.
+    ///
+    /// Returns [`ProofError::InvalidRange`] if the total does not fit
+    /// in a `u64`.
+    pub fn prove(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        balances: &[u64],
+        blindings: &[Scalar],
+        total_blinding: &Scalar,
+        n: usize,
+    ) -> Result<(SolvencyProof, Vec<CompressedRistretto>, CompressedRistretto), ProofError> {
+        if balances.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+        if bp_gens.party_capacity == 0 {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        // The largest power of two not above the party capacity
+        let chunk_size = (bp_gens.party_capacity / 2 + 1).next_power_of_two();
+        let total = balances
+            .iter()
+            .try_fold(0u64, |total, &v| total.checked_add(v))
+            .ok_or(ProofError::InvalidRange)?;
+
+        // Pad the accounts to a whole number of chunks
+        let num_chunks = (balances.len() + chunk_size - 1) / chunk_size;
+        let mut rng = rand::thread_rng();
+        let mut values = balances.to_vec();
+        let mut blindings = blindings.to_vec();
+        values.resize(num_chunks * chunk_size, 0);
+        while blindings.len() < values.len() {
+            blindings.push(Scalar::random(&mut rng));
+        }
+
+        transcript.solvency_domain_sep(values.len() as u64, chunk_size as u64);
+
+        let mut range_proofs = Vec::with_capacity(num_chunks);
+        let mut account_commitments = Vec::with_capacity(values.len());
+        for (v, v_blinding) in values.chunks(chunk_size).zip(blindings.chunks(chunk_size)) {
+            let (proof, commitments) =
+                RangeProof::prove_multiple(bp_gens, pc_gens, transcript, v, v_blinding, n)?;
+            range_proofs.push(proof);
+            account_commitments.extend(commitments);
+        }
+
+        let blinding_sum: Scalar = blindings.iter().sum();
+        let (equality_proof, _, total_commitment) =
+            EqualityProof::prove(pc_gens, transcript, total, &blinding_sum, total_blinding);
+
+        let proof = SolvencyProof {
+            range_proofs,
+            equality_proof,
+        };
+        Ok((proof, account_commitments, total_commitment))
+    }
+
+    /// Verifies that the values committed in the
+    /// `account_commitments` are `n`-bit values which add up to the
+    /// value committed in `total_commitment`.
+    pub fn verify(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        account_commitments: &[CompressedRistretto],
+        total_commitment: &CompressedRistretto,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        let num_chunks = self.range_proofs.len();
+        if num_chunks == 0 || account_commitments.len() % num_chunks != 0 {
+            return Err(ProofError::ProofSizeMismatch);
+        }
+        let chunk_size = account_commitments.len() / num_chunks;
+
+        transcript.solvency_domain_sep(account_commitments.len() as u64, chunk_size as u64);
+
+        let mut rng = rand::thread_rng();
+        let mut batch = BatchTerms::new();
+        let chunks = account_commitments.chunks(chunk_size);
+        for (proof, commitments) in self.range_proofs.iter().zip(chunks) {
+            let terms = proof.verification_terms(bp_gens, transcript, commitments, n)?;
+            batch.add_terms(&terms, Scalar::random(&mut rng));
+        }
+
+        let mut account_sum = RistrettoPoint::identity();
+        for V in account_commitments {
+            account_sum += V.decompress().ok_or(ProofError::FormatError)?;
+        }
+        let account_sum = account_sum.compress();
+        let c = self
+            .equality_proof
+            .challenge(transcript, &account_sum, total_commitment);
+
+        // w (s B_blinding - R - c sum(V) + c T) = 0
+        let w = Scalar::random(&mut rng);
+        batch.B_blinding_scalar += w * self.equality_proof.s;
+        batch.add_point(-w, self.equality_proof.R);
+        for V in account_commitments {
+            batch.add_point(-w * c, *V);
+        }
+        batch.add_point(w * c, *total_commitment);
+
+        batch.verify(bp_gens, pc_gens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use scalar;
+
+    #[test]
+    fn prove_and_verify_liabilities() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 4);
+        let mut rng = rand::thread_rng();
+
+        let balances = [120u64, 0, 4000, 7, 35, 1 << 31];
+        let blindings = scalar::random_vec(&mut rng, balances.len());
+        let total_blinding = Scalar::random(&mut rng);
+
+        let mut transcript = Transcript::new(b"SolvencyTest");
+        let (proof, accounts, T) = SolvencyProof::prove(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &balances,
+            &blindings,
+            &total_blinding,
+            32,
+        ).unwrap();
+        assert_eq!(accounts.len(), 8);
+        let V_2 = pc_gens.commit(Scalar::from(4000u64), blindings[2]);
+        assert_eq!(accounts[2], V_2.compress());
+        let total = balances.iter().sum::<u64>();
+        let expected = pc_gens.commit(Scalar::from(total), total_blinding);
+        assert_eq!(T, expected.compress());
+
+        let mut transcript = Transcript::new(b"SolvencyTest");
+        assert!(
+            proof
+                .verify(&bp_gens, &pc_gens, &mut transcript, &accounts, &T, 32)
+                .is_ok()
+        );
+
+        // Hiding an account changes the total
+        let mut transcript = Transcript::new(b"SolvencyTest");
+        let mut hidden = accounts.clone();
+        hidden[3] = pc_gens.commit(Scalar::zero(), blindings[3]).compress();
+        assert!(
+            proof
+                .verify(&bp_gens, &pc_gens, &mut transcript, &hidden, &T, 32)
+                .is_err()
+        );
+
+        // A smaller total does not verify
+        let smaller = pc_gens
+            .commit(Scalar::from(total - 1), total_blinding)
+            .compress();
+        let mut transcript = Transcript::new(b"SolvencyTest");
+        assert!(
+            proof
+                .verify(&bp_gens, &pc_gens, &mut transcript, &accounts, &smaller, 32)
+                .is_err()
+        );
+    }
+}
@@ -5,11 +5,84 @@ use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::scalar::Scalar;
 use merlin::Transcript;
 
+/// Extends a Merlin transcript with the operations used by the
+/// Bulletproofs protocols.
+///
+/// This can be used by protocols composed with Bulletproofs to commit
+/// points and scalars, and to derive challenge scalars, in the same
+/// way as the proofs in this crate.
 pub trait TranscriptProtocol {
     /// Commit a domain separator for an `n`-bit, `m`-party range proof.
     fn rangeproof_domain_sep(&mut self, n: u64, m: u64);
+    /// Commit a `salt` applied to the Bulletproofs generators.
+    fn generators_salt(&mut self, salt: &[u8]);
+    /// Commit a `nonce` chosen by the verifier.
+    fn verifier_nonce(&mut self, nonce: &[u8; 32]);
+    /// Commit a domain separator for the metadata of `m` three-term
+    /// commitments.
+    fn metadata_domain_sep(&mut self, m: u64);
+    /// Commit a domain separator for a proof that a value lies in the
+    /// range `[min, max]`.
+    fn range_bounds_domain_sep(&mut self, min: u64, max: u64);
     /// Commit a domain separator for a length-`n` inner product proof.
     fn innerproduct_domain_sep(&mut self, n: u64);
+    /// Commit a domain separator for an `n`-bit, `m`-party
+    /// Bulletproofs+ range proof.
+    fn rangeproof_plus_domain_sep(&mut self, n: u64, m: u64);
+    /// Commit a domain separator for a length-`n` weighted inner
+    /// product proof.
+    fn weighted_innerproduct_domain_sep(&mut self, n: u64);
+    /// Commit a domain separator for a value opening proof.
+    fn value_opening_domain_sep(&mut self);
+    /// Commit a domain separator for a proof that one committed value
+    /// is at most another.
+    fn comparison_domain_sep(&mut self);
+    /// Commit a domain separator for a proof that two commitments
+    /// open to the same value.
+    fn equality_domain_sep(&mut self);
+    /// Commit a domain separator for a balance proof with `inputs`
+    /// input and `outputs` output commitments.
+    fn balance_domain_sep(&mut self, inputs: u64, outputs: u64);
+    /// Commit a domain separator for a surjection proof over `n`
+    /// input asset tags.
+    fn surjection_domain_sep(&mut self, n: u64);
+    /// Commit a domain separator for a solvency proof over `m`
+    /// accounts, in chunks of `chunk_size` accounts.
+    fn solvency_domain_sep(&mut self, m: u64, chunk_size: u64);
+    /// Commit a domain separator for deriving the nonces of a
+    /// rewindable range proof.
+    fn rewind_domain_sep(&mut self);
+    /// Commit a domain separator for an aggregated multiparty
+    /// computation session of `m` parties, with the session `nonce`.
+    fn mpc_session_domain_sep(&mut self, nonce: &[u8; 32], m: u64);
+    /// Commit the `identity` of the party at position `j` of an
+    /// aggregated multiparty computation session.
+    fn mpc_party_identity(&mut self, j: u64, identity: &[u8]);
+    /// Commit a domain separator for a proof of membership in a set
+    /// of `n` elements.
+    fn membership_domain_sep(&mut self, n: u64);
+    /// Commit a domain separator for a shuffle of `k` tuples of `m`
+    /// values.
+    fn shuffle_domain_sep(&mut self, k: u64, m: u64);
+    /// Commit a domain separator for a constraint system proof.
+    fn r1cs_domain_sep(&mut self);
+    /// Commit the number `m` of external commitments of a constraint
+    /// system proof.
+    fn r1cs_num_commitments(&mut self, m: u64);
+    /// Commit a domain separator for a constraint system proof
+    /// without randomized constraints.
+    fn r1cs_1phase_domain_sep(&mut self);
+    /// Commit a domain separator for a constraint system proof with
+    /// randomized constraints.
+    fn r1cs_2phase_domain_sep(&mut self);
+    /// Commit a domain separator for a proof of an external circuit
+    /// with `k` instance variables, `m` witness variables and `q`
+    /// constraints.
+    fn external_circuit_domain_sep(&mut self, k: u64, m: u64, q: u64);
+    /// Commit a domain separator for an arithmetic circuit proof with
+    /// `n` multiplication gates, `m` committed values and `q` linear
+    /// constraints.
+    fn circuit_domain_sep(&mut self, n: u64, m: u64, q: u64);
     /// Commit a `scalar` with the given `label`.
     fn commit_scalar(&mut self, label: &'static [u8], scalar: &Scalar);
     /// Commit a `point` with the given `label`.
@@ -31,11 +104,126 @@ impl TranscriptProtocol for Transcript {
         self.commit_bytes(b"m", &le_u64(m));
     }
 
+    fn generators_salt(&mut self, salt: &[u8]) {
+        self.commit_bytes(b"gens-salt", salt);
+    }
+
+    fn verifier_nonce(&mut self, nonce: &[u8; 32]) {
+        self.commit_bytes(b"verifier-nonce", nonce);
+    }
+
+    fn metadata_domain_sep(&mut self, m: u64) {
+        self.commit_bytes(b"dom-sep", b"metadata v1");
+        self.commit_bytes(b"m", &le_u64(m));
+    }
+
+    fn range_bounds_domain_sep(&mut self, min: u64, max: u64) {
+        self.commit_bytes(b"dom-sep", b"range bounds v1");
+        self.commit_bytes(b"min", &le_u64(min));
+        self.commit_bytes(b"max", &le_u64(max));
+    }
+
     fn innerproduct_domain_sep(&mut self, n: u64) {
         self.commit_bytes(b"dom-sep", b"ipp v1");
         self.commit_bytes(b"n", &le_u64(n));
     }
 
+    fn rangeproof_plus_domain_sep(&mut self, n: u64, m: u64) {
+        self.commit_bytes(b"dom-sep", b"rangeproof+ v1");
+        self.commit_bytes(b"n", &le_u64(n));
+        self.commit_bytes(b"m", &le_u64(m));
+    }
+
+    fn weighted_innerproduct_domain_sep(&mut self, n: u64) {
+        self.commit_bytes(b"dom-sep", b"wip v1");
+        self.commit_bytes(b"n", &le_u64(n));
+    }
+
+    fn value_opening_domain_sep(&mut self) {
+        self.commit_bytes(b"dom-sep", b"value opening v1");
+    }
+
+    fn comparison_domain_sep(&mut self) {
+        self.commit_bytes(b"dom-sep", b"comparison v1");
+    }
+
+    fn equality_domain_sep(&mut self) {
+        self.commit_bytes(b"dom-sep", b"equality v1");
+    }
+
+    fn balance_domain_sep(&mut self, inputs: u64, outputs: u64) {
+        self.commit_bytes(b"dom-sep", b"balance v1");
+        self.commit_bytes(b"inputs", &le_u64(inputs));
+        self.commit_bytes(b"outputs", &le_u64(outputs));
+    }
+
+    fn surjection_domain_sep(&mut self, n: u64) {
+        self.commit_bytes(b"dom-sep", b"surjection v1");
+        self.commit_bytes(b"n", &le_u64(n));
+    }
+
+    fn solvency_domain_sep(&mut self, m: u64, chunk_size: u64) {
+        self.commit_bytes(b"dom-sep", b"solvency v1");
+        self.commit_bytes(b"m", &le_u64(m));
+        self.commit_bytes(b"chunk_size", &le_u64(chunk_size));
+    }
+
+    fn rewind_domain_sep(&mut self) {
+        self.commit_bytes(b"dom-sep", b"rewind v1");
+    }
+
+    fn mpc_session_domain_sep(&mut self, nonce: &[u8; 32], m: u64) {
+        self.commit_bytes(b"dom-sep", b"mpc session v1");
+        self.commit_bytes(b"session-nonce", nonce);
+        self.commit_bytes(b"m", &le_u64(m));
+    }
+
+    fn mpc_party_identity(&mut self, j: u64, identity: &[u8]) {
+        self.commit_bytes(b"j", &le_u64(j));
+        self.commit_bytes(b"party-identity", identity);
+    }
+
+    fn membership_domain_sep(&mut self, n: u64) {
+        self.commit_bytes(b"dom-sep", b"membership v1");
+        self.commit_bytes(b"n", &le_u64(n));
+    }
+
+    fn shuffle_domain_sep(&mut self, k: u64, m: u64) {
+        self.commit_bytes(b"dom-sep", b"shuffle v1");
+        self.commit_bytes(b"k", &le_u64(k));
+        self.commit_bytes(b"m", &le_u64(m));
+    }
+
+    fn r1cs_domain_sep(&mut self) {
+        self.commit_bytes(b"dom-sep", b"r1cs v1");
+    }
+
+    fn r1cs_num_commitments(&mut self, m: u64) {
+        self.commit_bytes(b"m", &le_u64(m));
+    }
+
+    fn r1cs_1phase_domain_sep(&mut self) {
+        self.commit_bytes(b"dom-sep", b"r1cs-1phase");
+    }
+
+    fn r1cs_2phase_domain_sep(&mut self) {
+        self.commit_bytes(b"dom-sep", b"r1cs-2phase");
+    }
+
+    fn external_circuit_domain_sep(&mut self, k: u64, m: u64, q: u64) {
+        self.commit_bytes(b"dom-sep", b"external circuit v1");
+        self.commit_bytes(b"k", &le_u64(k));
+        self.commit_bytes(b"m", &le_u64(m));
+        self.commit_bytes(b"q", &le_u64(q));
+    }
+
+    fn circuit_domain_sep(&mut self, n: u64, m: u64, q: u64) {
+        self.commit_bytes(b"dom-sep", b"circuit v1");
+        self.commit_bytes(b"n", &le_u64(n));
+        self.commit_bytes(b"m", &le_u64(m));
+        self.commit_bytes(b"q", &le_u64(q));
+    }
+
     fn commit_scalar(&mut self, label: &'static [u8], scalar: &Scalar) {
         self.commit_bytes(label, scalar.as_bytes());
     }
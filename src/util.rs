@@ -3,107 +3,147 @@
 
 use clear_on_drop::clear::Clear;
 use curve25519_dalek::scalar::Scalar;
-use inner_product_proof::inner_product;
+use scalar::ScalarVec;
 
 /// Represents a degree-1 vector polynomial \\(\mathbf{a} + \mathbf{b} \cdot x\\).
-pub struct VecPoly1(pub Vec<Scalar>, pub Vec<Scalar>);
+pub struct VecPoly1(pub ScalarVec, pub ScalarVec);
 
 /// Represents a degree-2 scalar polynomial \\(a + b \cdot x + c \cdot x^2\\)
 pub struct Poly2(pub Scalar, pub Scalar, pub Scalar);
 
-/// Provides an iterator over the powers of a `Scalar`.
-///
-/// This struct is created by the `exp_iter` function.
-pub struct ScalarExp {
-    x: Scalar,
-    next_exp_x: Scalar,
+/// Represents a degree-3 vector polynomial
+/// \\(\mathbf{a} + \mathbf{b} \cdot x + \mathbf{c} \cdot x^2 + \mathbf{d} \cdot x^3 \\).
+pub struct VecPoly3(pub ScalarVec, pub ScalarVec, pub ScalarVec, pub ScalarVec);
+
+/// Represents a degree-6 scalar polynomial
+/// \\(t\_0 + t\_1 \cdot x + \cdots + t\_6 \cdot x^6\\).
+pub struct Poly6 {
+    pub t0: Scalar,
+    pub t1: Scalar,
+    pub t2: Scalar,
+    pub t3: Scalar,
+    pub t4: Scalar,
+    pub t5: Scalar,
+    pub t6: Scalar,
 }
 
-impl Iterator for ScalarExp {
-    type Item = Scalar;
+/// Computes the coefficients of the product of the degree-1 vector
+/// polynomials \\(\mathbf{a}\_0 + \mathbf{a}\_1 \cdot x\\) and
+/// \\(\mathbf{b}\_0 + \mathbf{b}\_1 \cdot x\\) with Karatsuba's method,
+/// using three inner products instead of four.
+fn karatsuba(
+    a0: &ScalarVec,
+    a1: &ScalarVec,
+    b0: &ScalarVec,
+    b1: &ScalarVec,
+) -> (Scalar, Scalar, Scalar) {
+    let t0 = a0.inner_product(b0);
+    let t2 = a1.inner_product(b1);
+
+    let a0_plus_a1 = a0 + a1;
+    let b0_plus_b1 = b0 + b1;
+
+    let t1 = a0_plus_a1.inner_product(&b0_plus_b1) - t0 - t2;
+
+    (t0, t1, t2)
+}
 
-    fn next(&mut self) -> Option<Scalar> {
-        let exp_x = self.next_exp_x;
-        self.next_exp_x *= self.x;
-        Some(exp_x)
+impl VecPoly1 {
+    pub fn inner_product(&self, rhs: &VecPoly1) -> Poly2 {
+        let (t0, t1, t2) = karatsuba(&self.0, &self.1, &rhs.0, &rhs.1);
+        Poly2(t0, t1, t2)
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        (usize::max_value(), None)
+    pub fn eval(&self, x: Scalar) -> Vec<Scalar> {
+        (&self.1 * x + &self.0).into_vec()
     }
 }
 
-/// Return an iterator of the powers of `x`.
-pub fn exp_iter(x: Scalar) -> ScalarExp {
-    let next_exp_x = Scalar::one();
-    ScalarExp { x, next_exp_x }
+impl Poly2 {
+    pub fn eval(&self, x: Scalar) -> Scalar {
+        self.0 + x * (self.1 + x * self.2)
+    }
 }
 
-pub fn add_vec(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
-    let mut out = Vec::new();
-    if a.len() != b.len() {
-        // throw some error
-        println!("lengths of vectors don't match for vector addition");
-    }
-    for i in 0..a.len() {
-        out.push(a[i] + b[i]);
+impl Drop for Poly2 {
+    fn drop(&mut self) {
+        self.0.clear();
+        self.1.clear();
+        self.2.clear();
     }
-    out
 }
 
-impl VecPoly1 {
+impl VecPoly3 {
     pub fn zero(n: usize) -> Self {
-        VecPoly1(vec![Scalar::zero(); n], vec![Scalar::zero(); n])
+        VecPoly3(
+            ScalarVec::zero(n),
+            ScalarVec::zero(n),
+            ScalarVec::zero(n),
+            ScalarVec::zero(n),
+        )
     }
 
-    pub fn inner_product(&self, rhs: &VecPoly1) -> Poly2 {
-        // Uses Karatsuba's method
+    pub fn inner_product(&self, rhs: &VecPoly3) -> Poly6 {
+        // Write l(x) = l_lo(x) + x^2 * l_hi(x), where l_lo and l_hi have
+        // degree 1, and likewise for r(x).  Then
+        //   <l, r> = <l_lo, r_lo> + x^2 * mid(x) + x^4 * <l_hi, r_hi>,
+        // where mid = <l_lo + l_hi, r_lo + r_hi> - <l_lo, r_lo> - <l_hi, r_hi>.
+        // With Karatsuba for each product this takes 9 inner products
+        // instead of 16.
         let l = self;
         let r = rhs;
 
-        let t0 = inner_product(&l.0, &r.0);
-        let t2 = inner_product(&l.1, &r.1);
-
-        let l0_plus_l1 = add_vec(&l.0, &l.1);
-        let r0_plus_r1 = add_vec(&r.0, &r.1);
-
-        let t1 = inner_product(&l0_plus_l1, &r0_plus_r1) - t0 - t2;
+        let lo = karatsuba(&l.0, &l.1, &r.0, &r.1);
+        let hi = karatsuba(&l.2, &l.3, &r.2, &r.3);
+        let sum = karatsuba(
+            &(&l.0 + &l.2),
+            &(&l.1 + &l.3),
+            &(&r.0 + &r.2),
+            &(&r.1 + &r.3),
+        );
 
-        Poly2(t0, t1, t2)
+        let mid0 = sum.0 - lo.0 - hi.0;
+        let mid1 = sum.1 - lo.1 - hi.1;
+        let mid2 = sum.2 - lo.2 - hi.2;
+
+        Poly6 {
+            t0: lo.0,
+            t1: lo.1,
+            t2: lo.2 + mid0,
+            t3: mid1,
+            t4: hi.0 + mid2,
+            t5: hi.1,
+            t6: hi.2,
+        }
     }
 
     pub fn eval(&self, x: Scalar) -> Vec<Scalar> {
         let n = self.0.len();
         let mut out = vec![Scalar::zero(); n];
         for i in 0..n {
-            out[i] += self.0[i] + self.1[i] * x;
+            out[i] = self.0[i] + x * (self.1[i] + x * (self.2[i] + x * self.3[i]));
         }
         out
     }
 }
 
-impl Poly2 {
+impl Poly6 {
     pub fn eval(&self, x: Scalar) -> Scalar {
-        self.0 + x * (self.1 + x * self.2)
-    }
-}
-
-impl Drop for VecPoly1 {
-    fn drop(&mut self) {
-        for e in self.0.iter_mut() {
-            e.clear();
-        }
-        for e in self.1.iter_mut() {
-            e.clear();
-        }
+        self.t0
+            + x * (self.t1
+                + x * (self.t2 + x * (self.t3 + x * (self.t4 + x * (self.t5 + x * self.t6)))))
     }
 }
 
-impl Drop for Poly2 {
+impl Drop for Poly6 {
     fn drop(&mut self) {
-        self.0.clear();
-        self.1.clear();
-        self.2.clear();
+        self.t0.clear();
+        self.t1.clear();
+        self.t2.clear();
+        self.t3.clear();
+        self.t4.clear();
+        self.t5.clear();
+        self.t6.clear();
     }
 }
 
@@ -124,33 +164,6 @@ pub fn scalar_exp_vartime(x: &Scalar, mut n: u64) -> Scalar {
     result
 }
 
-/// Takes the sum of all the powers of `x`, up to `n`
-/// If `n` is a power of 2, it uses the efficient algorithm with `2*lg n` multiplications and additions.
-/// If `n` is not a power of 2, it uses the slow algorithm with `n` multiplications and additions.
-/// In the Bulletproofs case, all calls to `sum_of_powers` should have `n` as a power of 2.
-pub fn sum_of_powers(x: &Scalar, n: usize) -> Scalar {
-    if !n.is_power_of_two() {
-        return sum_of_powers_slow(x, n);
-    }
-    if n == 0 || n == 1 {
-        return Scalar::from(n as u64);
-    }
-    let mut m = n;
-    let mut result = Scalar::one() + x;
-    let mut factor = *x;
-    while m > 2 {
-        factor = factor * factor;
-        result = result + factor * result;
-        m = m / 2;
-    }
-    result
-}
-
-// takes the sum of all of the powers of x, up to n
-fn sum_of_powers_slow(x: &Scalar, n: usize) -> Scalar {
-    exp_iter(*x).take(n).sum()
-}
-
 /// Given `data` with `len >= 32`, return the first 32 bytes.
 pub fn read32(data: &[u8]) -> [u8; 32] {
     let mut buf32 = [0u8; 32];
@@ -161,33 +174,9 @@ pub fn read32(data: &[u8]) -> [u8; 32] {
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    #[test]
-    fn exp_2_is_powers_of_2() {
-        let exp_2: Vec<_> = exp_iter(Scalar::from(2u64)).take(4).collect();
-
-        assert_eq!(exp_2[0], Scalar::from(1u64));
-        assert_eq!(exp_2[1], Scalar::from(2u64));
-        assert_eq!(exp_2[2], Scalar::from(4u64));
-        assert_eq!(exp_2[3], Scalar::from(8u64));
-    }
-
-    #[test]
-    fn test_inner_product() {
-        let a = vec![
-            Scalar::from(1u64),
-            Scalar::from(2u64),
-            Scalar::from(3u64),
-            Scalar::from(4u64),
-        ];
-        let b = vec![
-            Scalar::from(2u64),
-            Scalar::from(3u64),
-            Scalar::from(4u64),
-            Scalar::from(5u64),
-        ];
-        assert_eq!(Scalar::from(40u64), inner_product(&a, &b));
-    }
+    use math;
+    use rand;
+    use scalar;
 
     /// Raises `x` to the power `n`.
     fn scalar_exp_vartime_slow(x: &Scalar, n: u64) -> Scalar {
@@ -217,28 +206,23 @@ mod tests {
     }
 
     #[test]
-    fn test_sum_of_powers() {
-        let x = Scalar::from(10u64);
-        assert_eq!(sum_of_powers_slow(&x, 0), sum_of_powers(&x, 0));
-        assert_eq!(sum_of_powers_slow(&x, 1), sum_of_powers(&x, 1));
-        assert_eq!(sum_of_powers_slow(&x, 2), sum_of_powers(&x, 2));
-        assert_eq!(sum_of_powers_slow(&x, 4), sum_of_powers(&x, 4));
-        assert_eq!(sum_of_powers_slow(&x, 8), sum_of_powers(&x, 8));
-        assert_eq!(sum_of_powers_slow(&x, 16), sum_of_powers(&x, 16));
-        assert_eq!(sum_of_powers_slow(&x, 32), sum_of_powers(&x, 32));
-        assert_eq!(sum_of_powers_slow(&x, 64), sum_of_powers(&x, 64));
-    }
-
-    #[test]
-    fn test_sum_of_powers_slow() {
-        let x = Scalar::from(10u64);
-        assert_eq!(sum_of_powers_slow(&x, 0), Scalar::zero());
-        assert_eq!(sum_of_powers_slow(&x, 1), Scalar::one());
-        assert_eq!(sum_of_powers_slow(&x, 2), Scalar::from(11u64));
-        assert_eq!(sum_of_powers_slow(&x, 3), Scalar::from(111u64));
-        assert_eq!(sum_of_powers_slow(&x, 4), Scalar::from(1111u64));
-        assert_eq!(sum_of_powers_slow(&x, 5), Scalar::from(11111u64));
-        assert_eq!(sum_of_powers_slow(&x, 6), Scalar::from(111111u64));
+    fn vec_poly3_inner_product() {
+        let mut rng = rand::thread_rng();
+        let mut random_poly = || {
+            VecPoly3(
+                scalar::random_vec(&mut rng, 5).into(),
+                scalar::random_vec(&mut rng, 5).into(),
+                scalar::random_vec(&mut rng, 5).into(),
+                scalar::random_vec(&mut rng, 5).into(),
+            )
+        };
+        let l = random_poly();
+        let r = random_poly();
+
+        let t = l.inner_product(&r);
+
+        let x = Scalar::random(&mut rand::thread_rng());
+        assert_eq!(t.eval(x), math::inner_product(&l.eval(x), &r.eval(x)));
     }
 
     #[test]
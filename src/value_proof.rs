@@ -0,0 +1,297 @@
+//! The `value_proof` module contains an API for proving that a Pedersen
+//! commitment opens to a public value.
+
+#![allow(non_snake_case)]
+
+use std::slice;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+use merlin::Transcript;
+use rand;
+
+use errors::ProofError;
+use generators::PedersenGens;
+use scalar;
+use transcript::TranscriptProtocol;
+use util::read32;
+
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A proof that a commitment \\(V = vB + \tilde{v}B\_{blinding}\\)
+/// opens to a public value \\(v\\).
+///
+/// This is a Schnorr proof of knowledge of \\(\tilde{v}\\) such that
+/// \\(V - vB = \tilde{v}B\_{blinding}\\), so the value is revealed but
+/// the blinding factor is not.  Confidential transaction systems can
+/// use it for outputs with a public amount, such as fees.
+///
+/// The verifier checks
+/// \\[
+///     s B\_{blinding} = R + c (V - vB),
+/// \\]
+/// where \\(c\\) is the challenge derived from the transcript.
+#[derive(Copy, Clone, Debug)]
+pub struct ValueOpeningProof {
+    /// Commitment \\(R = k B\_{blinding}\\) to the nonce \\(k\\)
+    R: CompressedRistretto,
+    /// Response \\(s = k + c \tilde{v}\\)
+    s: Scalar,
+}
+
+impl ValueOpeningProof {
+    /// Creates a proof that the commitment to `v` with blinding factor
+    /// `v_blinding` opens to `v`, returning the proof and the
+    /// commitment.
+    pub fn prove(
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+    ) -> (ValueOpeningProof, CompressedRistretto) {
+        let V = pc_gens.commit(Scalar::from(v), *v_blinding).compress();
+
+        transcript.value_opening_domain_sep();
+        transcript.commit_point(b"V", &V);
+        transcript.commit_scalar(b"v", &Scalar::from(v));
+
+        let mut rng = transcript
+            .build_rng()
+            .commit_witness_bytes(b"v_blinding", v_blinding.as_bytes())
+            .finalize(&mut rand::thread_rng());
+        let k = Scalar::random(&mut rng);
+        let R = (k * pc_gens.B_blinding).compress();
+        transcript.commit_point(b"R", &R);
+
+        let c = transcript.challenge_scalar(b"c");
+        let s = k + c * v_blinding;
+
+        (ValueOpeningProof { R, s }, V)
+    }
+
+    /// Verifies that the commitment `V` opens to the value `v`.
+    pub fn verify(
+        &self,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        v: u64,
+    ) -> Result<(), ProofError> {
+        ValueOpeningProof::verify_batch(pc_gens, &[*self], slice::from_mut(transcript), &[*V], &[v])
+    }
+
+    /// Verifies a batch of proofs with a single multiscalar
+    /// multiplication.
+    ///
+    /// The `i`-th proof is checked with the `i`-th transcript,
+    /// commitment and value.  Each equation is weighted by a random
+    /// scalar, so the check fails except with negligible probability
+    /// if any proof is invalid.
+    ///
+    /// Returns `ProofError::FormatError` if the slices have different
+    /// lengths or a point is not valid.
+    pub fn verify_batch(
+        pc_gens: &PedersenGens,
+        proofs: &[ValueOpeningProof],
+        transcripts: &mut [Transcript],
+        value_commitments: &[CompressedRistretto],
+        values: &[u64],
+    ) -> Result<(), ProofError> {
+        let m = proofs.len();
+        if transcripts.len() != m || value_commitments.len() != m || values.len() != m {
+            return Err(ProofError::FormatError);
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut scalars = Vec::with_capacity(2 * m + 2);
+        let mut points = Vec::with_capacity(2 * m + 2);
+        let mut B_scalar = Scalar::zero();
+        let mut B_blinding_scalar = Scalar::zero();
+
+        for i in 0..m {
+            let weight = Scalar::random(&mut rng);
+            let (s, p) = proofs[i].verification_terms(
+                &mut transcripts[i],
+                &value_commitments[i],
+                values[i],
+                weight,
+            );
+            scalars.extend_from_slice(&s[0..2]);
+            points.extend_from_slice(&p);
+            B_scalar += s[2];
+            B_blinding_scalar += weight * proofs[i].s;
+        }
+        scalars.push(B_blinding_scalar);
+        points.push(Some(pc_gens.B_blinding));
+        scalars.push(B_scalar);
+        points.push(Some(pc_gens.B));
+
+        let check = RistrettoPoint::optional_multiscalar_mul(scalars, points)
+            .ok_or(ProofError::FormatError)?;
+
+        if check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Replays the proof on the `transcript` and returns the scalars
+    /// for \\(R\\), \\(V\\) and \\(B\\), each multiplied by `weight`,
+    /// together with the points \\(R\\) and \\(V\\).
+    fn verification_terms(
+        &self,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        v: u64,
+        weight: Scalar,
+    ) -> ([Scalar; 3], [Option<RistrettoPoint>; 2]) {
+        transcript.value_opening_domain_sep();
+        transcript.commit_point(b"V", V);
+        transcript.commit_scalar(b"v", &Scalar::from(v));
+        transcript.commit_point(b"R", &self.R);
+
+        let c = transcript.challenge_scalar(b"c");
+
+        // s B_blinding - R - c V + c v B = 0
+        let scalars = [-weight, -(weight * c), weight * c * Scalar::from(v)];
+        (scalars, [self.R.decompress(), V.decompress()])
+    }
+
+    /// Serializes the proof into a 64-byte array.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(self.R.as_bytes());
+        buf[32..].copy_from_slice(self.s.as_bytes());
+        buf
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a
+    /// `ValueOpeningProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<ValueOpeningProof, ProofError> {
+        if slice.len() != 64 {
+            return Err(ProofError::FormatError);
+        }
+        let R = CompressedRistretto(read32(&slice[..32]));
+        let s = scalar::decode(&slice[32..])?;
+        Ok(ValueOpeningProof { R, s })
+    }
+}
+
+impl Serialize for ValueOpeningProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for ValueOpeningProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ValueOpeningProofVisitor;
+
+        impl<'de> Visitor<'de> for ValueOpeningProofVisitor {
+            type Value = ValueOpeningProof;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("a valid ValueOpeningProof")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<ValueOpeningProof, E>
+            where
+                E: serde::de::Error,
+            {
+                ValueOpeningProof::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(ValueOpeningProofVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prove_and_verify() {
+        let pc_gens = PedersenGens::default();
+        let blinding = Scalar::random(&mut rand::thread_rng());
+
+        let (proof, V) = ValueOpeningProof::prove(
+            &pc_gens,
+            &mut Transcript::new(b"OpeningTest"),
+            25,
+            &blinding,
+        );
+
+        let proof = ValueOpeningProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(
+            proof
+                .verify(&pc_gens, &mut Transcript::new(b"OpeningTest"), &V, 25)
+                .is_ok()
+        );
+        assert_eq!(
+            proof.verify(&pc_gens, &mut Transcript::new(b"OpeningTest"), &V, 26),
+            Err(ProofError::VerificationError)
+        );
+        assert!(
+            proof
+                .verify(&pc_gens, &mut Transcript::new(b"OtherTest"), &V, 25)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn batch_verify() {
+        let pc_gens = PedersenGens::default();
+        let mut rng = rand::thread_rng();
+        let values = [1u64, 2, 3];
+
+        let (proofs, commitments): (Vec<_>, Vec<_>) = values
+            .iter()
+            .map(|&v| {
+                let blinding = Scalar::random(&mut rng);
+                ValueOpeningProof::prove(&pc_gens, &mut Transcript::new(b"BatchTest"), v, &blinding)
+            }).unzip();
+        let transcripts = || vec![Transcript::new(b"BatchTest"); 3];
+
+        assert!(
+            ValueOpeningProof::verify_batch(
+                &pc_gens,
+                &proofs,
+                &mut transcripts(),
+                &commitments,
+                &values
+            ).is_ok()
+        );
+        assert_eq!(
+            ValueOpeningProof::verify_batch(
+                &pc_gens,
+                &proofs,
+                &mut transcripts(),
+                &commitments,
+                &[1, 2, 4]
+            ),
+            Err(ProofError::VerificationError)
+        );
+        assert_eq!(
+            ValueOpeningProof::verify_batch(
+                &pc_gens,
+                &proofs,
+                &mut transcripts(),
+                &commitments[..2],
+                &values
+            ),
+            Err(ProofError::FormatError)
+        );
+    }
+}
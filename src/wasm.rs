@@ -0,0 +1,121 @@
+//! The `wasm` module contains prove and verify entry points which take
+//! and return byte slices, for callers such as JavaScript bindings
+//! which cannot hold Rust types.
+//!
+//! Points and scalars are encoded as 32 bytes each, and proofs with
+//! [`RangeProof::to_bytes`].  The proofs use the default
+//! [`PedersenGens`] and the [`BulletproofGens::shared`] generators,
+//! and the transcript is bound to the caller's `label`, which must be
+//! the same for the prover and the verifier.
+//!
+//! The `wasm` feature also enables `rand`'s `wasm-bindgen` support, so
+//! that on `wasm32-unknown-unknown` the blinding factors of the proofs
+//! are seeded from the host's `crypto.getRandomValues`.
+//!
+//! This module is only available with the `wasm` feature.
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use merlin::Transcript;
+
+use errors::ProofError;
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::RangeProof;
+use scalar;
+use util::read32;
+
+/// Creates a range proof that each of `values` is in \\([0, 2^n)\\).
+///
+/// `blindings` holds one 32-byte blinding factor per value.  Returns
+/// the proof bytes and the concatenated 32-byte commitments to the
+/// values.
+pub fn prove(
+    label: &[u8],
+    values: &[u64],
+    blindings: &[u8],
+    n: usize,
+) -> Result<(Vec<u8>, Vec<u8>), ProofError> {
+    if blindings.len() != 32 * values.len() {
+        return Err(ProofError::WrongNumBlindingFactors);
+    }
+    let blindings = scalar::decode_vec(blindings)?;
+
+    let bp_gens = BulletproofGens::shared(n, values.len());
+    let (proof, value_commitments) = RangeProof::prove_multiple(
+        &bp_gens,
+        &PedersenGens::default(),
+        &mut transcript(label),
+        values,
+        &blindings,
+        n,
+    )?;
+
+    let commitment_bytes = value_commitments
+        .iter()
+        .flat_map(|commitment| commitment.as_bytes().iter().cloned())
+        .collect();
+    Ok((proof.to_bytes(), commitment_bytes))
+}
+
+/// Verifies a range proof created by [`prove`] against the
+/// concatenated 32-byte `value_commitments`.
+pub fn verify(
+    label: &[u8],
+    proof: &[u8],
+    value_commitments: &[u8],
+    n: usize,
+) -> Result<(), ProofError> {
+    if value_commitments.len() % 32 != 0 {
+        return Err(ProofError::FormatError);
+    }
+    let proof = RangeProof::from_bytes(proof)?;
+    let value_commitments: Vec<_> = value_commitments
+        .chunks(32)
+        .map(|chunk| CompressedRistretto(read32(chunk)))
+        .collect();
+
+    let bp_gens = BulletproofGens::shared(n, value_commitments.len());
+    proof.verify_multiple(
+        &bp_gens,
+        &PedersenGens::default(),
+        &mut transcript(label),
+        &value_commitments,
+        n,
+    )
+}
+
+/// Returns the transcript for the caller's `label`.
+fn transcript(label: &[u8]) -> Transcript {
+    let mut transcript = Transcript::new(b"bulletproofs wasm");
+    transcript.commit_bytes(b"label", label);
+    transcript
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+    use rand::thread_rng;
+
+    #[test]
+    fn prove_and_verify_bytes() {
+        let mut rng = thread_rng();
+        let values = [7u64, 1 << 31];
+        let blindings: Vec<u8> = (0..values.len())
+            .flat_map(|_| Scalar::random(&mut rng).to_bytes().to_vec())
+            .collect();
+
+        let (proof, value_commitments) = prove(b"wasm test", &values, &blindings, 32).unwrap();
+        assert_eq!(value_commitments.len(), 64);
+
+        assert!(verify(b"wasm test", &proof, &value_commitments, 32).is_ok());
+        assert!(verify(b"other label", &proof, &value_commitments, 32).is_err());
+        assert!(verify(b"wasm test", &proof, &value_commitments[..63], 32).is_err());
+    }
+
+    #[test]
+    fn prove_rejects_wrong_blindings_length() {
+        let result = prove(b"wasm test", &[7u64, 8], &[0u8; 32], 32);
+        assert_eq!(result, Err(ProofError::WrongNumBlindingFactors));
+    }
+}
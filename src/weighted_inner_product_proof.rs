@@ -0,0 +1,615 @@
+#![allow(non_snake_case)]
+
+//! The weighted inner-product argument of Bulletproofs+.
+//!
+//! The argument proves knowledge of vectors \\(\mathbf{a}, \mathbf{b}\\)
+//! and a blinding factor \\(\alpha\\) such that
+//! \\[
+//!   P = \langle \mathbf{a}, \mathbf{G} \rangle + \langle \mathbf{b}, \mathbf{H} \rangle
+//!     + {\langle \mathbf{a}, \mathbf{b} \rangle}\_y \cdot g + \alpha \cdot h,
+//! \\]
+//! where \\({\langle \mathbf{a}, \mathbf{b} \rangle}\_y = \sum\_i a\_i b\_i y^{i+1}\\)
+//! is the weighted inner product.  Unlike the inner-product argument
+//! of Bulletproofs, it is zero-knowledge by itself, so the range proof
+//! built on it does not need to send \\(t(x)\\) and its blinding
+//! factors.
+
+use std::iter;
+
+use clear_on_drop::clear::Clear;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{IsIdentity, MultiscalarMul, VartimeMultiscalarMul};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+use errors::ProofError;
+use math::{exp_iter, weighted_inner_product};
+use scalar;
+use transcript::TranscriptProtocol;
+use util;
+
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A logarithmic-size, zero-knowledge proof of knowledge of two
+/// vectors with a given weighted inner product.
+///
+/// For vectors of generators \\(G\\) and \\(H\\) of length \\(n\\), a
+/// power of two, points \\(g\\) and \\(h\\), and a weight \\(y\\), the
+/// proof shows knowledge of vectors \\(a\\), \\(b\\) and a blinding
+/// factor \\(\alpha\\) such that
+/// \\[
+///     P = \langle a, G \rangle + \langle b, H \rangle + {\langle a, b \rangle}\_y g + \alpha h.
+/// \\]
+/// The powers of \\(y\\) are folded into the argument itself, so
+/// unlike the [`InnerProductProof`](::InnerProductProof), the callers
+/// do not need to pre-scale \\(H\\).
+///
+/// The proof consists of \\(\lg n\\) pairs of points \\((L\_j, R\_j)\\),
+/// two points \\(A, B\\) and three scalars.  The challenges are drawn
+/// from the transcript, so the statement \\(P\\) must be bound to it by
+/// the parent protocol before the proof is created or verified.
+#[derive(Clone, Debug)]
+pub struct WeightedInnerProductProof {
+    L_vec: Vec<CompressedRistretto>,
+    R_vec: Vec<CompressedRistretto>,
+    /// Commitment to the masks \\(r, s\\) of the final round
+    pub(crate) A: CompressedRistretto,
+    /// Commitment to the product of the masks of the final round
+    pub(crate) B: CompressedRistretto,
+    /// Masked final value \\(r' = r + a e\\)
+    pub(crate) r: Scalar,
+    /// Masked final value \\(s' = s + b e\\)
+    pub(crate) s: Scalar,
+    /// Masked blinding factor \\(\delta' = \eta + \delta e + \alpha e^2\\)
+    pub(crate) delta: Scalar,
+}
+
+/// The scalars used by a parent protocol to check a
+/// [`WeightedInnerProductProof`] in a single multiscalar
+/// multiplication.
+pub(crate) struct WeightedVerificationScalars {
+    /// The squares \\(e\_k^2\\) of the folding challenges.
+    pub(crate) challenges_sq: Vec<Scalar>,
+    /// The inverse squares \\(e\_k^{-2}\\) of the folding challenges.
+    pub(crate) challenges_inv_sq: Vec<Scalar>,
+    /// The products \\(s\_i\\) of the folding challenges for each
+    /// generator, as for the inner-product argument.
+    pub(crate) s: Vec<Scalar>,
+    /// The challenge \\(e\\) of the final round.
+    pub(crate) e: Scalar,
+}
+
+impl WeightedInnerProductProof {
+    /// Create a weighted inner-product proof.
+    ///
+    /// The proof is created with respect to the bases \\(\mathbf{G}\\),
+    /// \\(\mathbf{H}\\), \\(g\\) and \\(h\\), for the weight \\(y\\).
+    ///
+    /// The lengths of the vectors must all be the same, and must be a
+    /// power of 2.
+    pub fn create<R: RngCore + CryptoRng>(
+        transcript: &mut Transcript,
+        rng: &mut R,
+        g: &RistrettoPoint,
+        h: &RistrettoPoint,
+        mut G_vec: Vec<RistrettoPoint>,
+        mut H_vec: Vec<RistrettoPoint>,
+        mut a_vec: Vec<Scalar>,
+        mut b_vec: Vec<Scalar>,
+        mut alpha: Scalar,
+        y: &Scalar,
+    ) -> WeightedInnerProductProof {
+        let mut G = &mut G_vec[..];
+        let mut H = &mut H_vec[..];
+        let mut a = &mut a_vec[..];
+        let mut b = &mut b_vec[..];
+
+        let mut n = G.len();
+
+        // All of the input vectors must have the same length.
+        assert_eq!(H.len(), n);
+        assert_eq!(a.len(), n);
+        assert_eq!(b.len(), n);
+
+        // All of the input vectors must have a length that is a power of two.
+        assert!(n.is_power_of_two());
+
+        transcript.weighted_innerproduct_domain_sep(n as u64);
+
+        let lg_n = n.trailing_zeros() as usize;
+        let mut L_vec = Vec::with_capacity(lg_n);
+        let mut R_vec = Vec::with_capacity(lg_n);
+
+        let y_inv = y.invert();
+
+        while n != 1 {
+            n = n / 2;
+            let (a_L, a_R) = a.split_at_mut(n);
+            let (b_L, b_R) = b.split_at_mut(n);
+            let (G_L, G_R) = G.split_at_mut(n);
+            let (H_L, H_R) = H.split_at_mut(n);
+
+            let y_n = util::scalar_exp_vartime(y, n as u64);
+            let y_n_inv = util::scalar_exp_vartime(&y_inv, n as u64);
+
+            let c_L = weighted_inner_product(&a_L, &b_R, y);
+            let c_R = y_n * weighted_inner_product(&a_R, &b_L, y);
+
+            let mut d_L = Scalar::random(rng);
+            let mut d_R = Scalar::random(rng);
+
+            let L = RistrettoPoint::multiscalar_mul(
+                a_L.iter()
+                    .map(|a_L_i| a_L_i * y_n_inv)
+                    .chain(b_R.iter().cloned())
+                    .chain(iter::once(c_L))
+                    .chain(iter::once(d_L)),
+                G_R.iter()
+                    .chain(H_L.iter())
+                    .chain(iter::once(g))
+                    .chain(iter::once(h)),
+            ).compress();
+
+            let R = RistrettoPoint::multiscalar_mul(
+                a_R.iter()
+                    .map(|a_R_i| a_R_i * y_n)
+                    .chain(b_L.iter().cloned())
+                    .chain(iter::once(c_R))
+                    .chain(iter::once(d_R)),
+                G_L.iter()
+                    .chain(H_R.iter())
+                    .chain(iter::once(g))
+                    .chain(iter::once(h)),
+            ).compress();
+
+            L_vec.push(L);
+            R_vec.push(R);
+
+            transcript.commit_point(b"L", &L);
+            transcript.commit_point(b"R", &R);
+
+            let e = transcript.challenge_scalar(b"e");
+            let e_inv = e.invert();
+
+            for i in 0..n {
+                a_L[i] = a_L[i] * e + a_R[i] * e_inv * y_n;
+                b_L[i] = b_L[i] * e_inv + b_R[i] * e;
+                G_L[i] = RistrettoPoint::vartime_multiscalar_mul(
+                    &[e_inv, e * y_n_inv],
+                    &[G_L[i], G_R[i]],
+                );
+                H_L[i] = RistrettoPoint::vartime_multiscalar_mul(&[e, e_inv], &[H_L[i], H_R[i]]);
+            }
+            alpha += d_L * e * e + d_R * e_inv * e_inv;
+            d_L.clear();
+            d_R.clear();
+
+            a = a_L;
+            b = b_L;
+            G = G_L;
+            H = H_L;
+        }
+
+        // Prove knowledge of the final a, b, alpha with a masked
+        // Schnorr-like protocol.
+        let mut r = Scalar::random(rng);
+        let mut s = Scalar::random(rng);
+        let mut delta = Scalar::random(rng);
+        let mut eta = Scalar::random(rng);
+
+        let A = RistrettoPoint::multiscalar_mul(
+            &[r, s, y * (r * b[0] + s * a[0]), delta],
+            &[G[0], H[0], *g, *h],
+        ).compress();
+        let B = RistrettoPoint::multiscalar_mul(&[r * y * s, eta], &[*g, *h]).compress();
+
+        transcript.commit_point(b"A", &A);
+        transcript.commit_point(b"B", &B);
+
+        let e = transcript.challenge_scalar(b"e");
+
+        let proof = WeightedInnerProductProof {
+            L_vec,
+            R_vec,
+            A,
+            B,
+            r: r + a[0] * e,
+            s: s + b[0] * e,
+            delta: eta + delta * e + alpha * e * e,
+        };
+
+        // Overwrite the witness and the masks with null bytes.
+        for e in a_vec.iter_mut().chain(b_vec.iter_mut()) {
+            e.clear();
+        }
+        alpha.clear();
+        r.clear();
+        s.clear();
+        delta.clear();
+        eta.clear();
+
+        proof
+    }
+
+    /// Returns the number of folding rounds in the proof, which is
+    /// \\(\lg n\\) for vectors of length \\(n\\).
+    pub fn rounds(&self) -> usize {
+        self.L_vec.len()
+    }
+
+    /// Returns an iterator over the pairs of points \\((L_j, R_j)\\)
+    /// committed by the prover, in creation order.
+    pub fn lr_points(&self) -> impl Iterator<Item = (&CompressedRistretto, &CompressedRistretto)> {
+        self.L_vec.iter().zip(self.R_vec.iter())
+    }
+
+    /// Replays the proof on the transcript and computes the scalars
+    /// for a combined multiscalar multiplication in a parent protocol.
+    ///
+    /// The proof is valid for \\(P\\) if
+    /// \\[
+    ///   e^2 P + \sum\_k e^2 (e\_k^2 L\_k + e\_k^{-2} R\_k) + e A + B
+    ///   = r' e \sum\_i y^{-i} s\_i G\_i + s' e \sum\_i s\_{n-1-i} H\_i
+    ///   + r' y s' g + \delta' h.
+    /// \\]
+    /// The verifier must provide the input length \\(n\\) explicitly to
+    /// avoid unbounded allocation within the proof.
+    pub(crate) fn verification_scalars(
+        &self,
+        n: usize,
+        transcript: &mut Transcript,
+    ) -> Result<WeightedVerificationScalars, ProofError> {
+        let lg_n = self.rounds();
+        if lg_n >= 32 {
+            // This check prevents overflow in 1<<lg_n below.
+            return Err(ProofError::VerificationError);
+        }
+        if n != (1 << lg_n) {
+            return Err(ProofError::VerificationError);
+        }
+
+        transcript.weighted_innerproduct_domain_sep(n as u64);
+
+        let mut challenges = Vec::with_capacity(lg_n);
+        for (L, R) in self.L_vec.iter().zip(self.R_vec.iter()) {
+            transcript.commit_point(b"L", L);
+            transcript.commit_point(b"R", R);
+            challenges.push(transcript.challenge_scalar(b"e"));
+        }
+
+        transcript.commit_point(b"A", &self.A);
+        transcript.commit_point(b"B", &self.B);
+
+        let e = transcript.challenge_scalar(b"e");
+
+        let mut challenges_inv = challenges.clone();
+        let allinv = Scalar::batch_invert(&mut challenges_inv);
+
+        for i in 0..lg_n {
+            challenges[i] = challenges[i] * challenges[i];
+            challenges_inv[i] = challenges_inv[i] * challenges_inv[i];
+        }
+        let challenges_sq = challenges;
+        let challenges_inv_sq = challenges_inv;
+
+        // The challenges are stored in creation order, so the
+        // challenge of the round splitting on bit lg_i of i is
+        // indexed by (lg_n-1) - lg_i.
+        let mut s = Vec::with_capacity(n);
+        s.push(allinv);
+        for i in 1..n {
+            let lg_i = (32 - 1 - (i as u32).leading_zeros()) as usize;
+            let k = 1 << lg_i;
+            let e_lg_i_sq = challenges_sq[(lg_n - 1) - lg_i];
+            s.push(s[i - k] * e_lg_i_sq);
+        }
+
+        Ok(WeightedVerificationScalars {
+            challenges_sq,
+            challenges_inv_sq,
+            s,
+            e,
+        })
+    }
+
+    /// Verifies the proof against the statement
+    /// \\(P = \langle a, G \rangle + \langle b, H \rangle + {\langle a, b \rangle}\_y g + \alpha h\\),
+    /// where the length \\(n\\) of the vectors is the length of `G`.
+    ///
+    /// The `transcript` must be in the same state as the prover's was
+    /// when it created the proof.  Protocols built on the proof can use
+    /// the verification scalars instead, to combine this check with
+    /// their other checks in a single multiscalar multiplication.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate curve25519_dalek;
+    /// extern crate merlin;
+    /// extern crate rand;
+    /// extern crate bulletproofs;
+    /// use curve25519_dalek::ristretto::RistrettoPoint;
+    /// use curve25519_dalek::scalar::Scalar;
+    /// use curve25519_dalek::traits::MultiscalarMul;
+    /// use merlin::Transcript;
+    /// use bulletproofs::{PedersenGens, WeightedInnerProductProof};
+    ///
+    /// # fn main() {
+    /// // The generators must have no known discrete logarithm relation
+    /// let mut rng = rand::thread_rng();
+    /// let n = 4;
+    /// let G: Vec<RistrettoPoint> = (0..n).map(|_| RistrettoPoint::random(&mut rng)).collect();
+    /// let H: Vec<RistrettoPoint> = (0..n).map(|_| RistrettoPoint::random(&mut rng)).collect();
+    /// let pc_gens = PedersenGens::default();
+    /// let (g, h) = (pc_gens.B, pc_gens.B_blinding);
+    ///
+    /// let a: Vec<Scalar> = (1u64..5).map(Scalar::from).collect();
+    /// let b: Vec<Scalar> = (5u64..9).map(Scalar::from).collect();
+    /// let alpha = Scalar::random(&mut rng);
+    /// // <a, b>_y = 1*5*2 + 2*6*4 + 3*7*8 + 4*8*16 = 738 for y = 2
+    /// let y = Scalar::from(2u64);
+    /// let P = RistrettoPoint::multiscalar_mul(
+    ///     a.iter().chain(b.iter()).chain(&[Scalar::from(738u64), alpha]),
+    ///     G.iter().chain(H.iter()).chain(&[g, h]),
+    /// );
+    ///
+    /// let mut transcript = Transcript::new(b"WIPPExample");
+    /// let proof = WeightedInnerProductProof::create(
+    ///     &mut transcript, &mut rng, &g, &h, G.clone(), H.clone(), a, b, alpha, &y,
+    /// );
+    ///
+    /// let mut transcript = Transcript::new(b"WIPPExample");
+    /// assert!(proof.verify(&mut transcript, &y, &P, &g, &h, &G, &H).is_ok());
+    /// # }
+    /// ```
+    pub fn verify(
+        &self,
+        transcript: &mut Transcript,
+        y: &Scalar,
+        P: &RistrettoPoint,
+        g: &RistrettoPoint,
+        h: &RistrettoPoint,
+        G: &[RistrettoPoint],
+        H: &[RistrettoPoint],
+    ) -> Result<(), ProofError> {
+        if H.len() != G.len() {
+            return Err(ProofError::VerificationError);
+        }
+        let scalars = self.verification_scalars(G.len(), transcript)?;
+        let (e, ee) = (scalars.e, scalars.e * scalars.e);
+        let y_inv = y.invert();
+
+        let r_e = self.r * e;
+        let s_e = self.s * e;
+        let check = RistrettoPoint::optional_multiscalar_mul(
+            iter::once(ee)
+                .chain(scalars.challenges_sq.iter().map(|e_sq| ee * e_sq))
+                .chain(
+                    scalars
+                        .challenges_inv_sq
+                        .iter()
+                        .map(|e_inv_sq| ee * e_inv_sq),
+                ).chain(iter::once(e))
+                .chain(iter::once(Scalar::one()))
+                .chain(
+                    scalars
+                        .s
+                        .iter()
+                        .zip(exp_iter(y_inv))
+                        .map(|(s_i, y_inv_i)| -r_e * y_inv_i * s_i),
+                ).chain(scalars.s.iter().rev().map(|s_i_inv| -s_e * s_i_inv))
+                .chain(iter::once(-self.r * y * self.s))
+                .chain(iter::once(-self.delta)),
+            iter::once(Some(*P))
+                .chain(self.L_vec.iter().map(|L| L.decompress()))
+                .chain(self.R_vec.iter().map(|R| R.decompress()))
+                .chain(iter::once(self.A.decompress()))
+                .chain(iter::once(self.B.decompress()))
+                .chain(G.iter().map(|&G_i| Some(G_i)))
+                .chain(H.iter().map(|&H_i| Some(H_i)))
+                .chain(iter::once(Some(*g)))
+                .chain(iter::once(Some(*h))),
+        ).ok_or(ProofError::VerificationError)?;
+
+        if check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Returns the size in bytes required to serialize the proof,
+    /// \\(32 \cdot (2\lg n+5)\\) bytes.
+    pub fn serialized_size(&self) -> usize {
+        (self.rounds() * 2 + 5) * 32
+    }
+
+    /// Serializes the proof into a byte array of \\(2 \lg n+5\\) 32-byte
+    /// elements.  The layout of the proof is:
+    /// * \\(\lg n\\) pairs of compressed Ristretto points \\(L_0, R_0 \dots, L_{k-1}, R_{k-1}\\),
+    /// * two compressed Ristretto points \\(A, B\\),
+    /// * three scalars \\(r', s', \delta'\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.serialized_size());
+        for (l, r) in self.L_vec.iter().zip(self.R_vec.iter()) {
+            buf.extend_from_slice(l.as_bytes());
+            buf.extend_from_slice(r.as_bytes());
+        }
+        buf.extend_from_slice(self.A.as_bytes());
+        buf.extend_from_slice(self.B.as_bytes());
+        buf.extend_from_slice(self.r.as_bytes());
+        buf.extend_from_slice(self.s.as_bytes());
+        buf.extend_from_slice(self.delta.as_bytes());
+        buf
+    }
+
+    /// Deserializes the proof from a byte slice.
+    /// Returns an error in the following cases:
+    /// * the slice does not have \\(2 \lg n+5\\) 32-byte elements,
+    /// * \\(\lg n\\) is larger or equal to 32 (proof is too big),
+    /// * any of the 3 scalars are not canonical scalars modulo Ristretto group order.
+    pub fn from_bytes(slice: &[u8]) -> Result<WeightedInnerProductProof, ProofError> {
+        let b = slice.len();
+        if b % 32 != 0 {
+            return Err(ProofError::FormatError);
+        }
+        let num_elements = b / 32;
+        if num_elements < 5 {
+            return Err(ProofError::FormatError);
+        }
+        if (num_elements - 5) % 2 != 0 {
+            return Err(ProofError::FormatError);
+        }
+        let lg_n = (num_elements - 5) / 2;
+        if lg_n >= 32 {
+            return Err(ProofError::FormatError);
+        }
+
+        use util::read32;
+
+        let mut L_vec: Vec<CompressedRistretto> = Vec::with_capacity(lg_n);
+        let mut R_vec: Vec<CompressedRistretto> = Vec::with_capacity(lg_n);
+        for i in 0..lg_n {
+            let pos = 2 * i * 32;
+            L_vec.push(CompressedRistretto(read32(&slice[pos..])));
+            R_vec.push(CompressedRistretto(read32(&slice[pos + 32..])));
+        }
+
+        let pos = 2 * lg_n * 32;
+        let A = CompressedRistretto(read32(&slice[pos..]));
+        let B = CompressedRistretto(read32(&slice[pos + 32..]));
+        let r = scalar::decode(&slice[pos + 64..pos + 96])?;
+        let s = scalar::decode(&slice[pos + 96..pos + 128])?;
+        let delta = scalar::decode(&slice[pos + 128..pos + 160])?;
+
+        Ok(WeightedInnerProductProof {
+            L_vec,
+            R_vec,
+            A,
+            B,
+            r,
+            s,
+            delta,
+        })
+    }
+}
+
+impl Serialize for WeightedInnerProductProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for WeightedInnerProductProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct WeightedInnerProductProofVisitor;
+
+        impl<'de> Visitor<'de> for WeightedInnerProductProofVisitor {
+            type Value = WeightedInnerProductProof;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("a valid WeightedInnerProductProof")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<WeightedInnerProductProof, E>
+            where
+                E: serde::de::Error,
+            {
+                WeightedInnerProductProof::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(WeightedInnerProductProofVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+    use sha3::Sha3_512;
+
+    use generators::BulletproofGens;
+
+    fn test_helper_create(n: usize) {
+        let mut rng = OsRng::new().unwrap();
+
+        let bp_gens = BulletproofGens::new(n, 1);
+        let G: Vec<RistrettoPoint> = bp_gens.share(0).G(n).cloned().collect();
+        let H: Vec<RistrettoPoint> = bp_gens.share(0).H(n).cloned().collect();
+
+        // g and h would be determined upstream in the protocol, so we pick random ones.
+        let g = RistrettoPoint::hash_from_bytes::<Sha3_512>(b"test point g");
+        let h = RistrettoPoint::hash_from_bytes::<Sha3_512>(b"test point h");
+
+        let a: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let b: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let alpha = Scalar::random(&mut rng);
+        let y = Scalar::random(&mut rng);
+
+        let P = RistrettoPoint::vartime_multiscalar_mul(
+            a.iter()
+                .chain(b.iter())
+                .chain(iter::once(&weighted_inner_product(&a, &b, &y)))
+                .chain(iter::once(&alpha)),
+            G.iter()
+                .chain(H.iter())
+                .chain(iter::once(&g))
+                .chain(iter::once(&h)),
+        );
+
+        let proof = WeightedInnerProductProof::create(
+            &mut Transcript::new(b"wiptest"),
+            &mut rng,
+            &g,
+            &h,
+            G.clone(),
+            H.clone(),
+            a,
+            b,
+            alpha,
+            &y,
+        );
+        let proof = WeightedInnerProductProof::from_bytes(&proof.to_bytes()).unwrap();
+
+        assert!(
+            proof
+                .verify(&mut Transcript::new(b"wiptest"), &y, &P, &g, &h, &G, &H)
+                .is_ok()
+        );
+        let wrong_y = y + y;
+        assert!(
+            proof
+                .verify(&mut Transcript::new(b"wiptest"), &wrong_y, &P, &g, &h, &G, &H)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn make_wipp_1() {
+        test_helper_create(1);
+    }
+
+    #[test]
+    fn make_wipp_2() {
+        test_helper_create(2);
+    }
+
+    #[test]
+    fn make_wipp_4() {
+        test_helper_create(4);
+    }
+
+    #[test]
+    fn make_wipp_32() {
+        test_helper_create(32);
+    }
+}